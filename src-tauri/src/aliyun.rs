@@ -0,0 +1,163 @@
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha1::Sha1;
+use tracing::debug;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// POP签名编码保留字符集（仅保留 A-Z a-z 0-9 - _ . ~）
+const POP_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// 录音文件识别API版本
+const FILETRANS_API_VERSION: &str = "2018-08-17";
+/// 默认服务地域（录音文件识别目前只在上海提供）
+pub const DEFAULT_REGION: &str = "cn-shanghai";
+
+/// 阿里云配置信息
+#[derive(Debug, Clone)]
+pub struct AliyunConfig {
+    pub access_key_id: String,
+    pub access_key_secret: String,
+    /// 智能语音交互控制台中项目的Appkey（识别模型、语言由项目决定）
+    pub app_key: String,
+    pub region: String,
+}
+
+/// 阿里云智能语音交互录音文件识别客户端
+pub struct AliyunClient {
+    config: AliyunConfig,
+    client: Client,
+}
+
+impl AliyunClient {
+    /// 创建新的阿里云客户端
+    pub fn new(config: AliyunConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// 提交录音文件识别任务，返回TaskId
+    ///
+    /// `file_link` 必须是识别服务可以直接下载的公网地址。
+    pub async fn submit_task(&self, file_link: &str) -> Result<String, String> {
+        let task = json!({
+            "appkey": self.config.app_key,
+            "file_link": file_link,
+            "version": "4.0",
+            "enable_words": false,
+            "enable_sample_rate_adaptive": true,
+        });
+        let response = self
+            .call("SubmitTask", &[("Task", task.to_string())])
+            .await?;
+
+        let status_text = response["StatusText"].as_str().unwrap_or("");
+        if status_text != "SUCCESS" {
+            return Err(format!("阿里云识别任务创建失败: {}", status_text));
+        }
+        response["TaskId"]
+            .as_str()
+            .map(|id| id.to_string())
+            .ok_or_else(|| "无法获取阿里云识别任务ID".to_string())
+    }
+
+    /// 查询录音文件识别任务结果
+    pub async fn get_task_result(&self, task_id: &str) -> Result<Value, String> {
+        self.call("GetTaskResult", &[("TaskId", task_id.to_string())])
+            .await
+    }
+
+    /// 调用录音文件识别RPC接口（签名参数放在查询字符串中）
+    async fn call(&self, action: &str, params: &[(&str, String)]) -> Result<Value, String> {
+        let mut query: Vec<(String, String)> = vec![
+            ("AccessKeyId".to_string(), self.config.access_key_id.clone()),
+            ("Action".to_string(), action.to_string()),
+            ("Format".to_string(), "JSON".to_string()),
+            ("RegionId".to_string(), self.config.region.clone()),
+            ("SignatureMethod".to_string(), "HMAC-SHA1".to_string()),
+            (
+                "SignatureNonce".to_string(),
+                uuid::Uuid::new_v4().to_string(),
+            ),
+            ("SignatureVersion".to_string(), "1.0".to_string()),
+            (
+                "Timestamp".to_string(),
+                Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            ),
+            ("Version".to_string(), FILETRANS_API_VERSION.to_string()),
+        ];
+        query.extend(
+            params
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone())),
+        );
+        query.sort();
+
+        let canonical_query = query
+            .iter()
+            .map(|(key, value)| format!("{}={}", pop_encode(key), pop_encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let signature = self.sign("GET", &canonical_query)?;
+        let url = format!(
+            "https://filetrans.{}.aliyuncs.com/?Signature={}&{}",
+            self.config.region,
+            pop_encode(&signature),
+            canonical_query
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("调用阿里云{}失败: {}", action, e))?;
+
+        let status = response.status();
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| format!("读取响应失败: {}", e))?;
+
+        debug!("阿里云{}响应状态: {}", action, status);
+
+        if status.is_success() {
+            serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))
+        } else {
+            Err(format!(
+                "阿里云{}失败: HTTP {}, {}",
+                action, status, response_text
+            ))
+        }
+    }
+
+    /// 生成POP签名：HMAC-SHA1(AccessKeySecret + "&", 方法&%2F&编码后的查询字符串)
+    fn sign(&self, method: &str, canonical_query: &str) -> Result<String, String> {
+        let string_to_sign = format!(
+            "{}&{}&{}",
+            method,
+            pop_encode("/"),
+            pop_encode(canonical_query)
+        );
+        let mut mac =
+            HmacSha1::new_from_slice(format!("{}&", self.config.access_key_secret).as_bytes())
+                .map_err(|e| format!("创建签名失败: {}", e))?;
+        mac.update(string_to_sign.as_bytes());
+        Ok(base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes()))
+    }
+}
+
+/// 按POP签名规则编码（空格为%20，~不编码）
+fn pop_encode(value: &str) -> String {
+    utf8_percent_encode(value, POP_ENCODE_SET).to_string()
+}
@@ -0,0 +1,172 @@
+use crate::video::{self, ExportOptions, Subtitle};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::{debug, error, info};
+
+/// 自动保存的最短间隔（秒）
+const MIN_AUTOSAVE_INTERVAL: u64 = 5;
+
+/// 自动保存状态
+struct AutosaveState {
+    /// 自动保存文件的基础路径（视频或字幕文件路径）
+    base_path: PathBuf,
+    /// 前端推送的最新字幕
+    subtitles: Vec<Subtitle>,
+    /// 自上次写入后是否有修改
+    dirty: bool,
+    handle: JoinHandle<()>,
+}
+
+lazy_static::lazy_static! {
+    static ref AUTOSAVE: Mutex<Option<AutosaveState>> = Mutex::new(None);
+}
+
+/// 自动保存文件路径：与基础路径同目录，`<文件名>.autosave.json` 和 `<文件名>.autosave.srt`
+fn autosave_paths(base_path: &Path) -> (PathBuf, PathBuf) {
+    (
+        base_path.with_extension("autosave.json"),
+        base_path.with_extension("autosave.srt"),
+    )
+}
+
+/// 开启自动保存，返回自动保存的JSON文件路径
+///
+/// 之后通过 `update_autosave` 推送编辑中的字幕，每隔 `interval_secs` 秒
+/// 将有改动的字幕写入JSON（完整数据）和SRT（便于直接查看）两个文件。
+/// 已开启时会先停止之前的自动保存。
+pub fn enable_autosave(path: &str, interval_secs: u64) -> Result<String, String> {
+    let base_path = PathBuf::from(path);
+    if base_path.file_name().is_none() {
+        return Err(format!("无效的自动保存路径: {}", path));
+    }
+    if let Some(dir) = base_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if !dir.exists() {
+            return Err(format!("自动保存目录不存在: {}", dir.display()));
+        }
+    }
+
+    disable_autosave()?;
+
+    let interval_secs = interval_secs.max(MIN_AUTOSAVE_INTERVAL);
+    let handle = tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(interval_secs));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await; // 第一次tick立即返回
+
+        loop {
+            ticker.tick().await;
+            if let Err(e) = flush_autosave() {
+                error!("自动保存失败: {}", e);
+            }
+        }
+    });
+
+    let mut state = AUTOSAVE
+        .lock()
+        .map_err(|_| "无法获取自动保存锁".to_string())?;
+    *state = Some(AutosaveState {
+        base_path: base_path.clone(),
+        subtitles: Vec::new(),
+        dirty: false,
+        handle,
+    });
+
+    let (json_path, _) = autosave_paths(&base_path);
+    info!(
+        "已开启自动保存，间隔{}秒: {}",
+        interval_secs,
+        json_path.display()
+    );
+    Ok(json_path.to_string_lossy().to_string())
+}
+
+/// 推送编辑中的最新字幕，下次定时保存时写入
+pub fn update_autosave(subtitles: Vec<Subtitle>) -> Result<(), String> {
+    let mut state = AUTOSAVE
+        .lock()
+        .map_err(|_| "无法获取自动保存锁".to_string())?;
+    let state = state.as_mut().ok_or_else(|| "自动保存未开启".to_string())?;
+    state.subtitles = subtitles;
+    state.dirty = true;
+    Ok(())
+}
+
+/// 停止自动保存，停止前写入尚未保存的修改
+pub fn disable_autosave() -> Result<(), String> {
+    flush_autosave()?;
+
+    let state = AUTOSAVE
+        .lock()
+        .map_err(|_| "无法获取自动保存锁".to_string())?
+        .take();
+    if let Some(state) = state {
+        state.handle.abort();
+        info!("已停止自动保存: {}", state.base_path.display());
+    }
+    Ok(())
+}
+
+/// 读取自动保存的字幕（优先JSON，其次SRT）
+pub fn recover_autosave(path: &str) -> Result<Vec<Subtitle>, String> {
+    let (json_path, srt_path) = autosave_paths(Path::new(path));
+
+    if json_path.exists() {
+        let content = std::fs::read_to_string(&json_path)
+            .map_err(|e| format!("读取自动保存文件失败: {}", e))?;
+        return serde_json::from_str(&content).map_err(|e| format!("解析自动保存文件失败: {}", e));
+    }
+
+    if srt_path.exists() {
+        let srt_path = srt_path
+            .to_str()
+            .ok_or_else(|| "自动保存路径无效".to_string())?;
+        return video::import_subtitles(srt_path, None);
+    }
+
+    Err(format!("未找到自动保存文件: {}", json_path.display()))
+}
+
+/// 有未保存的修改时写入自动保存文件
+fn flush_autosave() -> Result<(), String> {
+    // 在锁内取出快照，写文件时不持有锁
+    let snapshot = {
+        let mut state = AUTOSAVE
+            .lock()
+            .map_err(|_| "无法获取自动保存锁".to_string())?;
+        match state.as_mut() {
+            Some(state) if state.dirty => {
+                state.dirty = false;
+                Some((state.base_path.clone(), state.subtitles.clone()))
+            }
+            _ => None,
+        }
+    };
+
+    if let Some((base_path, subtitles)) = snapshot {
+        write_autosave(&base_path, &subtitles)?;
+        debug!("已自动保存{}条字幕", subtitles.len());
+    }
+    Ok(())
+}
+
+/// 写入自动保存文件（先写临时文件再重命名，避免崩溃时留下半个文件）
+fn write_autosave(base_path: &Path, subtitles: &[Subtitle]) -> Result<(), String> {
+    let (json_path, srt_path) = autosave_paths(base_path);
+
+    let json_tmp = json_path.with_extension("json.tmp");
+    let json_data =
+        serde_json::to_string_pretty(subtitles).map_err(|e| format!("序列化JSON失败: {}", e))?;
+    std::fs::write(&json_tmp, json_data).map_err(|e| format!("写入文件失败: {}", e))?;
+    std::fs::rename(&json_tmp, &json_path).map_err(|e| format!("保存自动保存文件失败: {}", e))?;
+
+    let srt_tmp = srt_path.with_extension("srt.tmp");
+    let srt_tmp_str = srt_tmp
+        .to_str()
+        .ok_or_else(|| "自动保存路径无效".to_string())?;
+    video::export_srt_to_path(subtitles, srt_tmp_str, &ExportOptions::default())?;
+    std::fs::rename(&srt_tmp, &srt_path).map_err(|e| format!("保存自动保存文件失败: {}", e))?;
+
+    Ok(())
+}
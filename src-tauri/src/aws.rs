@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SigV4 URI编码保留字符集（仅保留 A-Z a-z 0-9 - _ . ~）
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// AWS配置信息
+#[derive(Debug, Clone)]
+pub struct AwsConfig {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub region: String,
+    pub bucket: Option<String>,
+}
+
+/// AWS客户端（S3上传 + Transcribe）
+pub struct AwsClient {
+    config: AwsConfig,
+    client: Client,
+}
+
+impl AwsClient {
+    /// 创建新的AWS客户端
+    pub fn new(config: AwsConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// 上传文件到S3，返回 s3:// 形式的对象URI
+    pub async fn upload_to_s3(
+        &self,
+        file_data: &[u8],
+        object_key: &str,
+        content_type: &str,
+    ) -> Result<String, String> {
+        let bucket = self
+            .config
+            .bucket
+            .as_deref()
+            .filter(|b| !b.is_empty())
+            .ok_or("AWS Transcribe需要配置S3存储桶(bucket)")?;
+
+        let host = format!("{}.s3.{}.amazonaws.com", bucket, self.config.region);
+        let canonical_uri = format!("/{}", encode_object_key(object_key));
+        let url = format!("https://{}{}", host, canonical_uri);
+
+        let now = Utc::now();
+        let payload_hash = sha256_hex(file_data);
+        let headers = vec![
+            ("content-type".to_string(), content_type.to_string()),
+            ("host".to_string(), host.clone()),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), amz_date(&now)),
+        ];
+
+        let authorization =
+            self.sign_request("PUT", &canonical_uri, &headers, &payload_hash, "s3", &now)?;
+
+        let response = self
+            .client
+            .put(&url)
+            .header("Authorization", authorization)
+            .header("Content-Type", content_type)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date(&now))
+            .body(file_data.to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("上传文件到S3失败: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(format!("s3://{}/{}", bucket, object_key))
+        } else {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            Err(format!("S3上传失败: HTTP {}, {}", status, error_text))
+        }
+    }
+
+    /// 创建Transcribe转写任务
+    pub async fn start_transcription_job(
+        &self,
+        job_name: &str,
+        media_uri: &str,
+        language_code: &str,
+        media_format: &str,
+    ) -> Result<Value, String> {
+        let body = json!({
+            "TranscriptionJobName": job_name,
+            "LanguageCode": language_code,
+            "MediaFormat": media_format,
+            "Media": {
+                "MediaFileUri": media_uri
+            }
+        });
+
+        self.call_transcribe("StartTranscriptionJob", &body).await
+    }
+
+    /// 查询Transcribe转写任务
+    pub async fn get_transcription_job(&self, job_name: &str) -> Result<Value, String> {
+        let body = json!({
+            "TranscriptionJobName": job_name
+        });
+
+        self.call_transcribe("GetTranscriptionJob", &body).await
+    }
+
+    /// 下载转写结果JSON（TranscriptFileUri为预签名URL）
+    pub async fn fetch_transcript(&self, transcript_uri: &str) -> Result<String, String> {
+        let response = self
+            .client
+            .get(transcript_uri)
+            .send()
+            .await
+            .map_err(|e| format!("下载转写结果失败: {}", e))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("读取转写结果失败: {}", e))?;
+
+        if status.is_success() {
+            Ok(text)
+        } else {
+            Err(format!("下载转写结果失败: HTTP {}, {}", status, text))
+        }
+    }
+
+    /// 调用Transcribe JSON协议接口
+    async fn call_transcribe(&self, action: &str, body: &Value) -> Result<Value, String> {
+        let host = format!("transcribe.{}.amazonaws.com", self.config.region);
+        let url = format!("https://{}/", host);
+        let target = format!("Transcribe.{}", action);
+        let payload = body.to_string();
+
+        let now = Utc::now();
+        let payload_hash = sha256_hex(payload.as_bytes());
+        let headers = vec![
+            (
+                "content-type".to_string(),
+                "application/x-amz-json-1.1".to_string(),
+            ),
+            ("host".to_string(), host.clone()),
+            ("x-amz-date".to_string(), amz_date(&now)),
+            ("x-amz-target".to_string(), target.clone()),
+        ];
+
+        let authorization =
+            self.sign_request("POST", "/", &headers, &payload_hash, "transcribe", &now)?;
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", authorization)
+            .header("Content-Type", "application/x-amz-json-1.1")
+            .header("x-amz-date", amz_date(&now))
+            .header("x-amz-target", target)
+            .body(payload)
+            .send()
+            .await
+            .map_err(|e| format!("调用AWS Transcribe {}失败: {}", action, e))?;
+
+        let status = response.status();
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| format!("读取响应失败: {}", e))?;
+
+        debug!("AWS Transcribe {}响应状态: {}", action, status);
+
+        if status.is_success() {
+            serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))
+        } else {
+            Err(format!(
+                "AWS Transcribe {}失败: HTTP {}, {}",
+                action, status, response_text
+            ))
+        }
+    }
+
+    /// 生成SigV4签名（Authorization头）
+    fn sign_request(
+        &self,
+        method: &str,
+        canonical_uri: &str,
+        headers: &[(String, String)],
+        payload_hash: &str,
+        service: &str,
+        now: &DateTime<Utc>,
+    ) -> Result<String, String> {
+        let date_stamp = now.format("%Y%m%d").to_string();
+
+        // 1. 规范请求（本客户端的请求均不带查询参数）
+        let mut sorted_headers = headers.to_vec();
+        sorted_headers.sort_by(|a, b| a.0.cmp(&b.0));
+        let canonical_headers: String = sorted_headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let signed_headers = sorted_headers
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, canonical_uri, canonical_headers, signed_headers, payload_hash
+        );
+
+        // 2. 待签名字符串
+        let credential_scope = format!(
+            "{}/{}/{}/aws4_request",
+            date_stamp, self.config.region, service
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date(now),
+            credential_scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        // 3. 计算签名
+        let k_date = hmac_sha256(
+            format!("AWS4{}", self.config.secret_access_key).as_bytes(),
+            &date_stamp,
+        )?;
+        let k_region = hmac_sha256(&k_date, &self.config.region)?;
+        let k_service = hmac_sha256(&k_region, service)?;
+        let k_signing = hmac_sha256(&k_service, "aws4_request")?;
+        let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign)?);
+
+        // 4. 构建Authorization
+        Ok(format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id, credential_scope, signed_headers, signature
+        ))
+    }
+}
+
+/// 格式化为 x-amz-date 时间格式
+fn amz_date(now: &DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// 按段编码S3对象键（保留路径分隔符）
+fn encode_object_key(object_key: &str) -> String {
+    object_key
+        .split('/')
+        .map(|segment| utf8_percent_encode(segment, URI_ENCODE_SET).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 计算SHA256哈希
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+/// 计算HMAC-SHA256
+fn hmac_sha256(key: &[u8], data: &str) -> Result<Vec<u8>, String> {
+    let mut mac = HmacSha256::new_from_slice(key).map_err(|e| format!("创建HMAC失败: {}", e))?;
+    mac.update(data.as_bytes());
+    Ok(mac.finalize().into_bytes().to_vec())
+}
@@ -0,0 +1,139 @@
+use crate::video::Subtitle;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::PathBuf;
+use tracing::{debug, info, warn};
+
+/// 识别结果缓存目录名（位于应用配置目录）
+const CACHE_DIR: &str = "recognition_cache";
+
+/// 缓存目录路径
+fn cache_dir() -> Result<PathBuf, String> {
+    crate::recognition::config_file_path(CACHE_DIR)
+}
+
+/// 计算缓存键：音频内容的SHA-256与引擎、语言及模型参数一起再做一次SHA-256
+///
+/// `variant` 描述影响识别结果的其他参数（如模型大小、提示词），没有时传空字符串。
+pub fn cache_key(
+    audio_path: &str,
+    engine: &str,
+    language: &str,
+    variant: &str,
+) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
+    let mut audio_hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("读取音频文件失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        audio_hasher.update(&buffer[..read]);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(audio_hasher.finalize());
+    for part in [engine, language, variant] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 读取缓存的识别结果，未命中或缓存损坏时返回None
+pub fn lookup(key: &str) -> Option<Vec<Subtitle>> {
+    let path = cache_dir().ok()?.join(format!("{}.json", key));
+    let content = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(subtitles) => Some(subtitles),
+        Err(e) => {
+            warn!("识别缓存损坏，忽略: {} ({})", path.display(), e);
+            let _ = std::fs::remove_file(&path);
+            None
+        }
+    }
+}
+
+/// 保存识别结果到缓存，失败只记录日志
+pub fn store(key: &str, subtitles: &[Subtitle]) {
+    let result = cache_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+        let content =
+            serde_json::to_string(subtitles).map_err(|e| format!("序列化识别结果失败: {}", e))?;
+        std::fs::write(dir.join(format!("{}.json", key)), content)
+            .map_err(|e| format!("写入识别缓存失败: {}", e))
+    });
+    if let Err(e) = result {
+        warn!("保存识别缓存失败: {}", e);
+    }
+}
+
+/// 清空识别结果缓存，返回删除的条目数
+pub fn clear_recognition_cache() -> Result<usize, String> {
+    let dir = cache_dir()?;
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in std::fs::read_dir(&dir).map_err(|e| format!("读取缓存目录失败: {}", e))? {
+        let path = entry
+            .map_err(|e| format!("读取缓存目录失败: {}", e))?
+            .path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            std::fs::remove_file(&path).map_err(|e| format!("删除缓存文件失败: {}", e))?;
+            removed += 1;
+        }
+    }
+
+    info!("已清空识别缓存，共{}条", removed);
+    Ok(removed)
+}
+
+/// 查找缓存；未命中时执行识别并缓存成功的结果
+///
+/// `key` 为None（跳过缓存或无法计算缓存键）时直接执行识别。
+/// 所有 `Ok` 结果都会被缓存，因此识别失败时必须返回错误，不能以占位或测试字幕代替。
+pub async fn cached_or<F>(key: Option<String>, recognize: F) -> Result<Vec<Subtitle>, String>
+where
+    F: std::future::Future<Output = Result<Vec<Subtitle>, String>>,
+{
+    let Some(key) = key else {
+        return recognize.await;
+    };
+    if let Some(subtitles) = lookup(&key) {
+        debug!("命中识别缓存: {}，共{}条字幕", key, subtitles.len());
+        return Ok(subtitles);
+    }
+
+    let result = recognize.await;
+    if let Ok(subtitles) = &result {
+        store(&key, subtitles);
+    }
+    result
+}
+
+/// 在后台线程中计算缓存键，失败时记录日志并跳过缓存
+pub async fn task_cache_key(
+    audio_path: String,
+    engine: String,
+    language: String,
+    variant: String,
+) -> Option<String> {
+    let result =
+        tokio::task::spawn_blocking(move || cache_key(&audio_path, &engine, &language, &variant))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|key| key);
+    match result {
+        Ok(key) => Some(key),
+        Err(e) => {
+            warn!("计算识别缓存键失败，跳过缓存: {}", e);
+            None
+        }
+    }
+}
@@ -1,15 +1,122 @@
 use chrono::Utc;
+use futures_util::StreamExt;
 use hex;
 use hmac::{Hmac, Mac};
 
 use reqwest::Client;
+use serde::Serialize;
 use sha1::Sha1;
 use sha2::Digest;
 use std::collections::HashMap;
-use uuid::Uuid;
+use tracing::{info, warn};
 
 type HmacSha1 = Hmac<Sha1>;
 
+/// 上传时每次发送的数据块大小（用于进度回调）
+const UPLOAD_CHUNK_SIZE: usize = 256 * 1024;
+
+/// 超过此大小的文件使用分块上传（100MB）
+const MULTIPART_THRESHOLD: usize = 100 * 1024 * 1024;
+
+/// 上传遇到网络错误时的默认最大重试次数
+const DEFAULT_UPLOAD_RETRIES: u32 = 3;
+
+/// 分块上传的每块大小（8MB，COS要求除最后一块外不小于1MB，最多10000块）
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// 腾讯云COS地域（代码, 显示名称）
+const COS_REGIONS: &[(&str, &str)] = &[
+    ("ap-beijing", "北京"),
+    ("ap-nanjing", "南京"),
+    ("ap-shanghai", "上海"),
+    ("ap-guangzhou", "广州"),
+    ("ap-chengdu", "成都"),
+    ("ap-chongqing", "重庆"),
+    ("ap-beijing-fsi", "北京金融"),
+    ("ap-shanghai-fsi", "上海金融"),
+    ("ap-shenzhen-fsi", "深圳金融"),
+    ("ap-hongkong", "中国香港"),
+    ("ap-singapore", "新加坡"),
+    ("ap-jakarta", "雅加达"),
+    ("ap-seoul", "首尔"),
+    ("ap-bangkok", "曼谷"),
+    ("ap-tokyo", "东京"),
+    ("ap-mumbai", "孟买"),
+    ("na-siliconvalley", "硅谷"),
+    ("na-ashburn", "弗吉尼亚"),
+    ("sa-saopaulo", "圣保罗"),
+    ("eu-frankfurt", "法兰克福"),
+];
+
+/// COS地域
+#[derive(Debug, Clone, Serialize)]
+pub struct CosRegion {
+    pub code: String,
+    pub name: String,
+}
+
+/// 获取已知的COS地域列表
+pub fn get_cos_regions() -> Vec<CosRegion> {
+    COS_REGIONS
+        .iter()
+        .map(|(code, name)| CosRegion {
+            code: code.to_string(),
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// 校验COS地域代码，未知地域时给出最接近的建议
+pub fn validate_region(region: &str) -> Result<(), String> {
+    let region = region.trim();
+    if COS_REGIONS.iter().any(|(code, _)| *code == region) {
+        return Ok(());
+    }
+    match suggest_region(region) {
+        Some(code) => Err(format!("未知的COS地域: {}，你是否想用 {}？", region, code)),
+        None => Err(format!(
+            "未知的COS地域: {}，地域代码应形如 ap-guangzhou",
+            region
+        )),
+    }
+}
+
+/// 按代码片段、中文名称或编辑距离查找最接近的地域代码
+fn suggest_region(input: &str) -> Option<&'static str> {
+    let input = input.to_lowercase().replace(['_', ' '], "-");
+    if input.is_empty() {
+        return None;
+    }
+
+    // 大小写或分隔符写错、省略前缀（beijing）、使用中文名称（北京）
+    if let Some((code, _)) = COS_REGIONS.iter().find(|(code, name)| {
+        *code == input
+            || code.split_once('-').is_some_and(|(_, rest)| rest == input)
+            || *name == input
+    }) {
+        return Some(code);
+    }
+
+    COS_REGIONS
+        .iter()
+        .map(|(code, _)| (*code, crate::video::edit_distance(&input, code)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(code, _)| code)
+}
+
+/// 由文件内容生成对象键：`audio/<内容SHA-256>.<扩展名>`
+fn content_object_key(file_data: &[u8], file_name: &str) -> String {
+    let hash = hex::encode(sha2::Sha256::digest(file_data));
+    match std::path::Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some(ext) => format!("audio/{}.{}", hash, ext.to_lowercase()),
+        None => format!("audio/{}", hash),
+    }
+}
+
 /// COS配置信息
 #[derive(Debug, Clone)]
 pub struct CosConfig {
@@ -18,6 +125,19 @@ pub struct CosConfig {
     pub bucket: String,
     pub region: String,
     pub domain: Option<String>,
+    /// 存储类型：STANDARD（标准）或 STANDARD_IA（低频），为空时使用存储桶默认值
+    pub storage_class: Option<String>,
+    /// 对象访问权限：private 或 public-read，为空时继承存储桶权限
+    ///
+    /// 私有对象需要通过预签名URL才能被语音识别服务读取。
+    pub acl: Option<String>,
+    /// 识别结束后保留上传的音频（默认删除，避免产生存储费用）
+    pub keep_uploads: bool,
+    /// 上传遇到连接错误或超时时的最大重试次数，为空时使用默认值
+    pub upload_retries: Option<u32>,
+    /// 自定义访问端点（不含存储桶名），如全球加速 `cos.accelerate.myqcloud.com`
+    /// 或内网端点；为空时使用 `cos.{region}.myqcloud.com`
+    pub endpoint: Option<String>,
 }
 
 /// COS客户端
@@ -42,16 +162,43 @@ impl CosClient {
         file_name: &str,
         content_type: Option<&str>,
     ) -> Result<String, String> {
-        // 生成唯一的对象键
-        let object_key = format!("audio/{}/{}", Uuid::new_v4(), file_name);
+        self.upload_file_with_progress(file_data, file_name, content_type, |_, _| {})
+            .await
+    }
+
+    /// 上传文件到COS，并通过回调报告已发送字节数 `(已发送, 总大小)`
+    pub async fn upload_file_with_progress<F>(
+        &self,
+        file_data: &[u8],
+        file_name: &str,
+        content_type: Option<&str>,
+        mut on_progress: F,
+    ) -> Result<String, String>
+    where
+        F: FnMut(u64, u64) + Send + Sync + 'static,
+    {
+        // 大文件分块上传，避免单个请求过大导致失败
+        if file_data.len() > MULTIPART_THRESHOLD {
+            return self
+                .upload_file_multipart(file_data, file_name, content_type, on_progress)
+                .await;
+        }
+
+        // 对象键由文件内容决定，重试上传同一文件时复用同一对象
+        let object_key = content_object_key(file_data, file_name);
 
         // 构建上传URL
-        let host = format!(
-            "{}.cos.{}.myqcloud.com",
-            self.config.bucket, self.config.region
-        );
+        let host = self.host();
         let url = format!("https://{}/{}", host, object_key);
 
+        // 对象已存在（如上次上传超时但实际已成功）时跳过上传
+        let total_bytes = file_data.len() as u64;
+        if self.object_exists(&object_key).await {
+            warn!("COS对象已存在，跳过上传: {}", object_key);
+            on_progress(total_bytes, total_bytes);
+            return Ok(self.access_url(&object_key, url));
+        }
+
         // 获取当前时间
         let now = Utc::now();
         let timestamp = now.timestamp();
@@ -74,33 +221,68 @@ impl CosClient {
             headers.insert("Content-Type".to_string(), mime_type);
         }
 
+        // 存储类型和访问权限头（需参与签名）
+        let option_headers = self.upload_option_headers()?;
+        for (key, value) in &option_headers {
+            headers.insert(key.to_string(), value.clone());
+        }
+
         // 生成签名
         let authorization = self.generate_authorization("PUT", &object_key, &headers, timestamp)?;
 
-        // 构建请求
-        let mut request_builder = self
-            .client
-            .put(&url)
-            .header("Authorization", authorization)
-            .header("Host", &host)
-            .header("Date", now.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
-            .body(file_data.to_vec());
-
-        // 添加Content-Type头
-        if let Some(ct) = content_type {
-            request_builder = request_builder.header("Content-Type", ct);
-        } else {
-            let mime_type = mime_guess::from_path(file_name)
+        let date = now.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let content_type = content_type.map(str::to_string).unwrap_or_else(|| {
+            mime_guess::from_path(file_name)
                 .first_or_octet_stream()
-                .to_string();
-            request_builder = request_builder.header("Content-Type", mime_type);
-        }
+                .to_string()
+        });
+
+        // 进度回调在多次尝试间共享，重试时从0重新计数
+        let on_progress = std::sync::Arc::new(std::sync::Mutex::new(on_progress));
+
+        // 每次尝试重新构建请求：流式请求体无法克隆，需从原始数据重新生成
+        let build_request = || {
+            // 分块流式发送请求体，每发送一块回调一次进度
+            let chunks: Vec<Result<Vec<u8>, std::io::Error>> = file_data
+                .chunks(UPLOAD_CHUNK_SIZE)
+                .map(|chunk| Ok(chunk.to_vec()))
+                .collect();
+            let mut sent_bytes = 0u64;
+            let on_progress = on_progress.clone();
+            let body_stream = futures_util::stream::iter(chunks).inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    sent_bytes += chunk.len() as u64;
+                    if let Ok(mut on_progress) = on_progress.lock() {
+                        on_progress(sent_bytes, total_bytes);
+                    }
+                }
+            });
+
+            // 构建请求（显式设置Content-Length，避免分块传输编码）
+            let mut request_builder = self
+                .client
+                .put(&url)
+                .header("Authorization", &authorization)
+                .header("Host", &host)
+                .header("Date", &date)
+                .header("Content-Length", total_bytes)
+                .header("Content-Type", &content_type)
+                .body(reqwest::Body::wrap_stream(body_stream));
+            for (key, value) in &option_headers {
+                request_builder = request_builder.header(*key, value);
+            }
+            request_builder
+        };
 
-        // 发送请求
-        let response = request_builder.send().await.map_err(|e| {
-            if e.is_connect() {
-                format!(
-                    "COS上传失败: 网络连接错误\n\n可能的原因：\n\
+        // 发送请求，连接错误和超时自动重试
+        let max_retries = self.config.upload_retries.unwrap_or(DEFAULT_UPLOAD_RETRIES);
+        let response = self
+            .send_request_with_retry(build_request, max_retries)
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    format!(
+                        "COS上传失败: 网络连接错误\n\n可能的原因：\n\
                     1. 网络连接不稳定，请检查网络连接\n\
                     2. 腾讯云COS服务暂时不可用\n\
                     3. 防火墙或代理设置阻止了连接\n\
@@ -110,11 +292,11 @@ impl CosClient {
                     - 尝试使用Whisper本地识别（不需要网络）\n\
                     - 稍后重试\n\n\
                     详细错误: {}",
-                    e
-                )
-            } else if e.is_timeout() {
-                format!(
-                    "COS上传失败: 请求超时\n\n\
+                        e
+                    )
+                } else if e.is_timeout() {
+                    format!(
+                        "COS上传失败: 请求超时\n\n\
                     文件大小: {:.1} MB\n\
                     可能原因：网络速度较慢或文件过大\n\n\
                     建议：\n\
@@ -122,22 +304,17 @@ impl CosClient {
                     - 尝试使用Whisper本地识别\n\
                     - 压缩音频文件后重试\n\n\
                     详细错误: {}",
-                    file_data.len() as f64 / (1024.0 * 1024.0),
-                    e
-                )
-            } else {
-                format!("上传文件到COS失败: {}", e)
-            }
-        })?;
+                        file_data.len() as f64 / (1024.0 * 1024.0),
+                        e
+                    )
+                } else {
+                    format!("上传文件到COS失败: {}", e)
+                }
+            })?;
 
         if response.status().is_success() {
             // 返回文件的访问URL
-            let access_url = if let Some(domain) = &self.config.domain {
-                format!("https://{}/{}", domain, object_key)
-            } else {
-                url
-            };
-            Ok(access_url)
+            Ok(self.access_url(&object_key, url))
         } else {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
@@ -146,7 +323,7 @@ impl CosClient {
             let detailed_error = match status.as_u16() {
                 403 => format!(
                     "COS上传失败: 权限被拒绝 (HTTP 403)\n\n可能的原因：\n\
-                    1. 存储桶权限设置不正确，请设置为'公有读私有写'\n\
+                    1. 存储桶权限设置不正确（私有存储桶需使用预签名URL读取）\n\
                     2. API密钥缺少COS操作权限\n\
                     3. 存储桶名称或地域配置错误\n\
                     4. 存储桶不存在或已被删除\n\n\
@@ -173,6 +350,329 @@ impl CosClient {
         }
     }
 
+    /// 分块上传文件到COS（InitiateMultipartUpload → UploadPart → CompleteMultipartUpload）
+    ///
+    /// 每块约8MB，逐块上传并在每块完成后回调进度 `(已发送, 总大小)`；
+    /// 任一步骤失败时中止分块上传，避免残留未完成的分块。
+    pub async fn upload_file_multipart<F>(
+        &self,
+        file_data: &[u8],
+        file_name: &str,
+        content_type: Option<&str>,
+        mut on_progress: F,
+    ) -> Result<String, String>
+    where
+        F: FnMut(u64, u64) + Send + Sync + 'static,
+    {
+        let object_key = content_object_key(file_data, file_name);
+        let url = format!("https://{}/{}", self.host(), object_key);
+
+        let total_bytes = file_data.len() as u64;
+        if self.object_exists(&object_key).await {
+            warn!("COS对象已存在，跳过上传: {}", object_key);
+            on_progress(total_bytes, total_bytes);
+            return Ok(self.access_url(&object_key, url));
+        }
+
+        // 1. 初始化分块上传，存储类型、访问权限和Content-Type在此时指定
+        let content_type = content_type.map(str::to_string).unwrap_or_else(|| {
+            mime_guess::from_path(file_name)
+                .first_or_octet_stream()
+                .to_string()
+        });
+        let mut initiate_headers = vec![("Content-Type".to_string(), content_type)];
+        for (key, value) in self.upload_option_headers()? {
+            initiate_headers.push((key.to_string(), value));
+        }
+        let response = self
+            .send_signed(
+                reqwest::Method::POST,
+                &object_key,
+                &[("uploads", String::new())],
+                initiate_headers,
+                Vec::new(),
+            )
+            .await
+            .map_err(|e| format!("初始化COS分块上传失败: {}", e))?;
+        let upload_id = xml_tag_value(&response, "UploadId")
+            .ok_or_else(|| format!("初始化COS分块上传失败: 响应中没有UploadId: {}", response))?;
+
+        info!(
+            "开始COS分块上传: {}，{:.1} MB，共{}块",
+            object_key,
+            total_bytes as f64 / (1024.0 * 1024.0),
+            file_data.len().div_ceil(MULTIPART_PART_SIZE)
+        );
+
+        // 2. 逐块上传，记录每块的ETag
+        let mut parts = Vec::new();
+        let mut sent_bytes = 0u64;
+        for (index, part) in file_data.chunks(MULTIPART_PART_SIZE).enumerate() {
+            let part_number = index + 1;
+            let etag = match self
+                .upload_part(&object_key, &upload_id, part_number, part)
+                .await
+            {
+                Ok(etag) => etag,
+                Err(e) => {
+                    self.abort_multipart_upload(&object_key, &upload_id).await;
+                    return Err(format!("COS分块上传第{}块失败: {}", part_number, e));
+                }
+            };
+            parts.push((part_number, etag));
+            sent_bytes += part.len() as u64;
+            on_progress(sent_bytes, total_bytes);
+        }
+
+        // 3. 完成分块上传
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+                .iter()
+                .map(|(number, etag)| format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    number,
+                    crate::video::xml_escape(etag)
+                ))
+                .collect::<String>()
+        );
+        if let Err(e) = self
+            .send_signed(
+                reqwest::Method::POST,
+                &object_key,
+                &[("uploadId", upload_id.clone())],
+                vec![("Content-Type".to_string(), "application/xml".to_string())],
+                body.into_bytes(),
+            )
+            .await
+        {
+            self.abort_multipart_upload(&object_key, &upload_id).await;
+            return Err(format!("完成COS分块上传失败: {}", e));
+        }
+
+        info!("COS分块上传完成: {}", object_key);
+        Ok(self.access_url(&object_key, url))
+    }
+
+    /// 上传一个分块，返回COS返回的ETag
+    async fn upload_part(
+        &self,
+        object_key: &str,
+        upload_id: &str,
+        part_number: usize,
+        data: &[u8],
+    ) -> Result<String, String> {
+        let params = [
+            ("partNumber", part_number.to_string()),
+            ("uploadId", upload_id.to_string()),
+        ];
+        let response = self
+            .signed_request(reqwest::Method::PUT, object_key, &params, Vec::new())?
+            .body(data.to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("HTTP {}, {}", status, error_text));
+        }
+        response
+            .headers()
+            .get("ETag")
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| "响应中没有ETag".to_string())
+    }
+
+    /// 中止分块上传，删除已上传的分块（失败只记录警告）
+    async fn abort_multipart_upload(&self, object_key: &str, upload_id: &str) {
+        if let Err(e) = self
+            .send_signed(
+                reqwest::Method::DELETE,
+                object_key,
+                &[("uploadId", upload_id.to_string())],
+                Vec::new(),
+                Vec::new(),
+            )
+            .await
+        {
+            warn!("中止COS分块上传失败: {}", e);
+        }
+    }
+
+    /// 发送带签名的请求，成功时返回响应正文
+    async fn send_signed(
+        &self,
+        method: reqwest::Method,
+        object_key: &str,
+        params: &[(&str, String)],
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    ) -> Result<String, String> {
+        let response = self
+            .signed_request(method, object_key, params, headers)?
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if status.is_success() {
+            Ok(text)
+        } else {
+            Err(format!("HTTP {}, {}", status, text))
+        }
+    }
+
+    /// 构建带签名的请求：Host、Date和 `headers` 参与签名，`params` 作为URL参数并参与签名
+    fn signed_request(
+        &self,
+        method: reqwest::Method,
+        object_key: &str,
+        params: &[(&str, String)],
+        extra_headers: Vec<(String, String)>,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let host = self.host();
+        let now = Utc::now();
+        let date = now.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+        let mut headers = HashMap::new();
+        headers.insert("Host".to_string(), host.clone());
+        headers.insert("Date".to_string(), date.clone());
+        for (key, value) in &extra_headers {
+            headers.insert(key.clone(), value.clone());
+        }
+        let authorization = self.generate_authorization_with_params(
+            method.as_str(),
+            object_key,
+            &headers,
+            params,
+            now.timestamp(),
+        )?;
+
+        let query = params
+            .iter()
+            .map(|(key, value)| {
+                if value.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}={}", key, self.url_encode(value))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let url = format!("https://{}/{}?{}", host, object_key, query);
+
+        let mut request = self
+            .client
+            .request(method, &url)
+            .header("Authorization", authorization)
+            .header("Host", &host)
+            .header("Date", date);
+        for (key, value) in extra_headers {
+            request = request.header(key, value);
+        }
+        Ok(request)
+    }
+
+    /// 请求的存储桶域名（同时作为签名中的Host头）
+    ///
+    /// 配置了 `endpoint` 时为 `{bucket}.{endpoint}`，否则为 `{bucket}.cos.{region}.myqcloud.com`。
+    fn host(&self) -> String {
+        match self
+            .config
+            .endpoint
+            .as_deref()
+            .map(normalize_endpoint)
+            .filter(|e| !e.is_empty())
+        {
+            Some(endpoint) => format!("{}.{}", self.config.bucket, endpoint),
+            None => format!(
+                "{}.cos.{}.myqcloud.com",
+                self.config.bucket, self.config.region
+            ),
+        }
+    }
+
+    /// 文件的访问URL：配置了自定义域名时使用自定义域名
+    fn access_url(&self, object_key: &str, default_url: String) -> String {
+        match &self.config.domain {
+            Some(domain) => format!("https://{}/{}", domain, object_key),
+            None => default_url,
+        }
+    }
+
+    /// 用HEAD请求检查对象是否已存在，请求失败时视为不存在
+    async fn object_exists(&self, object_key: &str) -> bool {
+        let host = self.host();
+        let url = format!("https://{}/{}", host, object_key);
+        let now = Utc::now();
+
+        let mut headers = HashMap::new();
+        headers.insert("Host".to_string(), host.clone());
+        headers.insert(
+            "Date".to_string(),
+            now.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        );
+        let authorization =
+            match self.generate_authorization("HEAD", object_key, &headers, now.timestamp()) {
+                Ok(authorization) => authorization,
+                Err(_) => return false,
+            };
+
+        match self
+            .client
+            .head(&url)
+            .header("Authorization", authorization)
+            .header("Host", &host)
+            .header("Date", now.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+            .send()
+            .await
+        {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                warn!("检查COS对象是否存在失败，继续上传: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 构建上传时的存储类型和访问权限头
+    fn upload_option_headers(&self) -> Result<Vec<(&'static str, String)>, String> {
+        let mut option_headers = Vec::new();
+
+        if let Some(storage_class) = self
+            .config
+            .storage_class
+            .as_deref()
+            .filter(|s| !s.is_empty())
+        {
+            let storage_class = storage_class.to_uppercase();
+            if !matches!(storage_class.as_str(), "STANDARD" | "STANDARD_IA") {
+                return Err(format!(
+                    "不支持的COS存储类型: {}（可选 STANDARD、STANDARD_IA）",
+                    storage_class
+                ));
+            }
+            option_headers.push(("x-cos-storage-class", storage_class));
+        }
+
+        if let Some(acl) = self.config.acl.as_deref().filter(|s| !s.is_empty()) {
+            if !matches!(acl, "private" | "public-read") {
+                return Err(format!(
+                    "不支持的COS访问权限: {}（可选 private、public-read）",
+                    acl
+                ));
+            }
+            option_headers.push(("x-cos-acl", acl.to_string()));
+        }
+
+        Ok(option_headers)
+    }
+
     /// 生成COS API签名（按照腾讯云官方文档）
     fn generate_authorization(
         &self,
@@ -180,6 +680,18 @@ impl CosClient {
         object_key: &str,
         headers: &HashMap<String, String>,
         timestamp: i64,
+    ) -> Result<String, String> {
+        self.generate_authorization_with_params(method, object_key, headers, &[], timestamp)
+    }
+
+    /// 生成带URL参数的COS API签名（参数参与签名，如分块上传的uploadId）
+    fn generate_authorization_with_params(
+        &self,
+        method: &str,
+        object_key: &str,
+        headers: &HashMap<String, String>,
+        params: &[(&str, String)],
+        timestamp: i64,
     ) -> Result<String, String> {
         // 签名有效期（1小时）
         let expire_time = timestamp + 3600;
@@ -194,7 +706,7 @@ impl CosClient {
         let sign_key = hex::encode(mac.finalize().into_bytes());
 
         // 3. 生成 HttpString（按照官方格式）
-        let http_string = self.build_http_string_official(method, object_key, headers)?;
+        let http_string = self.build_http_string_official(method, object_key, headers, params)?;
 
         // 4. 生成 StringToSign
         let string_to_sign = format!("sha1\n{}\n{}\n", key_time, self.sha1_hash(&http_string));
@@ -207,12 +719,15 @@ impl CosClient {
 
         // 6. 构建 Authorization
         let header_list = self.get_header_list_official(headers);
+        let mut param_keys: Vec<String> = params.iter().map(|(k, _)| k.to_lowercase()).collect();
+        param_keys.sort();
         let authorization = format!(
-            "q-sign-algorithm=sha1&q-ak={}&q-sign-time={}&q-key-time={}&q-header-list={}&q-url-param-list=&q-signature={}",
+            "q-sign-algorithm=sha1&q-ak={}&q-sign-time={}&q-key-time={}&q-header-list={}&q-url-param-list={}&q-signature={}",
             self.config.secret_id,
             key_time,
             key_time,
             header_list,
+            param_keys.join(";"),
             signature
         );
 
@@ -225,6 +740,7 @@ impl CosClient {
         method: &str,
         object_key: &str,
         headers: &HashMap<String, String>,
+        params: &[(&str, String)],
     ) -> Result<String, String> {
         // 1. HTTP方法（小写）
         let http_method = method.to_lowercase();
@@ -232,8 +748,13 @@ impl CosClient {
         // 2. URI路径
         let uri_path = format!("/{}", object_key);
 
-        // 3. HTTP参数（暂时为空）
-        let http_parameters = "";
+        // 3. HTTP参数（键小写，值URL编码，按键排序）
+        let mut param_pairs: Vec<String> = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key.to_lowercase(), self.url_encode(value)))
+            .collect();
+        param_pairs.sort();
+        let http_parameters = param_pairs.join("&");
 
         // 4. HTTP头部（按照官方格式）
         let http_headers = self.build_header_string_official(headers);
@@ -283,52 +804,30 @@ impl CosClient {
     }
 
     /// 带重试机制的请求发送
-    async fn send_request_with_retry(
+    ///
+    /// `build_request` 每次尝试构建一个新请求；连接错误和超时按1、2、4秒……指数退避重试，
+    /// 其他错误或重试次数用完时返回最后一次的错误。
+    async fn send_request_with_retry<B>(
         &self,
-        request_builder: reqwest::RequestBuilder,
+        mut build_request: B,
         max_retries: u32,
-    ) -> Result<reqwest::Response, String> {
-        let mut last_error = String::new();
-
-        for attempt in 0..=max_retries {
-            // 克隆请求构建器（注意：这里需要重新构建请求）
-            let result = if attempt == 0 {
-                request_builder
-                    .try_clone()
-                    .ok_or("无法克隆请求")?
-                    .send()
-                    .await
-            } else {
-                // 对于重试，我们需要重新构建整个请求
-                // 这里先返回错误，让调用方重新构建请求
-                return Err(format!("网络连接失败，已重试{}次: {}", attempt, last_error));
-            };
-
-            match result {
+    ) -> Result<reqwest::Response, reqwest::Error>
+    where
+        B: FnMut() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            match build_request().send().await {
                 Ok(response) => return Ok(response),
-                Err(e) => {
-                    last_error = format!("{}", e);
-
-                    // 检查是否是网络连接错误
-                    if e.is_connect() || e.is_timeout() {
-                        if attempt < max_retries {
-                            println!("COS上传失败，第{}次重试中... 错误: {}", attempt + 1, e);
-                            // 等待一段时间后重试
-                            tokio::time::sleep(tokio::time::Duration::from_secs(
-                                2_u64.pow(attempt),
-                            ))
-                            .await;
-                            continue;
-                        }
-                    }
-
-                    // 非网络错误或已达到最大重试次数
-                    return Err(format!("上传文件到COS失败: {}", e));
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < max_retries => {
+                    attempt += 1;
+                    warn!("COS上传失败，第{}次重试中... 错误: {}", attempt, e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(2_u64.pow(attempt - 1)))
+                        .await;
                 }
+                Err(e) => return Err(e),
             }
         }
-
-        Err(format!("上传失败，已重试{}次: {}", max_retries, last_error))
     }
 
     /// 获取头部列表
@@ -365,12 +864,92 @@ impl CosClient {
         hex::encode(hasher.finalize())
     }
 
+    /// 下载文件（使用签名请求，私有存储桶同样可读），可用于重新处理已上传的音频
+    pub async fn download_file(&self, object_key: &str) -> Result<Vec<u8>, String> {
+        let host = self.host();
+        let url = format!("https://{}/{}", host, object_key);
+
+        let now = Utc::now();
+        let timestamp = now.timestamp();
+
+        let mut headers = HashMap::new();
+        headers.insert("Host".to_string(), host.clone());
+        headers.insert(
+            "Date".to_string(),
+            now.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        );
+
+        let authorization = self.generate_authorization("GET", object_key, &headers, timestamp)?;
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", authorization)
+            .header("Host", &host)
+            .header("Date", now.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+            .send()
+            .await
+            .map_err(|e| format!("下载COS文件失败: {}", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            response
+                .bytes()
+                .await
+                .map(|bytes| bytes.to_vec())
+                .map_err(|e| format!("读取COS文件失败: {}", e))
+        } else if status == reqwest::StatusCode::NOT_FOUND {
+            Err(format!("COS下载失败: 对象不存在: {}", object_key))
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(format!("COS下载失败: HTTP {}, {}", status, error_text))
+        }
+    }
+
+    /// 端到端测试COS配置：上传一个小测试文件，读回校验内容后删除
+    ///
+    /// 失败时错误信息标明出错的步骤。
+    pub async fn test_connection(&self) -> Result<(), String> {
+        const TEST_CONTENT: &[u8] = b"FlowText COS test";
+
+        // 1. 上传
+        let file_url = self
+            .upload_file(TEST_CONTENT, "flowtext_cos_test.txt", Some("text/plain"))
+            .await
+            .map_err(|e| format!("第1步（上传测试文件）失败:\n{}", e))?;
+        let object_key = extract_object_key_from_url(&file_url)
+            .ok_or_else(|| format!("无法从上传地址解析对象键: {}", file_url))?;
+        info!(
+            "COS测试文件已上传: {}",
+            crate::logging::redact_url(&file_url)
+        );
+
+        // 2. 读回并校验内容（无论成功与否都尝试删除测试文件）
+        let read_result = match self.download_file(&object_key).await {
+            Ok(data) if data == TEST_CONTENT => Ok(()),
+            Ok(data) => Err(format!(
+                "第2步（读取测试文件）失败: 内容不一致（期望{}字节，实际{}字节）",
+                TEST_CONTENT.len(),
+                data.len()
+            )),
+            Err(e) => Err(format!("第2步（读取测试文件）失败: {}", e)),
+        };
+
+        // 3. 删除
+        let delete_result = self
+            .delete_file(&object_key)
+            .await
+            .map_err(|e| format!("第3步（删除测试文件）失败: {}", e));
+
+        read_result?;
+        delete_result?;
+        info!("COS配置测试通过");
+        Ok(())
+    }
+
     /// 删除文件（可选功能）
     pub async fn delete_file(&self, object_key: &str) -> Result<(), String> {
-        let host = format!(
-            "{}.cos.{}.myqcloud.com",
-            self.config.bucket, self.config.region
-        );
+        let host = self.host();
         let url = format!("https://{}/{}", host, object_key);
 
         let now = Utc::now();
@@ -406,6 +985,50 @@ impl CosClient {
     }
 }
 
+/// 识别结束后删除上传到COS的音频，配置了保留上传文件时跳过；删除失败只记录警告
+pub async fn delete_recognition_upload(config: &CosConfig, file_url: &str) {
+    if config.keep_uploads {
+        return;
+    }
+    let Some(object_key) = extract_object_key_from_url(file_url) else {
+        warn!(
+            "无法从上传地址解析对象键，跳过删除: {}",
+            crate::logging::redact_url(file_url)
+        );
+        return;
+    };
+    match CosClient::new(config.clone())
+        .delete_file(&object_key)
+        .await
+    {
+        Ok(()) => info!("已删除COS上的识别音频: {}", object_key),
+        Err(e) => warn!("删除COS上的识别音频失败: {}", e),
+    }
+}
+
+/// 规范化端点配置：去掉协议前缀、路径和首尾的点
+fn normalize_endpoint(endpoint: &str) -> &str {
+    let endpoint = endpoint.trim();
+    let endpoint = endpoint
+        .strip_prefix("https://")
+        .or_else(|| endpoint.strip_prefix("http://"))
+        .unwrap_or(endpoint);
+    endpoint
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .trim_matches('.')
+}
+
+/// 读取XML响应中第一个指定标签的文本
+fn xml_tag_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim().to_string())
+}
+
 /// 从文件名提取对象键
 pub fn extract_object_key_from_url(url: &str) -> Option<String> {
     if let Ok(parsed_url) = url::Url::parse(url) {
@@ -419,3 +1042,82 @@ pub fn extract_object_key_from_url(url: &str) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(endpoint: Option<&str>) -> CosConfig {
+        CosConfig {
+            secret_id: "AKIDtest".to_string(),
+            secret_key: "secret".to_string(),
+            bucket: "media-1250000000".to_string(),
+            region: "ap-guangzhou".to_string(),
+            domain: Some("cdn.example.com".to_string()),
+            storage_class: None,
+            acl: None,
+            keep_uploads: false,
+            upload_retries: None,
+            endpoint: endpoint.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn signed_host_matches_custom_endpoint() {
+        let client = CosClient::new(config(Some("https://cos.accelerate.myqcloud.com/")));
+        let request = client
+            .signed_request(reqwest::Method::GET, "audio/a.wav", &[], Vec::new())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let host = "media-1250000000.cos.accelerate.myqcloud.com";
+        assert_eq!(request.url().host_str(), Some(host));
+        assert_eq!(request.headers()["Host"], host);
+
+        // 用请求实际携带的Host和Date重新计算签名，应与Authorization一致
+        let authorization = request.headers()["Authorization"].to_str().unwrap();
+        assert!(authorization.contains("q-header-list=date;host"));
+        let timestamp: i64 = authorization
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("q-sign-time="))
+            .and_then(|time| time.split(';').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let headers: HashMap<String, String> = [
+            ("Host".to_string(), host.to_string()),
+            (
+                "Date".to_string(),
+                request.headers()["Date"].to_str().unwrap().to_string(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let expected = client
+            .generate_authorization("get", "audio/a.wav", &headers, timestamp)
+            .unwrap();
+        assert_eq!(authorization, expected);
+
+        // 自定义访问域名只影响返回的URL，不影响签名用的Host
+        assert_eq!(
+            client.access_url("audio/a.wav", String::new()),
+            "https://cdn.example.com/audio/a.wav"
+        );
+    }
+
+    #[test]
+    fn default_host_uses_region() {
+        let client = CosClient::new(config(None));
+        assert_eq!(
+            client.host(),
+            "media-1250000000.cos.ap-guangzhou.myqcloud.com"
+        );
+
+        let client = CosClient::new(config(Some("  ")));
+        assert_eq!(
+            client.host(),
+            "media-1250000000.cos.ap-guangzhou.myqcloud.com"
+        );
+    }
+}
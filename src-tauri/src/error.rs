@@ -0,0 +1,103 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// 命令返回给前端的错误
+///
+/// 序列化为带 `kind` 的对象，前端可按类型给出对应的提示和操作，`message` 为中文说明：
+/// `{ "kind": "fileTooLarge", "sizeMb": 12.5, "limitMb": 5.0, "message": "..." }`
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FlowError {
+    /// 本地文件不存在
+    #[error("文件不存在: {path}")]
+    FileNotFound { path: String },
+    /// API密钥未配置或缺少必填字段（`field` 为空表示整个密钥未配置）
+    #[error("{}", api_key_missing_message(provider, field.as_deref()))]
+    ApiKeyMissing {
+        provider: String,
+        field: Option<String>,
+    },
+    /// 文件超过识别服务的大小限制
+    #[error("文件过大（{size_mb:.1} MB），超过{limit_mb:.0} MB的限制")]
+    FileTooLarge { size_mb: f64, limit_mb: f64 },
+    /// 不支持的识别引擎
+    #[error("不支持的识别引擎: {engine}")]
+    EngineUnsupported { engine: String },
+    /// 网络请求失败（连接错误、超时等）
+    #[error("{0}")]
+    Network(String),
+    /// FFmpeg/FFprobe执行失败
+    #[error("{0}")]
+    Ffmpeg(String),
+    /// 其他错误
+    #[error("{0}")]
+    Other(String),
+}
+
+fn api_key_missing_message(provider: &str, field: Option<&str>) -> String {
+    match field {
+        Some(field) => format!("{}密钥缺少字段: {}", provider, field),
+        None => format!("{}密钥未配置", provider),
+    }
+}
+
+impl FlowError {
+    /// 错误类型标识（序列化后的 `kind` 字段）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FlowError::FileNotFound { .. } => "fileNotFound",
+            FlowError::ApiKeyMissing { .. } => "apiKeyMissing",
+            FlowError::FileTooLarge { .. } => "fileTooLarge",
+            FlowError::EngineUnsupported { .. } => "engineUnsupported",
+            FlowError::Network(_) => "network",
+            FlowError::Ffmpeg(_) => "ffmpeg",
+            FlowError::Other(_) => "other",
+        }
+    }
+}
+
+impl Serialize for FlowError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        match self {
+            FlowError::FileNotFound { path } => map.serialize_entry("path", path)?,
+            FlowError::ApiKeyMissing { provider, field } => {
+                map.serialize_entry("provider", provider)?;
+                map.serialize_entry("field", field)?;
+            }
+            FlowError::FileTooLarge { size_mb, limit_mb } => {
+                map.serialize_entry("sizeMb", size_mb)?;
+                map.serialize_entry("limitMb", limit_mb)?;
+            }
+            FlowError::EngineUnsupported { engine } => map.serialize_entry("engine", engine)?,
+            FlowError::Network(_) | FlowError::Ffmpeg(_) | FlowError::Other(_) => {}
+        }
+        map.serialize_entry("message", &self.to_string())?;
+        map.end()
+    }
+}
+
+/// 尚未分类的字符串错误归为 `Other`
+impl From<String> for FlowError {
+    fn from(message: String) -> Self {
+        FlowError::Other(message)
+    }
+}
+
+impl From<&str> for FlowError {
+    fn from(message: &str) -> Self {
+        FlowError::Other(message.to_string())
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for FlowError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        FlowError::Other(error.to_string())
+    }
+}
+
+/// 内部仍返回字符串错误的函数可以直接用 `?` 传递结构化错误
+impl From<FlowError> for String {
+    fn from(error: FlowError) -> Self {
+        error.to_string()
+    }
+}
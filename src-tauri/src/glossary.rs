@@ -0,0 +1,169 @@
+use crate::video::Subtitle;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 术语表的文件名（位于应用配置目录）
+const GLOSSARY_FILE: &str = "glossary.json";
+
+/// 术语表条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    /// 识别结果中要查找的写法（区分大小写）
+    pub term: String,
+    /// 统一替换成的写法，为空时保持原写法
+    #[serde(default)]
+    pub preferred: String,
+    /// 翻译时保持原样（如产品名、单位）
+    #[serde(default)]
+    pub protected: bool,
+}
+
+lazy_static::lazy_static! {
+    /// 已加载的术语表（None表示尚未从配置文件读取）
+    static ref GLOSSARY: Mutex<Option<Vec<GlossaryEntry>>> = Mutex::new(None);
+}
+
+/// 读取术语表，首次调用时从配置文件加载
+pub fn get_glossary() -> Result<Vec<GlossaryEntry>, String> {
+    let mut glossary = GLOSSARY
+        .lock()
+        .map_err(|_| "无法获取术语表锁".to_string())?;
+    if let Some(entries) = glossary.as_ref() {
+        return Ok(entries.clone());
+    }
+
+    let path = crate::recognition::config_file_path(GLOSSARY_FILE)?;
+    let entries: Vec<GlossaryEntry> = if path.exists() {
+        let content =
+            std::fs::read_to_string(&path).map_err(|e| format!("读取术语表失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析术语表失败: {}", e))?
+    } else {
+        Vec::new()
+    };
+
+    *glossary = Some(entries.clone());
+    Ok(entries)
+}
+
+/// 保存术语表，返回规范化后的条目
+///
+/// 去除首尾空白和空条目，重复的术语保留最后一条；按术语长度降序排列，
+/// 使较长的术语优先匹配（如 "kWh" 先于 "W"）。
+pub fn set_glossary(entries: Vec<GlossaryEntry>) -> Result<Vec<GlossaryEntry>, String> {
+    let mut normalized: Vec<GlossaryEntry> = Vec::new();
+    for entry in entries {
+        let term = entry.term.trim().to_string();
+        if term.is_empty() {
+            continue;
+        }
+        let preferred = match entry.preferred.trim() {
+            "" => term.clone(),
+            preferred => preferred.to_string(),
+        };
+        normalized.retain(|existing| existing.term != term);
+        normalized.push(GlossaryEntry {
+            term,
+            preferred,
+            protected: entry.protected,
+        });
+    }
+    normalized.sort_by_key(|entry| std::cmp::Reverse(entry.term.chars().count()));
+
+    let path = crate::recognition::config_file_path(GLOSSARY_FILE)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&normalized)
+        .map_err(|e| format!("序列化术语表失败: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("保存术语表失败: {}", e))?;
+
+    *GLOSSARY
+        .lock()
+        .map_err(|_| "无法获取术语表锁".to_string())? = Some(normalized.clone());
+
+    info!("术语表已保存: {}，共{}条", path.display(), normalized.len());
+    Ok(normalized)
+}
+
+/// 按术语生成一次性匹配的正则（长术语在前），术语表为空时返回None
+fn terms_regex<'a>(terms: impl Iterator<Item = &'a str>) -> Option<regex::Regex> {
+    let mut terms: Vec<&str> = terms.filter(|t| !t.is_empty()).collect();
+    if terms.is_empty() {
+        return None;
+    }
+    terms.sort_by_key(|t| std::cmp::Reverse(t.chars().count()));
+    let pattern = terms
+        .iter()
+        .map(|t| regex::escape(t))
+        .collect::<Vec<_>>()
+        .join("|");
+    regex::Regex::new(&pattern).ok()
+}
+
+/// 将字幕中的术语统一替换为术语表中的写法（区分大小写，单次扫描不会连锁替换）
+pub fn apply_glossary(subtitles: Vec<Subtitle>) -> Vec<Subtitle> {
+    let entries = match get_glossary() {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("读取术语表失败，跳过术语替换: {}", e);
+            return subtitles;
+        }
+    };
+    let Some(re) = terms_regex(entries.iter().map(|e| e.term.as_str())) else {
+        return subtitles;
+    };
+
+    subtitles
+        .into_iter()
+        .map(|mut subtitle| {
+            subtitle.text = re
+                .replace_all(&subtitle.text, |caps: &regex::Captures| {
+                    let matched = &caps[0];
+                    entries
+                        .iter()
+                        .find(|e| e.term == matched)
+                        .map(|e| e.preferred.clone())
+                        .unwrap_or_else(|| matched.to_string())
+                })
+                .to_string();
+            subtitle
+        })
+        .collect()
+}
+
+/// 翻译前保护术语：将受保护的术语替换为占位符，返回处理后的文本和按序号对应的原文
+pub fn protect_terms(text: &str) -> (String, Vec<String>) {
+    let entries = get_glossary().unwrap_or_default();
+    let protected = entries
+        .iter()
+        .filter(|e| e.protected)
+        .map(|e| e.preferred.as_str());
+    let Some(re) = terms_regex(protected) else {
+        return (text.to_string(), Vec::new());
+    };
+
+    let mut originals: Vec<String> = Vec::new();
+    let masked = re
+        .replace_all(text, |caps: &regex::Captures| {
+            originals.push(caps[0].to_string());
+            placeholder(originals.len() - 1)
+        })
+        .to_string();
+    (masked, originals)
+}
+
+/// 翻译后还原占位符为受保护的术语
+pub fn restore_terms(text: &str, originals: &[String]) -> String {
+    originals
+        .iter()
+        .enumerate()
+        .fold(text.to_string(), |restored, (index, original)| {
+            restored.replace(&placeholder(index), original)
+        })
+}
+
+/// 术语占位符（不含可翻译的词，机器翻译通常原样保留）
+fn placeholder(index: usize) -> String {
+    format!("__GT{}__", index)
+}
@@ -0,0 +1,174 @@
+use crate::video::Subtitle;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// 编辑历史最多保留的快照数
+const MAX_HISTORY: usize = 50;
+
+/// 字幕编辑历史：按时间顺序保存快照，`cursor` 指向当前状态
+///
+/// 撤销/重做只移动游标；在历史中间记录新编辑时丢弃游标之后的快照。
+#[derive(Default)]
+struct EditHistory {
+    snapshots: VecDeque<Vec<Subtitle>>,
+    cursor: usize,
+}
+
+impl EditHistory {
+    fn current(&self) -> Option<&Vec<Subtitle>> {
+        self.snapshots.get(self.cursor)
+    }
+
+    /// 记录一个新状态，与当前状态相同时忽略
+    fn record(&mut self, subtitles: &[Subtitle]) {
+        if self.current().is_some_and(|current| current == subtitles) {
+            return;
+        }
+
+        if !self.snapshots.is_empty() {
+            self.snapshots.truncate(self.cursor + 1);
+        }
+        self.snapshots.push_back(subtitles.to_vec());
+        while self.snapshots.len() > MAX_HISTORY {
+            self.snapshots.pop_front();
+        }
+        self.cursor = self.snapshots.len() - 1;
+    }
+
+    /// 游标后退一步，已在最早状态时返回None
+    fn undo(&mut self) -> Option<&Vec<Subtitle>> {
+        if self.cursor == 0 || self.snapshots.is_empty() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.current()
+    }
+
+    /// 游标前进一步，已在最新状态时返回None
+    fn redo(&mut self) -> Option<&Vec<Subtitle>> {
+        if self.cursor + 1 >= self.snapshots.len() {
+            return None;
+        }
+        self.cursor += 1;
+        self.current()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref HISTORY: Mutex<EditHistory> = Mutex::new(EditHistory::default());
+}
+
+/// 清空编辑历史并以给定字幕作为初始状态（载入新字幕时调用）
+pub fn reset_history(subtitles: &[Subtitle]) -> Result<(), String> {
+    let mut history = HISTORY
+        .lock()
+        .map_err(|_| "无法获取编辑历史锁".to_string())?;
+    *history = EditHistory::default();
+    history.record(subtitles);
+    Ok(())
+}
+
+/// 记录一次编辑后的字幕状态
+pub fn record_edit(subtitles: &[Subtitle]) -> Result<(), String> {
+    HISTORY
+        .lock()
+        .map_err(|_| "无法获取编辑历史锁".to_string())?
+        .record(subtitles);
+    Ok(())
+}
+
+/// 记录一次修改：编辑前的状态不在历史中时先补记，再记录修改后的状态
+pub fn record_change(before: &[Subtitle], after: &[Subtitle]) {
+    if let Ok(mut history) = HISTORY.lock() {
+        history.record(before);
+        history.record(after);
+    }
+}
+
+/// 撤销，返回上一个状态的字幕
+pub fn undo() -> Result<Vec<Subtitle>, String> {
+    HISTORY
+        .lock()
+        .map_err(|_| "无法获取编辑历史锁".to_string())?
+        .undo()
+        .cloned()
+        .ok_or_else(|| "没有可撤销的操作".to_string())
+}
+
+/// 重做，返回下一个状态的字幕
+pub fn redo() -> Result<Vec<Subtitle>, String> {
+    HISTORY
+        .lock()
+        .map_err(|_| "无法获取编辑历史锁".to_string())?
+        .redo()
+        .cloned()
+        .ok_or_else(|| "没有可重做的操作".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 只含一条字幕的状态，用文本区分
+    fn state(text: &str) -> Vec<Subtitle> {
+        vec![Subtitle {
+            id: "1".to_string(),
+            start_time: 0.0,
+            end_time: 1.0,
+            text: text.to_string(),
+            position: None,
+            words: None,
+        }]
+    }
+
+    #[test]
+    fn undo_stops_at_the_first_state() {
+        let mut history = EditHistory::default();
+        assert!(history.undo().is_none());
+
+        history.record(&state("a"));
+        history.record(&state("b"));
+        assert_eq!(history.undo(), Some(&state("a")));
+        assert!(history.undo().is_none());
+        assert_eq!(history.current(), Some(&state("a")));
+        assert_eq!(history.redo(), Some(&state("b")));
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn new_edit_after_undo_drops_redo_tail() {
+        let mut history = EditHistory::default();
+        history.record(&state("a"));
+        history.record(&state("b"));
+        history.record(&state("c"));
+        history.undo();
+        history.undo();
+
+        history.record(&state("d"));
+        assert!(history.redo().is_none());
+        assert_eq!(history.snapshots.len(), 2);
+        assert_eq!(history.undo(), Some(&state("a")));
+        assert_eq!(history.redo(), Some(&state("d")));
+    }
+
+    #[test]
+    fn oldest_snapshots_are_evicted_beyond_max_history() {
+        let mut history = EditHistory::default();
+        for i in 0..MAX_HISTORY + 5 {
+            history.record(&state(&i.to_string()));
+        }
+        assert_eq!(history.snapshots.len(), MAX_HISTORY);
+        assert_eq!(
+            history.current(),
+            Some(&state(&(MAX_HISTORY + 4).to_string()))
+        );
+
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_HISTORY - 1);
+        // 最早的5个状态已被淘汰
+        assert_eq!(history.current(), Some(&state("5")));
+    }
+}
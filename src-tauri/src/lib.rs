@@ -1,19 +1,87 @@
+mod aliyun;
+mod autosave;
+mod aws;
+mod cache;
 mod cos;
+mod error;
+mod glossary;
+mod history;
+mod logging;
+mod profile;
 mod recognition;
 mod video;
 
+use error::FlowError;
 use recognition::*;
+use tauri::Manager;
 use video::*;
 
 // 视频处理命令
 #[tauri::command]
-async fn get_video_info(file_path: String) -> Result<VideoInfo, String> {
-    video::get_video_info(&file_path).map_err(|e| e.to_string())
+async fn get_video_info(file_path: String) -> Result<VideoInfo, FlowError> {
+    video::get_video_info(&file_path)
 }
 
 #[tauri::command]
-async fn extract_audio(video_path: String, audio_track_id: u32) -> Result<String, String> {
-    video::extract_audio(&video_path, audio_track_id).map_err(|e| e.to_string())
+async fn extract_audio(
+    video_path: String,
+    audio_track_id: u32,
+    beside_video: Option<bool>,
+) -> Result<String, FlowError> {
+    video::extract_audio_with_format(
+        &video_path,
+        audio_track_id,
+        &AudioFormat::default(),
+        beside_video.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+async fn extract_audio_with_format(
+    video_path: String,
+    audio_track_id: u32,
+    sample_rate: Option<u32>,
+    channels: Option<u32>,
+    codec: Option<String>,
+    beside_video: Option<bool>,
+) -> Result<String, FlowError> {
+    let default = AudioFormat::default();
+    let format = AudioFormat {
+        sample_rate: sample_rate.unwrap_or(default.sample_rate),
+        channels: channels.unwrap_or(default.channels),
+        codec: codec.unwrap_or(default.codec),
+    };
+    video::extract_audio_with_format(
+        &video_path,
+        audio_track_id,
+        &format,
+        beside_video.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+async fn cleanup_extracted_audio(path: String) -> Result<(), FlowError> {
+    video::cleanup_extracted_audio(&path).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn cleanup_temp_files(max_age_secs: Option<u64>) -> Result<usize, FlowError> {
+    video::cleanup_temp_files(max_age_secs.unwrap_or(video::DEFAULT_TEMP_FILE_MAX_AGE_SECS))
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn generate_waveform(
+    video_path: String,
+    audio_track_id: u32,
+    samples_per_second: Option<u32>,
+) -> Result<Vec<f32>, FlowError> {
+    video::generate_waveform(
+        &video_path,
+        audio_track_id,
+        samples_per_second.unwrap_or(video::DEFAULT_WAVEFORM_SAMPLES_PER_SECOND),
+    )
+    .map_err(FlowError::from)
 }
 
 // 字幕处理命令
@@ -22,8 +90,10 @@ async fn export_subtitles(
     subtitles: Vec<Subtitle>,
     format: String,
     file_name: String,
-) -> Result<String, String> {
-    video::export_subtitles(&subtitles, &format, &file_name).map_err(|e| e.to_string())
+    options: Option<ExportOptions>,
+) -> Result<String, FlowError> {
+    let options = options.unwrap_or_default();
+    video::export_subtitles(&subtitles, &format, &file_name, &options).map_err(FlowError::from)
 }
 
 #[tauri::command]
@@ -32,24 +102,361 @@ async fn export_subtitles_to_path(
     format: String,
     file_name: String,
     export_path: String,
-) -> Result<String, String> {
-    video::export_subtitles_to_path(&subtitles, &format, &file_name, &export_path)
-        .map_err(|e| e.to_string())
+    options: Option<ExportOptions>,
+) -> Result<String, FlowError> {
+    let options = options.unwrap_or_default();
+    video::export_subtitles_to_path(&subtitles, &format, &file_name, &export_path, &options)
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn open_folder(path: String) -> Result<(), FlowError> {
+    video::open_folder(&path).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_default_export_path() -> Result<String, FlowError> {
+    video::get_default_export_path().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn import_subtitles(
+    file_path: String,
+    fps: Option<f64>,
+    normalize: Option<bool>,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let subtitles = video::import_subtitles(&file_path, fps).map_err(FlowError::from)?;
+    if normalize.unwrap_or(false) {
+        return Ok(video::normalize_subtitles(&subtitles).subtitles);
+    }
+    Ok(subtitles)
+}
+
+#[tauri::command]
+async fn align_to_speech(
+    subtitles: Vec<Subtitle>,
+    audio_path: String,
+    rebase_to: Option<f64>,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let aligned =
+        video::align_to_speech(&subtitles, &audio_path, rebase_to).map_err(FlowError::from)?;
+    history::record_change(&subtitles, &aligned);
+    Ok(aligned)
+}
+
+#[tauri::command]
+async fn find_replace(
+    subtitles: Vec<Subtitle>,
+    pattern: String,
+    replacement: String,
+    use_regex: bool,
+    case_insensitive: bool,
+) -> Result<(Vec<Subtitle>, usize), FlowError> {
+    let (updated, count) = video::find_replace(
+        &subtitles,
+        &pattern,
+        &replacement,
+        use_regex,
+        case_insensitive,
+    )
+    .map_err(FlowError::from)?;
+    history::record_change(&subtitles, &updated);
+    Ok((updated, count))
+}
+
+// 编辑历史命令
+#[tauri::command]
+async fn reset_edit_history(subtitles: Vec<Subtitle>) -> Result<(), FlowError> {
+    history::reset_history(&subtitles).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn record_edit(subtitles: Vec<Subtitle>) -> Result<(), FlowError> {
+    history::record_edit(&subtitles).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn undo() -> Result<Vec<Subtitle>, FlowError> {
+    history::undo().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn redo() -> Result<Vec<Subtitle>, FlowError> {
+    history::redo().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn export_chapters(
+    subtitles: Vec<Subtitle>,
+    gap_threshold: f64,
+    output_path: String,
+    video_path: Option<String>,
+) -> Result<String, FlowError> {
+    video::export_chapters_to_path(
+        &subtitles,
+        gap_threshold,
+        &output_path,
+        video_path.as_deref(),
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn detect_scene_changes(
+    video_path: String,
+    threshold: Option<f64>,
+) -> Result<Vec<f64>, FlowError> {
+    video::detect_scene_changes(
+        &video_path,
+        threshold.unwrap_or(video::DEFAULT_SCENE_THRESHOLD),
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn mux_subtitles(
+    video_path: String,
+    subtitles: Vec<Subtitle>,
+    output_path: String,
+    lang_code: Option<String>,
+    subtitle_codec: Option<String>,
+) -> Result<String, FlowError> {
+    video::mux_subtitles(
+        &video_path,
+        &subtitles,
+        &output_path,
+        lang_code.as_deref(),
+        subtitle_codec.as_deref(),
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn burn_subtitles(
+    video_path: String,
+    subtitles: Vec<Subtitle>,
+    output_path: String,
+    style: Option<BurnStyle>,
+) -> Result<String, FlowError> {
+    video::burn_subtitles(
+        &video_path,
+        &subtitles,
+        &output_path,
+        &style.unwrap_or_default(),
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn extract_thumbnail(
+    video_path: String,
+    time_seconds: f64,
+    output_path: String,
+) -> Result<String, FlowError> {
+    video::extract_thumbnail(&video_path, time_seconds, &output_path).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn enforce_min_duration(
+    subtitles: Vec<Subtitle>,
+    min_duration: f64,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let updated = video::enforce_min_duration(&subtitles, min_duration).map_err(FlowError::from)?;
+    history::record_change(&subtitles, &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn clamp_to_duration(
+    subtitles: Vec<Subtitle>,
+    duration: f64,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let updated = video::clamp_to_duration(&subtitles, duration);
+    history::record_change(&subtitles, &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn shift_subtitles(
+    subtitles: Vec<Subtitle>,
+    offset_seconds: f64,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let updated = video::shift_subtitles(&subtitles, offset_seconds).map_err(FlowError::from)?;
+    history::record_change(&subtitles, &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn split_long_subtitles(
+    subtitles: Vec<Subtitle>,
+    max_chars: usize,
+    max_lines: usize,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let updated =
+        video::split_long_subtitles(&subtitles, max_chars, max_lines).map_err(FlowError::from)?;
+    history::record_change(&subtitles, &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn normalize_subtitles(subtitles: Vec<Subtitle>) -> Result<NormalizeResult, FlowError> {
+    let result = video::normalize_subtitles(&subtitles);
+    history::record_change(&subtitles, &result.subtitles);
+    Ok(result)
+}
+
+#[tauri::command]
+async fn merge_subtitles(
+    subtitles: Vec<Subtitle>,
+    max_gap_seconds: f64,
+    max_merged_chars: usize,
+) -> Result<Vec<Subtitle>, FlowError> {
+    let updated = video::merge_subtitles(&subtitles, max_gap_seconds, max_merged_chars)
+        .map_err(FlowError::from)?;
+    history::record_change(&subtitles, &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn get_export_profiles() -> Result<Vec<profile::ExportProfile>, FlowError> {
+    Ok(profile::builtin_profiles())
+}
+
+#[tauri::command]
+async fn export_with_profile(
+    subtitles: Vec<Subtitle>,
+    profile: profile::ExportProfile,
+    output_path: String,
+) -> Result<profile::ProfileExportReport, FlowError> {
+    profile::export_with_profile(&subtitles, &profile, &output_path).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn check_line_lengths(
+    subtitles: Vec<Subtitle>,
+    limits: std::collections::HashMap<String, usize>,
+) -> Result<Vec<LineLengthIssue>, FlowError> {
+    Ok(video::check_line_lengths(&subtitles, &limits))
+}
+
+#[tauri::command]
+async fn diff_subtitles(
+    before: Vec<Subtitle>,
+    after: Vec<Subtitle>,
+) -> Result<Vec<SubtitleDiff>, FlowError> {
+    Ok(video::diff_subtitles(&before, &after))
+}
+
+#[tauri::command]
+async fn split_at_times(
+    video_path: String,
+    subtitles: Vec<Subtitle>,
+    times: Vec<f64>,
+    output_dir: String,
+) -> Result<Vec<(String, String)>, FlowError> {
+    video::split_at_times(&video_path, &subtitles, &times, &output_dir).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn locate_ffmpeg() -> Result<FfmpegPaths, FlowError> {
+    Ok(video::locate_ffmpeg())
+}
+
+#[tauri::command]
+async fn set_ffmpeg_path(
+    ffmpeg: Option<String>,
+    ffprobe: Option<String>,
+) -> Result<FfmpegPaths, FlowError> {
+    video::set_ffmpeg_path(ffmpeg.as_deref(), ffprobe.as_deref()).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_process_timeouts() -> Result<ProcessTimeouts, FlowError> {
+    Ok(video::get_process_timeouts())
+}
+
+#[tauri::command]
+async fn set_process_timeouts(
+    probe_secs: Option<u64>,
+    extract_secs: Option<u64>,
+) -> Result<ProcessTimeouts, FlowError> {
+    video::set_process_timeouts(probe_secs, extract_secs).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn open_folder(path: String) -> Result<(), String> {
-    video::open_folder(&path).map_err(|e| e.to_string())
+async fn get_keep_empty_cues() -> Result<bool, FlowError> {
+    Ok(recognition::get_keep_empty_cues())
 }
 
 #[tauri::command]
-async fn get_default_export_path() -> Result<String, String> {
-    video::get_default_export_path().map_err(|e| e.to_string())
+async fn set_keep_empty_cues(keep: bool) -> Result<(), FlowError> {
+    recognition::set_keep_empty_cues(keep).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn import_subtitles(file_path: String) -> Result<Vec<Subtitle>, String> {
-    video::import_subtitles(&file_path).map_err(|e| e.to_string())
+async fn set_task_ttl(secs: u64) -> Result<(), FlowError> {
+    recognition::set_task_ttl(secs).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_max_concurrent_tasks() -> Result<usize, FlowError> {
+    Ok(recognition::get_max_concurrent_tasks())
+}
+
+#[tauri::command]
+async fn set_max_concurrent_tasks(max: usize) -> Result<(), FlowError> {
+    recognition::set_max_concurrent_tasks(max).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn clear_finished_tasks() -> Result<usize, FlowError> {
+    recognition::clear_finished_tasks().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn list_recognition_tasks() -> Result<Vec<recognition::RecognitionTaskSummary>, FlowError> {
+    recognition::list_recognition_tasks().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_task_memory_report() -> Result<recognition::TaskMemoryReport, FlowError> {
+    recognition::get_task_memory_report().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn set_log_level(level: String) -> Result<(), FlowError> {
+    logging::set_log_level(&level).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn set_file_logging(enabled: bool) -> Result<Option<String>, FlowError> {
+    logging::set_file_logging(enabled).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_supported_formats() -> Result<SupportedFormats, FlowError> {
+    Ok(video::get_supported_formats())
+}
+
+// 自动保存命令
+#[tauri::command]
+async fn enable_autosave(path: String, interval_secs: u64) -> Result<String, FlowError> {
+    autosave::enable_autosave(&path, interval_secs).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn update_autosave(subtitles: Vec<Subtitle>) -> Result<(), FlowError> {
+    autosave::update_autosave(subtitles).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn disable_autosave() -> Result<(), FlowError> {
+    autosave::disable_autosave().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn recover_autosave(path: String) -> Result<Vec<Subtitle>, FlowError> {
+    autosave::recover_autosave(&path).map_err(FlowError::from)
 }
 
 // 语音识别命令
@@ -57,32 +464,162 @@ async fn import_subtitles(file_path: String) -> Result<Vec<Subtitle>, String> {
 async fn start_recognition(
     task_id: String,
     audio_path: String,
+    options: Option<StartRecognitionOptions>,
+) -> Result<(), FlowError> {
+    recognition::start_recognition(task_id, &audio_path, options.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn recognize_video(
+    task_id: String,
+    video_path: String,
+    audio_track_id: u32,
+    engine: Option<String>,
+    language: Option<String>,
+    api_keys: Option<serde_json::Value>,
+) -> Result<(), FlowError> {
+    let audio_path = video::extract_audio(&video_path, audio_track_id)?;
+    // 先登记再启动：命中缓存的任务可能立即结束
+    recognition::delete_audio_when_finished(&task_id, &audio_path);
+    recognition::start_recognition(
+        task_id.clone(),
+        &audio_path,
+        StartRecognitionOptions {
+            engine,
+            language,
+            api_keys,
+            ..Default::default()
+        },
+    )
+    .inspect_err(|_| recognition::remove_temp_audio(&task_id))
+}
+
+#[tauri::command]
+async fn start_batch_recognition(
+    batch_id: String,
+    files: Vec<recognition::BatchFile>,
+    engine: Option<String>,
+    language: Option<String>,
+    api_keys: Option<serde_json::Value>,
+) -> Result<(), FlowError> {
+    let (engine, language) = recognition::resolve_engine_and_language(engine, language);
+    recognition::start_batch_recognition(batch_id, files, &engine, &language, api_keys)
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_batch_status(batch_id: String) -> Result<recognition::BatchStatus, FlowError> {
+    recognition::get_batch_status(&batch_id).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn transcribe_url(
+    task_id: String,
+    url: String,
+    engine: Option<String>,
+    language: Option<String>,
+    api_keys: Option<serde_json::Value>,
+) -> Result<String, FlowError> {
+    let (engine, language) = recognition::resolve_engine_and_language(engine, language);
+    recognition::transcribe_url(task_id, &url, &engine, &language, api_keys)
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn transcribe_and_translate(
+    task_id: String,
+    video_path: String,
+    engine: String,
+    source_lang: String,
+    target_lang: String,
+    api_keys: Option<serde_json::Value>,
+) -> Result<String, FlowError> {
+    recognition::transcribe_and_translate(
+        task_id,
+        &video_path,
+        &engine,
+        &source_lang,
+        &target_lang,
+        api_keys,
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn preview_recognition(
+    video_path: String,
+    seconds: f64,
     engine: String,
     language: String,
     api_keys: Option<serde_json::Value>,
-) -> Result<(), String> {
-    recognition::start_recognition(task_id, &audio_path, &engine, &language, api_keys)
-        .map_err(|e| e.to_string())
+) -> Result<Vec<Subtitle>, FlowError> {
+    recognition::preview_recognition(&video_path, seconds, &engine, &language, api_keys)
+        .await
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn estimate_recognition(
+    video_path: String,
+    engine: String,
+    model: Option<String>,
+) -> Result<recognition::RecognitionEstimate, FlowError> {
+    recognition::estimate_recognition(&video_path, &engine, model.as_deref())
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn start_live_recognition(
+    task_id: String,
+    source: Option<String>,
+    engine: Option<String>,
+    language: Option<String>,
+    api_keys: Option<serde_json::Value>,
+    window_seconds: Option<f64>,
+) -> Result<(), FlowError> {
+    let (engine, language) = recognition::resolve_engine_and_language(engine, language);
+    recognition::start_live_recognition(
+        task_id,
+        source,
+        &engine,
+        &language,
+        api_keys,
+        window_seconds,
+    )
+    .map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn get_recognition_status(task_id: String) -> Result<RecognitionStatus, String> {
-    recognition::get_recognition_status(&task_id).map_err(|e| e.to_string())
+async fn stop_live_recognition(task_id: String) -> Result<(), FlowError> {
+    recognition::stop_live_recognition(&task_id).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn cancel_recognition(task_id: String) -> Result<(), String> {
-    recognition::cancel_recognition(&task_id).map_err(|e| e.to_string())
+async fn retry_recognition(task_id: String) -> Result<String, FlowError> {
+    recognition::retry_recognition(&task_id).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn get_supported_languages(engine: String) -> Result<Vec<Language>, String> {
-    recognition::get_supported_languages(&engine).map_err(|e| e.to_string())
+async fn get_recognition_status(task_id: String) -> Result<RecognitionStatus, FlowError> {
+    recognition::get_recognition_status(&task_id).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn validate_api_keys(engine: String, api_keys: serde_json::Value) -> Result<bool, String> {
-    recognition::validate_api_keys(&engine, api_keys).map_err(|e| e.to_string())
+async fn cancel_recognition(task_id: String) -> Result<(), FlowError> {
+    recognition::cancel_recognition(&task_id).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_supported_languages(engine: String) -> Result<Vec<Language>, FlowError> {
+    recognition::get_supported_languages(&engine)
+}
+
+#[tauri::command]
+async fn validate_api_keys(
+    engine: String,
+    api_keys: serde_json::Value,
+) -> Result<ApiKeyValidation, FlowError> {
+    recognition::validate_api_keys(&engine, api_keys).await
 }
 
 // 扩展的语音识别命令
@@ -90,59 +627,229 @@ async fn validate_api_keys(engine: String, api_keys: serde_json::Value) -> Resul
 async fn start_recognition_with_config(
     task_id: String,
     params: ExtendedRecognitionParams,
-) -> Result<(), String> {
-    recognition::start_recognition_with_config(task_id, params).map_err(|e| e.to_string())
+    api_keys: Option<serde_json::Value>,
+    dedupe: Option<bool>,
+    bypass_cache: Option<bool>,
+) -> Result<(), FlowError> {
+    recognition::start_recognition_with_config(
+        task_id,
+        params,
+        api_keys,
+        dedupe.unwrap_or(false),
+        bypass_cache.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+async fn test_cos_config(config: serde_json::Value) -> Result<(), FlowError> {
+    recognition::test_cos_config(config)
+        .await
+        .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_cos_regions() -> Result<Vec<cos::CosRegion>, FlowError> {
+    Ok(cos::get_cos_regions())
+}
+
+#[tauri::command]
+async fn clear_recognition_cache() -> Result<usize, FlowError> {
+    cache::clear_recognition_cache().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_glossary() -> Result<Vec<glossary::GlossaryEntry>, FlowError> {
+    glossary::get_glossary().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn set_glossary(
+    entries: Vec<glossary::GlossaryEntry>,
+) -> Result<Vec<glossary::GlossaryEntry>, FlowError> {
+    glossary::set_glossary(entries).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_recognition_defaults() -> Result<RecognitionDefaults, FlowError> {
+    recognition::get_recognition_defaults().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn set_recognition_defaults(
+    defaults: RecognitionDefaults,
+) -> Result<RecognitionDefaults, FlowError> {
+    recognition::set_recognition_defaults(defaults).map_err(FlowError::from)
 }
 
 // 模型管理命令
 #[tauri::command]
-async fn get_available_models() -> Result<Vec<serde_json::Value>, String> {
-    recognition::get_available_models().map_err(|e| e.to_string())
+async fn get_available_models() -> Result<Vec<serde_json::Value>, FlowError> {
+    recognition::get_available_models().map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn check_model_installation(engine: String) -> Result<bool, FlowError> {
+    recognition::check_model_installation(&engine).map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn get_model_info(engine: String) -> Result<serde_json::Value, FlowError> {
+    recognition::get_model_info(&engine).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn check_model_installation(engine: String) -> Result<bool, String> {
-    recognition::check_model_installation(&engine).map_err(|e| e.to_string())
+async fn check_model_size_available(engine: String, size: String) -> Result<bool, FlowError> {
+    recognition::check_model_size_available(&engine, &size).map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn get_model_info(engine: String) -> Result<serde_json::Value, String> {
-    recognition::get_model_info(&engine).map_err(|e| e.to_string())
+async fn get_whisper_model_status() -> Result<Vec<WhisperModelStatus>, FlowError> {
+    recognition::get_whisper_model_status().map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn check_model_size_available(engine: String, size: String) -> Result<bool, String> {
-    recognition::check_model_size_available(&engine, &size).map_err(|e| e.to_string())
+async fn get_os_info() -> Result<serde_json::Value, FlowError> {
+    recognition::get_os_info().map_err(FlowError::from)
 }
 
 #[tauri::command]
-async fn get_os_info() -> Result<serde_json::Value, String> {
-    recognition::get_os_info().map_err(|e| e.to_string())
+async fn get_binary_paths() -> Result<BinaryPaths, FlowError> {
+    Ok(recognition::get_binary_paths())
+}
+
+#[tauri::command]
+async fn set_binary_paths(
+    ffmpeg: Option<String>,
+    ffprobe: Option<String>,
+    whisper: Option<String>,
+    python: Option<String>,
+) -> Result<BinaryPaths, FlowError> {
+    recognition::set_binary_paths(
+        ffmpeg.as_deref(),
+        ffprobe.as_deref(),
+        whisper.as_deref(),
+        python.as_deref(),
+    )
+    .map_err(FlowError::from)
+}
+
+#[tauri::command]
+async fn check_dependencies() -> Result<DependencyStatus, FlowError> {
+    Ok(recognition::check_dependencies())
 }
 
 pub fn run() {
+    logging::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            // 记录资源目录，用于查找随应用打包的FFmpeg
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                video::set_resource_dir(resource_dir);
+            }
+            // 记录配置目录，用于保存识别默认配置
+            if let Ok(config_dir) = app.path().app_config_dir() {
+                recognition::set_config_dir(config_dir);
+            }
+            // 识别任务状态变化时向前端推送事件
+            recognition::set_app_handle(app.handle().clone());
+            // 恢复上次运行保存的识别任务
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                recognition::load_persisted_tasks(data_dir);
+            }
+            // 记录日志目录，用于开启文件日志
+            if let Ok(log_dir) = app.path().app_log_dir() {
+                logging::set_log_dir(log_dir);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_video_info,
             extract_audio,
+            extract_audio_with_format,
+            cleanup_extracted_audio,
+            cleanup_temp_files,
+            generate_waveform,
             export_subtitles,
             export_subtitles_to_path,
             open_folder,
             get_default_export_path,
             import_subtitles,
+            get_supported_formats,
+            align_to_speech,
+            find_replace,
+            reset_edit_history,
+            record_edit,
+            undo,
+            redo,
+            enforce_min_duration,
+            clamp_to_duration,
+            shift_subtitles,
+            split_long_subtitles,
+            merge_subtitles,
+            normalize_subtitles,
+            get_export_profiles,
+            export_with_profile,
+            check_line_lengths,
+            diff_subtitles,
+            export_chapters,
+            mux_subtitles,
+            burn_subtitles,
+            extract_thumbnail,
+            split_at_times,
+            detect_scene_changes,
+            locate_ffmpeg,
+            set_ffmpeg_path,
+            get_process_timeouts,
+            set_process_timeouts,
+            get_keep_empty_cues,
+            set_keep_empty_cues,
+            set_task_ttl,
+            clear_finished_tasks,
+            get_max_concurrent_tasks,
+            set_max_concurrent_tasks,
+            get_task_memory_report,
+            list_recognition_tasks,
+            set_log_level,
+            set_file_logging,
+            enable_autosave,
+            update_autosave,
+            disable_autosave,
+            recover_autosave,
             start_recognition,
+            recognize_video,
+            start_batch_recognition,
+            get_batch_status,
             start_recognition_with_config,
+            transcribe_url,
+            preview_recognition,
+            estimate_recognition,
+            transcribe_and_translate,
             get_recognition_status,
             cancel_recognition,
+            retry_recognition,
+            start_live_recognition,
+            stop_live_recognition,
+            get_recognition_defaults,
+            set_recognition_defaults,
+            clear_recognition_cache,
+            get_glossary,
+            set_glossary,
             get_supported_languages,
             validate_api_keys,
+            test_cos_config,
+            get_cos_regions,
             get_available_models,
             check_model_installation,
             get_model_info,
             check_model_size_available,
-            get_os_info
+            get_whisper_model_status,
+            get_os_info,
+            check_dependencies,
+            get_binary_paths,
+            set_binary_paths
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
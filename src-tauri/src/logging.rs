@@ -0,0 +1,134 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// 日志文件名（位于应用日志目录）
+const LOG_FILE: &str = "flowtext.log";
+
+lazy_static::lazy_static! {
+    /// 运行时调整日志级别的句柄（init之前为None）
+    static ref LEVEL_HANDLE: Mutex<Option<reload::Handle<LevelFilter, Registry>>> = Mutex::new(None);
+    /// 应用日志目录（由Tauri启动时设置）
+    static ref LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// 已打开的日志文件，None表示未开启文件日志
+    static ref LOG_FILE_HANDLE: Mutex<Option<std::fs::File>> = Mutex::new(None);
+}
+
+/// 写入日志文件；未开启文件日志时丢弃输出
+struct LogFileWriter;
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match LOG_FILE_HANDLE.lock() {
+            Ok(mut file) => match file.as_mut() {
+                Some(file) => file.write(buf),
+                None => Ok(buf.len()),
+            },
+            Err(_) => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match LOG_FILE_HANDLE.lock() {
+            Ok(mut file) => file.as_mut().map_or(Ok(()), |file| file.flush()),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// 初始化日志：默认输出info及以上级别到标准输出，文件日志默认关闭
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(LevelFilter::INFO);
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_target(false))
+        .with(
+            fmt::layer()
+                .with_target(false)
+                .with_ansi(false)
+                .with_writer(|| LogFileWriter),
+        )
+        .try_init();
+
+    match result {
+        Ok(()) => {
+            if let Ok(mut level_handle) = LEVEL_HANDLE.lock() {
+                *level_handle = Some(handle);
+            }
+        }
+        Err(e) => eprintln!("初始化日志失败: {}", e),
+    }
+}
+
+/// 设置应用日志目录
+pub fn set_log_dir(dir: PathBuf) {
+    if let Ok(mut log_dir) = LOG_DIR.lock() {
+        *log_dir = Some(dir);
+    }
+}
+
+/// 设置日志级别：trace、debug、info、warn、error 或 off
+pub fn set_log_level(level: &str) -> Result<(), String> {
+    let filter = match level.trim().to_lowercase().as_str() {
+        "trace" => LevelFilter::TRACE,
+        "debug" => LevelFilter::DEBUG,
+        "info" => LevelFilter::INFO,
+        "warn" => LevelFilter::WARN,
+        "error" => LevelFilter::ERROR,
+        "off" => LevelFilter::OFF,
+        _ => {
+            return Err(format!(
+                "不支持的日志级别: {}（可选 trace、debug、info、warn、error、off）",
+                level
+            ))
+        }
+    };
+
+    let level_handle = LEVEL_HANDLE
+        .lock()
+        .map_err(|_| "无法获取日志配置锁".to_string())?;
+    level_handle
+        .as_ref()
+        .ok_or_else(|| "日志尚未初始化".to_string())?
+        .modify(|current| *current = filter)
+        .map_err(|e| format!("设置日志级别失败: {}", e))?;
+
+    tracing::info!("日志级别已设置为: {}", filter);
+    Ok(())
+}
+
+/// 开启或关闭文件日志，开启时返回日志文件路径
+pub fn set_file_logging(enabled: bool) -> Result<Option<String>, String> {
+    let mut log_file = LOG_FILE_HANDLE
+        .lock()
+        .map_err(|_| "无法获取日志文件锁".to_string())?;
+    if !enabled {
+        *log_file = None;
+        return Ok(None);
+    }
+
+    let dir = LOG_DIR
+        .lock()
+        .map_err(|_| "无法获取日志目录锁".to_string())?
+        .clone()
+        .ok_or_else(|| "应用日志目录未初始化".to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    let path = dir.join(LOG_FILE);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开日志文件失败: {}", e))?;
+    *log_file = Some(file);
+
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// 去掉URL中的查询参数（预签名URL的签名等）后用于日志输出
+pub fn redact_url(url: &str) -> &str {
+    url.split_once('?').map_or(url, |(base, _)| base)
+}
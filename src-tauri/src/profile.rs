@@ -0,0 +1,121 @@
+use crate::video::{self, ExportOptions, LineLengthIssue, ReadingSpeedIssue, Subtitle};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// 导出配置：交付前依次执行 折行 → 最短时长 → 阅读速度检查 → 按格式导出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProfile {
+    /// 配置名称（内置配置为 "netflix"、"youtube"、"broadcast"）
+    pub name: String,
+    /// 导出格式（srt、vtt、itt等）
+    pub format: String,
+    /// 每行最大显示宽度（全角字符计2），None表示不折行
+    #[serde(default, alias = "maxLineWidth")]
+    pub max_line_width: Option<usize>,
+    /// 最短显示时长（秒），None表示不处理
+    #[serde(default, alias = "minDuration")]
+    pub min_duration: Option<f64>,
+    /// 最大阅读速度（字符/秒），None表示不检查
+    #[serde(default, alias = "maxCps")]
+    pub max_cps: Option<f64>,
+    /// 导出帧率（CSV、iTT使用）
+    #[serde(default)]
+    pub fps: Option<f64>,
+}
+
+impl ExportProfile {
+    fn builtin(
+        name: &str,
+        format: &str,
+        max_line_width: usize,
+        min_duration: f64,
+        max_cps: f64,
+        fps: Option<f64>,
+    ) -> Self {
+        ExportProfile {
+            name: name.to_string(),
+            format: format.to_string(),
+            max_line_width: Some(max_line_width),
+            min_duration: Some(min_duration),
+            max_cps: Some(max_cps),
+            fps,
+        }
+    }
+}
+
+/// 内置导出配置
+///
+/// - netflix：每行42个字符、最短5/6秒、每秒20字符（Netflix英文字幕规范）
+/// - youtube：每行42个字符、最短1秒、每秒25字符，导出WebVTT
+/// - broadcast：每行37个字符、最短1秒、每秒17字符（EBU/BBC广播字幕习惯）
+pub fn builtin_profiles() -> Vec<ExportProfile> {
+    vec![
+        ExportProfile::builtin("netflix", "srt", 42, 5.0 / 6.0, 20.0, None),
+        ExportProfile::builtin("youtube", "vtt", 42, 1.0, 25.0, None),
+        ExportProfile::builtin("broadcast", "srt", 37, 1.0, 17.0, None),
+    ]
+}
+
+/// 按配置导出的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileExportReport {
+    /// 导出的文件路径
+    pub path: String,
+    /// 导出的字幕条数（最短时长处理可能丢弃字幕）
+    pub subtitle_count: usize,
+    /// 折行后仍超出行宽的字幕（如单个超长单词）
+    pub line_issues: Vec<LineLengthIssue>,
+    /// 阅读速度超限的字幕，需要人工精简
+    pub speed_issues: Vec<ReadingSpeedIssue>,
+}
+
+/// 按导出配置处理字幕并导出到 `output_path`
+///
+/// 行宽和最短时长直接修正；阅读速度无法自动修正，只在报告中列出超限字幕。
+pub fn export_with_profile(
+    subtitles: &[Subtitle],
+    profile: &ExportProfile,
+    output_path: &str,
+) -> Result<ProfileExportReport, String> {
+    let mut processed = subtitles.to_vec();
+    let mut line_issues = Vec::new();
+    if let Some(max_width) = profile.max_line_width {
+        processed = video::wrap_subtitles(&processed, max_width)?;
+        let limits = HashMap::from([("default".to_string(), max_width)]);
+        line_issues = video::check_line_lengths(&processed, &limits);
+    }
+    if let Some(min_duration) = profile.min_duration {
+        processed = video::enforce_min_duration(&processed, min_duration)?;
+    }
+    let speed_issues = profile
+        .max_cps
+        .map(|max_cps| video::check_reading_speed(&processed, max_cps))
+        .unwrap_or_default();
+
+    if let Some(parent) = std::path::Path::new(output_path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+        }
+    }
+    let options = ExportOptions {
+        fps: profile.fps,
+        ..ExportOptions::default()
+    };
+    let path = video::export_subtitles_to_file(&processed, &profile.format, output_path, &options)?;
+
+    info!(
+        "按导出配置{}导出{}条字幕: {}（行宽超限{}条，阅读速度超限{}条）",
+        profile.name,
+        processed.len(),
+        path,
+        line_issues.len(),
+        speed_issues.len()
+    );
+    Ok(ProfileExportReport {
+        path,
+        subtitle_count: processed.len(),
+        line_issues,
+        speed_issues,
+    })
+}
@@ -1,3 +1,4 @@
+use crate::error::FlowError;
 use base64::Engine;
 use chrono::Utc;
 use hmac::{Hmac, Mac};
@@ -5,10 +6,12 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
+use tracing::{debug, error, info, warn};
 
 // 模型配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,13 +33,444 @@ pub struct ExtendedRecognitionParams {
     pub engine: String,
     pub language: String,
     pub model_config: ModelConfig,
+    /// 自定义初始提示词（Whisper系列引擎）
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+    /// 热词列表（用于引导识别专有名词）
+    #[serde(default)]
+    pub hotwords: Vec<String>,
+    /// 将口语数字转换为阿拉伯数字（腾讯云ConvertNumMode），默认开启
+    #[serde(default = "default_true")]
+    pub convert_numbers: bool,
+    /// 过滤脏词（腾讯云FilterDirty）
+    #[serde(default)]
+    pub filter_dirty: bool,
+    /// 过滤语气词（腾讯云FilterModal）
+    #[serde(default)]
+    pub filter_modal: bool,
+    /// 过滤标点符号（腾讯云FilterPunc）
+    #[serde(default)]
+    pub filter_punc: bool,
+    /// Whisper识别后用whisperx/stable-ts做强制对齐，校准时间轴并生成单词时间
+    #[serde(default)]
+    pub align: bool,
+    /// Whisper模型大小（tiny、base、small、medium、large、large-v2、large-v3等），设置时覆盖model_config.size
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Whisper任务："transcribe" 转写原语言，"translate" 翻译为英文（仅Whisper系列引擎）
+    #[serde(default)]
+    pub task: WhisperTask,
+    /// 腾讯云音频超过直接上传限制时的处理方式，None表示配置了COS时上传，否则分段识别
+    #[serde(default, alias = "largeFileStrategy")]
+    pub large_file_strategy: Option<LargeFileStrategy>,
 }
 
+/// 超出直接上传限制的大文件处理方式（腾讯云）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LargeFileStrategy {
+    /// 上传到COS后通过URL识别（需要配置COS）
+    Cos,
+    /// 在静音处切分为可直接上传的片段，逐段识别后合并（不需要COS）
+    Chunk,
+    /// 直接报错，不上传也不切分
+    Error,
+}
+
+/// Whisper任务类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperTask {
+    /// 按原语言转写
+    #[default]
+    Transcribe,
+    /// 将任意语言的语音翻译为英文字幕
+    Translate,
+}
+
+impl WhisperTask {
+    /// Whisper命令行和Python接口使用的任务名
+    fn as_str(self) -> &'static str {
+        match self {
+            WhisperTask::Transcribe => "transcribe",
+            WhisperTask::Translate => "translate",
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// 腾讯云识别选项
+#[derive(Debug, Clone)]
+pub struct TencentOptions {
+    pub cos_config: Option<crate::cos::CosConfig>,
+    /// 之前已上传的音频URL，仍可访问时跳过COS上传
+    pub uploaded_url: Option<String>,
+    pub hotwords: Vec<String>,
+    /// 识别结果格式（ResTextFormat）：0为句级结果（默认），2为带标点的词级时间戳
+    ///
+    /// 格式2仅中文普通话、英语及粤语等通用引擎支持（如 16k_zh、16k_zh_large、
+    /// 16k_en、16k_yue、8k_zh）；方言、小语种等引擎请保持为0。
+    pub res_text_format: u32,
+    /// 单个识别任务的最长音频时长（秒），超过时在静音处切分后分段识别；0表示不切分
+    pub max_segment_seconds: f64,
+    /// ConvertNumMode：true为1（智能转换为阿拉伯数字），false为0（保留中文数字）
+    pub convert_numbers: bool,
+    /// FilterDirty：true为1（过滤脏词）
+    pub filter_dirty: bool,
+    /// FilterModal：true为1（过滤部分语气词）
+    pub filter_modal: bool,
+    /// FilterPunc：true为2（过滤全部标点）
+    pub filter_punc: bool,
+    /// 大文件处理方式，None表示配置了COS时上传，否则分段识别
+    pub large_file_strategy: Option<LargeFileStrategy>,
+}
+
+impl Default for TencentOptions {
+    fn default() -> Self {
+        Self {
+            cos_config: None,
+            uploaded_url: None,
+            hotwords: Vec::new(),
+            res_text_format: 0,
+            max_segment_seconds: 0.0,
+            convert_numbers: true,
+            filter_dirty: false,
+            filter_modal: false,
+            filter_punc: false,
+            large_file_strategy: None,
+        }
+    }
+}
+
+impl TencentOptions {
+    /// 使用扩展识别参数中的热词、过滤选项和大文件处理方式
+    fn apply_params(&mut self, params: &ExtendedRecognitionParams) {
+        self.hotwords = params.hotwords.clone();
+        self.convert_numbers = params.convert_numbers;
+        self.filter_dirty = params.filter_dirty;
+        self.filter_modal = params.filter_modal;
+        self.filter_punc = params.filter_punc;
+        self.large_file_strategy = params.large_file_strategy.or(self.large_file_strategy);
+    }
+
+    /// 请求参数中的(ConvertNumMode, FilterDirty, FilterModal, FilterPunc)取值
+    fn filter_values(&self) -> (u32, u32, u32, u32) {
+        (
+            self.convert_numbers as u32,
+            self.filter_dirty as u32,
+            self.filter_modal as u32,
+            if self.filter_punc { 2 } else { 0 },
+        )
+    }
+}
+
+/// 腾讯云识别默认的单段最长时长（分钟）
+const TENCENT_MAX_SEGMENT_MINUTES: f64 = 60.0;
+
+/// 腾讯云直接上传音频数据的大小上限（5MB）
+const TENCENT_LOCAL_UPLOAD_LIMIT: usize = 5 * 1024 * 1024;
+
+/// 腾讯云API请求体上限（10MB，留出余量按9.5MB计算）
+const TENCENT_REQUEST_BODY_LIMIT: f64 = 9.5 * 1024.0 * 1024.0;
+
+/// 估算音频数据base64编码后的请求体大小（编码约增加37%）
+fn estimated_request_size(audio_len: usize) -> f64 {
+    audio_len as f64 * 1.37
+}
+
+/// 音频数据能否直接放入请求体上传
+fn tencent_request_fits(audio_len: usize) -> bool {
+    audio_len <= TENCENT_LOCAL_UPLOAD_LIMIT
+        && estimated_request_size(audio_len) <= TENCENT_REQUEST_BODY_LIMIT
+}
+
+/// 识别默认配置，调用时未指定的引擎、语言、提示词和热词回退到这里
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecognitionDefaults {
+    pub engine: String,
+    pub language: String,
+    /// 默认模型配置（本地Whisper系列引擎）
+    pub model_config: Option<ModelConfig>,
+    pub initial_prompt: Option<String>,
+    pub hotwords: Vec<String>,
+}
+
+impl Default for RecognitionDefaults {
+    fn default() -> Self {
+        Self {
+            engine: "whisper".to_string(),
+            language: "zh".to_string(),
+            model_config: None,
+            initial_prompt: None,
+            hotwords: Vec::new(),
+        }
+    }
+}
+
+/// 识别默认配置的文件名（位于应用配置目录）
+const RECOGNITION_DEFAULTS_FILE: &str = "recognition_defaults.json";
+
 // 全局任务状态管理
 lazy_static::lazy_static! {
     static ref RECOGNITION_TASKS: Arc<Mutex<HashMap<String, RecognitionTask>>> = {
         Arc::new(Mutex::new(HashMap::new()))
     };
+    /// 应用配置目录（由Tauri启动时设置）
+    static ref CONFIG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// 是否保留识别结果中文本为空的字幕（作为待手动填写的占位）
+    static ref KEEP_EMPTY_CUES: Mutex<bool> = Mutex::new(false);
+    /// 结束的任务在内存中保留的时间（秒），到期后清理
+    static ref TASK_TTL_SECS: Mutex<u64> = Mutex::new(DEFAULT_TASK_TTL_SECS);
+    /// 任务持久化文件路径（由Tauri启动时设置，None表示不持久化）
+    static ref TASKS_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// 同时运行的识别任务名额，超出的任务保持pending直到有名额释放
+    static ref RECOGNITION_SLOTS: Arc<tokio::sync::Semaphore> =
+        Arc::new(tokio::sync::Semaphore::new(DEFAULT_MAX_CONCURRENT_TASKS));
+    /// 当前的最大并发识别任务数
+    static ref MAX_CONCURRENT_TASKS: Mutex<usize> = Mutex::new(DEFAULT_MAX_CONCURRENT_TASKS);
+    /// 批量识别：批次ID -> 各文件的子任务
+    static ref RECOGNITION_BATCHES: Mutex<HashMap<String, Vec<BatchEntry>>> =
+        Mutex::new(HashMap::new());
+    /// 用于推送识别进度事件的应用句柄（由Tauri启动时设置）
+    static ref APP_HANDLE: Mutex<Option<tauri::AppHandle>> = Mutex::new(None);
+    /// 任务结束后需要删除的临时音频：任务ID -> 音频路径
+    static ref TEMP_AUDIO: Mutex<HashMap<String, PathBuf>> = Mutex::new(HashMap::new());
+    /// 手动设置的whisper命令路径（None表示使用PATH中的whisper）
+    static ref WHISPER_PATH: Mutex<Option<String>> = Mutex::new(None);
+    /// 手动设置的Python解释器路径（None表示使用PATH中的python3）
+    static ref PYTHON_PATH: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// 识别任务状态变化时推送的事件名
+pub const RECOGNITION_PROGRESS_EVENT: &str = "recognition://progress";
+
+/// 识别进度事件的内容
+#[derive(Debug, Clone, Serialize)]
+struct RecognitionProgressEvent {
+    task_id: String,
+    status: RecognitionStatus,
+}
+
+/// 设置应用句柄，之后任务状态更新会推送 `recognition://progress` 事件
+pub fn set_app_handle(handle: tauri::AppHandle) {
+    if let Ok(mut app_handle) = APP_HANDLE.lock() {
+        *app_handle = Some(handle);
+    }
+}
+
+/// 推送任务状态事件（未设置应用句柄时跳过）
+fn emit_task_status(task_id: &str, status: RecognitionStatus) {
+    use tauri::Emitter;
+
+    let Some(handle) = APP_HANDLE.lock().ok().and_then(|handle| handle.clone()) else {
+        return;
+    };
+    let event = RecognitionProgressEvent {
+        task_id: task_id.to_string(),
+        status,
+    };
+    if let Err(e) = handle.emit(RECOGNITION_PROGRESS_EVENT, event) {
+        warn!("推送识别进度事件失败: {}", e);
+    }
+}
+
+/// 默认最多同时运行2个识别任务（本地Whisper很占CPU/GPU）
+const DEFAULT_MAX_CONCURRENT_TASKS: usize = 2;
+
+/// 识别任务持久化的文件名（位于应用数据目录）
+const RECOGNITION_TASKS_FILE: &str = "recognition_tasks.json";
+
+/// 结束的任务默认保留30分钟，给前端足够时间获取结果
+const DEFAULT_TASK_TTL_SECS: u64 = 1800;
+
+/// 获取结束任务的保留时间（秒）
+pub fn get_task_ttl() -> u64 {
+    TASK_TTL_SECS
+        .lock()
+        .map(|ttl| *ttl)
+        .unwrap_or(DEFAULT_TASK_TTL_SECS)
+}
+
+/// 设置结束任务的保留时间（秒），只影响之后结束的任务
+pub fn set_task_ttl(secs: u64) -> Result<(), String> {
+    if secs == 0 {
+        return Err("任务保留时间必须大于0秒".to_string());
+    }
+    *TASK_TTL_SECS
+        .lock()
+        .map_err(|_| "无法获取任务保留时间锁".to_string())? = secs;
+    info!("任务保留时间已设置为{}秒", secs);
+    Ok(())
+}
+
+/// 获取最大并发识别任务数
+pub fn get_max_concurrent_tasks() -> usize {
+    MAX_CONCURRENT_TASKS
+        .lock()
+        .map(|max| *max)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_TASKS)
+}
+
+/// 设置最大并发识别任务数
+///
+/// 调小时正在运行的任务不受影响，多出的名额在这些任务结束后回收。
+pub fn set_max_concurrent_tasks(max: usize) -> Result<(), String> {
+    if max == 0 {
+        return Err("最大并发任务数必须大于0".to_string());
+    }
+    let mut current = MAX_CONCURRENT_TASKS
+        .lock()
+        .map_err(|_| "无法获取并发设置锁".to_string())?;
+
+    if max > *current {
+        RECOGNITION_SLOTS.add_permits(max - *current);
+    } else if max < *current {
+        let excess = *current - max;
+        let forgotten = RECOGNITION_SLOTS.forget_permits(excess);
+        if forgotten < excess {
+            // 剩余名额被运行中的任务占用，等它们释放后再回收
+            let remaining = (excess - forgotten) as u32;
+            tokio::spawn(async move {
+                if let Ok(permits) = RECOGNITION_SLOTS
+                    .clone()
+                    .acquire_many_owned(remaining)
+                    .await
+                {
+                    permits.forget();
+                }
+            });
+        }
+    }
+    *current = max;
+    info!("最大并发识别任务数已设置为{}", max);
+    Ok(())
+}
+
+/// 等待识别并发名额，等待期间收到取消信号时返回任务取消错误
+async fn acquire_recognition_slot(
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<tokio::sync::OwnedSemaphorePermit, String> {
+    if RECOGNITION_SLOTS.available_permits() == 0 {
+        info!("识别任务排队等待: {}", task_id);
+    }
+    tokio::select! {
+        permit = RECOGNITION_SLOTS.clone().acquire_owned() => {
+            permit.map_err(|e| format!("获取识别名额失败: {}", e))
+        }
+        _ = cancel_rx.recv() => Err(TASK_CANCELLED.to_string()),
+    }
+}
+
+/// 识别任务内存占用报告
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskMemoryReport {
+    /// 内存中的任务总数
+    pub total: usize,
+    /// 按状态统计的任务数
+    pub by_status: HashMap<String, usize>,
+    /// 所有任务结果中的字幕条数
+    pub subtitle_count: usize,
+    /// 任务结果占用内存的估算值（字节）
+    pub result_bytes: usize,
+    /// 当前的任务保留时间（秒）
+    pub ttl_secs: u64,
+}
+
+/// 估算一条字幕占用的内存（结构体本身加上字符串堆内存）
+fn subtitle_heap_estimate(subtitle: &crate::video::Subtitle) -> usize {
+    let words = subtitle.words.as_deref().unwrap_or_default();
+    std::mem::size_of::<crate::video::Subtitle>()
+        + subtitle.id.capacity()
+        + subtitle.text.capacity()
+        + subtitle.position.as_ref().map_or(0, |p| p.capacity())
+        + words
+            .iter()
+            .map(|w| std::mem::size_of::<crate::video::WordTiming>() + w.word.capacity())
+            .sum::<usize>()
+}
+
+/// 统计内存中的识别任务，用于排查批量识别时内存增长
+pub fn get_task_memory_report() -> Result<TaskMemoryReport, String> {
+    let tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    let mut report = TaskMemoryReport {
+        total: tasks.len(),
+        by_status: HashMap::new(),
+        subtitle_count: 0,
+        result_bytes: 0,
+        ttl_secs: get_task_ttl(),
+    };
+    for task in tasks.values() {
+        *report
+            .by_status
+            .entry(task.status.status.clone())
+            .or_insert(0) += 1;
+        if let Some(result) = &task.status.result {
+            report.subtitle_count += result.len();
+            report.result_bytes += result.iter().map(subtitle_heap_estimate).sum::<usize>();
+        }
+    }
+    Ok(report)
+}
+
+/// 是否保留文本为空的字幕
+pub fn get_keep_empty_cues() -> bool {
+    KEEP_EMPTY_CUES.lock().map(|keep| *keep).unwrap_or(false)
+}
+
+/// 设置是否保留文本为空的字幕
+pub fn set_keep_empty_cues(keep: bool) -> Result<(), String> {
+    *KEEP_EMPTY_CUES
+        .lock()
+        .map_err(|_| "无法获取空字幕设置锁".to_string())? = keep;
+    info!("识别结果空字幕处理: {}", if keep { "保留" } else { "丢弃" });
+    Ok(())
+}
+
+/// 统一处理各引擎结果中的空字幕
+///
+/// 默认丢弃文本为空（或只有空白）的字幕；设置为保留时将其文本规范为空字符串。
+/// 有字幕被丢弃时重新编号。
+pub fn drop_empty_cues(subtitles: Vec<crate::video::Subtitle>) -> Vec<crate::video::Subtitle> {
+    handle_empty_cues(subtitles, get_keep_empty_cues())
+}
+
+/// 按 `keep` 保留（文本规范为空字符串）或丢弃空字幕
+fn handle_empty_cues(
+    subtitles: Vec<crate::video::Subtitle>,
+    keep: bool,
+) -> Vec<crate::video::Subtitle> {
+    if keep {
+        return subtitles
+            .into_iter()
+            .map(|mut subtitle| {
+                if subtitle.text.trim().is_empty() {
+                    subtitle.text.clear();
+                }
+                subtitle
+            })
+            .collect();
+    }
+
+    let total = subtitles.len();
+    let mut kept: Vec<crate::video::Subtitle> = subtitles
+        .into_iter()
+        .filter(|subtitle| !subtitle.text.trim().is_empty())
+        .collect();
+    if kept.len() < total {
+        info!("丢弃{}条空字幕", total - kept.len());
+        for (index, subtitle) in kept.iter_mut().enumerate() {
+            subtitle.id = (index + 1).to_string();
+        }
+    }
+    kept
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +482,37 @@ pub struct RecognitionTask {
     pub status: RecognitionStatus,
     #[serde(skip)]
     pub cancel_sender: Option<mpsc::Sender<()>>,
+    /// 原始API密钥（用于重试，不序列化）
+    #[serde(skip)]
+    pub api_keys: Option<Value>,
+    /// 选择的Whisper模型（随任务保存，重试时沿用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 扩展配置参数（通过start_recognition_with_config创建的任务）
+    #[serde(skip)]
+    pub extended_params: Option<ExtendedRecognitionParams>,
+    /// 已上传到对象存储的音频URL（重试时可复用，避免重复上传）
+    #[serde(skip)]
+    pub uploaded_url: Option<String>,
+    /// 识别完成后翻译的目标语言（输出双语字幕）
+    #[serde(skip)]
+    pub translate_to: Option<String>,
+    /// 引擎上报进度映射到的区间（识别+翻译任务中识别阶段占0~0.7）
+    #[serde(skip)]
+    pub progress_range: Option<(f32, f32)>,
+    /// 是否为实时识别任务
+    #[serde(skip)]
+    pub live: bool,
+}
+
+/// 创建任务时的附加信息（重试来源、Whisper模型、翻译目标语言、是否跳过结果缓存）
+#[derive(Default)]
+struct TaskOrigin {
+    retry_of: Option<String>,
+    model: Option<String>,
+    uploaded_url: Option<String>,
+    translate_to: Option<String>,
+    bypass_cache: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +522,18 @@ pub struct RecognitionStatus {
     #[serde(rename = "subtitles")]
     pub result: Option<Vec<crate::video::Subtitle>>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 若为重试任务，记录原任务ID
+    #[serde(default, rename = "retryOf", skip_serializing_if = "Option::is_none")]
+    pub retry_of: Option<String>,
+    /// 自动检测语言时识别出的语言代码
+    #[serde(
+        default,
+        rename = "detectedLanguage",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub detected_language: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,1706 +542,5520 @@ pub struct Language {
     pub name: String,
 }
 
-/// 开始语音识别任务
-pub fn start_recognition(
-    task_id: String,
-    audio_path: &str,
-    engine: &str,
-    language: &str,
-    api_keys: Option<Value>,
-) -> Result<(), String> {
-    // 检查任务是否已存在
-    let mut tasks = RECOGNITION_TASKS
-        .lock()
-        .map_err(|_| "无法获取任务锁".to_string())?;
+// API密钥验证结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyValidation {
+    pub status: String, // "valid", "missing", "invalid", "network_error"
+    pub message: Option<String>,
+}
 
-    if tasks.contains_key(&task_id) {
-        return Err(format!("任务ID已存在: {}", task_id));
+impl ApiKeyValidation {
+    fn valid() -> Self {
+        Self {
+            status: "valid".to_string(),
+            message: None,
+        }
     }
 
-    // 创建取消通道
-    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+    fn missing(message: String) -> Self {
+        Self {
+            status: "missing".to_string(),
+            message: Some(message),
+        }
+    }
 
-    // 创建新任务
-    let task = RecognitionTask {
-        task_id: task_id.clone(),
-        audio_path: audio_path.to_string(),
-        engine: engine.to_string(),
-        language: language.to_string(),
-        status: RecognitionStatus {
-            status: "pending".to_string(),
-            progress: 0.0,
-            result: None,
-            error: None,
-        },
-        cancel_sender: Some(cancel_tx),
-    };
+    fn invalid(message: String) -> Self {
+        Self {
+            status: "invalid".to_string(),
+            message: Some(message),
+        }
+    }
 
-    // 存储任务
-    tasks.insert(task_id.clone(), task);
+    fn network_error(message: String) -> Self {
+        Self {
+            status: "network_error".to_string(),
+            message: Some(message),
+        }
+    }
+}
 
-    // 启动异步任务
-    let task_id_clone = task_id.clone();
-    let audio_path = audio_path.to_string();
-    let engine = engine.to_string();
-    let language = language.to_string();
+// Whisper模型缓存状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperModelStatus {
+    pub name: String,
+    pub present: bool,
+    /// 已下载模型文件的实际大小（字节）
+    pub size_bytes: Option<u64>,
+    /// 模型下载大小（约，字节）
+    pub download_size_bytes: u64,
+    pub path: Option<String>,
+}
 
-    tokio::spawn(async move {
-        // 更新状态为处理中
-        update_task_status(&task_id_clone, "processing".to_string(), 0.0, None, None);
+/// Whisper模型列表及下载大小（约，MB）
+const WHISPER_MODELS: &[(&str, u64)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1500),
+    ("medium.en", 1500),
+    ("large-v1", 2900),
+    ("large-v2", 2900),
+    ("large-v3", 2900),
+    ("large-v3-turbo", 1550),
+];
+
+/// 未指定时使用的Whisper模型（平衡速度和精度）
+const DEFAULT_WHISPER_MODEL: &str = "base";
+
+/// 检查Whisper模型名称（"large" 为最新large版本的别名）
+fn validate_whisper_model(model: &str) -> Result<(), String> {
+    if model == "large" || WHISPER_MODELS.iter().any(|(name, _)| *name == model) {
+        return Ok(());
+    }
+    let names: Vec<&str> = WHISPER_MODELS.iter().map(|(name, _)| *name).collect();
+    Err(format!(
+        "未知的Whisper模型: {}（可选 {}、large）",
+        model,
+        names.join("、")
+    ))
+}
 
-        // 使用Whisper本地识别
-        let result = {
-            println!("使用Whisper引擎进行本地识别...");
-            println!("音频文件路径: {}", audio_path);
+/// `start_recognition` 命令的可选参数，未指定的引擎和语言使用默认配置
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StartRecognitionOptions {
+    #[serde(default)]
+    pub engine: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default, alias = "apiKeys")]
+    pub api_keys: Option<Value>,
+    /// Whisper模型，未指定时使用base
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 同一音频和引擎已有进行中的任务时拒绝重复提交
+    #[serde(default)]
+    pub dedupe: bool,
+    /// 跳过识别结果缓存，重新识别
+    #[serde(default, alias = "bypassCache")]
+    pub bypass_cache: bool,
+}
 
-            match call_whisper_api(&audio_path, &language, &task_id_clone, &mut cancel_rx).await {
-                Ok(subtitles) => {
-                    println!("Whisper识别成功，共生成{}条字幕", subtitles.len());
-                    Ok(subtitles)
-                }
-                Err(e) => {
-                    eprintln!("Whisper识别失败: {}", e);
-                    // 如果Whisper未安装，提供安装指导和测试数据
-                    if e.contains("未找到whisper") || e.contains("ModuleNotFoundError") {
-                        println!("生成Whisper安装指导的测试数据...");
-                        let installation_guide = generate_whisper_installation_guide(&audio_path);
-                        Ok(installation_guide)
-                    } else {
-                        Err(format!("Whisper识别失败: {}", e))
-                    }
-                }
-            }
-        };
+/// 识别任务上下文
+pub struct TaskCtx {
+    pub task_id: String,
+    pub cancel_rx: mpsc::Receiver<()>,
+    pub api_keys: Value,
+    /// 选择的Whisper模型，未指定时使用引擎的默认模型
+    pub model: Option<String>,
+    /// 扩展配置识别的参数（`start_recognition_with_config`），普通识别为None
+    pub params: Option<ExtendedRecognitionParams>,
+}
 
-        // 处理结果
-        match result {
-            Ok(subtitles) => {
-                update_task_status(
-                    &task_id_clone,
-                    "completed".to_string(),
-                    1.0,
-                    Some(subtitles),
-                    None,
-                );
+/// 语音识别引擎
+///
+/// 每个引擎负责自己的语言列表、密钥检查和识别流程，通过 `find_engine` 按名称查找。
+#[async_trait::async_trait]
+pub trait RecognitionEngine: Send + Sync {
+    /// 识别音频，返回字幕列表
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String>;
+
+    /// 支持的语言列表
+    fn languages(&self) -> Vec<Language>;
+
+    /// 检查必要的API密钥是否齐全
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError>;
+
+    /// 调用服务鉴权接口在线验证密钥（默认不做在线验证）
+    async fn verify_keys(&self, _keys: &Value) -> ApiKeyValidation {
+        ApiKeyValidation::valid()
+    }
+}
 
-                // 延迟清理已完成的任务（给前端足够时间获取结果）
-                let cleanup_task_id = task_id_clone.clone();
-                tokio::spawn(async move {
-                    sleep(Duration::from_secs(1800)).await; // 30分钟后清理，给前端足够时间
-                    cleanup_completed_task(&cleanup_task_id);
-                });
-            }
-            Err(err) => {
-                update_task_status(&task_id_clone, "failed".to_string(), 0.0, None, Some(err));
+/// 根据名称查找识别引擎
+pub fn find_engine(name: &str) -> Option<Box<dyn RecognitionEngine>> {
+    match name {
+        "whisper" => Some(Box::new(WhisperEngine)),
+        "faster-whisper" => Some(Box::new(FasterWhisperEngine)),
+        "sensevoice" => Some(Box::new(SenseVoiceEngine)),
+        "tencent" => Some(Box::new(TencentEngine)),
+        "baidu" => Some(Box::new(BaiduEngine)),
+        "aliyun" => Some(Box::new(AliyunEngine)),
+        "google" => Some(Box::new(GoogleEngine)),
+        "openai" => Some(Box::new(OpenAiEngine)),
+        "aws" => Some(Box::new(AwsEngine)),
+        _ => None,
+    }
+}
 
-                // 延迟清理失败的任务
-                let cleanup_task_id = task_id_clone.clone();
-                tokio::spawn(async move {
-                    sleep(Duration::from_secs(1800)).await; // 30分钟后清理，给前端足够时间
-                    cleanup_completed_task(&cleanup_task_id);
-                });
-            }
-        }
-    });
+/// 根据名称查找识别引擎，不支持时返回 `EngineUnsupported` 错误
+pub fn require_engine(name: &str) -> Result<Box<dyn RecognitionEngine>, FlowError> {
+    find_engine(name).ok_or_else(|| FlowError::EngineUnsupported {
+        engine: name.to_string(),
+    })
+}
 
-    Ok(())
+/// 由 (代码, 名称) 列表构建语言列表
+fn language_list(languages: &[(&str, &str)]) -> Vec<Language> {
+    languages
+        .iter()
+        .map(|(code, name)| Language {
+            code: code.to_string(),
+            name: name.to_string(),
+        })
+        .collect()
 }
 
-/// 使用扩展配置开始识别
-pub fn start_recognition_with_config(
-    task_id: String,
-    params: ExtendedRecognitionParams,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    println!("开始扩展配置识别任务: {}", task_id);
-    println!("识别参数: {:?}", params);
+/// 读取COS配置中的keepUploads（识别结束后保留上传的音频），默认false
+fn cos_keep_uploads(cos: &Value) -> bool {
+    ["keepUploads", "keep_uploads"]
+        .iter()
+        .find_map(|name| cos.get(*name).and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
 
-    // 创建取消通道
-    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+/// 读取COS配置中的uploadRetries（上传遇到网络错误时的最大重试次数）
+fn cos_upload_retries(cos: &Value) -> Option<u32> {
+    ["uploadRetries", "upload_retries"]
+        .iter()
+        .find_map(|name| cos.get(*name).and_then(|v| v.as_u64()))
+        .map(|retries| retries.min(10) as u32)
+}
 
-    // 创建识别任务
-    let task = RecognitionTask {
-        task_id: task_id.clone(),
-        audio_path: params.audio_path.clone(),
-        engine: params.engine.clone(),
-        language: params.language.clone(),
-        status: RecognitionStatus {
-            status: "pending".to_string(),
-            progress: 0.0,
-            result: None,
-            error: None,
-        },
-        cancel_sender: Some(cancel_tx),
+/// 读取API密钥字段（兼容驼峰与下划线命名），空值视为缺失
+fn api_key_field<'a>(keys: &'a Value, names: &[&str]) -> Option<&'a str> {
+    names.iter().find_map(|name| {
+        keys.get(*name)
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// 读取必填的API密钥字段（依次尝试各个名称），缺失、类型错误或为空时给出具体字段
+fn required_key_field<'a>(
+    keys: &'a Value,
+    provider: &str,
+    names: &[&str],
+) -> Result<&'a str, FlowError> {
+    optional_key_field(keys, provider, names)?.ok_or_else(|| FlowError::ApiKeyMissing {
+        provider: provider.to_string(),
+        field: Some(names[0].to_string()),
+    })
+}
+
+/// 读取可选的API密钥字段，字段存在但不是字符串时报错，空字符串视为未填写
+fn optional_key_field<'a>(
+    keys: &'a Value,
+    provider: &str,
+    names: &[&str],
+) -> Result<Option<&'a str>, FlowError> {
+    match keys {
+        Value::Object(_) => {}
+        Value::Null => {
+            return Err(FlowError::ApiKeyMissing {
+                provider: provider.to_string(),
+                field: None,
+            })
+        }
+        _ => return Err(format!("{}密钥格式错误：应为JSON对象", provider).into()),
+    }
+
+    let Some((name, value)) = names
+        .iter()
+        .find_map(|name| keys.get(*name).filter(|v| !v.is_null()).map(|v| (*name, v)))
+    else {
+        return Ok(None);
     };
+    let value = value
+        .as_str()
+        .ok_or_else(|| format!("{}密钥字段类型错误: {}应为字符串", provider, name))
+        .map_err(FlowError::from)?
+        .trim();
+    Ok(Some(value).filter(|v| !v.is_empty()))
+}
 
-    // 存储任务
-    {
-        let mut tasks = RECOGNITION_TASKS.lock().unwrap();
-        tasks.insert(task_id.clone(), task);
+/// 腾讯云API密钥
+struct TencentKeys<'a> {
+    secret_id: &'a str,
+    secret_key: &'a str,
+}
+
+impl<'a> TencentKeys<'a> {
+    fn parse(keys: &'a Value) -> Result<Self, FlowError> {
+        Ok(Self {
+            secret_id: required_key_field(keys, "腾讯云", &["secretId", "secret_id"])?,
+            secret_key: required_key_field(keys, "腾讯云", &["secretKey", "secret_key"])?,
+        })
     }
+}
 
-    let task_id_clone = task_id.clone();
+/// 百度智能云API密钥
+struct BaiduKeys<'a> {
+    api_key: &'a str,
+    secret_key: &'a str,
+}
 
-    tokio::spawn(async move {
-        // 更新状态为处理中
-        update_task_status(&task_id_clone, "processing".to_string(), 0.0, None, None);
+impl<'a> BaiduKeys<'a> {
+    fn parse(keys: &'a Value) -> Result<Self, FlowError> {
+        Ok(Self {
+            api_key: required_key_field(keys, "百度", &["apiKey", "api_key"])?,
+            secret_key: required_key_field(keys, "百度", &["secretKey", "secret_key"])?,
+        })
+    }
+}
 
-        // 使用扩展配置进行识别
-        let result = call_recognition_with_config(&params, &task_id_clone, &mut cancel_rx).await;
+/// 阿里云API密钥
+struct AliyunKeys<'a> {
+    access_key_id: &'a str,
+    access_key_secret: &'a str,
+    /// 智能语音交互项目的Appkey
+    app_key: &'a str,
+}
 
-        match result {
-            Ok(subtitles) => {
-                println!("识别成功，共生成{}条字幕", subtitles.len());
-                update_task_status(
-                    &task_id_clone,
-                    "completed".to_string(),
-                    100.0,
-                    Some(subtitles),
-                    None,
-                );
+impl<'a> AliyunKeys<'a> {
+    fn parse(keys: &'a Value) -> Result<Self, FlowError> {
+        Ok(Self {
+            access_key_id: required_key_field(keys, "阿里云", &["accessKeyId", "access_key_id"])?,
+            access_key_secret: required_key_field(
+                keys,
+                "阿里云",
+                &["accessKeySecret", "access_key_secret"],
+            )?,
+            app_key: required_key_field(keys, "阿里云", &["appKey", "app_key"])?,
+        })
+    }
+}
+
+/// 只需单个api_key的服务（Google、OpenAI）
+struct SingleApiKey<'a> {
+    api_key: &'a str,
+}
+
+impl<'a> SingleApiKey<'a> {
+    fn parse(keys: &'a Value, provider: &str) -> Result<Self, FlowError> {
+        Ok(Self {
+            api_key: required_key_field(keys, provider, &["apiKey", "api_key"])?,
+        })
+    }
+}
+
+/// 将音频路径转换为字符串
+fn audio_path_str(audio: &Path) -> Result<&str, String> {
+    audio.to_str().ok_or_else(|| "音频路径无效".to_string())
+}
+
+/// Whisper本地识别引擎
+struct WhisperEngine;
+
+impl WhisperEngine {
+    /// 读取任务选择的模型大小，未指定时使用base
+    fn model(ctx: &TaskCtx) -> &str {
+        ctx.model.as_deref().unwrap_or(DEFAULT_WHISPER_MODEL)
+    }
+}
+
+#[async_trait::async_trait]
+impl RecognitionEngine for WhisperEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        if let Some(params) = ctx.params.clone() {
+            return transcribe_whisper_family(&params, ctx).await;
+        }
+
+        let audio_path = audio_path_str(audio)?;
+        let model = Self::model(ctx).to_string();
+        info!("使用Whisper引擎进行本地识别，模型: {}", model);
+        debug!("音频文件路径: {}", audio_path);
+
+        match call_whisper_api(audio_path, lang, &model, &ctx.task_id, &mut ctx.cancel_rx).await {
+            Ok(subtitles) => {
+                info!("Whisper识别成功，共生成{}条字幕", subtitles.len());
+                Ok(subtitles)
             }
             Err(e) => {
-                eprintln!("识别失败: {}", e);
-                update_task_status(&task_id_clone, "failed".to_string(), 0.0, None, Some(e));
+                error!("Whisper识别失败: {}", e);
+                // 未安装时返回安装指导作为错误，不能以占位字幕冒充识别结果（会被写入缓存）
+                if e.contains("未找到whisper") || e.contains("ModuleNotFoundError") {
+                    Err(
+                        "Whisper未安装，请先安装: pip install openai-whisper（或 brew install whisper）"
+                            .to_string(),
+                    )
+                } else {
+                    Err(format!("Whisper识别失败: {}", e))
+                }
             }
         }
-    });
+    }
 
-    Ok(())
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh", "中文"),
+            ("en", "英语"),
+            ("ja", "日语"),
+            ("ko", "韩语"),
+            ("fr", "法语"),
+            ("de", "德语"),
+            ("es", "西班牙语"),
+            ("ru", "俄语"),
+        ])
+    }
+
+    fn validate_keys(&self, _keys: &Value) -> Result<(), FlowError> {
+        // Whisper不需要API密钥，选择的模型在创建任务时检查
+        Ok(())
+    }
 }
 
-/// 获取任务状态
-pub fn get_recognition_status(task_id: &str) -> Result<RecognitionStatus, String> {
-    let tasks = RECOGNITION_TASKS
-        .lock()
-        .map_err(|_| "无法获取任务锁".to_string())?;
+/// 读取任务的扩展识别参数；普通识别没有扩展参数时按任务选择的模型生成默认配置
+fn task_params(
+    ctx: &TaskCtx,
+    engine: &str,
+    audio_path: &str,
+    lang: &str,
+    default_size: &str,
+) -> ExtendedRecognitionParams {
+    if let Some(params) = &ctx.params {
+        return params.clone();
+    }
+    ExtendedRecognitionParams {
+        audio_path: audio_path.to_string(),
+        engine: engine.to_string(),
+        language: lang.to_string(),
+        model_config: ModelConfig {
+            engine: engine.to_string(),
+            size: ctx.model.as_deref().unwrap_or(default_size).to_string(),
+            device: "cpu".to_string(),
+            compute_type: None,
+            beam_size: None,
+            temperature: None,
+            enable_emotion_recognition: None,
+            enable_event_detection: None,
+        },
+        initial_prompt: None,
+        hotwords: Vec::new(),
+        convert_numbers: true,
+        filter_dirty: false,
+        filter_modal: false,
+        filter_punc: false,
+        align: false,
+        model: None,
+        task: WhisperTask::Transcribe,
+        large_file_strategy: None,
+    }
+}
 
-    tasks
-        .get(task_id)
-        .map(|task| task.status.clone())
-        .ok_or_else(|| format!("任务不存在: {}", task_id))
+/// 按扩展配置运行Whisper/Faster-Whisper，需要时再做强制对齐
+async fn transcribe_whisper_family(
+    params: &ExtendedRecognitionParams,
+    ctx: &mut TaskCtx,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    let subtitles = if params.engine == "faster-whisper" {
+        call_faster_whisper_with_config(params, &ctx.task_id, &mut ctx.cancel_rx).await?
+    } else {
+        call_whisper_with_config(params, &ctx.task_id, &mut ctx.cancel_rx).await?
+    };
+    if params.align && params.task == WhisperTask::Translate {
+        // 译文与原语音无法逐词对齐
+        add_task_warning(&ctx.task_id, "翻译模式不支持强制对齐，已跳过".to_string());
+        Ok(subtitles)
+    } else if params.align {
+        align_whisper_result(params, &ctx.task_id, subtitles, &mut ctx.cancel_rx).await
+    } else {
+        Ok(subtitles)
+    }
 }
 
-/// 取消识别任务
-pub fn cancel_recognition(task_id: &str) -> Result<(), String> {
-    let mut tasks = RECOGNITION_TASKS
-        .lock()
-        .map_err(|_| "无法获取任务锁".to_string())?;
+/// Faster-Whisper本地识别引擎
+struct FasterWhisperEngine;
+
+#[async_trait::async_trait]
+impl RecognitionEngine for FasterWhisperEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let params = task_params(
+            ctx,
+            "faster-whisper",
+            audio_path,
+            lang,
+            DEFAULT_WHISPER_MODEL,
+        );
+        info!(
+            "使用Faster-Whisper引擎进行本地识别，模型: {}",
+            params.model_config.size
+        );
+        transcribe_whisper_family(&params, ctx).await
+    }
 
-    if let Some(task) = tasks.get_mut(task_id) {
-        if let Some(sender) = task.cancel_sender.take() {
-            // 发送取消信号
-            let _ = sender.try_send(());
-            return Ok(());
-        }
+    fn languages(&self) -> Vec<Language> {
+        WhisperEngine.languages()
     }
 
-    Err(format!("无法取消任务: {}", task_id))
+    fn validate_keys(&self, _keys: &Value) -> Result<(), FlowError> {
+        Ok(())
+    }
 }
 
-/// 获取支持的语言列表
-pub fn get_supported_languages(engine: &str) -> Result<Vec<Language>, String> {
-    match engine {
-        "whisper" => {
-            // Whisper支持的语言列表
-            let languages = vec![
-                Language {
-                    code: "zh".to_string(),
-                    name: "中文".to_string(),
-                },
-                Language {
-                    code: "en".to_string(),
-                    name: "英语".to_string(),
-                },
-                Language {
-                    code: "ja".to_string(),
-                    name: "日语".to_string(),
-                },
-                Language {
-                    code: "ko".to_string(),
-                    name: "韩语".to_string(),
-                },
-                Language {
-                    code: "fr".to_string(),
-                    name: "法语".to_string(),
-                },
-                Language {
-                    code: "de".to_string(),
-                    name: "德语".to_string(),
-                },
-                Language {
-                    code: "es".to_string(),
-                    name: "西班牙语".to_string(),
-                },
-                Language {
-                    code: "ru".to_string(),
-                    name: "俄语".to_string(),
-                },
-            ];
-            Ok(languages)
-        }
-        "tencent" => {
-            // 腾讯云支持的语言列表
-            let languages = vec![
-                Language {
-                    code: "zh".to_string(),
-                    name: "中文".to_string(),
-                },
-                Language {
-                    code: "en".to_string(),
-                    name: "英语".to_string(),
-                },
-                Language {
-                    code: "ja".to_string(),
-                    name: "日语".to_string(),
-                },
-                Language {
-                    code: "ko".to_string(),
-                    name: "韩语".to_string(),
-                },
-            ];
-            Ok(languages)
-        }
-        "aliyun" => {
-            // 阿里云支持的语言列表
-            let languages = vec![
-                Language {
-                    code: "zh".to_string(),
-                    name: "中文".to_string(),
-                },
-                Language {
-                    code: "en".to_string(),
-                    name: "英语".to_string(),
-                },
-                Language {
-                    code: "ja".to_string(),
-                    name: "日语".to_string(),
-                },
-                Language {
-                    code: "ko".to_string(),
-                    name: "韩语".to_string(),
-                },
-            ];
-            Ok(languages)
-        }
-        "baidu" => {
-            // 百度支持的语言列表
-            let languages = vec![
-                Language {
-                    code: "zh".to_string(),
-                    name: "中文".to_string(),
-                },
-                Language {
-                    code: "en".to_string(),
-                    name: "英语".to_string(),
-                },
-                Language {
-                    code: "jp".to_string(),
-                    name: "日语".to_string(),
-                },
-                Language {
-                    code: "kor".to_string(),
-                    name: "韩语".to_string(),
-                },
-            ];
-            Ok(languages)
-        }
-        "google" => {
-            // Google支持的语言列表
-            let languages = vec![
-                Language {
-                    code: "zh-CN".to_string(),
-                    name: "中文（简体）".to_string(),
-                },
-                Language {
-                    code: "zh-TW".to_string(),
-                    name: "中文（繁体）".to_string(),
-                },
-                Language {
-                    code: "en-US".to_string(),
-                    name: "英语（美国）".to_string(),
-                },
-                Language {
-                    code: "en-GB".to_string(),
-                    name: "英语（英国）".to_string(),
-                },
-                Language {
-                    code: "ja-JP".to_string(),
-                    name: "日语".to_string(),
-                },
-                Language {
-                    code: "ko-KR".to_string(),
-                    name: "韩语".to_string(),
-                },
-                Language {
-                    code: "fr-FR".to_string(),
-                    name: "法语".to_string(),
-                },
-                Language {
-                    code: "de-DE".to_string(),
-                    name: "德语".to_string(),
-                },
-            ];
-            Ok(languages)
-        }
-        _ => Err(format!("不支持的识别引擎: {}", engine)),
+/// SenseVoice本地识别引擎
+struct SenseVoiceEngine;
+
+#[async_trait::async_trait]
+impl RecognitionEngine for SenseVoiceEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let params = task_params(ctx, "sensevoice", audio_path, lang, "small");
+        info!("使用SenseVoice引擎进行本地识别");
+        call_sensevoice_with_config(&params, &ctx.task_id, &mut ctx.cancel_rx).await
     }
-}
 
-/// 获取可用模型列表
-pub fn get_available_models(
-) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
-    let models = vec![
-        serde_json::json!({
-            "engine": "whisper",
-            "name": "OpenAI Whisper",
-            "description": "OpenAI开源的多语言语音识别模型",
-            "sizes": ["tiny", "base", "small", "medium", "large", "large-v2", "large-v3"],
-            "features": ["multilingual", "timestamps", "translation"],
-            "requirements": {
-                "python": ">=3.8",
-                "packages": ["openai-whisper", "torch", "torchaudio"]
-            }
-        }),
-        serde_json::json!({
-            "engine": "faster-whisper",
-            "name": "Faster Whisper",
-            "description": "基于CTranslate2优化的Whisper实现，速度提升4-5倍",
-            "sizes": ["tiny", "base", "small", "medium", "large", "large-v2", "large-v3"],
-            "features": ["multilingual", "timestamps", "optimization", "quantization"],
-            "requirements": {
-                "python": ">=3.8",
-                "packages": ["faster-whisper", "ctranslate2"]
-            }
-        }),
-        serde_json::json!({
-            "engine": "sensevoice",
-            "name": "SenseVoice",
-            "description": "阿里巴巴开源的多语言语音理解模型，支持情感识别和事件检测",
-            "sizes": ["small", "large"],
-            "features": ["multilingual", "timestamps", "emotion", "event", "language_id"],
-            "requirements": {
-                "python": ">=3.8",
-                "packages": ["funasr", "modelscope", "torch", "torchaudio"]
-            }
-        }),
-    ];
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("auto", "自动检测"),
+            ("zh", "中文"),
+            ("en", "英语"),
+            ("yue", "粤语"),
+            ("ja", "日语"),
+            ("ko", "韩语"),
+        ])
+    }
 
-    Ok(models)
+    fn validate_keys(&self, _keys: &Value) -> Result<(), FlowError> {
+        // 本地模型不需要API密钥
+        Ok(())
+    }
 }
 
-/// 检查模型是否已安装
-pub fn check_model_installation(
-    engine: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    match engine {
-        "whisper" => {
-            // 检查whisper命令或Python包
-            let whisper_cmd = std::process::Command::new("whisper").arg("--help").output();
-            if whisper_cmd.is_ok() {
-                return Ok(true);
-            }
+/// 腾讯云录音文件识别引擎
+struct TencentEngine;
 
-            // 检查Python whisper包
-            let python_check = std::process::Command::new("python3")
-                .arg("-c")
-                .arg("import whisper; print('OK')")
-                .output();
+impl TencentEngine {
+    /// 读取secretId和secretKey
+    fn credentials(keys: &Value) -> Result<(&str, &str), FlowError> {
+        TencentKeys::parse(keys).map(|keys| (keys.secret_id, keys.secret_key))
+    }
 
-            Ok(python_check.is_ok() && python_check.unwrap().status.success())
+    /// 从API密钥中读取识别选项（可选的COS配置用于大文件上传，maxSegmentMinutes控制分段时长，
+    /// largeFileStrategy指定大文件处理方式）
+    fn options(keys: &Value, secret_id: &str, secret_key: &str) -> TencentOptions {
+        let cos_config = keys.get("cos").and_then(|cos| {
+            Some(crate::cos::CosConfig {
+                secret_id: secret_id.to_string(),
+                secret_key: secret_key.to_string(),
+                bucket: api_key_field(cos, &["bucket"])?.to_string(),
+                region: api_key_field(cos, &["region"])?.to_string(),
+                domain: api_key_field(cos, &["domain"]).map(|v| v.to_string()),
+                endpoint: api_key_field(cos, &["endpoint"]).map(|v| v.to_string()),
+                storage_class: api_key_field(cos, &["storageClass", "storage_class"])
+                    .map(|v| v.to_string()),
+                acl: api_key_field(cos, &["acl"]).map(|v| v.to_string()),
+                keep_uploads: cos_keep_uploads(cos),
+                upload_retries: cos_upload_retries(cos),
+            })
+        });
+
+        let max_segment_minutes = ["maxSegmentMinutes", "max_segment_minutes"]
+            .iter()
+            .find_map(|name| keys.get(*name).and_then(|v| v.as_f64()))
+            .unwrap_or(TENCENT_MAX_SEGMENT_MINUTES);
+
+        let large_file_strategy =
+            api_key_field(keys, &["largeFileStrategy", "large_file_strategy"]).and_then(
+                |strategy| serde_json::from_value(Value::String(strategy.to_string())).ok(),
+            );
+
+        TencentOptions {
+            cos_config,
+            max_segment_seconds: max_segment_minutes.max(0.0) * 60.0,
+            large_file_strategy,
+            ..Default::default()
         }
-        "faster-whisper" => {
-            // 检查faster-whisper Python包
-            let python_check = std::process::Command::new("python3")
-                .arg("-c")
-                .arg("import faster_whisper; print('OK')")
-                .output();
+    }
 
-            Ok(python_check.is_ok() && python_check.unwrap().status.success())
+    /// 执行腾讯云识别，可附带扩展识别参数（热词与过滤选项）
+    async fn run(
+        audio_path: &str,
+        lang: &str,
+        task_id: &str,
+        cancel_rx: &mut mpsc::Receiver<()>,
+        api_keys: &Value,
+        params: Option<&ExtendedRecognitionParams>,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let (secret_id, secret_key) = Self::credentials(api_keys)?;
+        let mut options = Self::options(api_keys, secret_id, secret_key);
+        options.uploaded_url = task_uploaded_url(task_id);
+        if let Some(params) = params {
+            options.apply_params(params);
         }
-        "sensevoice" => {
-            // 检查funasr Python包
-            let python_check = std::process::Command::new("python3")
-                .arg("-c")
-                .arg("import funasr; print('OK')")
-                .output();
 
-            Ok(python_check.is_ok() && python_check.unwrap().status.success())
+        // 超长音频在静音处切分，逐段识别后合并
+        if options.max_segment_seconds > 0.0 {
+            let duration = crate::video::get_video_info(audio_path)
+                .map(|info| info.duration)
+                .unwrap_or(0.0);
+            if duration > options.max_segment_seconds {
+                info!(
+                    "音频时长{:.0}秒超过单段上限{:.0}秒，使用分段识别",
+                    duration, options.max_segment_seconds
+                );
+                let (secret_id, secret_key) = (secret_id.to_string(), secret_key.to_string());
+                return call_tencent_api_in_segments(
+                    audio_path,
+                    task_id,
+                    cancel_rx,
+                    &secret_id,
+                    &secret_key,
+                    options,
+                )
+                .await;
+            }
         }
-        _ => Ok(false),
+
+        info!("使用腾讯云引擎进行识别...");
+        call_tencent_api(
+            audio_path, lang, task_id, cancel_rx, secret_id, secret_key, options,
+        )
+        .await
     }
 }
 
-/// 检查特定模型大小是否已下载
-pub fn check_model_size_available(
-    engine: &str,
-    size: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    match engine {
-        "whisper" => {
-            // 检查Whisper模型是否已下载
-            let python_script = format!(
-                r#"
-import whisper
-try:
-    model = whisper.load_model("{}")
-    print("available")
-except Exception as e:
-    print("not_available: " + str(e))
-"#,
-                size
-            );
+#[async_trait::async_trait]
+impl RecognitionEngine for TencentEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        Self::run(
+            audio_path,
+            lang,
+            &ctx.task_id,
+            &mut ctx.cancel_rx,
+            &ctx.api_keys,
+            ctx.params.as_ref(),
+        )
+        .await
+    }
 
-            let output = std::process::Command::new("python3")
-                .arg("-c")
-                .arg(&python_script)
-                .output()?;
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh", "中文"),
+            ("en", "英语"),
+            ("ja", "日语"),
+            ("ko", "韩语"),
+        ])
+    }
 
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            Ok(output_str.contains("available"))
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        Self::credentials(keys).map(|_| ())
+    }
+
+    async fn verify_keys(&self, keys: &Value) -> ApiKeyValidation {
+        let (secret_id, secret_key) = match Self::credentials(keys) {
+            Ok(credentials) => credentials,
+            Err(e) => return ApiKeyValidation::missing(e.to_string()),
+        };
+
+        // 查询一个不存在的任务：鉴权失败返回AuthFailure，其余错误说明密钥有效
+        match describe_tencent_task_status(secret_id, secret_key, "0").await {
+            Ok(response) => {
+                let response_json: Value = match serde_json::from_str(&response) {
+                    Ok(json) => json,
+                    Err(e) => return ApiKeyValidation::invalid(format!("解析响应JSON失败: {}", e)),
+                };
+                let error_code = response_json["Response"]["Error"]["Code"]
+                    .as_str()
+                    .unwrap_or("");
+                if error_code.starts_with("AuthFailure") {
+                    let message = response_json["Response"]["Error"]["Message"]
+                        .as_str()
+                        .unwrap_or(error_code);
+                    ApiKeyValidation::invalid(format!("腾讯云密钥无效: {}", message))
+                } else {
+                    ApiKeyValidation::valid()
+                }
+            }
+            Err(e) if e.starts_with("HTTP请求失败") => ApiKeyValidation::network_error(e),
+            Err(e) => ApiKeyValidation::invalid(e),
         }
-        "faster-whisper" => {
-            // 检查Faster-Whisper模型是否已下载
-            let python_script = format!(
-                r#"
-try:
-    from faster_whisper import WhisperModel
-    model = WhisperModel("{}", device="cpu")
-    print("available")
-except ImportError:
-    print("not_available: faster_whisper module not installed")
-except Exception as e:
-    print("not_available: " + str(e))
-"#,
-                size
-            );
+    }
+}
 
-            let output = std::process::Command::new("python3")
-                .arg("-c")
-                .arg(&python_script)
-                .output()?;
+/// 百度智能云语音识别引擎
+struct BaiduEngine;
 
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            let stderr_str = String::from_utf8_lossy(&output.stderr);
+impl BaiduEngine {
+    /// 读取api_key和secret_key
+    fn credentials(keys: &Value) -> Result<(&str, &str), FlowError> {
+        BaiduKeys::parse(keys).map(|keys| (keys.api_key, keys.secret_key))
+    }
+}
 
-            // 如果有错误输出，记录但不抛出异常
-            if !stderr_str.is_empty() {
-                println!("Faster-Whisper检查警告: {}", stderr_str);
-            }
+#[async_trait::async_trait]
+impl RecognitionEngine for BaiduEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let (api_key, secret_key) = Self::credentials(&ctx.api_keys)?;
+
+        info!("使用百度智能云引擎进行识别...");
+        call_baidu_api(
+            audio_path,
+            lang,
+            &ctx.task_id,
+            &mut ctx.cancel_rx,
+            api_key,
+            secret_key,
+        )
+        .await
+    }
 
-            Ok(output_str.contains("available"))
-        }
-        "sensevoice" => {
-            // SenseVoice模型检查（简化版）
-            let python_script = r#"
-import funasr
-try:
-    # 检查funasr是否可以正常导入
-    print("available")
-except Exception as e:
-    print("not_available: " + str(e))
-"#;
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh", "中文"),
+            ("en", "英语"),
+            ("jp", "日语"),
+            ("kor", "韩语"),
+        ])
+    }
 
-            let output = std::process::Command::new("python3")
-                .arg("-c")
-                .arg(&python_script)
-                .output()?;
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        Self::credentials(keys).map(|_| ())
+    }
 
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            Ok(output_str.contains("available"))
+    async fn verify_keys(&self, keys: &Value) -> ApiKeyValidation {
+        let (api_key, secret_key) = match Self::credentials(keys) {
+            Ok(credentials) => credentials,
+            Err(e) => return ApiKeyValidation::missing(e.to_string()),
+        };
+
+        match get_baidu_access_token(api_key, secret_key).await {
+            Ok(_) => ApiKeyValidation::valid(),
+            Err(e) if e.starts_with("获取访问令牌请求失败") => {
+                ApiKeyValidation::network_error(e)
+            }
+            Err(e) => ApiKeyValidation::invalid(e),
         }
-        _ => Ok(false),
     }
 }
 
-/// 获取操作系统信息
-pub fn get_os_info() -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
-    let os = std::env::consts::OS;
-    let arch = std::env::consts::ARCH;
+/// 阿里云语音识别引擎
+struct AliyunEngine;
+
+impl AliyunEngine {
+    /// 从API密钥中读取阿里云配置（region可选，默认cn-shanghai）
+    fn config(keys: &Value) -> Result<crate::aliyun::AliyunConfig, String> {
+        let aliyun_keys = AliyunKeys::parse(keys)?;
+        Ok(crate::aliyun::AliyunConfig {
+            access_key_id: aliyun_keys.access_key_id.to_string(),
+            access_key_secret: aliyun_keys.access_key_secret.to_string(),
+            app_key: aliyun_keys.app_key.to_string(),
+            region: optional_key_field(keys, "阿里云", &["region"])?
+                .unwrap_or(crate::aliyun::DEFAULT_REGION)
+                .to_string(),
+        })
+    }
 
-    Ok(serde_json::json!({
-        "os": os,
-        "arch": arch,
-        "platform": format!("{}-{}", os, arch)
-    }))
+    /// 读取用于上传音频的COS配置（录音文件识别只接受公网URL）
+    ///
+    /// COS使用腾讯云密钥，需要在 `cos` 中单独提供secretId和secretKey。
+    fn cos_config(keys: &Value) -> Result<Option<crate::cos::CosConfig>, String> {
+        let Some(cos) = keys.get("cos") else {
+            return Ok(None);
+        };
+        let required = |names: &[&str]| required_key_field(cos, "COS", names).map(str::to_string);
+        Ok(Some(crate::cos::CosConfig {
+            secret_id: required(&["secretId", "secret_id"])?,
+            secret_key: required(&["secretKey", "secret_key"])?,
+            bucket: required(&["bucket"])?,
+            region: required(&["region"])?,
+            domain: api_key_field(cos, &["domain"]).map(|v| v.to_string()),
+            endpoint: api_key_field(cos, &["endpoint"]).map(|v| v.to_string()),
+            storage_class: api_key_field(cos, &["storageClass", "storage_class"])
+                .map(|v| v.to_string()),
+            acl: api_key_field(cos, &["acl"]).map(|v| v.to_string()),
+            keep_uploads: cos_keep_uploads(cos),
+            upload_retries: cos_upload_retries(cos),
+        }))
+    }
 }
 
-/// 获取模型详细信息
-pub fn get_model_info(
-    engine: &str,
-) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
-    let models = get_available_models()?;
+#[async_trait::async_trait]
+impl RecognitionEngine for AliyunEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let config = Self::config(&ctx.api_keys)?;
+        let cos_config = Self::cos_config(&ctx.api_keys)?;
+
+        info!("使用阿里云引擎进行识别...");
+        call_aliyun_api(
+            audio_path,
+            lang,
+            &ctx.task_id,
+            &mut ctx.cancel_rx,
+            config,
+            cos_config,
+        )
+        .await
+    }
 
-    for model in models {
-        if model["engine"].as_str() == Some(engine) {
-            let mut info = model.clone();
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh", "中文"),
+            ("en", "英语"),
+            ("ja", "日语"),
+            ("ko", "韩语"),
+        ])
+    }
 
-            // 添加安装状态
-            let installed = check_model_installation(engine).unwrap_or(false);
-            info["installed"] = serde_json::Value::Bool(installed);
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        AliyunKeys::parse(keys).map(|_| ())
+    }
+}
 
-            // 添加性能信息
-            match engine {
-                "whisper" => {
-                    info["performance"] = serde_json::json!({
-                        "wer": 0.15,
-                        "rtf": 0.3,
-                        "latency": 2000,
-                        "throughput": 150
-                    });
-                }
-                "faster-whisper" => {
-                    info["performance"] = serde_json::json!({
-                        "wer": 0.15,
-                        "rtf": 0.06,
-                        "latency": 500,
-                        "throughput": 600
-                    });
-                }
-                "sensevoice" => {
-                    info["performance"] = serde_json::json!({
-                        "wer": 0.12,
-                        "rtf": 0.1,
-                        "latency": 800,
-                        "throughput": 400
-                    });
-                }
-                _ => {}
-            }
+/// Google Cloud语音识别引擎
+struct GoogleEngine;
+
+#[async_trait::async_trait]
+impl RecognitionEngine for GoogleEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let api_key = SingleApiKey::parse(&ctx.api_keys, "Google API")?
+            .api_key
+            .to_string();
 
-            return Ok(info);
-        }
+        info!("使用Google语音识别引擎进行识别...");
+        call_google_api(audio_path, lang, &ctx.task_id, &mut ctx.cancel_rx, &api_key).await
     }
 
-    Err(format!("未找到模型: {}", engine).into())
-}
-
-/// 验证API密钥
-pub fn validate_api_keys(engine: &str, api_keys: Value) -> Result<bool, String> {
-    // 在实际应用中，这里应该调用相应API进行验证
-    // 目前简单返回true表示验证成功
-    match engine {
-        "whisper" => Ok(true), // Whisper不需要API密钥
-        "tencent" => {
-            // 检查必要的密钥
-            if let Some(obj) = api_keys.as_object() {
-                if obj.contains_key("secretId") && obj.contains_key("secretKey") {
-                    return Ok(true);
-                }
-            }
-            Err("腾讯云API需要提供secretId和secretKey".to_string())
-        }
-        "aliyun" => {
-            // 检查必要的密钥
-            if let Some(obj) = api_keys.as_object() {
-                if obj.contains_key("accessKeyId") && obj.contains_key("accessKeySecret") {
-                    return Ok(true);
-                }
-            }
-            Err("阿里云API需要提供accessKeyId和accessKeySecret".to_string())
-        }
-        "baidu" => {
-            // 检查必要的密钥
-            if let Some(obj) = api_keys.as_object() {
-                if obj.contains_key("api_key") && obj.contains_key("secret_key") {
-                    return Ok(true);
-                }
-            }
-            Err("百度API需要提供api_key和secret_key".to_string())
-        }
-        "google" => {
-            // 检查必要的密钥
-            if let Some(obj) = api_keys.as_object() {
-                if obj.contains_key("api_key") {
-                    return Ok(true);
-                }
-            }
-            Err("Google API需要提供api_key".to_string())
-        }
-        _ => Err(format!("不支持的识别引擎: {}", engine)),
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh-CN", "中文（简体）"),
+            ("zh-TW", "中文（繁体）"),
+            ("en-US", "英语（美国）"),
+            ("en-GB", "英语（英国）"),
+            ("ja-JP", "日语"),
+            ("ko-KR", "韩语"),
+            ("fr-FR", "法语"),
+            ("de-DE", "德语"),
+        ])
     }
-}
 
-/// 更新任务状态
-fn update_task_status(
-    task_id: &str,
-    state: String,
-    progress: f32,
-    result: Option<Vec<crate::video::Subtitle>>,
-    error: Option<String>,
-) {
-    println!("更新任务状态: {} -> {}, 进度: {}", task_id, state, progress);
-    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
-        if let Some(task) = tasks.get_mut(task_id) {
-            task.status = RecognitionStatus {
-                status: state.clone(),
-                progress,
-                result,
-                error,
-            };
-            println!("任务状态已更新: {} -> {}", task_id, state);
-        } else {
-            println!("警告: 任务不存在: {}", task_id);
-        }
-    } else {
-        println!("错误: 无法获取任务锁");
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        SingleApiKey::parse(keys, "Google API").map(|_| ())
     }
-}
 
-/// 清理已完成的任务
-fn cleanup_completed_task(task_id: &str) {
-    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
-        if let Some(task) = tasks.get(task_id) {
-            // 只清理已完成、失败或取消的任务
-            match task.status.status.as_str() {
-                "completed" | "failed" | "cancelled" => {
-                    println!("清理已完成的任务: {}", task_id);
-                    tasks.remove(task_id);
-                }
-                _ => {
-                    // 任务仍在进行中，不清理
+    async fn verify_keys(&self, keys: &Value) -> ApiKeyValidation {
+        let api_key = match SingleApiKey::parse(keys, "Google API") {
+            Ok(keys) => keys.api_key,
+            Err(e) => return ApiKeyValidation::missing(e.to_string()),
+        };
+
+        // 发送空识别请求：密钥无效时返回API_KEY_INVALID，否则返回参数错误
+        let response = reqwest::Client::new()
+            .post(format!(
+                "https://speech.googleapis.com/v1/speech:recognize?key={}",
+                api_key
+            ))
+            .json(&json!({}))
+            .send()
+            .await;
+
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                if body.contains("API_KEY_INVALID")
+                    || status == reqwest::StatusCode::UNAUTHORIZED
+                    || status == reqwest::StatusCode::FORBIDDEN
+                {
+                    ApiKeyValidation::invalid(format!("Google API密钥无效: HTTP {}", status))
+                } else {
+                    ApiKeyValidation::valid()
                 }
             }
+            Err(e) => ApiKeyValidation::network_error(format!("连接Google API失败: {}", e)),
         }
     }
 }
 
-/// 使用配置参数进行识别（新的统一入口）
-async fn call_recognition_with_config(
-    params: &ExtendedRecognitionParams,
-    task_id: &str,
-    cancel_rx: &mut mpsc::Receiver<()>,
-) -> Result<Vec<crate::video::Subtitle>, String> {
-    use std::fs;
-    use std::process::Command;
+/// OpenAI语音识别引擎
+struct OpenAiEngine;
+
+#[async_trait::async_trait]
+impl RecognitionEngine for OpenAiEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let api_key = SingleApiKey::parse(&ctx.api_keys, "OpenAI API")?
+            .api_key
+            .to_string();
 
-    // 检查音频文件是否存在
-    if !std::path::Path::new(&params.audio_path).exists() {
-        return Err(format!("音频文件不存在: {}", params.audio_path));
+        info!("使用OpenAI Whisper API进行识别...");
+        call_openai_whisper_api(audio_path, lang, &ctx.task_id, &mut ctx.cancel_rx, &api_key).await
     }
 
-    update_task_status(task_id, "processing".to_string(), 0.1, None, None);
-
-    match params.engine.as_str() {
-        "whisper" => call_whisper_with_config(params, task_id, cancel_rx).await,
-        "faster-whisper" => call_faster_whisper_with_config(params, task_id, cancel_rx).await,
-        "sensevoice" => call_sensevoice_with_config(params, task_id, cancel_rx).await,
-        _ => {
-            // 回退到原有的Whisper API
-            call_whisper_api(&params.audio_path, &params.language, task_id, cancel_rx).await
-        }
+    fn languages(&self) -> Vec<Language> {
+        WhisperEngine.languages()
     }
-}
 
-/// 调用Whisper进行本地语音识别
-async fn call_whisper_api(
-    audio_path: &str,
-    language: &str,
-    task_id: &str,
-    cancel_rx: &mut mpsc::Receiver<()>,
-) -> Result<Vec<crate::video::Subtitle>, String> {
-    // 检查音频文件是否存在
-    if !std::path::Path::new(audio_path).exists() {
-        return Err(format!("音频文件不存在: {}", audio_path));
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        SingleApiKey::parse(keys, "OpenAI API").map(|_| ())
     }
 
-    update_task_status(task_id, "processing".to_string(), 0.1, None, None);
+    async fn verify_keys(&self, keys: &Value) -> ApiKeyValidation {
+        let api_key = match SingleApiKey::parse(keys, "OpenAI API") {
+            Ok(keys) => keys.api_key,
+            Err(e) => return ApiKeyValidation::missing(e.to_string()),
+        };
 
-    // 检查whisper命令是否可用
-    let whisper_check = Command::new("whisper").arg("--help").output();
+        let response = reqwest::Client::new()
+            .get("https://api.openai.com/v1/models")
+            .bearer_auth(api_key)
+            .send()
+            .await;
 
-    match whisper_check {
-        Ok(_) => {
-            println!("发现whisper命令，使用本地Whisper进行识别");
-            call_local_whisper(audio_path, language, task_id, cancel_rx).await
-        }
-        Err(_) => {
-            println!("未找到whisper命令，尝试使用Python whisper");
-            call_python_whisper(audio_path, language, task_id, cancel_rx).await
+        match response {
+            Ok(response) if response.status().is_success() => ApiKeyValidation::valid(),
+            Ok(response) => {
+                ApiKeyValidation::invalid(format!("OpenAI API密钥无效: HTTP {}", response.status()))
+            }
+            Err(e) => ApiKeyValidation::network_error(format!("连接OpenAI API失败: {}", e)),
         }
     }
 }
 
-/// 使用配置的Whisper进行识别
-async fn call_whisper_with_config(
-    params: &ExtendedRecognitionParams,
-    task_id: &str,
-    cancel_rx: &mut mpsc::Receiver<()>,
-) -> Result<Vec<crate::video::Subtitle>, String> {
-    println!("使用Whisper模型进行识别，配置: {:?}", params.model_config);
+/// AWS Transcribe语音识别引擎
+struct AwsEngine;
+
+#[async_trait::async_trait]
+impl RecognitionEngine for AwsEngine {
+    async fn transcribe(
+        &self,
+        audio: &Path,
+        lang: &str,
+        ctx: &mut TaskCtx,
+    ) -> Result<Vec<crate::video::Subtitle>, String> {
+        let audio_path = audio_path_str(audio)?;
+        let config = aws_config_from_api_keys(Some(&ctx.api_keys))?;
+
+        info!("使用AWS Transcribe引擎进行识别...");
+        call_aws_transcribe_api(audio_path, lang, &ctx.task_id, &mut ctx.cancel_rx, config).await
+    }
 
-    // 检查whisper命令是否可用
-    let whisper_check = Command::new("whisper").arg("--help").output();
+    fn languages(&self) -> Vec<Language> {
+        language_list(&[
+            ("zh-CN", "中文（简体）"),
+            ("zh-TW", "中文（繁体）"),
+            ("en-US", "英语（美国）"),
+            ("en-GB", "英语（英国）"),
+            ("ja-JP", "日语"),
+            ("ko-KR", "韩语"),
+            ("fr-FR", "法语"),
+            ("de-DE", "德语"),
+            ("es-ES", "西班牙语"),
+        ])
+    }
 
-    match whisper_check {
-        Ok(_) => {
-            println!("发现whisper命令，使用本地Whisper进行识别");
-            call_local_whisper_with_config(params, task_id, cancel_rx).await
-        }
-        Err(_) => {
-            println!("未找到whisper命令，尝试使用Python whisper");
-            call_python_whisper_with_config(params, task_id, cancel_rx).await
-        }
+    fn validate_keys(&self, keys: &Value) -> Result<(), FlowError> {
+        aws_config_from_api_keys(Some(keys)).map(|_| ())
     }
 }
 
-/// 使用配置的Faster-Whisper进行识别
-async fn call_faster_whisper_with_config(
-    params: &ExtendedRecognitionParams,
-    task_id: &str,
-    cancel_rx: &mut mpsc::Receiver<()>,
-) -> Result<Vec<crate::video::Subtitle>, String> {
-    println!(
-        "使用Faster-Whisper模型进行识别，配置: {:?}",
-        params.model_config
-    );
+/// 开始语音识别任务
+///
+/// 未指定的引擎和语言使用默认配置；`dedupe`为true时，若同一音频和引擎已有进行中的任务，
+/// 则拒绝重复提交；`bypass_cache`为true时不读取识别结果缓存（结果仍会写入缓存）
+pub fn start_recognition(
+    task_id: String,
+    audio_path: &str,
+    options: StartRecognitionOptions,
+) -> Result<(), FlowError> {
+    let (engine, language) = resolve_engine_and_language(options.engine, options.language);
+    start_recognition_task(
+        task_id,
+        audio_path,
+        &engine,
+        &language,
+        options.api_keys,
+        options.dedupe,
+        TaskOrigin {
+            model: options.model,
+            bypass_cache: options.bypass_cache,
+            ..Default::default()
+        },
+    )
+}
 
-    // 首先检查模型是否已下载
-    let model_available =
-        check_model_size_available(&params.model_config.engine, &params.model_config.size)
-            .unwrap_or(false);
+/// 批量识别中的一个文件
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchFile {
+    #[serde(alias = "taskId")]
+    pub task_id: String,
+    #[serde(alias = "audioPath")]
+    pub audio_path: String,
+}
 
-    if !model_available {
-        update_task_status(
-            task_id,
-            "processing".to_string(),
-            0.05,
-            None,
-            Some(format!(
-                "正在下载 {} 模型 ({})，请稍候...",
-                params.model_config.engine, params.model_config.size
-            )),
-        );
-    } else {
-        update_task_status(
-            task_id,
-            "processing".to_string(),
-            0.1,
-            None,
-            Some("正在初始化模型...".to_string()),
-        );
-    }
+/// 批次中的子任务记录，`start_error` 为创建任务时的错误（如音频无声）
+#[derive(Debug, Clone)]
+struct BatchEntry {
+    task_id: String,
+    audio_path: String,
+    start_error: Option<String>,
+}
 
-    // 构建Python脚本来调用faster-whisper
-    let python_script = format!(
-        r#"
-import sys
-import json
-from faster_whisper import WhisperModel
+/// 批量识别中单个文件的状态
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchFileStatus {
+    pub task_id: String,
+    pub audio_path: String,
+    /// 子任务状态；任务已过保留时间被清理时为 "expired"
+    pub status: String,
+    pub progress: f32,
+    pub error: Option<String>,
+}
 
-# 尝试导入繁简转换库
-converter = None
-try:
-    import opencc
-    converter = opencc.OpenCC('t2s')  # 繁体转简体
-except ImportError:
-    try:
-        from zhconv import convert
-        def convert_to_simplified(text):
-            return convert(text, 'zh-cn')
-        converter = convert_to_simplified
-    except ImportError:
-        print("警告: 未安装繁简转换库，将保持原始输出", file=sys.stderr)
+/// 批量识别的汇总状态
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchStatus {
+    pub batch_id: String,
+    /// 各文件进度的平均值（已结束的文件计为1.0）
+    pub progress: f32,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// 所有文件都已结束
+    pub finished: bool,
+    pub files: Vec<BatchFileStatus>,
+}
 
-try:
-    # 检查模型是否需要下载
-    import os
-    from pathlib import Path
+/// 批量识别多个音频文件：为每个文件创建识别任务后立即返回
+///
+/// 子任务与单个识别任务相同，受并发名额限制排队执行，也可以单独查询、取消或重试。
+/// 个别文件无法创建任务时记录为失败，其余文件照常识别；全部失败时返回错误。
+pub fn start_batch_recognition(
+    batch_id: String,
+    files: Vec<BatchFile>,
+    engine: &str,
+    language: &str,
+    api_keys: Option<Value>,
+) -> Result<(), String> {
+    if files.is_empty() {
+        return Err("批量识别的文件列表为空".to_string());
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = files
+        .iter()
+        .find(|file| !seen.insert(file.task_id.as_str()))
+    {
+        return Err(format!("批量识别中的任务ID重复: {}", duplicate.task_id));
+    }
+    if RECOGNITION_BATCHES
+        .lock()
+        .map_err(|_| "无法获取批量任务锁".to_string())?
+        .contains_key(&batch_id)
+    {
+        return Err(format!("批次ID已存在: {}", batch_id));
+    }
 
-    # 获取模型缓存路径
-    cache_dir = Path.home() / ".cache" / "huggingface" / "hub"
-    model_pattern = "models--Systran--faster-whisper-{model_size}"
-    model_exists = any(cache_dir.glob("*" + model_pattern + "*")) if cache_dir.exists() else False
+    let entries: Vec<BatchEntry> = files
+        .into_iter()
+        .map(|file| {
+            let start_error = start_recognition_task(
+                file.task_id.clone(),
+                &file.audio_path,
+                engine,
+                language,
+                api_keys.clone(),
+                false,
+                TaskOrigin::default(),
+            )
+            .err()
+            .map(|e| e.to_string());
+            if let Some(err) = &start_error {
+                warn!("批量识别文件无法开始: {} -> {}", file.audio_path, err);
+            }
+            BatchEntry {
+                task_id: file.task_id,
+                audio_path: file.audio_path,
+                start_error,
+            }
+        })
+        .collect();
+
+    if let Some(err) = entries
+        .iter()
+        .map(|entry| entry.start_error.as_deref())
+        .collect::<Option<Vec<_>>>()
+        .and_then(|errors| errors.first().copied())
+    {
+        return Err(format!("批量识别的文件均无法开始: {}", err));
+    }
 
-    if not model_exists:
-        print("DOWNLOAD_START", file=sys.stderr)
+    info!("开始批量识别: {}，共{}个文件", batch_id, entries.len());
+    RECOGNITION_BATCHES
+        .lock()
+        .map_err(|_| "无法获取批量任务锁".to_string())?
+        .insert(batch_id, entries);
+    Ok(())
+}
 
-    # 初始化模型（如果需要会自动下载）
-    model = WhisperModel(
-        "{model_size}",
-        device="{device}",
-        compute_type="{compute_type}"
-    )
+/// 查询批量识别的汇总进度和各文件状态
+pub fn get_batch_status(batch_id: &str) -> Result<BatchStatus, String> {
+    let entries = RECOGNITION_BATCHES
+        .lock()
+        .map_err(|_| "无法获取批量任务锁".to_string())?
+        .get(batch_id)
+        .cloned()
+        .ok_or_else(|| format!("批次不存在: {}", batch_id))?;
+    let tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
 
-    if not model_exists:
-        print("DOWNLOAD_COMPLETE", file=sys.stderr)
+    let files: Vec<BatchFileStatus> = entries
+        .into_iter()
+        .map(|entry| {
+            let (status, progress, error) = match (&entry.start_error, tasks.get(&entry.task_id)) {
+                (Some(err), _) => ("failed".to_string(), 1.0, Some(err.clone())),
+                (None, Some(task)) => (
+                    task.status.status.clone(),
+                    task.status.progress,
+                    task.status.error.clone(),
+                ),
+                (None, None) => ("expired".to_string(), 1.0, None),
+            };
+            BatchFileStatus {
+                task_id: entry.task_id,
+                audio_path: entry.audio_path,
+                status,
+                progress,
+                error,
+            }
+        })
+        .collect();
+
+    let count = |status: &str| files.iter().filter(|file| file.status == status).count();
+    let active = |file: &BatchFileStatus| matches!(file.status.as_str(), "pending" | "processing");
+    let progress = files
+        .iter()
+        .map(|file| {
+            if active(file) {
+                file.progress.clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        })
+        .sum::<f32>()
+        / files.len().max(1) as f32;
+
+    Ok(BatchStatus {
+        batch_id: batch_id.to_string(),
+        progress,
+        completed: count("completed"),
+        failed: count("failed"),
+        cancelled: count("cancelled"),
+        finished: !files.iter().any(active),
+        files,
+    })
+}
+
+/// 查找同一音频和引擎下进行中的任务，返回其任务ID
+fn find_active_duplicate(
+    tasks: &HashMap<String, RecognitionTask>,
+    audio_path: &str,
+    engine: &str,
+) -> Option<String> {
+    tasks
+        .values()
+        .find(|task| {
+            task.audio_path == audio_path
+                && task.engine == engine
+                && matches!(task.status.status.as_str(), "pending" | "processing")
+        })
+        .map(|task| task.task_id.clone())
+}
+
+/// 登记新任务：任务ID已存在，或 `dedupe` 时同一音频和引擎已有进行中的任务，则拒绝登记
+fn register_task(task: RecognitionTask, dedupe: bool) -> Result<(), FlowError> {
+    let mut tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    if tasks.contains_key(&task.task_id) {
+        return Err(format!("任务ID已存在: {}", task.task_id).into());
+    }
+
+    if dedupe {
+        if let Some(existing_id) = find_active_duplicate(&tasks, &task.audio_path, &task.engine) {
+            return Err(format!("相同音频的识别任务正在进行中: {}", existing_id).into());
+        }
+    }
+
+    tasks.insert(task.task_id.clone(), task);
+    Ok(())
+}
+
+/// 检查音频是否为空或无声
+///
+/// FFmpeg音量检测会解码音频，放到阻塞线程池中执行，避免占用异步运行时的工作线程。
+async fn check_audio_content(audio_path: &str) -> Result<(), String> {
+    let audio_path = audio_path.to_string();
+    let has_content =
+        tokio::task::spawn_blocking(move || crate::video::audio_has_content(&audio_path))
+            .await
+            .unwrap_or(true);
+    if has_content {
+        Ok(())
+    } else {
+        Err("提取的音频为空或无声".to_string())
+    }
+}
+
+/// 创建并启动识别任务（重试或翻译任务附带额外信息）
+fn start_recognition_task(
+    task_id: String,
+    audio_path: &str,
+    engine: &str,
+    language: &str,
+    api_keys: Option<Value>,
+    dedupe: bool,
+    origin: TaskOrigin,
+) -> Result<(), FlowError> {
+    // 查找识别引擎并检查API密钥
+    let recognition_engine = require_engine(engine)?;
+    let api_keys = api_keys.unwrap_or(Value::Null);
+    recognition_engine.validate_keys(&api_keys)?;
+    if let Some(model) = &origin.model {
+        if matches!(engine, "whisper" | "faster-whisper") {
+            validate_whisper_model(model)?;
+        }
+    }
+
+    // 创建取消通道
+    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+    // 创建新任务
+    let task = RecognitionTask {
+        task_id: task_id.clone(),
+        audio_path: audio_path.to_string(),
+        engine: engine.to_string(),
+        language: language.to_string(),
+        status: RecognitionStatus {
+            status: "pending".to_string(),
+            progress: 0.0,
+            result: None,
+            error: None,
+            warnings: Vec::new(),
+            retry_of: origin.retry_of,
+            detected_language: None,
+        },
+        cancel_sender: Some(cancel_tx),
+        api_keys: Some(api_keys.clone()),
+        model: origin.model.clone(),
+        extended_params: None,
+        uploaded_url: origin.uploaded_url,
+        translate_to: origin.translate_to.clone(),
+        progress_range: origin.translate_to.as_ref().map(|_| (0.0, 0.7)),
+        live: false,
+    };
+
+    // 存储任务
+    register_task(task, dedupe)?;
+
+    // 启动异步任务
+    let task_id_clone = task_id.clone();
+    let audio_path = audio_path.to_string();
+    let engine = engine.to_string();
+    let language = language.to_string();
+    let translate_to = origin.translate_to;
+    let use_cache = !origin.bypass_cache;
+    // 选择的模型影响识别结果，计入缓存键
+    let model = origin.model;
+    let variant = model.clone().unwrap_or_default();
+
+    tokio::spawn(async move {
+        if let Err(err) = check_audio_content(&audio_path).await {
+            finish_task_with_error(&task_id_clone, err);
+            schedule_task_cleanup(task_id_clone);
+            return;
+        }
+
+        // 排队等待名额，排队期间取消的任务不会启动
+        let _permit = match acquire_recognition_slot(&task_id_clone, &mut cancel_rx).await {
+            Ok(permit) => permit,
+            Err(err) => {
+                finish_task_with_error(&task_id_clone, err);
+                schedule_task_cleanup(task_id_clone);
+                return;
+            }
+        };
+
+        // 更新状态为处理中
+        update_task_status(&task_id_clone, "processing".to_string(), 0.0, None, None);
+
+        let cache_key = if use_cache {
+            crate::cache::task_cache_key(
+                audio_path.clone(),
+                engine.clone(),
+                language.clone(),
+                variant,
+            )
+            .await
+        } else {
+            None
+        };
+
+        let mut ctx = TaskCtx {
+            task_id: task_id_clone.clone(),
+            cancel_rx,
+            api_keys,
+            model,
+            params: None,
+        };
+        let result = crate::cache::cached_or(
+            cache_key,
+            recognition_engine.transcribe(Path::new(&audio_path), &language, &mut ctx),
+        )
+        .await
+        .map(|subtitles| clamp_to_audio_duration(&audio_path, subtitles))
+        .map(drop_empty_cues)
+        .map(crate::glossary::apply_glossary);
+
+        // 需要翻译时，在识别结果上追加译文生成双语字幕
+        let result = match (result, translate_to) {
+            (Ok(subtitles), Some(target)) => {
+                translate_to_bilingual(subtitles, &language, &target, &mut ctx).await
+            }
+            (result, _) => result,
+        };
+
+        // 处理结果
+        match result {
+            Ok(subtitles) => {
+                update_task_status(
+                    &task_id_clone,
+                    "completed".to_string(),
+                    1.0,
+                    Some(subtitles),
+                    None,
+                );
+            }
+            Err(err) => finish_task_with_error(&task_id_clone, err),
+        }
+
+        // 延迟清理已结束的任务（给前端足够时间获取结果）
+        schedule_task_cleanup(task_id_clone);
+    });
+
+    Ok(())
+}
+
+/// 从http(s)地址直接转写：FFmpeg读取远程视频提取音频后开始识别，返回本地音频路径
+///
+/// 提取的临时音频在任务完成或被清理后删除。
+pub fn transcribe_url(
+    task_id: String,
+    url: &str,
+    engine: &str,
+    language: &str,
+    api_keys: Option<Value>,
+) -> Result<String, String> {
+    let video_info = crate::video::get_video_info(url)?;
+    let audio_track_id = video_info
+        .audio_tracks
+        .first()
+        .map(|track| track.id)
+        .unwrap_or(0);
+
+    let audio_path = crate::video::extract_audio(url, audio_track_id)?;
+    // 先登记再启动：命中缓存的任务可能立即结束
+    delete_audio_when_finished(&task_id, &audio_path);
+    start_recognition(
+        task_id.clone(),
+        &audio_path,
+        StartRecognitionOptions {
+            engine: Some(engine.to_string()),
+            language: Some(language.to_string()),
+            api_keys,
+            ..Default::default()
+        },
+    )
+    .inspect_err(|_| remove_temp_audio(&task_id))?;
+
+    Ok(audio_path)
+}
+
+/// 识别视频语音并翻译为目标语言，生成原文在上、译文在下的双语字幕，返回本地音频路径
+///
+/// 识别与翻译在同一任务中完成：识别阶段进度占0~0.7，翻译阶段占0.7~1.0。
+/// 提取的临时音频在任务完成或被清理后删除。
+/// 翻译使用腾讯云机器翻译，密钥读取自 `api_keys` 中的 `translate`/`tencent` 子项或顶层的secretId和secretKey。
+pub fn transcribe_and_translate(
+    task_id: String,
+    video_path: &str,
+    engine: &str,
+    source_lang: &str,
+    target_lang: &str,
+    api_keys: Option<Value>,
+) -> Result<String, String> {
+    let target_lang = target_lang.trim();
+    if target_lang.is_empty() {
+        return Err("未指定翻译目标语言".to_string());
+    }
+    if tmt_language_code(source_lang) == tmt_language_code(target_lang) {
+        return Err("翻译目标语言与源语言相同".to_string());
+    }
+    translation_credentials(api_keys.as_ref().unwrap_or(&Value::Null))?;
+
+    let video_info = crate::video::get_video_info(video_path)?;
+    let audio_track_id = video_info
+        .audio_tracks
+        .first()
+        .map(|track| track.id)
+        .unwrap_or(0);
+
+    let audio_path = crate::video::extract_audio(video_path, audio_track_id)?;
+    delete_audio_when_finished(&task_id, &audio_path);
+    start_recognition_task(
+        task_id.clone(),
+        &audio_path,
+        engine,
+        source_lang,
+        api_keys,
+        false,
+        TaskOrigin {
+            translate_to: Some(target_lang.to_string()),
+            ..Default::default()
+        },
+    )
+    .inspect_err(|_| remove_temp_audio(&task_id))?;
+
+    Ok(audio_path)
+}
+
+/// 使用扩展配置开始识别
+pub fn start_recognition_with_config(
+    task_id: String,
+    mut params: ExtendedRecognitionParams,
+    api_keys: Option<Value>,
+    dedupe: bool,
+    bypass_cache: bool,
+) -> Result<(), FlowError> {
+    // 单独指定的模型覆盖model_config中的大小，启动前检查模型名称
+    if let Some(model) = params.model.take() {
+        params.model_config.size = model;
+    }
+    if matches!(params.engine.as_str(), "whisper" | "faster-whisper") {
+        validate_whisper_model(&params.model_config.size)?;
+    } else if params.task == WhisperTask::Translate {
+        return Err(format!(
+            "翻译为英文（task=translate）仅支持whisper和faster-whisper引擎，当前引擎: {}",
+            params.engine
+        )
+        .into());
+    }
+
+    // 未指定的提示词和热词回退到默认配置
+    if params.initial_prompt.is_none() || params.hotwords.is_empty() {
+        if let Ok(defaults) = get_recognition_defaults() {
+            if params.initial_prompt.is_none() {
+                params.initial_prompt = defaults.initial_prompt;
+            }
+            if params.hotwords.is_empty() {
+                params.hotwords = defaults.hotwords;
+            }
+        }
+    }
+
+    start_recognition_with_config_task(
+        task_id,
+        params,
+        api_keys,
+        dedupe,
+        TaskOrigin {
+            bypass_cache,
+            ..Default::default()
+        },
+    )
+}
+
+/// 创建并启动扩展配置识别任务（重试任务附带来源信息）
+fn start_recognition_with_config_task(
+    task_id: String,
+    params: ExtendedRecognitionParams,
+    api_keys: Option<Value>,
+    dedupe: bool,
+    origin: TaskOrigin,
+) -> Result<(), FlowError> {
+    info!("开始扩展配置识别任务: {}", task_id);
+    debug!("识别参数: {:?}", params);
+
+    // 查找识别引擎并检查API密钥
+    let recognition_engine = require_engine(&params.engine)?;
+    let api_keys = api_keys.unwrap_or(Value::Null);
+    recognition_engine.validate_keys(&api_keys)?;
+
+    // 创建取消通道
+    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+    // 创建识别任务
+    let task = RecognitionTask {
+        task_id: task_id.clone(),
+        audio_path: params.audio_path.clone(),
+        engine: params.engine.clone(),
+        language: params.language.clone(),
+        status: RecognitionStatus {
+            status: "pending".to_string(),
+            progress: 0.0,
+            result: None,
+            error: None,
+            warnings: Vec::new(),
+            retry_of: origin.retry_of,
+            detected_language: None,
+        },
+        cancel_sender: Some(cancel_tx),
+        api_keys: Some(api_keys.clone()),
+        // 记录模型，重启后扩展参数丢失时重试仍使用同一模型
+        model: matches!(params.engine.as_str(), "whisper" | "faster-whisper")
+            .then(|| params.model_config.size.clone()),
+        extended_params: Some(params.clone()),
+        uploaded_url: origin.uploaded_url,
+        translate_to: None,
+        progress_range: None,
+        live: false,
+    };
+
+    // 存储任务
+    register_task(task, dedupe)?;
+
+    let task_id_clone = task_id.clone();
+    let use_cache = !origin.bypass_cache;
+
+    tokio::spawn(async move {
+        if let Err(err) = check_audio_content(&params.audio_path).await {
+            finish_task_with_error(&task_id_clone, err);
+            schedule_task_cleanup(task_id_clone);
+            return;
+        }
+
+        // 排队等待名额，排队期间取消的任务不会启动
+        let _permit = match acquire_recognition_slot(&task_id_clone, &mut cancel_rx).await {
+            Ok(permit) => permit,
+            Err(err) => {
+                finish_task_with_error(&task_id_clone, err);
+                schedule_task_cleanup(task_id_clone);
+                return;
+            }
+        };
+
+        // 更新状态为处理中
+        update_task_status(&task_id_clone, "processing".to_string(), 0.0, None, None);
+
+        // 缓存键包含除音频路径外的全部识别参数
+        let cache_key = if use_cache {
+            let variant = serde_json::to_string(&ExtendedRecognitionParams {
+                audio_path: String::new(),
+                ..params.clone()
+            })
+            .unwrap_or_default();
+            crate::cache::task_cache_key(
+                params.audio_path.clone(),
+                params.engine.clone(),
+                params.language.clone(),
+                variant,
+            )
+            .await
+        } else {
+            None
+        };
+
+        // 使用扩展配置进行识别
+        let mut ctx = TaskCtx {
+            task_id: task_id_clone.clone(),
+            cancel_rx,
+            api_keys,
+            model: None,
+            params: Some(params.clone()),
+        };
+        let result = crate::cache::cached_or(
+            cache_key,
+            call_recognition_with_config(recognition_engine.as_ref(), &params, &mut ctx),
+        )
+        .await
+        .map(|subtitles| clamp_to_audio_duration(&params.audio_path, subtitles))
+        .map(drop_empty_cues)
+        .map(crate::glossary::apply_glossary);
+
+        match result {
+            Ok(subtitles) => {
+                info!("识别成功，共生成{}条字幕", subtitles.len());
+                update_task_status(
+                    &task_id_clone,
+                    "completed".to_string(),
+                    1.0,
+                    Some(subtitles),
+                    None,
+                );
+            }
+            Err(e) => {
+                error!("识别失败: {}", e);
+                finish_task_with_error(&task_id_clone, e);
+            }
+        }
+
+        schedule_task_cleanup(task_id_clone);
+    });
+
+    Ok(())
+}
+
+/// 重试失败或已取消的任务，使用相同参数创建新任务并返回新任务ID
+pub fn retry_recognition(task_id: &str) -> Result<String, String> {
+    let original = {
+        let tasks = RECOGNITION_TASKS
+            .lock()
+            .map_err(|_| "无法获取任务锁".to_string())?;
+        tasks
+            .get(task_id)
+            .cloned()
+            .ok_or_else(|| format!("任务不存在: {}", task_id))?
+    };
+
+    if original.live {
+        return Err("实时识别任务不支持重试".to_string());
+    }
+    if !matches!(original.status.status.as_str(), "failed" | "cancelled") {
+        return Err(format!(
+            "只能重试失败或已取消的任务，当前状态: {}",
+            original.status.status
+        ));
+    }
+
+    let new_task_id = uuid::Uuid::new_v4().to_string();
+    let origin = TaskOrigin {
+        retry_of: Some(task_id.to_string()),
+        model: original.model.clone(),
+        uploaded_url: original.uploaded_url.clone(),
+        translate_to: original.translate_to.clone(),
+        ..Default::default()
+    };
+
+    info!("重试任务: {} -> {}", task_id, new_task_id);
+
+    // 临时音频随重试任务结束时删除；启动失败时归还原任务
+    transfer_temp_audio(task_id, &new_task_id);
+    let started = match original.extended_params {
+        Some(params) => start_recognition_with_config_task(
+            new_task_id.clone(),
+            params,
+            original.api_keys,
+            false,
+            origin,
+        )
+        .map_err(|e| e.to_string()),
+        None => start_recognition_task(
+            new_task_id.clone(),
+            &original.audio_path,
+            &original.engine,
+            &original.language,
+            original.api_keys,
+            false,
+            origin,
+        )
+        .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = started {
+        transfer_temp_audio(&new_task_id, task_id);
+        return Err(e);
+    }
+
+    Ok(new_task_id)
+}
+
+/// 实时识别默认的识别窗口时长（秒）
+const LIVE_WINDOW_SECONDS: f64 = 10.0;
+
+/// 开始实时识别
+///
+/// FFmpeg持续采集麦克风、直播流或本地文件的音频，每累积 `window_seconds` 秒
+/// 交给识别引擎识别一次，结果按时间偏移追加到任务字幕中，前端轮询任务状态即可
+/// 获得不断增长的字幕。调用 `stop_live_recognition` 后识别剩余音频并结束任务。
+pub fn start_live_recognition(
+    task_id: String,
+    source: Option<String>,
+    engine: &str,
+    language: &str,
+    api_keys: Option<Value>,
+    window_seconds: Option<f64>,
+) -> Result<(), String> {
+    let recognition_engine = require_engine(engine)?;
+    let api_keys = api_keys.unwrap_or(Value::Null);
+    recognition_engine.validate_keys(&api_keys)?;
+
+    let window_seconds = window_seconds.unwrap_or(LIVE_WINDOW_SECONDS);
+    if !(2.0..=60.0).contains(&window_seconds) {
+        return Err("实时识别窗口时长需在2到60秒之间".to_string());
+    }
+
+    let mut tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+    if tasks.contains_key(&task_id) {
+        return Err(format!("任务ID已存在: {}", task_id));
+    }
+
+    // 启动FFmpeg采集
+    let mut command =
+        tokio::process::Command::from(crate::video::live_capture_command(source.as_deref())?);
+    let capture = command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("启动音频采集失败: {}（请在设置中配置FFmpeg路径）", e))?;
+
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    let source_label = source.unwrap_or_else(|| "mic".to_string());
+    info!("开始实时识别: {}，来源: {}", task_id, source_label);
+
+    tasks.insert(
+        task_id.clone(),
+        RecognitionTask {
+            task_id: task_id.clone(),
+            audio_path: source_label,
+            engine: engine.to_string(),
+            language: language.to_string(),
+            status: RecognitionStatus {
+                status: "processing".to_string(),
+                progress: 0.0,
+                result: None,
+                error: Some("正在采集音频...".to_string()),
+                warnings: Vec::new(),
+                retry_of: None,
+                detected_language: None,
+            },
+            cancel_sender: Some(cancel_tx),
+            api_keys: Some(api_keys.clone()),
+            model: None,
+            extended_params: None,
+            uploaded_url: None,
+            translate_to: None,
+            // 实时任务没有总进度，忽略引擎上报的进度
+            progress_range: Some((0.0, 0.0)),
+            live: true,
+        },
+    );
+
+    let language = language.to_string();
+    tokio::spawn(async move {
+        let mut ctx = TaskCtx {
+            task_id: task_id.clone(),
+            cancel_rx,
+            api_keys,
+            model: None,
+            params: None,
+        };
+        let result = run_live_recognition(
+            capture,
+            recognition_engine.as_ref(),
+            &language,
+            window_seconds,
+            &mut ctx,
+        )
+        .await;
+
+        set_task_progress_range(&task_id, None);
+        match result {
+            Ok(subtitles) => {
+                info!("实时识别结束: {}，共{}条字幕", task_id, subtitles.len());
+                update_task_status(
+                    &task_id,
+                    "completed".to_string(),
+                    1.0,
+                    Some(subtitles),
+                    None,
+                );
+            }
+            Err(err) => finish_task_with_error(&task_id, err),
+        }
+
+        schedule_task_cleanup(task_id);
+    });
+
+    Ok(())
+}
+
+/// 预览识别最多截取的秒数
+const PREVIEW_MAX_SECONDS: f64 = 300.0;
+/// 预览识别的超时时间（秒）
+const PREVIEW_TIMEOUT_SECS: u64 = 120;
+
+/// 快速预览识别效果：只识别视频开头的一段音频，直接返回字幕
+///
+/// 不经过任务系统和识别缓存，用于在完整识别前比较不同引擎/模型的效果。
+/// 截取时长限制在 `PREVIEW_MAX_SECONDS` 内，识别超过 `PREVIEW_TIMEOUT_SECS` 秒时返回错误。
+pub async fn preview_recognition(
+    video_path: &str,
+    seconds: f64,
+    engine: &str,
+    language: &str,
+    api_keys: Option<Value>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    let recognition_engine = require_engine(engine)?;
+    let api_keys = api_keys.unwrap_or(Value::Null);
+    recognition_engine.validate_keys(&api_keys)?;
+
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("无效的预览时长: {}", seconds));
+    }
+    let seconds = seconds.min(PREVIEW_MAX_SECONDS);
+
+    let video_path_owned = video_path.to_string();
+    let audio_path = tokio::task::spawn_blocking(move || {
+        let video_info = crate::video::get_video_info(&video_path_owned)?;
+        let audio_track_id = video_info
+            .audio_tracks
+            .first()
+            .map(|track| track.id)
+            .ok_or_else(|| "视频中没有音轨".to_string())?;
+        crate::video::extract_audio_range(&video_path_owned, audio_track_id, 0.0, seconds)
+    })
+    .await
+    .map_err(|e| format!("提取预览音频失败: {}", e))??;
+
+    if check_audio_content(&audio_path).await.is_err() {
+        let _ = std::fs::remove_file(&audio_path);
+        return Err(format!("视频开头{:.0}秒的音频为空或无声", seconds));
+    }
+
+    // 发送端保留到识别结束，避免引擎误判为已取消
+    let (_cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    let mut ctx = TaskCtx {
+        task_id: format!("preview-{}", uuid::Uuid::new_v4()),
+        cancel_rx,
+        api_keys,
+        model: None,
+        params: None,
+    };
+    info!(
+        "预览识别: {} 前{:.0}秒，引擎: {}",
+        video_path, seconds, engine
+    );
+    let result = tokio::time::timeout(
+        Duration::from_secs(PREVIEW_TIMEOUT_SECS),
+        recognition_engine.transcribe(Path::new(&audio_path), language, &mut ctx),
+    )
+    .await;
+    let _ = std::fs::remove_file(&audio_path);
+
+    let subtitles = result.map_err(|_| format!("预览识别超时（{}秒）", PREVIEW_TIMEOUT_SECS))??;
+    let subtitles = subtitles
+        .into_iter()
+        .filter(|subtitle| subtitle.start_time < seconds)
+        .map(|mut subtitle| {
+            subtitle.end_time = subtitle.end_time.min(seconds);
+            subtitle
+        })
+        .collect();
+    Ok(crate::glossary::apply_glossary(drop_empty_cues(subtitles)))
+}
+
+/// 云端引擎按小时计费的公开价格（元/小时，美元价格按 `USD_TO_CNY` 换算）
+const CLOUD_HOURLY_RATES: &[(&str, f64)] = &[
+    ("tencent", 1.75),
+    ("baidu", 2.0),
+    ("aliyun", 2.5),
+    ("google", 0.96 * USD_TO_CNY),
+    ("openai", 0.36 * USD_TO_CNY),
+    ("aws", 1.44 * USD_TO_CNY),
+];
+
+/// 估算费用使用的美元兑人民币汇率
+const USD_TO_CNY: f64 = 7.2;
+
+/// 本地模型在CPU上的实时率（处理耗时 / 音频时长），按Whisper官方实现粗略估计
+const LOCAL_REAL_TIME_FACTORS: &[(&str, f64)] = &[
+    ("turbo", 0.8),
+    ("tiny", 0.1),
+    ("base", 0.2),
+    ("small", 0.5),
+    ("medium", 1.5),
+    ("large", 3.0),
+];
+
+/// faster-whisper相对Whisper官方实现的耗时比例
+const FASTER_WHISPER_SPEEDUP: f64 = 0.25;
+/// SenseVoice在CPU上的实时率
+const SENSEVOICE_REAL_TIME_FACTOR: f64 = 0.05;
+/// 云端识别的实时率（含排队与转写）
+const CLOUD_REAL_TIME_FACTOR: f64 = 0.1;
+/// 云端识别的固定耗时（上传、创建任务、轮询间隔，秒）
+const CLOUD_OVERHEAD_SECS: f64 = 15.0;
+/// 提取音频的实时率
+const EXTRACT_REAL_TIME_FACTOR: f64 = 0.02;
+
+/// 识别费用与耗时的估算结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RecognitionEstimate {
+    /// 音频时长（秒）
+    pub duration: f64,
+    /// 估算费用（元），本地引擎为0
+    pub estimated_cost: f64,
+    /// 估算总耗时（秒，含音频提取）
+    pub estimated_seconds: f64,
+    /// 是否为本地引擎
+    pub local: bool,
+}
+
+/// 本地模型的实时率，未知大小按medium估计
+fn local_real_time_factor(engine: &str, model: &str) -> f64 {
+    if engine == "sensevoice" {
+        return SENSEVOICE_REAL_TIME_FACTOR;
+    }
+    let model = model.trim().to_lowercase();
+    let base = LOCAL_REAL_TIME_FACTORS
+        .iter()
+        .find(|(size, _)| model.contains(size))
+        .map(|(_, factor)| *factor)
+        .unwrap_or(1.5);
+    if engine == "faster-whisper" {
+        base * FASTER_WHISPER_SPEEDUP
+    } else {
+        base
+    }
+}
+
+/// 在开始识别前估算音频时长、费用和耗时
+///
+/// 云端引擎按公开单价计费、耗时按网络和服务端转写粗略估计；本地引擎按模型大小的CPU实时率估计。
+/// 结果仅供界面提示，实际费用以服务商账单为准。
+pub fn estimate_recognition(
+    video_path: &str,
+    engine: &str,
+    model: Option<&str>,
+) -> Result<RecognitionEstimate, String> {
+    let local = matches!(engine, "whisper" | "faster-whisper" | "sensevoice");
+    let hourly_rate = CLOUD_HOURLY_RATES
+        .iter()
+        .find(|(name, _)| *name == engine)
+        .map(|(_, rate)| *rate);
+    if !local && hourly_rate.is_none() {
+        return Err(format!("不支持的识别引擎: {}", engine));
+    }
+
+    let duration = crate::video::get_video_info(video_path)?.duration;
+    let extract_seconds = duration * EXTRACT_REAL_TIME_FACTOR;
+    let (estimated_cost, recognize_seconds) = if local {
+        (
+            0.0,
+            duration * local_real_time_factor(engine, model.unwrap_or("")),
+        )
+    } else {
+        (
+            duration / 3600.0 * hourly_rate.unwrap_or(0.0),
+            CLOUD_OVERHEAD_SECS + duration * CLOUD_REAL_TIME_FACTOR,
+        )
+    };
+
+    Ok(RecognitionEstimate {
+        duration,
+        estimated_cost,
+        estimated_seconds: extract_seconds + recognize_seconds,
+        local,
+    })
+}
+
+/// 按音频时长截断识别结果，获取时长失败时原样返回
+fn clamp_to_audio_duration(
+    audio_path: &str,
+    subtitles: Vec<crate::video::Subtitle>,
+) -> Vec<crate::video::Subtitle> {
+    match crate::video::get_video_info(audio_path) {
+        Ok(info) => {
+            let clamped = crate::video::clamp_to_duration(&subtitles, info.duration);
+            if clamped != subtitles {
+                info!("识别结果超出音频时长{:.2}秒，已截断", info.duration);
+            }
+            clamped
+        }
+        Err(e) => {
+            warn!("获取音频时长失败，跳过时长校正: {}", e);
+            subtitles
+        }
+    }
+}
+
+/// 停止实时识别（识别完已采集的音频后任务变为completed）
+pub fn stop_live_recognition(task_id: &str) -> Result<(), String> {
+    let mut tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    let task = tasks
+        .get_mut(task_id)
+        .filter(|task| task.live)
+        .ok_or_else(|| format!("实时识别任务不存在: {}", task_id))?;
+
+    match task.cancel_sender.take() {
+        Some(sender) => {
+            let _ = sender.try_send(());
+            Ok(())
+        }
+        None => Err(format!("实时识别任务已停止: {}", task_id)),
+    }
+}
+
+/// 实时识别主循环：按窗口切分采集到的PCM数据并逐段识别
+async fn run_live_recognition(
+    mut capture: tokio::process::Child,
+    recognition_engine: &dyn RecognitionEngine,
+    language: &str,
+    window_seconds: f64,
+    ctx: &mut TaskCtx,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    use tokio::io::AsyncReadExt;
+
+    let bytes_per_second = crate::video::LIVE_SAMPLE_RATE as usize * 2;
+    let window_bytes = (window_seconds * bytes_per_second as f64) as usize;
+    let wav_path = std::env::temp_dir().join(format!("flowtext_live_{}.wav", ctx.task_id));
+
+    // 单独的读取任务持续消费FFmpeg输出，避免识别期间采集阻塞丢音
+    let mut stdout = capture
+        .stdout
+        .take()
+        .ok_or_else(|| "无法读取音频采集输出".to_string())?;
+    let (pcm_tx, mut pcm_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    tokio::spawn(async move {
+        let mut chunk = vec![0u8; bytes_per_second / 4];
+        while let Ok(n) = stdout.read(&mut chunk).await {
+            if n == 0 || pcm_tx.send(chunk[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subtitles: Vec<crate::video::Subtitle> = Vec::new();
+    let mut buffer: Vec<u8> = Vec::with_capacity(window_bytes);
+    let mut offset = 0.0;
+    let mut stopping = false;
+
+    loop {
+        let mut input_ended = false;
+        tokio::select! {
+            _ = ctx.cancel_rx.recv() => stopping = true,
+            data = pcm_rx.recv() => match data {
+                Some(data) => buffer.extend_from_slice(&data),
+                None => input_ended = true,
+            },
+        }
+        let finishing = stopping || input_ended;
+
+        // 窗口已满，或结束时剩余至少1秒音频
+        if buffer.len() >= window_bytes || (finishing && buffer.len() >= bytes_per_second) {
+            let take = buffer.len().min(window_bytes) & !1;
+            let window: Vec<u8> = buffer.drain(..take).collect();
+            let window_duration = window.len() as f64 / bytes_per_second as f64;
+
+            if pcm_is_silent(&window) {
+                warn!("实时识别窗口{:.0}秒起为静音，跳过", offset);
+            } else {
+                crate::video::write_pcm_wav(&wav_path, &window, crate::video::LIVE_SAMPLE_RATE)?;
+                match recognition_engine
+                    .transcribe(&wav_path, language, ctx)
+                    .await
+                {
+                    Ok(window_subtitles) => {
+                        for mut subtitle in crate::glossary::apply_glossary(window_subtitles) {
+                            if subtitle.text.trim().is_empty() {
+                                continue;
+                            }
+                            subtitle.start_time = (subtitle.start_time + offset).max(0.0);
+                            subtitle.end_time =
+                                (subtitle.end_time + offset).min(offset + window_duration);
+                            subtitle.id = (subtitles.len() + 1).to_string();
+                            subtitles.push(subtitle);
+                        }
+                    }
+                    // 引擎在识别时收到了停止信号
+                    Err(e) if e.contains("任务已取消") => stopping = true,
+                    Err(e) => {
+                        error!("实时识别窗口{:.0}秒起识别失败: {}", offset, e);
+                        add_task_warning(
+                            &ctx.task_id,
+                            format!("{:.0}秒起的音频识别失败: {}", offset, e),
+                        );
+                    }
+                }
+            }
+            offset += window_duration;
+
+            update_task_status(
+                &ctx.task_id,
+                "processing".to_string(),
+                0.0,
+                Some(subtitles.clone()),
+                Some(format!(
+                    "实时识别中，已处理{:.0}秒，共{}条字幕",
+                    offset,
+                    subtitles.len()
+                )),
+            );
+        }
+
+        if stopping || (input_ended && buffer.len() < bytes_per_second) {
+            break;
+        }
+    }
+
+    let _ = capture.kill().await;
+    let _ = std::fs::remove_file(&wav_path);
+    Ok(subtitles)
+}
+
+/// 判断PCM窗口是否为静音（均方根电平低于约-50dBFS）
+fn pcm_is_silent(pcm: &[u8]) -> bool {
+    let samples = pcm.len() / 2;
+    if samples == 0 {
+        return true;
+    }
+    let sum_squares: f64 = pcm
+        .chunks_exact(2)
+        .map(|bytes| {
+            let sample = i16::from_le_bytes([bytes[0], bytes[1]]) as f64;
+            sample * sample
+        })
+        .sum();
+    (sum_squares / samples as f64).sqrt() < 100.0
+}
+
+/// 获取任务状态
+pub fn get_recognition_status(task_id: &str) -> Result<RecognitionStatus, String> {
+    let tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    tasks
+        .get(task_id)
+        .map(|task| task.status.clone())
+        .ok_or_else(|| format!("任务不存在: {}", task_id))
+}
+
+/// 取消识别任务
+pub fn cancel_recognition(task_id: &str) -> Result<(), String> {
+    let mut tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    if let Some(task) = tasks.get_mut(task_id) {
+        if let Some(sender) = task.cancel_sender.take() {
+            // 发送取消信号
+            let _ = sender.try_send(());
+            return Ok(());
+        }
+    }
+
+    Err(format!("无法取消任务: {}", task_id))
+}
+
+/// 任务列表中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct RecognitionTaskSummary {
+    pub task_id: String,
+    pub engine: String,
+    pub language: String,
+    pub status: String,
+    pub progress: f32,
+}
+
+/// 列出内存中的所有识别任务（包括上次运行保存下来的任务）
+pub fn list_recognition_tasks() -> Result<Vec<RecognitionTaskSummary>, String> {
+    let tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    let mut summaries: Vec<RecognitionTaskSummary> = tasks
+        .values()
+        .map(|task| RecognitionTaskSummary {
+            task_id: task.task_id.clone(),
+            engine: task.engine.clone(),
+            language: task.language.clone(),
+            status: task.status.status.clone(),
+            progress: task.status.progress,
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+    Ok(summaries)
+}
+
+/// 从应用数据目录加载上次运行保存的识别任务（应用启动时调用）
+///
+/// 上次退出时仍在进行的任务已无法继续，标记为失败；恢复的任务从现在起按保留时间重新计时。
+pub fn load_persisted_tasks(data_dir: PathBuf) {
+    let path = data_dir.join(RECOGNITION_TASKS_FILE);
+    if let Ok(mut tasks_file) = TASKS_FILE.lock() {
+        *tasks_file = Some(path.clone());
+    }
+    if !path.exists() {
+        return;
+    }
+
+    let persisted: Vec<RecognitionTask> = match std::fs::read_to_string(&path)
+        .map_err(|e| format!("读取识别任务失败: {}", e))
+        .and_then(|content| {
+            serde_json::from_str(&content).map_err(|e| format!("解析识别任务失败: {}", e))
+        }) {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+
+    let mut restored = Vec::new();
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        for mut task in persisted {
+            if !matches!(
+                task.status.status.as_str(),
+                "completed" | "failed" | "cancelled"
+            ) {
+                task.status.status = "failed".to_string();
+                task.status.error = Some("应用退出时任务尚未完成，请重新识别".to_string());
+            }
+            restored.push(task.task_id.clone());
+            tasks.entry(task.task_id.clone()).or_insert(task);
+        }
+    }
+    info!("已恢复{}个识别任务: {}", restored.len(), path.display());
+
+    tauri::async_runtime::spawn(async move {
+        for task_id in restored {
+            schedule_task_cleanup(task_id);
+        }
+    });
+}
+
+/// 将任务表写入持久化文件（实时识别任务不保存）
+///
+/// 调用方持有任务锁，保证按顺序写入；先写临时文件再重命名，避免退出时留下不完整的文件。
+fn persist_tasks(tasks: &HashMap<String, RecognitionTask>) {
+    let Some(path) = TASKS_FILE.lock().ok().and_then(|file| file.clone()) else {
+        return;
+    };
+    let persisted: Vec<&RecognitionTask> = tasks.values().filter(|task| !task.live).collect();
+    let content = match serde_json::to_string(&persisted) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("序列化识别任务失败: {}", e);
+            return;
+        }
+    };
+
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, &path)
+    };
+    if let Err(e) = write() {
+        warn!("保存识别任务失败: {}", e);
+    }
+}
+
+/// 设置应用配置目录（应用启动时调用）
+pub fn set_config_dir(dir: PathBuf) {
+    if let Ok(mut config_dir) = CONFIG_DIR.lock() {
+        *config_dir = Some(dir);
+    }
+}
+
+/// 应用配置目录下的配置文件路径
+pub(crate) fn config_file_path(file_name: &str) -> Result<PathBuf, String> {
+    CONFIG_DIR
+        .lock()
+        .map_err(|_| "无法获取配置目录锁".to_string())?
+        .as_ref()
+        .map(|dir| dir.join(file_name))
+        .ok_or_else(|| "应用配置目录未初始化".to_string())
+}
+
+/// 识别默认配置文件路径
+fn recognition_defaults_path() -> Result<PathBuf, String> {
+    config_file_path(RECOGNITION_DEFAULTS_FILE)
+}
+
+/// 读取识别默认配置，尚未保存过时返回内置默认值
+pub fn get_recognition_defaults() -> Result<RecognitionDefaults, String> {
+    let path = recognition_defaults_path()?;
+    if !path.exists() {
+        return Ok(RecognitionDefaults::default());
+    }
+
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取识别默认配置失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析识别默认配置失败: {}", e))
+}
+
+/// 保存识别默认配置，返回规范化后的配置
+pub fn set_recognition_defaults(
+    mut defaults: RecognitionDefaults,
+) -> Result<RecognitionDefaults, String> {
+    defaults.engine = defaults.engine.trim().to_string();
+    defaults.language = defaults.language.trim().to_string();
+    require_engine(&defaults.engine)?;
+    if defaults.language.is_empty() {
+        return Err("默认识别语言不能为空".to_string());
+    }
+    defaults.initial_prompt = defaults
+        .initial_prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty());
+    defaults.hotwords = defaults
+        .hotwords
+        .iter()
+        .map(|word| word.trim().to_string())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let path = recognition_defaults_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&defaults)
+        .map_err(|e| format!("序列化识别默认配置失败: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("保存识别默认配置失败: {}", e))?;
+
+    info!("识别默认配置已保存: {}", path.display());
+    Ok(defaults)
+}
+
+/// 未指定的引擎和语言回退到默认配置（默认配置不可读时使用内置默认值）
+pub fn resolve_engine_and_language(
+    engine: Option<String>,
+    language: Option<String>,
+) -> (String, String) {
+    let engine = engine.filter(|e| !e.trim().is_empty());
+    let language = language.filter(|l| !l.trim().is_empty());
+    if let (Some(engine), Some(language)) = (&engine, &language) {
+        return (engine.clone(), language.clone());
+    }
+
+    let defaults = get_recognition_defaults().unwrap_or_else(|e| {
+        warn!("读取识别默认配置失败，使用内置默认值: {}", e);
+        RecognitionDefaults::default()
+    });
+    (
+        engine.unwrap_or(defaults.engine),
+        language.unwrap_or(defaults.language),
+    )
+}
+
+/// 测试腾讯云COS配置（密钥格式与腾讯云识别引擎相同，COS配置位于 `cos` 字段）
+pub async fn test_cos_config(config: Value) -> Result<(), String> {
+    let (secret_id, secret_key) = TencentEngine::credentials(&config)?;
+    let cos_config = TencentEngine::options(&config, secret_id, secret_key)
+        .cos_config
+        .ok_or_else(|| "COS配置不完整，需要提供存储桶(bucket)和地域(region)".to_string())?;
+    crate::cos::validate_region(&cos_config.region)?;
+
+    crate::cos::CosClient::new(cos_config)
+        .test_connection()
+        .await
+}
+
+/// 获取支持的语言列表
+pub fn get_supported_languages(engine: &str) -> Result<Vec<Language>, FlowError> {
+    require_engine(engine).map(|recognition_engine| recognition_engine.languages())
+}
+
+/// 获取可用模型列表
+pub fn get_available_models(
+) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+    let models = vec![
+        serde_json::json!({
+            "engine": "whisper",
+            "name": "OpenAI Whisper",
+            "description": "OpenAI开源的多语言语音识别模型",
+            "sizes": ["tiny", "base", "small", "medium", "large", "large-v2", "large-v3"],
+            "features": ["multilingual", "timestamps", "translation"],
+            "requirements": {
+                "python": ">=3.8",
+                "packages": ["openai-whisper", "torch", "torchaudio"]
+            }
+        }),
+        serde_json::json!({
+            "engine": "faster-whisper",
+            "name": "Faster Whisper",
+            "description": "基于CTranslate2优化的Whisper实现，速度提升4-5倍",
+            "sizes": ["tiny", "base", "small", "medium", "large", "large-v2", "large-v3"],
+            "features": ["multilingual", "timestamps", "optimization", "quantization"],
+            "requirements": {
+                "python": ">=3.8",
+                "packages": ["faster-whisper", "ctranslate2"]
+            }
+        }),
+        serde_json::json!({
+            "engine": "sensevoice",
+            "name": "SenseVoice",
+            "description": "阿里巴巴开源的多语言语音理解模型，支持情感识别和事件检测",
+            "sizes": ["small", "large"],
+            "features": ["multilingual", "timestamps", "emotion", "event", "language_id"],
+            "requirements": {
+                "python": ">=3.8",
+                "packages": ["funasr", "modelscope", "torch", "torchaudio"]
+            }
+        }),
+    ];
+
+    Ok(models)
+}
+
+/// 外部程序路径（FFmpeg、FFprobe、whisper命令和Python解释器）
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryPaths {
+    pub ffmpeg: String,
+    pub ffprobe: String,
+    pub whisper: String,
+    pub python: String,
+}
+
+/// 读取配置的程序路径，未配置时使用程序名（由系统PATH查找）
+fn configured_program(path: &Mutex<Option<String>>, default: &str) -> String {
+    path.lock()
+        .ok()
+        .and_then(|path| path.clone())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// whisper命令的路径
+fn whisper_program() -> String {
+    configured_program(&WHISPER_PATH, "whisper")
+}
+
+/// Python解释器的路径
+fn python_program() -> String {
+    configured_program(&PYTHON_PATH, "python3")
+}
+
+/// 创建whisper命令
+fn whisper_cmd() -> Command {
+    Command::new(whisper_program())
+}
+
+/// 创建Python命令
+fn python_cmd() -> Command {
+    Command::new(python_program())
+}
+
+/// 获取当前使用的外部程序路径
+pub fn get_binary_paths() -> BinaryPaths {
+    let ffmpeg = crate::video::locate_ffmpeg();
+    BinaryPaths {
+        ffmpeg: ffmpeg.ffmpeg,
+        ffprobe: ffmpeg.ffprobe,
+        whisper: whisper_program(),
+        python: python_program(),
+    }
+}
+
+/// 设置外部程序路径（适用于应用包内置或不在PATH中的程序）
+///
+/// FFmpeg/FFprobe沿用 `set_ffmpeg_path` 的规则；whisper和Python路径为空时恢复使用PATH中的程序。
+/// 指定的路径必须是存在的文件。
+pub fn set_binary_paths(
+    ffmpeg: Option<&str>,
+    ffprobe: Option<&str>,
+    whisper: Option<&str>,
+    python: Option<&str>,
+) -> Result<BinaryPaths, String> {
+    let program_path = |path: Option<&str>, name: &str| -> Result<Option<String>, String> {
+        match path.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(path) if Path::new(path).is_file() => Ok(Some(path.to_string())),
+            Some(path) => Err(format!("{}路径不存在: {}", name, path)),
+            None => Ok(None),
+        }
+    };
+    let whisper = program_path(whisper, "whisper")?;
+    let python = program_path(python, "Python")?;
+
+    crate::video::set_ffmpeg_path(ffmpeg, ffprobe)?;
+    *WHISPER_PATH
+        .lock()
+        .map_err(|_| "无法获取whisper路径配置锁".to_string())? = whisper;
+    *PYTHON_PATH
+        .lock()
+        .map_err(|_| "无法获取Python路径配置锁".to_string())? = python;
+
+    let paths = get_binary_paths();
+    info!("已设置外部程序路径: {:?}", paths);
+    Ok(paths)
+}
+
+/// whisper命令行是否可用
+fn whisper_cli_available() -> bool {
+    whisper_cmd().arg("--help").output().is_ok()
+}
+
+/// Python whisper包是否已安装
+fn python_whisper_available() -> bool {
+    python_cmd()
+        .arg("-c")
+        .arg("import whisper; print('OK')")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// 外部依赖的安装情况，界面启动时检查并引导安装
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyStatus {
+    pub ffmpeg: bool,
+    pub ffprobe: bool,
+    /// whisper命令行
+    pub whisper: bool,
+    /// Python whisper包
+    pub python_whisper: bool,
+    pub ffmpeg_version: Option<String>,
+}
+
+/// 检查FFmpeg、FFprobe和Whisper是否可用
+///
+/// FFmpeg/FFprobe按配置的路径执行 `-version`，Whisper沿用识别时的探测方式。
+pub fn check_dependencies() -> DependencyStatus {
+    let ffmpeg_version = crate::video::ffmpeg_version();
+    let status = DependencyStatus {
+        ffmpeg: ffmpeg_version.is_some(),
+        ffprobe: crate::video::ffprobe_version().is_some(),
+        whisper: whisper_cli_available(),
+        python_whisper: python_whisper_available(),
+        ffmpeg_version,
+    };
+    info!("依赖检查结果: {:?}", status);
+    status
+}
+
+/// 检查模型是否已安装
+pub fn check_model_installation(
+    engine: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match engine {
+        // 检查whisper命令或Python包
+        "whisper" => Ok(whisper_cli_available() || python_whisper_available()),
+        "faster-whisper" => {
+            // 检查faster-whisper Python包
+            let python_check = python_cmd()
+                .arg("-c")
+                .arg("import faster_whisper; print('OK')")
+                .output();
+
+            Ok(python_check.is_ok() && python_check.unwrap().status.success())
+        }
+        "sensevoice" => {
+            // 检查funasr Python包
+            let python_check = python_cmd()
+                .arg("-c")
+                .arg("import funasr; print('OK')")
+                .output();
+
+            Ok(python_check.is_ok() && python_check.unwrap().status.success())
+        }
+        // 时间轴对齐工具
+        "whisperx" => Ok(python_module_available(Aligner::WhisperX.module())),
+        "stable-ts" => Ok(python_module_available(Aligner::StableTs.module())),
+        _ => Ok(false),
+    }
+}
+
+/// 检查特定模型大小是否已下载
+pub fn check_model_size_available(
+    engine: &str,
+    size: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match engine {
+        "whisper" => {
+            // 检查Whisper模型是否已下载
+            let python_script = format!(
+                r#"
+import whisper
+try:
+    model = whisper.load_model("{}")
+    print("available")
+except Exception as e:
+    print("not_available: " + str(e))
+"#,
+                size
+            );
+
+            let output = python_cmd().arg("-c").arg(&python_script).output()?;
+
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            Ok(output_str.contains("available"))
+        }
+        "faster-whisper" => {
+            // 检查Faster-Whisper模型是否已下载
+            let python_script = format!(
+                r#"
+try:
+    from faster_whisper import WhisperModel
+    model = WhisperModel("{}", device="cpu")
+    print("available")
+except ImportError:
+    print("not_available: faster_whisper module not installed")
+except Exception as e:
+    print("not_available: " + str(e))
+"#,
+                size
+            );
+
+            let output = python_cmd().arg("-c").arg(&python_script).output()?;
+
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            let stderr_str = String::from_utf8_lossy(&output.stderr);
+
+            // 如果有错误输出，记录但不抛出异常
+            if !stderr_str.is_empty() {
+                debug!("Faster-Whisper检查警告: {}", stderr_str);
+            }
+
+            Ok(output_str.contains("available"))
+        }
+        "sensevoice" => {
+            // SenseVoice模型检查（简化版）
+            let python_script = r#"
+import funasr
+try:
+    # 检查funasr是否可以正常导入
+    print("available")
+except Exception as e:
+    print("not_available: " + str(e))
+"#;
+
+            let output = python_cmd().arg("-c").arg(python_script).output()?;
+
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            Ok(output_str.contains("available"))
+        }
+        _ => Ok(false),
+    }
+}
+
+/// 获取Whisper模型下载/缓存状态
+///
+/// 检查 openai-whisper 的 `~/.cache/whisper/<name>.pt` 以及 whisper.cpp 的
+/// `ggml-<name>.bin` 模型文件。
+pub fn get_whisper_model_status() -> Result<Vec<WhisperModelStatus>, String> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cache")))
+        .ok_or("无法获取缓存目录")?;
+
+    let whisper_dir = cache_dir.join("whisper");
+    let whisper_cpp_dir = std::env::var_os("WHISPER_CPP_MODEL_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| cache_dir.join("whisper.cpp"));
+
+    let statuses = WHISPER_MODELS
+        .iter()
+        .map(|(name, download_mb)| {
+            let candidates = [
+                whisper_dir.join(format!("{}.pt", name)),
+                whisper_dir.join(format!("ggml-{}.bin", name)),
+                whisper_cpp_dir.join(format!("ggml-{}.bin", name)),
+            ];
+
+            let found = candidates.iter().find_map(|path| {
+                std::fs::metadata(path)
+                    .ok()
+                    .filter(|meta| meta.is_file())
+                    .map(|meta| (path.to_string_lossy().to_string(), meta.len()))
+            });
+
+            WhisperModelStatus {
+                name: name.to_string(),
+                present: found.is_some(),
+                size_bytes: found.as_ref().map(|(_, size)| *size),
+                download_size_bytes: download_mb * 1024 * 1024,
+                path: found.map(|(path, _)| path),
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+/// 获取操作系统信息
+pub fn get_os_info() -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let os = std::env::consts::OS;
+    let arch = std::env::consts::ARCH;
+
+    Ok(serde_json::json!({
+        "os": os,
+        "arch": arch,
+        "platform": format!("{}-{}", os, arch)
+    }))
+}
+
+/// 获取模型详细信息
+pub fn get_model_info(
+    engine: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let models = get_available_models()?;
+
+    for model in models {
+        if model["engine"].as_str() == Some(engine) {
+            let mut info = model.clone();
+
+            // 添加安装状态
+            let installed = check_model_installation(engine).unwrap_or(false);
+            info["installed"] = serde_json::Value::Bool(installed);
+
+            // 添加性能信息
+            match engine {
+                "whisper" => {
+                    info["performance"] = serde_json::json!({
+                        "wer": 0.15,
+                        "rtf": 0.3,
+                        "latency": 2000,
+                        "throughput": 150
+                    });
+                    // 添加模型缓存状态
+                    if let Ok(statuses) = get_whisper_model_status() {
+                        info["models"] = serde_json::to_value(statuses)?;
+                    }
+                }
+                "faster-whisper" => {
+                    info["performance"] = serde_json::json!({
+                        "wer": 0.15,
+                        "rtf": 0.06,
+                        "latency": 500,
+                        "throughput": 600
+                    });
+                }
+                "sensevoice" => {
+                    info["performance"] = serde_json::json!({
+                        "wer": 0.12,
+                        "rtf": 0.1,
+                        "latency": 800,
+                        "throughput": 400
+                    });
+                }
+                _ => {}
+            }
+
+            return Ok(info);
+        }
+    }
+
+    Err(format!("未找到模型: {}", engine).into())
+}
+
+/// 验证API密钥
+///
+/// 先检查必要字段是否存在，再尽可能调用对应服务的鉴权接口确认密钥有效。
+pub async fn validate_api_keys(
+    engine: &str,
+    api_keys: Value,
+) -> Result<ApiKeyValidation, FlowError> {
+    let recognition_engine = require_engine(engine)?;
+
+    if let Err(message) = recognition_engine.validate_keys(&api_keys) {
+        return Ok(ApiKeyValidation::missing(message.to_string()));
+    }
+
+    Ok(recognition_engine.verify_keys(&api_keys).await)
+}
+
+/// 任务被取消时识别流程返回的错误信息
+const TASK_CANCELLED: &str = "任务已取消";
+
+/// 以错误结束任务：取消导致的错误标记为cancelled，其余为failed
+fn finish_task_with_error(task_id: &str, error: String) {
+    let state = if error == TASK_CANCELLED {
+        "cancelled"
+    } else {
+        "failed"
+    };
+    update_task_status(task_id, state.to_string(), 0.0, None, Some(error));
+}
+
+/// 等待子进程结束并收集输出，收到取消信号时结束子进程并返回 `TASK_CANCELLED`
+async fn wait_child_cancellable(
+    mut child: tokio::process::Child,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<std::process::Output, String> {
+    use tokio::io::AsyncReadExt;
+
+    let read_all = |pipe: Option<Box<dyn tokio::io::AsyncRead + Send + Unpin>>| {
+        tokio::spawn(async move {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer).await;
+            }
+            buffer
+        })
+    };
+    let stdout_task = read_all(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr_task = read_all(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let status = tokio::select! {
+        status = child.wait() => status.map_err(|e| format!("等待子进程失败: {}", e))?,
+        Some(()) = cancel_rx.recv() => {
+            let _ = child.kill().await;
+            return Err(TASK_CANCELLED.to_string());
+        }
+    };
+
+    Ok(std::process::Output {
+        status,
+        stdout: stdout_task.await.unwrap_or_default(),
+        stderr: stderr_task.await.unwrap_or_default(),
+    })
+}
+
+/// 更新任务状态
+fn update_task_status(
+    task_id: &str,
+    state: String,
+    progress: f32,
+    result: Option<Vec<crate::video::Subtitle>>,
+    error: Option<String>,
+) {
+    debug!("更新任务状态: {} -> {}, 进度: {}", task_id, state, progress);
+    let mut updated = None;
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get_mut(task_id) {
+            // 只在状态变化时落盘，频繁的进度更新不写文件
+            let state_changed = task.status.status != state;
+            // 警告信息在整个任务生命周期内保留
+            let warnings = std::mem::take(&mut task.status.warnings);
+            let retry_of = task.status.retry_of.take();
+            let detected_language = task.status.detected_language.take();
+            let progress = match task.progress_range {
+                Some((start, end)) => start + progress * (end - start),
+                None => progress,
+            };
+            task.status = RecognitionStatus {
+                status: state.clone(),
+                progress,
+                result,
+                error,
+                warnings,
+                retry_of,
+                detected_language,
+            };
+            debug!("任务状态已更新: {} -> {}", task_id, state);
+            updated = Some(task.status.clone());
+            if state_changed {
+                persist_tasks(&tasks);
+            }
+        } else {
+            warn!("任务不存在: {}", task_id);
+        }
+    } else {
+        error!("无法获取任务锁");
+    }
+
+    // 在锁外推送事件，避免前端处理事件时阻塞其他任务更新
+    if let Some(status) = updated {
+        // 失败或取消的任务保留音频以便重试，在任务被清理时再删除
+        if status.status == "completed" {
+            remove_temp_audio(task_id);
+        }
+        emit_task_status(task_id, status);
+    }
+}
+
+/// 登记任务的临时音频：任务完成时删除；失败或取消的任务在被清理时删除
+pub fn delete_audio_when_finished(task_id: &str, audio_path: &str) {
+    if let Ok(mut temp_audio) = TEMP_AUDIO.lock() {
+        temp_audio.insert(task_id.to_string(), PathBuf::from(audio_path));
+    }
+}
+
+/// 把临时音频的登记转给重试任务，原任务被清理时不再删除
+fn transfer_temp_audio(from_task_id: &str, to_task_id: &str) {
+    if let Ok(mut temp_audio) = TEMP_AUDIO.lock() {
+        if let Some(path) = temp_audio.remove(from_task_id) {
+            temp_audio.insert(to_task_id.to_string(), path);
+        }
+    }
+}
+
+/// 删除任务登记的临时音频
+pub fn remove_temp_audio(task_id: &str) {
+    let Some(path) = TEMP_AUDIO
+        .lock()
+        .ok()
+        .and_then(|mut temp_audio| temp_audio.remove(task_id))
+    else {
+        return;
+    };
+    match std::fs::remove_file(&path) {
+        Ok(()) => debug!("已删除临时音频: {}", path.display()),
+        Err(e) => warn!("删除临时音频失败: {}: {}", path.display(), e),
+    }
+}
+
+/// 记录自动检测到的语言
+fn set_detected_language(task_id: &str, language: String) {
+    info!("检测到语言: {} -> {}", task_id, language);
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get_mut(task_id) {
+            task.status.detected_language = Some(language);
+        }
+    }
+}
+
+/// 获取任务自动检测到的语言
+fn task_detected_language(task_id: &str) -> Option<String> {
+    RECOGNITION_TASKS.lock().ok().and_then(|tasks| {
+        tasks
+            .get(task_id)
+            .and_then(|task| task.status.detected_language.clone())
+    })
+}
+
+/// 获取任务当前的进度映射区间
+fn task_progress_range(task_id: &str) -> Option<(f32, f32)> {
+    RECOGNITION_TASKS
+        .lock()
+        .ok()
+        .and_then(|tasks| tasks.get(task_id).and_then(|task| task.progress_range))
+}
+
+/// 设置任务进度映射区间（None表示直接使用上报的进度）
+fn set_task_progress_range(task_id: &str, range: Option<(f32, f32)>) {
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get_mut(task_id) {
+            task.progress_range = range;
+        }
+    }
+}
+
+/// 记录任务已上传的音频URL
+fn record_uploaded_url(task_id: &str, url: &str) {
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get_mut(task_id) {
+            task.uploaded_url = Some(url.to_string());
+        }
+    }
+}
+
+/// 获取任务已上传的音频URL
+fn task_uploaded_url(task_id: &str) -> Option<String> {
+    RECOGNITION_TASKS
+        .lock()
+        .ok()?
+        .get(task_id)
+        .and_then(|task| task.uploaded_url.clone())
+}
+
+/// 为任务添加警告信息
+fn add_task_warning(task_id: &str, warning: String) {
+    warn!("任务警告: {} -> {}", task_id, warning);
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get_mut(task_id) {
+            if !task.status.warnings.contains(&warning) {
+                task.status.warnings.push(warning);
+            }
+        }
+    }
+}
+
+/// 解码识别引擎输出，发生乱码替换时在任务状态中提示用户
+fn decode_task_output(task_id: &str, bytes: &[u8], source: &str) -> String {
+    let (text, replaced) = crate::video::decode_output(bytes);
+    if replaced {
+        add_task_warning(
+            task_id,
+            format!(
+                "{}输出包含无效的UTF-8字节，部分字符可能显示为乱码（通常是系统区域/编码设置不匹配导致）",
+                source
+            ),
+        );
+    }
+    text
+}
+
+/// 在保留时间到期后清理已结束的任务
+fn schedule_task_cleanup(task_id: String) {
+    let ttl = get_task_ttl();
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(ttl)).await;
+        cleanup_completed_task(&task_id);
+    });
+}
+
+/// 立即清理所有已结束（完成、失败、取消）的任务，返回清理的任务数
+pub fn clear_finished_tasks() -> Result<usize, String> {
+    let mut tasks = RECOGNITION_TASKS
+        .lock()
+        .map_err(|_| "无法获取任务锁".to_string())?;
+
+    let before = tasks.len();
+    let mut removed_ids = Vec::new();
+    tasks.retain(|task_id, task| {
+        let finished = matches!(
+            task.status.status.as_str(),
+            "completed" | "failed" | "cancelled"
+        );
+        if finished {
+            removed_ids.push(task_id.clone());
+        }
+        !finished
+    });
+    let removed = before - tasks.len();
+    if removed > 0 {
+        persist_tasks(&tasks);
+    }
+    drop(tasks);
+    for task_id in &removed_ids {
+        remove_temp_audio(task_id);
+    }
+    info!("已清理{}个结束的任务", removed);
+    Ok(removed)
+}
+
+/// 清理已完成的任务
+fn cleanup_completed_task(task_id: &str) {
+    if let Ok(mut tasks) = RECOGNITION_TASKS.lock() {
+        if let Some(task) = tasks.get(task_id) {
+            // 只清理已完成、失败或取消的任务
+            match task.status.status.as_str() {
+                "completed" | "failed" | "cancelled" => {
+                    info!("清理已完成的任务: {}", task_id);
+                    tasks.remove(task_id);
+                    persist_tasks(&tasks);
+                    drop(tasks);
+                    remove_temp_audio(task_id);
+                }
+                _ => {
+                    // 任务仍在进行中，不清理
+                }
+            }
+        }
+    }
+}
+
+/// 使用配置参数进行识别（新的统一入口）
+async fn call_recognition_with_config(
+    recognition_engine: &dyn RecognitionEngine,
+    params: &ExtendedRecognitionParams,
+    ctx: &mut TaskCtx,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    // 检查音频文件是否存在
+    if !std::path::Path::new(&params.audio_path).exists() {
+        return Err(format!("音频文件不存在: {}", params.audio_path));
+    }
+
+    update_task_status(&ctx.task_id, "processing".to_string(), 0.1, None, None);
+
+    recognition_engine
+        .transcribe(Path::new(&params.audio_path), &params.language, ctx)
+        .await
+}
+
+/// 强制对齐工具（Whisper识别后的第二遍时间轴校准）
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aligner {
+    WhisperX,
+    StableTs,
+}
+
+impl Aligner {
+    fn name(self) -> &'static str {
+        match self {
+            Aligner::WhisperX => "whisperx",
+            Aligner::StableTs => "stable-ts",
+        }
+    }
+
+    /// Python包名
+    fn module(self) -> &'static str {
+        match self {
+            Aligner::WhisperX => "whisperx",
+            Aligner::StableTs => "stable_whisper",
+        }
+    }
+}
+
+/// 检查Python包是否可导入
+fn python_module_available(module: &str) -> bool {
+    python_cmd()
+        .arg("-c")
+        .arg(format!("import {}; print('OK')", module))
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// 查找已安装的对齐工具，whisperx优先
+fn detect_aligner() -> Option<Aligner> {
+    [Aligner::WhisperX, Aligner::StableTs]
+        .into_iter()
+        .find(|aligner| python_module_available(aligner.module()))
+}
+
+/// 对齐工具输出的段落
+#[derive(Debug, Deserialize)]
+struct AlignedSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(default)]
+    words: Vec<AlignedWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlignedWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+/// 对齐脚本：从标准输入读取 [{start, end, text}]，输出带单词时间的段落JSON
+fn aligner_script(aligner: Aligner, params: &ExtendedRecognitionParams, language: &str) -> String {
+    let device = if params.model_config.device == "gpu" {
+        "cuda"
+    } else {
+        "cpu"
+    };
+    let align_call = match aligner {
+        Aligner::WhisperX => format!(
+            r#"
+import whisperx
+audio = whisperx.load_audio({audio})
+model, metadata = whisperx.load_align_model(language_code={language}, device={device})
+result = whisperx.align(segments, model, metadata, audio, {device}, return_char_alignments=False)
+aligned = [
+    {{"start": s["start"], "end": s["end"], "text": s["text"],
+      "words": [w for w in s.get("words", []) if "start" in w and "end" in w]}}
+    for s in result["segments"]
+]
+"#,
+            audio = python_string_literal(Some(&params.audio_path)),
+            language = python_string_literal(Some(language)),
+            device = python_string_literal(Some(device)),
+        ),
+        Aligner::StableTs => format!(
+            r#"
+import stable_whisper
+model = stable_whisper.load_model({model}, device={device})
+result = model.align({audio}, stable_whisper.WhisperResult({{"segments": segments, "language": {language}}}), language={language})
+aligned = [
+    {{"start": s.start, "end": s.end, "text": s.text,
+      "words": [{{"word": w.word, "start": w.start, "end": w.end}} for w in (s.words or [])]}}
+    for s in result.segments
+]
+"#,
+            model = python_string_literal(Some(&params.model_config.size)),
+            audio = python_string_literal(Some(&params.audio_path)),
+            language = python_string_literal(Some(language)),
+            device = python_string_literal(Some(device)),
+        ),
+    };
+
+    format!(
+        r#"
+import sys
+import json
+
+try:
+    segments = json.load(sys.stdin)
+{body}
+    print(json.dumps(aligned, ensure_ascii=False))
+except Exception as e:
+    print("Error: " + str(e), file=sys.stderr)
+    sys.exit(1)
+"#,
+        body = align_call
+            .lines()
+            .map(|line| format!("    {}", line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+/// 使用对齐工具校准字幕时间，返回带单词时间的字幕
+async fn run_aligner(
+    aligner: Aligner,
+    params: &ExtendedRecognitionParams,
+    language: &str,
+    subtitles: &[crate::video::Subtitle],
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    use tokio::io::AsyncWriteExt;
+
+    let segments: Vec<Value> = subtitles
+        .iter()
+        .map(|s| json!({ "start": s.start_time, "end": s.end_time, "text": s.text }))
+        .collect();
+    let input = serde_json::to_vec(&segments).map_err(|e| format!("序列化识别结果失败: {}", e))?;
+
+    let mut child = tokio::process::Command::new(python_program())
+        .arg("-c")
+        .arg(aligner_script(aligner, params, language))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("执行{}失败: {}", aligner.name(), e))?;
+    // 后台写入输入，同时读取输出，避免管道写满时互相等待
+    let stdin_task = child.stdin.take().map(|mut stdin| {
+        // 写完后stdin随任务结束关闭，对齐脚本读到EOF
+        tokio::spawn(async move { stdin.write_all(&input).await })
+    });
+    let output = wait_child_cancellable(child, cancel_rx).await?;
+    if let Some(stdin_task) = stdin_task {
+        if let Ok(Err(e)) = stdin_task.await {
+            if output.status.success() {
+                return Err(format!("写入{}输入失败: {}", aligner.name(), e));
+            }
+        }
+    }
+    if !output.status.success() {
+        return Err(format!(
+            "{}对齐失败: {}",
+            aligner.name(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // 只取最后一行JSON，忽略对齐工具打印的日志
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_line = stdout
+        .lines()
+        .rev()
+        .find(|line| line.trim_start().starts_with('['))
+        .ok_or_else(|| format!("{}没有输出对齐结果", aligner.name()))?;
+    let aligned: Vec<AlignedSegment> =
+        serde_json::from_str(json_line).map_err(|e| format!("解析对齐结果失败: {}", e))?;
+
+    let subtitles: Vec<crate::video::Subtitle> = aligned
+        .into_iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .enumerate()
+        .map(|(index, segment)| {
+            let words: Vec<crate::video::WordTiming> = segment
+                .words
+                .into_iter()
+                .map(|word| crate::video::WordTiming {
+                    word: word.word.trim().to_string(),
+                    start_time: word.start,
+                    end_time: word.end,
+                })
+                .filter(|word| !word.word.is_empty())
+                .collect();
+            crate::video::Subtitle {
+                id: (index + 1).to_string(),
+                start_time: segment.start,
+                end_time: segment.end,
+                text: segment.text.trim().to_string(),
+                position: None,
+                words: if words.is_empty() { None } else { Some(words) },
+            }
+        })
+        .collect();
+
+    if subtitles.is_empty() {
+        return Err(format!("{}对齐结果为空", aligner.name()));
+    }
+    Ok(subtitles)
+}
+
+/// Whisper识别后的强制对齐（第二遍）
+///
+/// 使用已安装的whisperx或stable-ts校准句子边界并补充单词时间；
+/// 未安装对齐工具、语言为自动检测或对齐失败时保留原结果，并在任务状态中提示；
+/// 对齐过程中收到取消信号时结束对齐进程并返回 `TASK_CANCELLED`。
+async fn align_whisper_result(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    subtitles: Vec<crate::video::Subtitle>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    if subtitles.is_empty() {
+        return Ok(subtitles);
+    }
+    let language = if is_auto_language(&params.language) {
+        task_detected_language(task_id)
+    } else {
+        whisper_language_code(&params.language)
+    };
+    let Some(language) = language else {
+        add_task_warning(
+            task_id,
+            "未能确定识别语言，无法进行时间轴对齐，已使用原始时间".to_string(),
+        );
+        return Ok(subtitles);
+    };
+    let language = language.as_str();
+    // 检测需要启动Python，放到阻塞线程中执行
+    let aligner = tokio::task::spawn_blocking(detect_aligner)
+        .await
+        .ok()
+        .flatten();
+    let Some(aligner) = aligner else {
+        add_task_warning(
+            task_id,
+            "未安装whisperx或stable-ts，已跳过时间轴对齐".to_string(),
+        );
+        return Ok(subtitles);
+    };
+
+    update_task_status(task_id, "processing".to_string(), 0.85, None, None);
+    info!(
+        "使用{}对齐时间轴，共{}条字幕",
+        aligner.name(),
+        subtitles.len()
+    );
+    match run_aligner(aligner, params, language, &subtitles, cancel_rx).await {
+        Ok(aligned) => Ok(aligned),
+        Err(e) if e == TASK_CANCELLED => Err(e),
+        Err(e) => {
+            add_task_warning(task_id, format!("时间轴对齐失败，已使用原始时间: {}", e));
+            Ok(subtitles)
+        }
+    }
+}
+
+/// 调用Whisper进行本地语音识别
+async fn call_whisper_api(
+    audio_path: &str,
+    language: &str,
+    model: &str,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    // 检查音频文件是否存在
+    if !std::path::Path::new(audio_path).exists() {
+        return Err(format!("音频文件不存在: {}", audio_path));
+    }
+
+    update_task_status(task_id, "processing".to_string(), 0.1, None, None);
+
+    // 检查whisper命令是否可用
+    if whisper_cli_available() {
+        info!("发现whisper命令，使用本地Whisper进行识别");
+        call_local_whisper(audio_path, language, model, task_id, cancel_rx).await
+    } else {
+        info!("未找到whisper命令，尝试使用Python whisper");
+        call_python_whisper(audio_path, language, model, task_id, cancel_rx).await
+    }
+}
+
+/// 使用配置的Whisper进行识别
+async fn call_whisper_with_config(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    debug!("使用Whisper模型进行识别，配置: {:?}", params.model_config);
+
+    // 检查whisper命令是否可用
+    if whisper_cli_available() {
+        info!("发现whisper命令，使用本地Whisper进行识别");
+        call_local_whisper_with_config(params, task_id, cancel_rx).await
+    } else {
+        info!("未找到whisper命令，尝试使用Python whisper");
+        call_python_whisper_with_config(params, task_id, cancel_rx).await
+    }
+}
+
+/// 使用配置的Faster-Whisper进行识别
+async fn call_faster_whisper_with_config(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    debug!(
+        "使用Faster-Whisper模型进行识别，配置: {:?}",
+        params.model_config
+    );
+
+    // 首先检查模型是否已下载（需要启动Python，放到阻塞线程中执行）
+    let (engine, size) = (
+        params.model_config.engine.clone(),
+        params.model_config.size.clone(),
+    );
+    let model_available =
+        tokio::task::spawn_blocking(move || check_model_size_available(&engine, &size))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or(false);
+
+    if !model_available {
+        update_task_status(
+            task_id,
+            "processing".to_string(),
+            0.05,
+            None,
+            Some(format!(
+                "正在下载 {} 模型 ({})，请稍候...",
+                params.model_config.engine, params.model_config.size
+            )),
+        );
+    } else {
+        update_task_status(
+            task_id,
+            "processing".to_string(),
+            0.1,
+            None,
+            Some("正在初始化模型...".to_string()),
+        );
+    }
+
+    // 构建Python脚本来调用faster-whisper
+    let python_script = format!(
+        r#"
+import sys
+import json
+from faster_whisper import WhisperModel
+
+# 尝试导入繁简转换库
+converter = None
+try:
+    import opencc
+    converter = opencc.OpenCC('t2s')  # 繁体转简体
+except ImportError:
+    try:
+        from zhconv import convert
+        def convert_to_simplified(text):
+            return convert(text, 'zh-cn')
+        converter = convert_to_simplified
+    except ImportError:
+        print("警告: 未安装繁简转换库，将保持原始输出", file=sys.stderr)
+
+try:
+    # 检查模型是否需要下载
+    import os
+    from pathlib import Path
+
+    # 获取模型缓存路径
+    cache_dir = Path.home() / ".cache" / "huggingface" / "hub"
+    model_pattern = "models--Systran--faster-whisper-{model_size}"
+    model_exists = any(cache_dir.glob("*" + model_pattern + "*")) if cache_dir.exists() else False
+
+    if not model_exists:
+        print("DOWNLOAD_START", file=sys.stderr)
+
+    # 初始化模型（如果需要会自动下载）
+    model = WhisperModel(
+        "{model_size}",
+        device="{device}",
+        compute_type="{compute_type}"
+    )
+
+    if not model_exists:
+        print("DOWNLOAD_COMPLETE", file=sys.stderr)
 
     # 设置参数
     beam_size = {beam_size}
     temperature = {temperature}
 
-    # 设置初始提示词，引导输出简体中文
-    initial_prompt = None
-    if "{language}" == "zh":
-        initial_prompt = "以下是简体中文语音："
+    # 设置初始提示词（包含热词）
+    initial_prompt = {initial_prompt}
+
+    # 进行识别
+    segments, info = model.transcribe(
+        "{audio_path}",
+        language={language},
+        task="{task}",
+        beam_size=beam_size,
+        temperature=temperature,
+        word_timestamps=True,
+        initial_prompt=initial_prompt
+    )
+    print("DETECTED_LANGUAGE:" + str(info.language), file=sys.stderr)
+
+    # 输出SRT格式
+    for i, segment in enumerate(segments):
+        start = segment.start
+        end = segment.end
+        text = segment.text.strip()
+
+        # 根据语言设置进行繁简转换
+        original_language = "{original_language}"
+        if original_language in ["zh-cn", "zh"] and converter and text:
+            # 转换为简体中文
+            try:
+                if hasattr(converter, 'convert'):
+                    # OpenCC 转换器
+                    text = converter.convert(text)
+                else:
+                    # zhconv 转换器
+                    text = converter(text)
+            except Exception as e:
+                print("繁简转换失败: " + str(e), file=sys.stderr)
+                # 转换失败时保持原文
+        elif original_language == "zh-tw" and text:
+            # 繁体中文，不进行转换或转换为繁体
+            try:
+                if converter:
+                    # 如果有转换器，可以进行简体到繁体的转换
+                    import opencc
+                    s2t_converter = opencc.OpenCC('s2t')  # 简体转繁体
+                    text = s2t_converter.convert(text)
+            except:
+                # 转换失败时保持原文
+                pass
+
+        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(start // 3600),
+            int((start % 3600) // 60),
+            start % 60
+        )
+        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(end // 3600),
+            int((end % 3600) // 60),
+            end % 60
+        )
+
+        print(i+1)
+        print(start_time + " --> " + end_time)
+        print(text)
+        print()
+
+except Exception as e:
+    print("Error: " + str(e), file=sys.stderr)
+    sys.exit(1)
+"#,
+        model_size = params.model_config.size,
+        device = params.model_config.device,
+        compute_type = params
+            .model_config
+            .compute_type
+            .as_ref()
+            .unwrap_or(&"int8".to_string()),
+        beam_size = params.model_config.beam_size.unwrap_or(5),
+        temperature = params.model_config.temperature.unwrap_or(0.0),
+        audio_path = params.audio_path,
+        language = python_string_literal(whisper_language_code(&params.language).as_deref()),
+        original_language = params.language,
+        task = params.task.as_str(),
+        initial_prompt = python_string_literal(whisper_initial_prompt(params).as_deref())
+    );
+
+    // 更新进度：开始识别
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.2,
+        None,
+        Some("正在进行语音识别...".to_string()),
+    );
+
+    // 执行Python脚本，监控下载进度（取消时结束进程）
+    let mut child = tokio::process::Command::new(python_program())
+        .arg("-c")
+        .arg(&python_script)
+        .stderr(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("启动faster-whisper失败: {}", e))?;
+
+    // 逐行读取stderr获取下载进度和检测到的语言，同时保留全文用于失败时的错误信息
+    let stderr_task = child.stderr.take().map(|stderr| {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+        let task_id = task_id.to_string();
+        let requested_language = params.language.clone();
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut stderr_text = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                record_detected_language(&task_id, &requested_language, &line);
+                if line.contains("DOWNLOAD_START") {
+                    update_task_status(
+                        &task_id,
+                        "processing".to_string(),
+                        0.1,
+                        None,
+                        Some("正在下载模型文件，首次使用需要一些时间...".to_string()),
+                    );
+                } else if line.contains("DOWNLOAD_COMPLETE") {
+                    update_task_status(
+                        &task_id,
+                        "processing".to_string(),
+                        0.15,
+                        None,
+                        Some("模型下载完成，开始初始化...".to_string()),
+                    );
+                }
+                stderr_text.push_str(&line);
+                stderr_text.push('\n');
+            }
+            stderr_text
+        })
+    });
+
+    // 等待进程完成
+    let output = wait_child_cancellable(child, cancel_rx).await?;
+
+    if !output.status.success() {
+        let error_msg = match stderr_task {
+            Some(task) => task.await.unwrap_or_default(),
+            None => String::new(),
+        };
+        return Err(format!("Faster-Whisper识别失败: {}", error_msg));
+    }
+
+    // 更新进度：处理结果
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.8,
+        None,
+        Some("正在处理识别结果...".to_string()),
+    );
+
+    // 解析输出
+    let output_str = decode_task_output(task_id, &output.stdout, "Faster-Whisper");
+    let result = parse_srt_content(&output_str);
+
+    // 更新进度：即将完成
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.95,
+        None,
+        Some("正在生成字幕...".to_string()),
+    );
+
+    result
+}
+
+/// 使用配置的SenseVoice进行识别
+async fn call_sensevoice_with_config(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    debug!(
+        "使用SenseVoice模型进行识别，配置: {:?}",
+        params.model_config
+    );
+
+    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+
+    // 构建Python脚本来调用SenseVoice
+    let python_script = format!(
+        r#"
+import sys
+from funasr import AutoModel
+
+try:
+    # 初始化SenseVoice模型
+    model = AutoModel(
+        model="iic/SenseVoiceSmall" if "{model_size}" == "small" else "iic/SenseVoiceLarge",
+        device="{device}"
+    )
+
+    # 进行识别
+    result = model.generate(
+        input="{audio_path}",
+        language="{language}" if "{language}" != "auto" else "auto",
+        use_itn=True,
+        batch_size_s=60
+    )
+
+    # 输出SRT格式
+    if result and len(result) > 0:
+        for i, item in enumerate(result):
+            if 'text' in item:
+                # SenseVoice通常返回整段文本，需要分段处理
+                text = item['text'].strip()
+                if text:
+                    # 简单分段，实际应用中可能需要更复杂的分段逻辑
+                    duration = 5.0  # 假设每段5秒
+                    start_time = i * duration
+                    end_time = start_time + duration
+
+                    start_formatted = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+                        int(start_time // 3600),
+                        int((start_time % 3600) // 60),
+                        start_time % 60
+                    )
+                    end_formatted = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+                        int(end_time // 3600),
+                        int((end_time % 3600) // 60),
+                        end_time % 60
+                    )
+
+                    print(i+1)
+                    print(start_formatted + " --> " + end_formatted)
+                    print(text)
+                    print()
 
-    # 进行识别
-    segments, info = model.transcribe(
+                    # 情感和事件信息暂时跳过，避免格式问题
+
+except Exception as e:
+    print(f"SenseVoice 识别错误: {{e}}", file=sys.stderr)
+    import traceback
+    traceback.print_exc(file=sys.stderr)
+
+    # 输出默认字幕以避免解析失败
+    print("1")
+    print("00:00:00.000 --> 00:00:03.000")
+    print(f"识别失败: {{str(e)}}")
+    print()
+
+    sys.exit(1)
+"#,
+        model_size = params.model_config.size,
+        device = params.model_config.device,
+        audio_path = params.audio_path,
+        language = match params.language.as_str() {
+            "zh-cn" | "zh-tw" | "zh" => "zh",
+            _ => &params.language,
+        }
+    );
+
+    // 执行Python脚本（取消时结束进程）
+    let child = tokio::process::Command::new(python_program())
+        .arg("-c")
+        .arg(&python_script)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("执行SenseVoice失败: {}", e))?;
+    let output = wait_child_cancellable(child, cancel_rx).await?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("SenseVoice识别失败: {}", error_msg));
+    }
+
+    // 解析输出
+    let output_str = decode_task_output(task_id, &output.stdout, "SenseVoice");
+    parse_srt_content(&output_str)
+}
+
+/// 使用配置的本地whisper命令进行识别
+async fn call_local_whisper_with_config(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    use std::path::Path;
+
+    let audio_file = Path::new(&params.audio_path);
+    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
+    let started = std::time::SystemTime::now();
+
+    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+
+    // 构建whisper命令
+    let mut cmd = tokio::process::Command::new(whisper_program());
+    cmd.arg(&params.audio_path)
+        .arg("--model")
+        .arg(&params.model_config.size) // 使用配置的模型大小
+        .arg("--output_format")
+        .arg("srt")
+        .arg("--output_dir")
+        .arg(output_dir)
+        .arg("--verbose")
+        .arg("False")
+        .arg("--task")
+        .arg(params.task.as_str()); // 转写或翻译为英文
+
+    // 添加设备参数
+    if params.model_config.device == "gpu" {
+        cmd.arg("--device").arg("cuda");
+    }
+
+    // 添加温度参数
+    if let Some(temperature) = params.model_config.temperature {
+        cmd.arg("--temperature").arg(temperature.to_string());
+    }
+
+    // 设置语言（"auto" 或未指定时不添加 --language 参数，让 Whisper 自动检测）
+    if params.language == "zh" || params.language == "zh-CN" {
+        cmd.arg("--language").arg("zh");
+    } else if !is_auto_language(&params.language) {
+        let whisper_lang = match params.language.as_str() {
+            "en" => "en",
+            "ja" => "ja",
+            "ko" => "ko",
+            "fr" => "fr",
+            "de" => "de",
+            "es" => "es",
+            "ru" => "ru",
+            "zh-cn" => "zh",
+            "zh-tw" => "zh",
+            _ => return Err(format!("不支持的语言: {}", params.language)),
+        };
+        cmd.arg("--language").arg(whisper_lang);
+    }
+
+    // 设置初始提示词和热词
+    if let Some(prompt) = whisper_initial_prompt(params) {
+        cmd.arg("--initial_prompt").arg(prompt);
+    }
+
+    debug!("执行whisper命令: {:?}", cmd);
+
+    // 执行命令（取消时结束进程并删除已写出的部分SRT文件）
+    let child = cmd
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("执行whisper命令失败: {}", e))?;
+    let output = match wait_child_cancellable(child, cancel_rx).await {
+        Ok(output) => output,
+        Err(e) => {
+            if let Some(srt_path) = find_whisper_srt(output_dir, audio_file, started) {
+                let _ = std::fs::remove_file(srt_path);
+            }
+            return Err(e);
+        }
+    };
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Whisper命令执行失败: {}", error_msg));
+    }
+
+    record_detected_language(
+        task_id,
+        &params.language,
+        &String::from_utf8_lossy(&output.stdout),
+    );
+    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
+
+    // 读取生成的SRT文件
+    let srt_file_path = find_whisper_srt(output_dir, audio_file, started)
+        .ok_or_else(|| format!("未找到生成的SRT文件（输出目录: {}）", output_dir.display()))?;
+
+    let srt_bytes = std::fs::read(&srt_file_path).map_err(|e| format!("读取SRT文件失败: {}", e))?;
+    let srt_content = decode_task_output(task_id, &srt_bytes, "Whisper");
+
+    // 解析SRT内容
+    parse_srt_content(&srt_content)
+}
+
+/// 使用配置的Python whisper进行识别
+async fn call_python_whisper_with_config(
+    params: &ExtendedRecognitionParams,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+
+    // 构建Python脚本
+    let python_script = format!(
+        r#"
+import sys
+import whisper
+
+try:
+    model = whisper.load_model("{model_size}")
+    result = model.transcribe(
         "{audio_path}",
-        language="{language}" if "{language}" != "auto" else None,
-        beam_size=beam_size,
-        temperature=temperature,
-        word_timestamps=True,
-        initial_prompt=initial_prompt
+        language={language},
+        task="{task}",
+        temperature={temperature},
+        initial_prompt={initial_prompt}
     )
+    print("DETECTED_LANGUAGE:" + str(result.get("language")), file=sys.stderr)
 
     # 输出SRT格式
-    for i, segment in enumerate(segments):
-        start = segment.start
-        end = segment.end
-        text = segment.text.strip()
+    for i, segment in enumerate(result['segments']):
+        start = segment['start']
+        end = segment['end']
+        text = segment['text'].strip()
+
+        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(start // 3600),
+            int((start % 3600) // 60),
+            start % 60
+        )
+        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(end // 3600),
+            int((end % 3600) // 60),
+            end % 60
+        )
+
+        print(i+1)
+        print(start_time + " --> " + end_time)
+        print(text)
+        print()
+
+except Exception as e:
+    print("Error: " + str(e), file=sys.stderr)
+    sys.exit(1)
+"#,
+        model_size = params.model_config.size,
+        audio_path = params.audio_path,
+        language = python_string_literal(whisper_language_code(&params.language).as_deref()),
+        task = params.task.as_str(),
+        temperature = params.model_config.temperature.unwrap_or(0.0),
+        initial_prompt = python_string_literal(whisper_initial_prompt(params).as_deref())
+    );
+
+    // 执行Python脚本（取消时结束进程）
+    let child = tokio::process::Command::new(python_program())
+        .arg("-c")
+        .arg(&python_script)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("执行Python whisper失败: {}", e))?;
+    let output = wait_child_cancellable(child, cancel_rx).await?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Python Whisper识别失败: {}", error_msg));
+    }
+
+    record_detected_language(
+        task_id,
+        &params.language,
+        &String::from_utf8_lossy(&output.stderr),
+    );
+
+    // 解析输出
+    let output_str = decode_task_output(task_id, &output.stdout, "Python Whisper");
+    parse_srt_content(&output_str)
+}
+
+/// 是否为自动检测语言（"auto" 或未指定）
+fn is_auto_language(language: &str) -> bool {
+    let language = language.trim();
+    language.is_empty() || language.eq_ignore_ascii_case("auto")
+}
+
+/// 转换为Whisper的语言代码，自动检测时返回None（不传语言参数）
+fn whisper_language_code(language: &str) -> Option<String> {
+    if is_auto_language(language) {
+        return None;
+    }
+    let language = language.trim().to_lowercase();
+    Some(
+        language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or(&language)
+            .to_string(),
+    )
+}
+
+/// 从Whisper输出中读取检测到的语言
+///
+/// 识别脚本输出 `DETECTED_LANGUAGE:en`；whisper命令行输出 `Detected language: English`，
+/// 常见语言名转换为语言代码。
+fn parse_detected_language(output: &str) -> Option<String> {
+    const LANGUAGE_NAMES: &[(&str, &str)] = &[
+        ("chinese", "zh"),
+        ("english", "en"),
+        ("japanese", "ja"),
+        ("korean", "ko"),
+        ("french", "fr"),
+        ("german", "de"),
+        ("spanish", "es"),
+        ("russian", "ru"),
+        ("italian", "it"),
+        ("portuguese", "pt"),
+        ("arabic", "ar"),
+        ("thai", "th"),
+        ("vietnamese", "vi"),
+        ("indonesian", "id"),
+        ("cantonese", "yue"),
+    ];
+
+    output.lines().find_map(|line| {
+        let line = line.trim();
+        if let Some(code) = line.strip_prefix("DETECTED_LANGUAGE:") {
+            let code = code.trim();
+            return (!code.is_empty() && code != "None").then(|| code.to_string());
+        }
+        let name = line
+            .strip_prefix("Detected language:")?
+            .trim()
+            .to_lowercase();
+        let code = LANGUAGE_NAMES
+            .iter()
+            .find(|(language_name, _)| *language_name == name)
+            .map_or(name, |(_, code)| code.to_string());
+        (!code.is_empty()).then_some(code)
+    })
+}
+
+/// 自动检测语言时，从输出中读取检测结果并记录到任务状态
+fn record_detected_language(task_id: &str, requested: &str, output: &str) {
+    if !is_auto_language(requested) {
+        return;
+    }
+    if let Some(language) = parse_detected_language(output) {
+        set_detected_language(task_id, language);
+    }
+}
+
+/// 生成Whisper初始提示词（自定义提示词优先，中文默认引导简体输出，并附加热词）
+fn whisper_initial_prompt(params: &ExtendedRecognitionParams) -> Option<String> {
+    let mut prompt = match params.initial_prompt.as_deref().map(str::trim) {
+        Some(prompt) if !prompt.is_empty() => prompt.to_string(),
+        // 翻译模式输出英文，不添加简体中文提示
+        _ if params.task == WhisperTask::Translate => String::new(),
+        _ => match params.language.as_str() {
+            "zh" | "zh-cn" | "zh-CN" => "以下是简体中文语音：".to_string(),
+            _ => String::new(),
+        },
+    };
+
+    let hotwords: Vec<&str> = params
+        .hotwords
+        .iter()
+        .map(|w| w.trim())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if !hotwords.is_empty() {
+        if !prompt.is_empty() {
+            prompt.push(' ');
+        }
+        prompt.push_str(&hotwords.join(", "));
+    }
+
+    if prompt.is_empty() {
+        None
+    } else {
+        Some(prompt)
+    }
+}
+
+/// 将可选字符串转换为Python字面量（JSON字符串即合法的Python字符串）
+fn python_string_literal(value: Option<&str>) -> String {
+    match value {
+        Some(value) => serde_json::to_string(value).unwrap_or_else(|_| "None".to_string()),
+        None => "None".to_string(),
+    }
+}
+
+/// 生成腾讯云热词列表参数（格式: 热词|权重,热词|权重）
+fn tencent_hotword_list(hotwords: &[String]) -> Option<String> {
+    let list: Vec<String> = hotwords
+        .iter()
+        .map(|w| w.replace(['|', ','], "").trim().to_string())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("{}|10", w))
+        .collect();
+
+    if list.is_empty() {
+        None
+    } else {
+        Some(list.join(","))
+    }
+}
+
+/// 在输出目录中查找Whisper本次生成的SRT文件
+///
+/// 不同版本的Whisper对含点号的文件名处理不同，也可能输出 `<文件名>.<语言>.srt`，
+/// 因此匹配完整文件名、去掉扩展名或去掉所有后缀的文件名开头的SRT，
+/// 只考虑识别开始后写入的文件，取最新的一个。
+fn find_whisper_srt(
+    output_dir: &Path,
+    audio_file: &Path,
+    started: std::time::SystemTime,
+) -> Option<std::path::PathBuf> {
+    let file_name = audio_file.file_name()?.to_string_lossy().to_string();
+    let file_stem = audio_file.file_stem()?.to_string_lossy().to_string();
+    let short_stem = file_name
+        .split('.')
+        .next()
+        .unwrap_or(&file_stem)
+        .to_string();
+    let candidates = [file_name, file_stem, short_stem];
+
+    // 文件系统时间戳精度有限，留出少量余量
+    let since = started
+        .checked_sub(std::time::Duration::from_secs(2))
+        .unwrap_or(started);
+
+    std::fs::read_dir(output_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let base = name.strip_suffix(".srt")?;
+            let matches = candidates.iter().any(|stem| {
+                base == stem
+                    || base
+                        .strip_prefix(stem.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+            if !matches {
+                return None;
+            }
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            (modified >= since).then(|| (modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// 使用本地whisper命令进行识别
+async fn call_local_whisper(
+    audio_path: &str,
+    language: &str,
+    model: &str,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    use std::path::Path;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 
-        # 根据语言设置进行繁简转换
-        original_language = "{original_language}"
-        if original_language in ["zh-cn", "zh"] and converter and text:
-            # 转换为简体中文
-            try:
-                if hasattr(converter, 'convert'):
-                    # OpenCC 转换器
-                    text = converter.convert(text)
-                else:
-                    # zhconv 转换器
-                    text = converter(text)
-            except Exception as e:
-                print("繁简转换失败: " + str(e), file=sys.stderr)
-                # 转换失败时保持原文
-        elif original_language == "zh-tw" and text:
-            # 繁体中文，不进行转换或转换为繁体
-            try:
-                if converter:
-                    # 如果有转换器，可以进行简体到繁体的转换
-                    import opencc
-                    s2t_converter = opencc.OpenCC('s2t')  # 简体转繁体
-                    text = s2t_converter.convert(text)
-            except:
-                # 转换失败时保持原文
-                pass
+    let audio_file = Path::new(audio_path);
+    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
+    let started = std::time::SystemTime::now();
+    // 媒体时长用于把Whisper输出的片段时间换算为进度，获取失败时不更新进度
+    let duration = crate::video::get_video_info(audio_path)
+        .map(|info| info.duration)
+        .unwrap_or(0.0);
 
-        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(start // 3600),
-            int((start % 3600) // 60),
-            start % 60
-        )
-        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(end // 3600),
-            int((end % 3600) // 60),
-            end % 60
-        )
+    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
 
-        print(i+1)
-        print(start_time + " --> " + end_time)
-        print(text)
-        print()
+    // 构建whisper命令（verbose模式逐段输出 [开始 --> 结束] 文本，用于计算进度）
+    let mut cmd = tokio::process::Command::new(whisper_program());
+    cmd.arg(audio_path)
+        .arg("--model")
+        .arg(model)
+        .arg("--output_format")
+        .arg("srt")
+        .arg("--output_dir")
+        .arg(output_dir)
+        .arg("--verbose")
+        .arg("True")
+        .arg("--task")
+        .arg("transcribe"); // 明确指定转写任务
 
-except Exception as e:
-    print("Error: " + str(e), file=sys.stderr)
-    sys.exit(1)
-"#,
-        model_size = params.model_config.size,
-        device = params.model_config.device,
-        compute_type = params
-            .model_config
-            .compute_type
-            .as_ref()
-            .unwrap_or(&"int8".to_string()),
-        beam_size = params.model_config.beam_size.unwrap_or(5),
-        temperature = params.model_config.temperature.unwrap_or(0.0),
-        audio_path = params.audio_path,
-        language = match params.language.as_str() {
-            "zh-cn" | "zh-tw" | "zh" => "zh",
-            _ => &params.language,
-        },
-        original_language = params.language
-    );
+    // 设置语言：中文引导简体输出，"auto" 或未指定时让 Whisper 自动检测
+    if let Some(code) = whisper_language_code(language) {
+        if code == "zh" {
+            cmd.arg("--initial_prompt").arg("以下是简体中文语音：");
+        }
+        cmd.arg("--language").arg(code);
+    }
 
-    // 更新进度：开始识别
-    update_task_status(
-        task_id,
-        "processing".to_string(),
-        0.2,
-        None,
-        Some("正在进行语音识别...".to_string()),
-    );
+    debug!("执行Whisper命令: {:?}", cmd);
 
-    // 执行Python脚本，监控下载进度
-    let mut child = Command::new("python3")
-        .arg("-c")
-        .arg(&python_script)
-        .stderr(std::process::Stdio::piped())
+    let mut child = cmd
         .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .map_err(|e| format!("启动faster-whisper失败: {}", e))?;
+        .map_err(|e| format!("执行whisper命令失败: {}", e))?;
 
-    // 监控stderr输出以获取下载进度
-    if let Some(stderr) = child.stderr.take() {
-        use std::io::{BufRead, BufReader};
-        let reader = BufReader::new(stderr);
-        let task_id_owned = task_id.to_string(); // 转换为owned string
+    // stderr在后台收集，用于失败时的错误信息
+    let mut stderr = child.stderr.take().ok_or("无法读取whisper错误输出")?;
+    let stderr_task = tokio::spawn(async move {
+        let mut buffer = Vec::new();
+        let _ = stderr.read_to_end(&mut buffer).await;
+        buffer
+    });
 
-        tokio::spawn(async move {
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if line.contains("DOWNLOAD_START") {
-                        update_task_status(
-                            &task_id_owned,
-                            "processing".to_string(),
-                            0.1,
-                            None,
-                            Some("正在下载模型文件，首次使用需要一些时间...".to_string()),
-                        );
-                    } else if line.contains("DOWNLOAD_COMPLETE") {
+    // 逐行读取stdout，按最新片段的结束时间更新进度（0.3-0.8区间）
+    let stdout = child.stdout.take().ok_or("无法读取whisper输出")?;
+    let mut lines = BufReader::new(stdout).split(b'\n');
+    let mut stdout_text = String::new();
+    let mut last_progress = 0.3;
+    loop {
+        tokio::select! {
+            Some(()) = cancel_rx.recv() => {
+                let _ = child.kill().await;
+                // 删除已写出的部分SRT文件
+                if let Some(srt_path) = find_whisper_srt(output_dir, audio_file, started) {
+                    let _ = std::fs::remove_file(srt_path);
+                }
+                return Err(TASK_CANCELLED.to_string());
+            }
+            line = lines.next_segment() => {
+                let Some(line) = line.map_err(|e| format!("读取whisper输出失败: {}", e))? else {
+                    break;
+                };
+                let line = String::from_utf8_lossy(&line);
+                if let Some(end) = parse_whisper_segment_end(&line).filter(|_| duration > 0.0) {
+                    let progress = 0.3 + 0.5 * (end / duration).min(1.0) as f32;
+                    if progress - last_progress >= 0.005 {
+                        last_progress = progress;
                         update_task_status(
-                            &task_id_owned,
+                            task_id,
                             "processing".to_string(),
-                            0.15,
+                            progress,
                             None,
-                            Some("模型下载完成，开始初始化...".to_string()),
+                            Some(format!("正在识别... {:.0}%", (end / duration).min(1.0) * 100.0)),
                         );
                     }
                 }
+                stdout_text.push_str(&line);
+                stdout_text.push('\n');
             }
-        });
+        }
     }
 
-    // 等待进程完成
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("执行faster-whisper失败: {}", e))?;
-
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Faster-Whisper识别失败: {}", error_msg));
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("执行whisper命令失败: {}", e))?;
+    let stderr = stderr_task.await.unwrap_or_default();
+    if !status.success() {
+        return Err(format!(
+            "Whisper执行失败: {}",
+            String::from_utf8_lossy(&stderr)
+        ));
     }
 
-    // 更新进度：处理结果
-    update_task_status(
-        task_id,
-        "processing".to_string(),
-        0.8,
-        None,
-        Some("正在处理识别结果...".to_string()),
-    );
+    record_detected_language(task_id, language, &stdout_text);
+    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
 
-    // 解析输出
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    let result = parse_srt_content(&output_str);
+    // 读取生成的SRT文件
+    let srt_path = find_whisper_srt(output_dir, audio_file, started)
+        .ok_or_else(|| format!("Whisper未生成SRT文件（输出目录: {}）", output_dir.display()))?;
 
-    // 更新进度：即将完成
-    update_task_status(
-        task_id,
-        "processing".to_string(),
-        0.95,
-        None,
-        Some("正在生成字幕...".to_string()),
-    );
+    let srt_bytes = std::fs::read(&srt_path).map_err(|e| format!("读取SRT文件失败: {}", e))?;
+    let srt_content = decode_task_output(task_id, &srt_bytes, "Whisper");
 
-    result
+    // 解析SRT文件
+    let subtitles = parse_srt_content(&srt_content)?;
+
+    // 清理临时文件
+    let _ = std::fs::remove_file(&srt_path);
+
+    info!("Whisper识别完成，共解析到{}条字幕", subtitles.len());
+    Ok(subtitles)
 }
 
-/// 使用配置的SenseVoice进行识别
-async fn call_sensevoice_with_config(
-    params: &ExtendedRecognitionParams,
+/// 解析Whisper verbose输出行 `[MM:SS.mmm --> MM:SS.mmm] 文本` 中的结束时间（秒）
+///
+/// 超过一小时的音频时间格式为 `HH:MM:SS.mmm`。
+fn parse_whisper_segment_end(line: &str) -> Option<f64> {
+    let rest = line.trim_start().strip_prefix('[')?;
+    let (range, _) = rest.split_once(']')?;
+    let (_, end) = range.split_once("-->")?;
+    end.trim().split(':').try_fold(0.0, |total, part| {
+        Some(total * 60.0 + part.parse::<f64>().ok()?)
+    })
+}
+
+/// 使用Python whisper进行识别
+async fn call_python_whisper(
+    audio_path: &str,
+    language: &str,
+    model: &str,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    println!(
-        "使用SenseVoice模型进行识别，配置: {:?}",
-        params.model_config
-    );
+    use std::path::Path;
+
+    // 检查是否安装了openai-whisper
+    let python_check = python_cmd()
+        .args(["-c", "import whisper; print('whisper available')"])
+        .output();
+
+    match python_check {
+        Ok(output) if output.status.success() => {
+            info!("发现Python whisper库");
+        }
+        _ => {
+            return Err("未找到whisper。请安装: pip install openai-whisper".to_string());
+        }
+    }
+
+    let audio_file = Path::new(audio_path);
+    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
+    let file_stem = audio_file.file_stem().unwrap().to_string_lossy();
 
     update_task_status(task_id, "processing".to_string(), 0.3, None, None);
 
-    // 构建Python脚本来调用SenseVoice
+    // 创建Python脚本（中文结果输出简体）
     let python_script = format!(
         r#"
+import whisper
 import sys
-from funasr import AutoModel
 
 try:
-    # 初始化SenseVoice模型
-    model = AutoModel(
-        model="iic/SenseVoiceSmall" if "{model_size}" == "small" else "iic/SenseVoiceLarge",
-        device="{device}"
-    )
-
-    # 进行识别
-    result = model.generate(
-        input="{audio_path}",
-        language="{language}" if "{language}" != "auto" else "auto",
-        use_itn=True,
-        batch_size_s=60
-    )
+    import opencc
+    converter = opencc.OpenCC('t2s')  # 繁体转简体
+except ImportError:
+    print("Warning: opencc not available, skipping traditional to simplified conversion", file=sys.stderr)
+    converter = None
 
+try:
+    model = whisper.load_model({model})
+    # 中文识别指定简体中文提示，自动检测时不指定语言
+    result = model.transcribe({audio_path}, language={language}, initial_prompt={initial_prompt})
+    print("DETECTED_LANGUAGE:" + str(result.get("language")), file=sys.stderr)
+    is_chinese = result.get("language") == "zh"
+    
     # 输出SRT格式
-    if result and len(result) > 0:
-        for i, item in enumerate(result):
-            if 'text' in item:
-                # SenseVoice通常返回整段文本，需要分段处理
-                text = item['text'].strip()
-                if text:
-                    # 简单分段，实际应用中可能需要更复杂的分段逻辑
-                    duration = 5.0  # 假设每段5秒
-                    start_time = i * duration
-                    end_time = start_time + duration
+    for i, segment in enumerate(result['segments']):
+        start = segment['start']
+        end = segment['end']
+        text = segment['text'].strip()
+        
+        # 中文结果转换为简体中文
+        if is_chinese and converter and text:
+            try:
+                text = converter.convert(text)
+            except:
+                pass  # 如果转换失败，保持原文
+        
+        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(start // 3600),
+            int((start % 3600) // 60),
+            start % 60
+        )
+        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
+            int(end // 3600),
+            int((end % 3600) // 60),
+            end % 60
+        )
+
+        print(i+1)
+        print(start_time + " --> " + end_time)
+        print(text)
+        print()
+except Exception as e:
+    print("Error: " + str(e), file=sys.stderr)
+    sys.exit(1)
+"#,
+        model = python_string_literal(Some(model)),
+        audio_path = python_string_literal(Some(audio_path)),
+        language = python_string_literal(whisper_language_code(language).as_deref()),
+        initial_prompt = python_string_literal(
+            (whisper_language_code(language).as_deref() == Some("zh"))
+                .then_some("以下是简体中文语音：")
+        ),
+    );
+
+    // 写入临时Python文件
+    let script_path = output_dir.join(format!("{}_whisper.py", file_stem));
+    std::fs::write(&script_path, python_script)
+        .map_err(|e| format!("写入Python脚本失败: {}", e))?;
+
+    update_task_status(task_id, "processing".to_string(), 0.5, None, None);
+
+    // 执行Python脚本（取消时结束进程）
+    info!("执行Python Whisper脚本...");
+    let child = tokio::process::Command::new(python_program())
+        .arg(&script_path)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("执行Python脚本失败: {}", e))?;
+    let output = wait_child_cancellable(child, cancel_rx).await;
+
+    // 清理临时文件
+    let _ = std::fs::remove_file(&script_path);
+    let output = output?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Python Whisper执行失败: {}", stderr));
+    }
+
+    record_detected_language(task_id, language, &String::from_utf8_lossy(&output.stderr));
+    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
+
+    // 解析输出的SRT内容
+    let srt_content = decode_task_output(task_id, &output.stdout, "Python Whisper");
+    let subtitles = parse_srt_content(&srt_content)?;
 
-                    start_formatted = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-                        int(start_time // 3600),
-                        int((start_time % 3600) // 60),
-                        start_time % 60
-                    )
-                    end_formatted = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-                        int(end_time // 3600),
-                        int((end_time % 3600) // 60),
-                        end_time % 60
-                    )
+    info!("Python Whisper识别完成，共解析到{}条字幕", subtitles.len());
+    Ok(subtitles)
+}
 
-                    print(i+1)
-                    print(start_formatted + " --> " + end_formatted)
-                    print(text)
-                    print()
+/// 解析SRT格式内容
+fn parse_srt_content(content: &str) -> Result<Vec<crate::video::Subtitle>, String> {
+    let mut subtitles = Vec::new();
+    // 统一换行符，Windows及部分Whisper版本输出的是 \r\n
+    let content = content.replace("\r\n", "\n").replace('\r', "\n");
+    let blocks: Vec<&str> = content.split("\n\n").collect();
 
-                    # 情感和事件信息暂时跳过，避免格式问题
+    for block in blocks {
+        let lines: Vec<&str> = block.trim().split('\n').collect();
 
-except Exception as e:
-    print(f"SenseVoice 识别错误: {{e}}", file=sys.stderr)
-    import traceback
-    traceback.print_exc(file=sys.stderr)
+        // 部分工具输出的字幕块省略序号行，首行即为时间行，此时按顺序生成序号
+        let (id, time_index) = if lines[0].contains("-->") {
+            ((subtitles.len() + 1).to_string(), 0)
+        } else {
+            (lines[0].trim().to_string(), 1)
+        };
+        // 只有时间行没有文本的块是空字幕，保留给 `drop_empty_cues` 统一处理
+        if lines.len() < time_index + 1 {
+            continue;
+        }
 
-    # 输出默认字幕以避免解析失败
-    print("1")
-    print("00:00:00.000 --> 00:00:03.000")
-    print(f"识别失败: {{str(e)}}")
-    print()
+        // 解析时间
+        let time_line = lines[time_index];
+        if let Some((start_str, end_str)) = time_line.split_once("-->") {
+            let start_time = parse_srt_time(start_str.trim())?;
+            let end_time = parse_srt_time(end_str.trim())?;
 
-    sys.exit(1)
-"#,
-        model_size = params.model_config.size,
-        device = params.model_config.device,
-        audio_path = params.audio_path,
-        language = match params.language.as_str() {
-            "zh-cn" | "zh-tw" | "zh" => "zh",
-            _ => &params.language,
+            // 解析文本（可能有多行）
+            let text = lines[time_index + 1..].join("\n").trim().to_string();
+
+            subtitles.push(crate::video::Subtitle {
+                id,
+                start_time,
+                end_time,
+                text,
+                position: None,
+                words: None,
+            });
         }
-    );
+    }
 
-    // 执行Python脚本
-    let output = Command::new("python3")
-        .arg("-c")
-        .arg(&python_script)
-        .output()
-        .map_err(|e| format!("执行SenseVoice失败: {}", e))?;
+    if subtitles.is_empty() {
+        return Err("未解析到任何字幕内容".to_string());
+    }
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("SenseVoice识别失败: {}", error_msg));
+    Ok(subtitles)
+}
+
+/// 解析SRT时间格式 (HH:MM:SS,mmm)
+fn parse_srt_time(time_str: &str) -> Result<f64, String> {
+    let time_str = time_str.replace(',', "."); // SRT使用逗号作为毫秒分隔符
+    let parts: Vec<&str> = time_str.split(':').collect();
+
+    if parts.len() != 3 {
+        return Err(format!("无效的时间格式: {}", time_str));
     }
 
-    // 解析输出
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    parse_srt_content(&output_str)
+    let hours: f64 = parts[0]
+        .parse()
+        .map_err(|_| format!("无效的小时: {}", parts[0]))?;
+    let minutes: f64 = parts[1]
+        .parse()
+        .map_err(|_| format!("无效的分钟: {}", parts[1]))?;
+    let seconds: f64 = parts[2]
+        .parse()
+        .map_err(|_| format!("无效的秒数: {}", parts[2]))?;
+
+    Ok(hours * 3600.0 + minutes * 60.0 + seconds)
 }
 
-/// 使用配置的本地whisper命令进行识别
-async fn call_local_whisper_with_config(
-    params: &ExtendedRecognitionParams,
+/// 调用百度智能云语音识别API
+async fn call_baidu_api(
+    audio_path: &str,
+    language: &str,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
+    api_key: &str,
+    secret_key: &str,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    use std::path::Path;
-    use std::process::Command;
+    use std::fs;
 
-    let audio_file = Path::new(&params.audio_path);
-    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
-    let file_stem = audio_file.file_stem().unwrap().to_string_lossy();
+    // 读取音频文件
+    let audio_data = fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
 
-    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+    // 将音频数据转换为base64
+    let audio_base64 = base64::engine::general_purpose::STANDARD.encode(&audio_data);
 
-    // 构建whisper命令
-    let mut cmd = Command::new("whisper");
-    cmd.arg(&params.audio_path)
-        .arg("--model")
-        .arg(&params.model_config.size) // 使用配置的模型大小
-        .arg("--output_format")
-        .arg("srt")
-        .arg("--output_dir")
-        .arg(output_dir)
-        .arg("--verbose")
-        .arg("False")
-        .arg("--task")
-        .arg("transcribe"); // 明确指定转写任务
+    // 获取访问令牌
+    update_task_status(task_id, "processing".to_string(), 0.1, None, None);
 
-    // 添加设备参数
-    if params.model_config.device == "gpu" {
-        cmd.arg("--device").arg("cuda");
-    }
+    let access_token = get_baidu_access_token(api_key, secret_key)
+        .await
+        .map_err(|e| format!("获取百度访问令牌失败: {}", e))?;
 
-    // 添加温度参数
-    if let Some(temperature) = params.model_config.temperature {
-        cmd.arg("--temperature").arg(temperature.to_string());
+    // 检查取消信号
+    if cancel_rx.try_recv().is_ok() {
+        return Err("任务已取消".to_string());
     }
 
+    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+
+    // 构建请求参数
+    let mut params = HashMap::new();
+    params.insert("format", "wav".to_string());
+    params.insert("rate", "16000".to_string());
+    params.insert("channel", "1".to_string());
+    params.insert("cuid", "flow-text-app".to_string());
+    params.insert("token", access_token);
+    params.insert("speech", audio_base64);
+    params.insert("len", audio_data.len().to_string());
+
     // 设置语言
-    if params.language == "zh" || params.language == "zh-CN" || params.language.is_empty() {
-        cmd.arg("--language").arg("zh");
-        cmd.arg("--initial_prompt").arg("以下是简体中文语音：");
-    } else if params.language != "auto" {
-        let whisper_lang = match params.language.as_str() {
-            "en" => "en",
-            "ja" => "ja",
-            "ko" => "ko",
-            "fr" => "fr",
-            "de" => "de",
-            "es" => "es",
-            "ru" => "ru",
-            "zh-cn" => "zh",
-            "zh-tw" => "zh",
-            _ => return Err(format!("不支持的语言: {}", params.language)),
-        };
-        cmd.arg("--language").arg(whisper_lang);
-    }
-    // 如果是 "auto"，则不添加 --language 参数，让 Whisper 自动检测
+    let dev_pid = match language {
+        "zh-CN" => "1537", // 普通话(支持简单的英文识别)
+        "en-US" => "1737", // 英语
+        _ => "1537",       // 默认普通话
+    };
+    params.insert("dev_pid", dev_pid.to_string());
 
-    println!("执行whisper命令: {:?}", cmd);
+    // 发送请求
+    update_task_status(task_id, "processing".to_string(), 0.5, None, None);
 
-    // 执行命令
-    let output = cmd
-        .output()
-        .map_err(|e| format!("执行whisper命令失败: {}", e))?;
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://vop.baidu.com/server_api")
+        .header("Content-Type", "application/json")
+        .json(&params)
+        .send()
+        .await
+        .map_err(|e| format!("发送请求失败: {}", e))?;
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Whisper命令执行失败: {}", error_msg));
+    // 检查取消信号
+    if cancel_rx.try_recv().is_ok() {
+        return Err("任务已取消".to_string());
     }
 
     update_task_status(task_id, "processing".to_string(), 0.8, None, None);
 
-    // 读取生成的SRT文件
-    let srt_file_path = output_dir.join(format!("{}.srt", file_stem));
+    // 解析响应
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("读取响应失败: {}", e))?;
+
+    let response_json: Value =
+        serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))?;
+
+    // 检查错误
+    if let Some(err_no) = response_json["err_no"].as_i64() {
+        if err_no != 0 {
+            let err_msg = response_json["err_msg"].as_str().unwrap_or("未知错误");
+            return Err(format!("百度API错误 {}: {}", err_no, err_msg));
+        }
+    }
+
+    // 提取识别结果
+    let result_text = response_json["result"]
+        .as_array()
+        .and_then(|arr| arr.first())
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    update_task_status(task_id, "processing".to_string(), 0.9, None, None);
+
+    // 将结果转换为字幕格式
+    let subtitles = if result_text.is_empty() {
+        vec![]
+    } else {
+        // 简单处理：将整个识别结果作为一个字幕段
+        // 实际应用中可能需要更复杂的分段逻辑
+        vec![crate::video::Subtitle {
+            id: "1".to_string(),
+            start_time: 0.0,
+            end_time: 10.0, // 默认时长，实际应该根据音频长度计算
+            text: result_text.to_string(),
+            position: None,
+            words: None,
+        }]
+    };
+
+    Ok(subtitles)
+}
+
+/// 获取百度访问令牌
+async fn get_baidu_access_token(api_key: &str, secret_key: &str) -> Result<String, String> {
+    if api_key.is_empty() || secret_key.is_empty() {
+        return Err("请在设置中配置百度API密钥".to_string());
+    }
+
+    let client = reqwest::Client::new();
+    let url = format!(
+        "https://aip.baidubce.com/oauth/2.0/token?grant_type=client_credentials&client_id={}&client_secret={}",
+        api_key, secret_key
+    );
+
+    let response = client
+        .post(&url)
+        .send()
+        .await
+        .map_err(|e| format!("获取访问令牌请求失败: {}", e))?;
+
+    let response_json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("解析访问令牌响应失败: {}", e))?;
 
-    if !srt_file_path.exists() {
-        return Err("未找到生成的SRT文件".to_string());
+    if let Some(error) = response_json["error"].as_str() {
+        return Err(format!("获取访问令牌失败: {}", error));
     }
 
-    let srt_content =
-        std::fs::read_to_string(&srt_file_path).map_err(|e| format!("读取SRT文件失败: {}", e))?;
+    let access_token = response_json["access_token"]
+        .as_str()
+        .ok_or("响应中未找到访问令牌")?;
 
-    // 解析SRT内容
-    parse_srt_content(&srt_content)
+    Ok(access_token.to_string())
 }
 
-/// 使用配置的Python whisper进行识别
-async fn call_python_whisper_with_config(
-    params: &ExtendedRecognitionParams,
+/// 调用腾讯云语音识别API
+async fn call_tencent_api(
+    audio_path: &str,
+    _language: &str,
     task_id: &str,
-    _cancel_rx: &mut mpsc::Receiver<()>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+    secret_id: &str,
+    secret_key: &str,
+    options: TencentOptions,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
-
-    // 构建Python脚本
-    let python_script = format!(
-        r#"
-import sys
-import whisper
+    // 检查API密钥
+    if secret_id.is_empty() || secret_key.is_empty() {
+        return Err("腾讯云API密钥未配置".to_string());
+    }
 
-try:
-    model = whisper.load_model("{model_size}")
-    result = model.transcribe(
-        "{audio_path}",
-        language="{language}" if "{language}" != "auto" else None,
-        temperature={temperature}
-    )
+    info!("腾讯云API调用开始");
+    debug!(
+        "Secret ID: {}",
+        if secret_id.is_empty() {
+            "[空]"
+        } else {
+            "[已配置]"
+        }
+    );
+    debug!(
+        "Secret Key: {}",
+        if secret_key.is_empty() {
+            "[空]"
+        } else {
+            "[已配置]"
+        }
+    );
 
-    # 输出SRT格式
-    for i, segment in enumerate(result['segments']):
-        start = segment['start']
-        end = segment['end']
-        text = segment['text'].strip()
+    // 更新进度：开始处理
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.1,
+        None,
+        Some("正在读取音频文件...".to_string()),
+    );
 
-        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(start // 3600),
-            int((start % 3600) // 60),
-            start % 60
-        )
-        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(end // 3600),
-            int((end % 3600) // 60),
-            end % 60
-        )
+    // 检查取消信号
+    if cancel_rx.try_recv().is_ok() {
+        return Err("任务已取消".to_string());
+    }
 
-        print(i+1)
-        print(start_time + " --> " + end_time)
-        print(text)
-        print()
+    // 读取音频文件并转换为base64
+    let audio_data = match std::fs::read(audio_path) {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(format!("读取音频文件失败: {}", e));
+        }
+    };
 
-except Exception as e:
-    print("Error: " + str(e), file=sys.stderr)
-    sys.exit(1)
-"#,
-        model_size = params.model_config.size,
-        audio_path = params.audio_path,
-        language = if params.language == "zh" {
-            "zh"
-        } else {
-            &params.language
-        },
-        temperature = params.model_config.temperature.unwrap_or(0.0)
+    // 更新进度：文件读取完成
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.3,
+        None,
+        Some("正在调用腾讯云API...".to_string()),
     );
 
-    // 执行Python脚本
-    let output = Command::new("python3")
-        .arg("-c")
-        .arg(&python_script)
-        .output()
-        .map_err(|e| format!("执行Python whisper失败: {}", e))?;
+    // 检查取消信号
+    if cancel_rx.try_recv().is_ok() {
+        return Err("任务已取消".to_string());
+    }
 
-    if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python Whisper识别失败: {}", error_msg));
+    // 超出直接上传限制时按大文件策略处理（重试任务有已上传的URL时直接复用）
+    let strategy = options
+        .large_file_strategy
+        .unwrap_or(if options.cos_config.is_some() {
+            LargeFileStrategy::Cos
+        } else {
+            LargeFileStrategy::Chunk
+        });
+    if !tencent_request_fits(audio_data.len()) && options.uploaded_url.is_none() {
+        match strategy {
+            LargeFileStrategy::Cos if options.cos_config.is_none() => {
+                return Err("大文件处理方式为COS上传，但未配置COS存储桶".to_string());
+            }
+            LargeFileStrategy::Error => {
+                let error = FlowError::FileTooLarge {
+                    size_mb: audio_data.len() as f64 / (1024.0 * 1024.0),
+                    limit_mb: (TENCENT_LOCAL_UPLOAD_LIMIT / (1024 * 1024)) as f64,
+                };
+                return Err(format!("{}，大文件处理方式设置为不处理", error));
+            }
+            _ => {}
+        }
+    }
+    if !tencent_request_fits(audio_data.len())
+        && strategy == LargeFileStrategy::Chunk
+        && options.uploaded_url.is_none()
+    {
+        // PCM WAV直接按采样帧分片；其他编码先由FFmpeg切分转码
+        if parse_pcm_wav(&audio_data).is_some() {
+            info!(
+                "音频{:.1} MB超过直接上传限制，分片识别",
+                audio_data.len() as f64 / (1024.0 * 1024.0)
+            );
+            return process_large_audio_in_chunks(
+                secret_id,
+                secret_key,
+                audio_path,
+                &audio_data,
+                task_id,
+                cancel_rx,
+                &options,
+            )
+            .await;
+        }
+
+        // 切分后的片段为16kHz单声道16位PCM（每秒32000字节），留10%余量
+        let max_segment_seconds = TENCENT_LOCAL_UPLOAD_LIMIT as f64 * 0.9 / 32000.0;
+        info!(
+            "音频{:.1} MB超过直接上传限制，按{:.0}秒分段识别",
+            audio_data.len() as f64 / (1024.0 * 1024.0),
+            max_segment_seconds
+        );
+        return call_tencent_api_in_segments(
+            audio_path,
+            task_id,
+            cancel_rx,
+            secret_id,
+            secret_key,
+            TencentOptions {
+                max_segment_seconds,
+                ..options
+            },
+        )
+        .await;
     }
 
-    // 解析输出
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    parse_srt_content(&output_str)
+    // 调用腾讯云录音文件识别极速版API
+    match call_tencent_rapid_asr(
+        secret_id,
+        secret_key,
+        &audio_data,
+        task_id,
+        cancel_rx,
+        options,
+    )
+    .await
+    {
+        Ok(result) => {
+            info!("腾讯云极速版识别成功，共生成{}条字幕", result.len());
+            Ok(result)
+        }
+        Err(e) => {
+            error!("腾讯云极速版API调用失败: {}", e);
+            Err(e)
+        }
+    }
 }
 
-/// 使用本地whisper命令进行识别
-async fn call_local_whisper(
+/// 分段识别超长音频
+///
+/// 在静音处将音频切分为不超过 `max_segment_seconds` 的片段，逐段提交腾讯云识别，
+/// 每段的进度映射到整体进度中的对应区间。单个片段失败记为警告并继续，
+/// 全部失败时返回错误。识别结束后删除临时片段文件。
+async fn call_tencent_api_in_segments(
     audio_path: &str,
-    language: &str,
     task_id: &str,
-    _cancel_rx: &mut mpsc::Receiver<()>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+    secret_id: &str,
+    secret_key: &str,
+    options: TencentOptions,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    use std::path::Path;
-    use std::process::Command;
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.02,
+        None,
+        Some("音频较长，正在按静音位置切分...".to_string()),
+    );
 
-    let audio_file = Path::new(audio_path);
-    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
-    let file_stem = audio_file.file_stem().unwrap().to_string_lossy();
+    let segments = crate::video::split_audio_at_silence(audio_path, options.max_segment_seconds)?;
+    let total = segments.len();
+    info!("音频已切分为{}段", total);
 
-    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+    let base_range = task_progress_range(task_id);
+    let (range_start, range_end) = base_range.unwrap_or((0.0, 1.0));
+    let range_span = range_end - range_start;
 
-    // 构建whisper命令
-    let mut cmd = Command::new("whisper");
-    cmd.arg(audio_path)
-        .arg("--model")
-        .arg("base") // 使用base模型，平衡速度和精度
-        .arg("--output_format")
-        .arg("srt")
-        .arg("--output_dir")
-        .arg(output_dir)
-        .arg("--verbose")
-        .arg("False")
-        .arg("--task")
-        .arg("transcribe"); // 明确指定转写任务
+    let mut chunk_results = Vec::new();
+    let mut failed = 0;
+    let mut cancelled = false;
 
-    // 设置语言（强制使用简体中文）
-    if language == "zh" || language == "zh-CN" || language.is_empty() {
-        cmd.arg("--language").arg("zh");
-        // 添加简体中文输出参数
-        cmd.arg("--initial_prompt").arg("以下是简体中文语音：");
-    } else {
-        let whisper_lang = match language {
-            "en" | "en-US" => "en",
-            "ja" | "ja-JP" => "ja",
-            "ko" | "ko-KR" => "ko",
-            _ => "zh",
+    for (index, (segment_path, offset)) in segments.iter().enumerate() {
+        if cancel_rx.try_recv().is_ok() {
+            cancelled = true;
+            break;
+        }
+
+        // 片段内上报的0~1进度映射到该片段在整体中的区间
+        set_task_progress_range(
+            task_id,
+            Some((
+                range_start + range_span * index as f32 / total as f32,
+                range_start + range_span * (index + 1) as f32 / total as f32,
+            )),
+        );
+        update_task_status(
+            task_id,
+            "processing".to_string(),
+            0.0,
+            None,
+            Some(format!("正在识别片段 {}/{}", index + 1, total)),
+        );
+
+        // 片段各自上传，不复用整段音频的上传地址
+        let segment_options = TencentOptions {
+            uploaded_url: None,
+            ..options.clone()
         };
-        cmd.arg("--language").arg(whisper_lang);
+        let outcome = match std::fs::read(segment_path) {
+            Ok(data) => {
+                call_tencent_rapid_asr(
+                    secret_id,
+                    secret_key,
+                    &data,
+                    task_id,
+                    cancel_rx,
+                    segment_options,
+                )
+                .await
+            }
+            Err(e) => Err(format!("读取音频片段失败: {}", e)),
+        };
+
+        match outcome {
+            Ok(mut subtitles) => {
+                for subtitle in &mut subtitles {
+                    subtitle.start_time += offset;
+                    subtitle.end_time += offset;
+                }
+                info!(
+                    "片段{}/{}识别完成，{}条字幕",
+                    index + 1,
+                    total,
+                    subtitles.len()
+                );
+                chunk_results.push(subtitles);
+            }
+            Err(e) if e == "任务已取消" => {
+                cancelled = true;
+                break;
+            }
+            Err(e) => {
+                failed += 1;
+                error!("片段{}/{}识别失败: {}", index + 1, total, e);
+                add_task_warning(
+                    task_id,
+                    format!(
+                        "片段 {}/{}（{:.0}秒起）识别失败: {}",
+                        index + 1,
+                        total,
+                        offset,
+                        e
+                    ),
+                );
+            }
+        }
     }
 
-    println!("执行Whisper命令: {:?}", cmd);
+    set_task_progress_range(task_id, base_range);
+    for (segment_path, _) in &segments {
+        let _ = std::fs::remove_file(segment_path);
+    }
 
-    // 执行命令
-    update_task_status(task_id, "processing".to_string(), 0.5, None, None);
+    if cancelled {
+        return Err("任务已取消".to_string());
+    }
+    if failed == total {
+        return Err(format!(
+            "全部{}个音频片段识别失败，请检查网络连接和API配置",
+            total
+        ));
+    }
 
-    let output = cmd
-        .output()
-        .map_err(|e| format!("执行whisper命令失败: {}", e))?;
+    let subtitles = stitch_chunks(chunk_results, 0.0);
+    info!(
+        "分段识别完成，共{}段（失败{}段），生成{}条字幕",
+        total,
+        failed,
+        subtitles.len()
+    );
+    Ok(subtitles)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Whisper执行失败: {}", stderr));
+/// Google同步识别接口单次请求的音频时长上限（秒，接口限制为1分钟）
+const GOOGLE_SYNC_MAX_SECONDS: f64 = 55.0;
+
+/// 将语言参数映射为Google的BCP-47语言代码
+fn google_language_code(language: &str) -> &str {
+    match language {
+        "zh" | "zh-cn" | "zh-CN" | "" | "auto" => "zh-CN",
+        "zh-tw" | "zh-TW" => "zh-TW",
+        "en" => "en-US",
+        "ja" => "ja-JP",
+        "ko" => "ko-KR",
+        "fr" => "fr-FR",
+        "de" => "de-DE",
+        "es" => "es-ES",
+        "ru" => "ru-RU",
+        other => other,
     }
+}
 
-    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
+/// 调用Google Speech-to-Text同步识别
+///
+/// 同步接口只接受1分钟以内的音频，较长的音频在静音处切分后逐段识别，
+/// 词级时间加上片段偏移后统一分组为字幕。
+async fn call_google_api(
+    audio_path: &str,
+    language: &str,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+    api_key: &str,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.1,
+        None,
+        Some("正在准备音频...".to_string()),
+    );
 
-    // 读取生成的SRT文件
-    let srt_path = output_dir.join(format!("{}.srt", file_stem));
+    let duration = crate::video::get_video_info(audio_path)?.duration;
+    let segments = if duration > GOOGLE_SYNC_MAX_SECONDS {
+        crate::video::split_audio_at_silence(audio_path, GOOGLE_SYNC_MAX_SECONDS)?
+    } else {
+        vec![(audio_path.to_string(), 0.0)]
+    };
+    let total = segments.len();
+    let language_code = google_language_code(language);
+    info!("Google识别语言: {}，共{}段", language_code, total);
 
-    if !srt_path.exists() {
-        return Err("Whisper未生成SRT文件".to_string());
-    }
+    let client = reqwest::Client::new();
+    let mut words = Vec::new();
+    let mut failed = 0;
+    let mut outcome = Ok(());
+    for (index, (segment_path, offset)) in segments.iter().enumerate() {
+        if cancel_rx.try_recv().is_ok() {
+            outcome = Err("任务已取消".to_string());
+            break;
+        }
+        update_task_status(
+            task_id,
+            "processing".to_string(),
+            0.2 + 0.7 * index as f32 / total as f32,
+            None,
+            Some(format!("正在识别片段 {}/{}", index + 1, total)),
+        );
 
-    let srt_content =
-        std::fs::read_to_string(&srt_path).map_err(|e| format!("读取SRT文件失败: {}", e))?;
+        match google_recognize(&client, api_key, segment_path, language_code).await {
+            Ok(segment_words) => {
+                words.extend(
+                    segment_words
+                        .into_iter()
+                        .map(|(word, start, end)| (word, start + offset, end + offset)),
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                error!("片段{}/{}识别失败: {}", index + 1, total, e);
+                add_task_warning(
+                    task_id,
+                    format!(
+                        "片段 {}/{}（{:.0}秒起）识别失败: {}",
+                        index + 1,
+                        total,
+                        offset,
+                        e
+                    ),
+                );
+            }
+        }
+    }
 
-    // 解析SRT文件
-    let subtitles = parse_srt_content(&srt_content)?;
+    // 只删除切分出的临时片段，不删除原音频
+    if total > 1 {
+        for (segment_path, _) in &segments {
+            let _ = std::fs::remove_file(segment_path);
+        }
+    }
+    outcome?;
+    if failed == total {
+        return Err(format!(
+            "全部{}个音频片段识别失败，请检查网络连接和API配置",
+            total
+        ));
+    }
 
-    // 清理临时文件
-    let _ = std::fs::remove_file(&srt_path);
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.9,
+        None,
+        Some("正在处理识别结果...".to_string()),
+    );
 
-    println!("Whisper识别完成，共解析到{}条字幕", subtitles.len());
+    let subtitles = group_google_words(&words);
+    info!("Google识别完成，共生成{}条字幕", subtitles.len());
     Ok(subtitles)
 }
 
-/// 使用Python whisper进行识别
-async fn call_python_whisper(
+/// 识别单段音频（WAV文件头提供编码和采样率），返回 (词, 开始秒, 结束秒)
+async fn google_recognize(
+    client: &reqwest::Client,
+    api_key: &str,
     audio_path: &str,
-    language: &str,
-    task_id: &str,
-    cancel_rx: &mut mpsc::Receiver<()>,
-) -> Result<Vec<crate::video::Subtitle>, String> {
-    use std::path::Path;
-    use std::process::Command;
+    language_code: &str,
+) -> Result<Vec<(String, f64, f64)>, String> {
+    let audio_data = std::fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
+    let body = json!({
+        "config": {
+            "languageCode": language_code,
+            "enableWordTimeOffsets": true,
+            "enableAutomaticPunctuation": true,
+        },
+        "audio": {
+            "content": base64::engine::general_purpose::STANDARD.encode(&audio_data),
+        },
+    });
 
-    // 检查是否安装了openai-whisper
-    let python_check = Command::new("python3")
-        .args(["-c", "import whisper; print('whisper available')"])
-        .output();
+    // 错误信息去掉URL，避免在日志和界面中暴露API密钥
+    let response = client
+        .post("https://speech.googleapis.com/v1/speech:recognize")
+        .query(&[("key", api_key)])
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("调用Google语音识别失败: {}", e.without_url()))?;
 
-    match python_check {
-        Ok(output) if output.status.success() => {
-            println!("发现Python whisper库");
-        }
-        _ => {
-            return Err("未找到whisper。请安装: pip install openai-whisper".to_string());
-        }
-    }
+    let status = response.status();
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("读取响应失败: {}", e.without_url()))?;
+    debug!("Google语音识别响应状态: {}", status);
 
-    let audio_file = Path::new(audio_path);
-    let output_dir = audio_file.parent().unwrap_or(Path::new("."));
-    let file_stem = audio_file.file_stem().unwrap().to_string_lossy();
+    let response_json: Value =
+        serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))?;
+    if !status.is_success() {
+        let message = response_json["error"]["message"]
+            .as_str()
+            .unwrap_or("未知错误");
+        return Err(format!("Google语音识别失败: HTTP {}, {}", status, message));
+    }
 
-    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
+    Ok(parse_google_words(&response_json))
+}
 
-    // 创建Python脚本（强制简体中文输出）
-    let python_script = format!(
-        r#"
-import whisper
-import sys
+/// 解析Google时间字符串（如 "1.500s"）
+fn parse_google_time(value: &Value) -> Option<f64> {
+    value.as_str()?.trim_end_matches('s').parse().ok()
+}
 
-try:
-    import opencc
-    converter = opencc.OpenCC('t2s')  # 繁体转简体
-except ImportError:
-    print("Warning: opencc not available, skipping traditional to simplified conversion", file=sys.stderr)
-    converter = None
+/// 提取每个结果首选候选的词级时间
+fn parse_google_words(response: &Value) -> Vec<(String, f64, f64)> {
+    response["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|result| result["alternatives"][0]["words"].as_array())
+        .flatten()
+        .map(|word| {
+            let text = word["word"].as_str().unwrap_or("").trim();
+            let start = parse_google_time(&word["startTime"]).unwrap_or(0.0);
+            let end = parse_google_time(&word["endTime"]).unwrap_or(start);
+            (text.to_string(), start, end)
+        })
+        .collect()
+}
 
-try:
-    model = whisper.load_model("base")
-    # 强制使用中文识别，并指定简体中文提示
-    result = model.transcribe("{}", language="zh", initial_prompt="以下是简体中文语音：")
-    
-    # 输出SRT格式
-    for i, segment in enumerate(result['segments']):
-        start = segment['start']
-        end = segment['end']
-        text = segment['text'].strip()
-        
-        # 转换为简体中文
-        if converter and text:
-            try:
-                text = converter.convert(text)
-            except:
-                pass  # 如果转换失败，保持原文
-        
-        start_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(start // 3600),
-            int((start % 3600) // 60),
-            start % 60
-        )
-        end_time = "{{:02d}}:{{:02d}}:{{:06.3f}}".format(
-            int(end // 3600),
-            int((end % 3600) // 60),
-            end % 60
-        )
+/// 将词级结果分组为字幕：句末标点、词数或时长超限时换条
+fn group_google_words(words: &[(String, f64, f64)]) -> Vec<crate::video::Subtitle> {
+    const MAX_WORDS_PER_CUE: usize = 12; // 每条字幕最多词数
+    const MAX_CUE_DURATION: f64 = 6.0; // 每条字幕最长时长（秒）
 
-        print(i+1)
-        print(start_time + " --> " + end_time)
-        print(text)
-        print()
-except Exception as e:
-    print("Error: " + str(e), file=sys.stderr)
-    sys.exit(1)
-"#,
-        audio_path
-    );
+    let mut subtitles: Vec<crate::video::Subtitle> = Vec::new();
+    let mut text = String::new();
+    let mut start = 0.0;
+    let mut end = 0.0;
+    let mut count = 0;
 
-    // 写入临时Python文件
-    let script_path = output_dir.join(format!("{}_whisper.py", file_stem));
-    std::fs::write(&script_path, python_script)
-        .map_err(|e| format!("写入Python脚本失败: {}", e))?;
+    // 有累积内容时输出一条字幕（文本为空也输出，由 `drop_empty_cues` 统一处理）
+    let mut flush = |text: &mut String, start: f64, end: f64, count: &mut usize| {
+        if *count > 0 || !text.is_empty() {
+            subtitles.push(crate::video::Subtitle {
+                id: (subtitles.len() + 1).to_string(),
+                start_time: start,
+                end_time: end,
+                text: text.trim().to_string(),
+                position: None,
+                words: None,
+            });
+        }
+        text.clear();
+        *count = 0;
+    };
 
-    update_task_status(task_id, "processing".to_string(), 0.5, None, None);
+    for (word, word_start, word_end) in words {
+        if count > 0 && (count >= MAX_WORDS_PER_CUE || word_end - start > MAX_CUE_DURATION) {
+            flush(&mut text, start, end, &mut count);
+        }
 
-    // 执行Python脚本
-    println!("执行Python Whisper脚本...");
-    let output = Command::new("python3")
-        .arg(&script_path)
-        .output()
-        .map_err(|e| format!("执行Python脚本失败: {}", e))?;
+        if count == 0 {
+            start = *word_start;
+        } else if text.chars().last().is_some_and(|c| !is_cjk_char(c))
+            && word.chars().next().is_some_and(|c| !is_cjk_char(c))
+        {
+            text.push(' ');
+        }
 
-    // 清理临时文件
-    let _ = std::fs::remove_file(&script_path);
+        text.push_str(word);
+        end = *word_end;
+        count += 1;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python Whisper执行失败: {}", stderr));
+        if word.ends_with(['.', '?', '!', '。', '？', '！']) {
+            flush(&mut text, start, end, &mut count);
+        }
     }
+    flush(&mut text, start, end, &mut count);
 
-    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
+    subtitles
+}
 
-    // 解析输出的SRT内容
-    let srt_content = String::from_utf8_lossy(&output.stdout);
-    let subtitles = parse_srt_content(&srt_content)?;
+/// OpenAI转写接口的上传文件大小上限（25MB）
+const OPENAI_UPLOAD_LIMIT: usize = 25 * 1024 * 1024;
 
-    println!("Python Whisper识别完成，共解析到{}条字幕", subtitles.len());
-    Ok(subtitles)
-}
+/// 调用OpenAI托管的Whisper接口（whisper-1）识别
+async fn call_openai_whisper_api(
+    audio_path: &str,
+    language: &str,
+    task_id: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+    api_key: &str,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.1,
+        None,
+        Some("正在读取音频文件...".to_string()),
+    );
 
-/// 解析SRT格式内容
-fn parse_srt_content(content: &str) -> Result<Vec<crate::video::Subtitle>, String> {
-    let mut subtitles = Vec::new();
-    let blocks: Vec<&str> = content.split("\n\n").collect();
+    let audio_data = std::fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
+    if audio_data.len() > OPENAI_UPLOAD_LIMIT {
+        return Err(format!(
+            "音频文件较大 ({:.1} MB)，超过了OpenAI接口的上传限制。\n\n\
+            📋 **OpenAI Whisper API限制说明**：\n\
+            • 单个文件上传：≤ 25MB（16kHz单声道WAV约13分钟）\n\n\
+            💡 **推荐解决方案**：\n\n\
+            1. 🏠 **使用Whisper本地识别**（推荐）\n\
+               • ✅ 支持任意大小音频文件\n\
+               • ✅ 完全本地处理，隐私安全\n\n\
+            2. 🎬 **视频分段处理**\n\
+               • 将长视频分成10分钟以内的片段\n\
+               • 分别识别后合并结果\n\n\
+            当前文件: {:.1} MB，请使用Whisper本地识别或分段处理。",
+            audio_data.len() as f64 / (1024.0 * 1024.0),
+            audio_data.len() as f64 / (1024.0 * 1024.0)
+        ));
+    }
 
-    for block in blocks {
-        let lines: Vec<&str> = block.trim().split('\n').collect();
-        if lines.len() < 3 {
-            continue;
+    // 检查取消信号
+    if cancel_rx.try_recv().is_ok() {
+        return Err(TASK_CANCELLED.to_string());
+    }
+
+    let file_name = Path::new(audio_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("audio.wav")
+        .to_string();
+    let file_part = reqwest::multipart::Part::bytes(audio_data)
+        .file_name(file_name)
+        .mime_str("audio/wav")
+        .map_err(|e| format!("创建上传数据失败: {}", e))?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file_part)
+        .text("model", "whisper-1")
+        .text("response_format", "verbose_json")
+        .text("timestamp_granularities[]", "segment");
+    if let Some(code) = whisper_language_code(language) {
+        if code == "zh" {
+            form = form.text("prompt", "以下是简体中文语音：");
         }
+        form = form.text("language", code);
+    }
 
-        // 解析序号
-        let id = lines[0].trim();
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.3,
+        None,
+        Some("正在上传音频并识别...".to_string()),
+    );
 
-        // 解析时间
-        let time_line = lines[1];
-        if let Some((start_str, end_str)) = time_line.split_once(" --> ") {
-            let start_time = parse_srt_time(start_str.trim())?;
-            let end_time = parse_srt_time(end_str.trim())?;
+    let request = reqwest::Client::new()
+        .post("https://api.openai.com/v1/audio/transcriptions")
+        .bearer_auth(api_key)
+        .multipart(form)
+        .send();
+    let response = tokio::select! {
+        response = request => response.map_err(|e| format!("调用OpenAI接口失败: {}", e))?,
+        Some(()) = cancel_rx.recv() => return Err(TASK_CANCELLED.to_string()),
+    };
 
-            // 解析文本（可能有多行）
-            let text = lines[2..].join("\n").trim().to_string();
+    let status = response.status();
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("读取响应失败: {}", e))?;
+    debug!("OpenAI转写接口响应状态: {}", status);
 
-            if !text.is_empty() {
-                subtitles.push(crate::video::Subtitle {
-                    id: id.to_string(),
-                    start_time,
-                    end_time,
-                    text,
-                });
-            }
-        }
+    let response_json: Value =
+        serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))?;
+    if !status.is_success() {
+        let message = response_json["error"]["message"]
+            .as_str()
+            .unwrap_or("未知错误");
+        return Err(format!("OpenAI识别失败: HTTP {}, {}", status, message));
     }
 
-    if subtitles.is_empty() {
-        return Err("未解析到任何字幕内容".to_string());
-    }
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.9,
+        None,
+        Some("正在处理识别结果...".to_string()),
+    );
 
+    if let Some(detected) = response_json["language"].as_str() {
+        record_detected_language(
+            task_id,
+            language,
+            &format!("Detected language: {}", detected),
+        );
+    }
+    let subtitles = parse_openai_segments(&response_json);
+    info!("OpenAI识别完成，共生成{}条字幕", subtitles.len());
     Ok(subtitles)
 }
 
-/// 解析SRT时间格式 (HH:MM:SS,mmm)
-fn parse_srt_time(time_str: &str) -> Result<f64, String> {
-    let time_str = time_str.replace(',', "."); // SRT使用逗号作为毫秒分隔符
-    let parts: Vec<&str> = time_str.split(':').collect();
-
-    if parts.len() != 3 {
-        return Err(format!("无效的时间格式: {}", time_str));
-    }
-
-    let hours: f64 = parts[0]
-        .parse()
-        .map_err(|_| format!("无效的小时: {}", parts[0]))?;
-    let minutes: f64 = parts[1]
-        .parse()
-        .map_err(|_| format!("无效的分钟: {}", parts[1]))?;
-    let seconds: f64 = parts[2]
-        .parse()
-        .map_err(|_| format!("无效的秒数: {}", parts[2]))?;
+/// 将verbose_json结果中的segments转换为字幕
+fn parse_openai_segments(response: &Value) -> Vec<crate::video::Subtitle> {
+    response["segments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|segment| {
+            (
+                segment["start"].as_f64().unwrap_or(0.0),
+                segment["end"].as_f64().unwrap_or(0.0),
+                segment["text"].as_str().unwrap_or("").trim().to_string(),
+            )
+        })
+        .enumerate()
+        .map(
+            |(index, (start_time, end_time, text))| crate::video::Subtitle {
+                id: (index + 1).to_string(),
+                start_time,
+                end_time,
+                text,
+                position: None,
+                words: None,
+            },
+        )
+        .collect()
+}
 
-    Ok(hours * 3600.0 + minutes * 60.0 + seconds)
+/// 从API密钥中读取AWS配置
+fn aws_config_from_api_keys(api_keys: Option<&Value>) -> Result<crate::aws::AwsConfig, FlowError> {
+    let keys = api_keys.ok_or_else(|| FlowError::ApiKeyMissing {
+        provider: "AWS".to_string(),
+        field: None,
+    })?;
+    let required = |names: &[&str]| required_key_field(keys, "AWS", names).map(str::to_string);
+
+    Ok(crate::aws::AwsConfig {
+        access_key_id: required(&["access_key_id", "accessKeyId"])?,
+        secret_access_key: required(&["secret_access_key", "secretAccessKey"])?,
+        region: required(&["region"])?,
+        bucket: optional_key_field(keys, "AWS", &["bucket"])?.map(str::to_string),
+    })
 }
 
-/// 调用百度智能云语音识别API
-async fn call_baidu_api(
+/// 调用AWS Transcribe语音识别
+async fn call_aws_transcribe_api(
     audio_path: &str,
     language: &str,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
-    api_key: &str,
-    secret_key: &str,
+    config: crate::aws::AwsConfig,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    use std::fs;
-
-    // 读取音频文件
-    let audio_data = fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
-
-    // 将音频数据转换为base64
-    let audio_base64 = base64::engine::general_purpose::STANDARD.encode(&audio_data);
-
-    // 获取访问令牌
-    update_task_status(task_id, "processing".to_string(), 0.1, None, None);
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.1,
+        None,
+        Some("正在读取音频文件...".to_string()),
+    );
 
-    let access_token = get_baidu_access_token(api_key, secret_key)
-        .await
-        .map_err(|e| format!("获取百度访问令牌失败: {}", e))?;
+    let audio_data = std::fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
 
     // 检查取消信号
     if cancel_rx.try_recv().is_ok() {
         return Err("任务已取消".to_string());
     }
 
-    update_task_status(task_id, "processing".to_string(), 0.3, None, None);
-
-    // 构建请求参数
-    let mut params = HashMap::new();
-    params.insert("format", "wav".to_string());
-    params.insert("rate", "16000".to_string());
-    params.insert("channel", "1".to_string());
-    params.insert("cuid", "flow-text-app".to_string());
-    params.insert("token", access_token);
-    params.insert("speech", audio_base64);
-    params.insert("len", audio_data.len().to_string());
-
-    // 设置语言
-    let dev_pid = match language {
-        "zh-CN" => "1537", // 普通话(支持简单的英文识别)
-        "en-US" => "1737", // 英语
-        _ => "1537",       // 默认普通话
-    };
-    params.insert("dev_pid", dev_pid.to_string());
+    let file_name = std::path::Path::new(audio_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("audio.wav");
+    let media_format = std::path::Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_else(|| "wav".to_string());
+    let job_name = format!("flowtext-{}", uuid::Uuid::new_v4());
+    let object_key = format!("flowtext/{}/{}", job_name, file_name);
 
-    // 发送请求
-    update_task_status(task_id, "processing".to_string(), 0.5, None, None);
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.2,
+        None,
+        Some("正在上传音频文件到S3...".to_string()),
+    );
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://vop.baidu.com/server_api")
-        .header("Content-Type", "application/json")
-        .json(&params)
-        .send()
-        .await
-        .map_err(|e| format!("发送请求失败: {}", e))?;
+    let client = crate::aws::AwsClient::new(config);
+    let media_uri = client
+        .upload_to_s3(&audio_data, &object_key, "application/octet-stream")
+        .await?;
+    info!("文件上传到S3成功: {}", media_uri);
 
     // 检查取消信号
     if cancel_rx.try_recv().is_ok() {
         return Err("任务已取消".to_string());
     }
 
-    update_task_status(task_id, "processing".to_string(), 0.8, None, None);
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.4,
+        None,
+        Some("正在创建转写任务...".to_string()),
+    );
 
-    // 解析响应
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| format!("读取响应失败: {}", e))?;
+    let language_code = match language {
+        "zh" | "zh-cn" | "zh-CN" | "" => "zh-CN",
+        "zh-tw" | "zh-TW" => "zh-TW",
+        "en" => "en-US",
+        "ja" => "ja-JP",
+        "ko" => "ko-KR",
+        "fr" => "fr-FR",
+        "de" => "de-DE",
+        "es" => "es-ES",
+        other => other,
+    };
 
-    let response_json: Value =
-        serde_json::from_str(&response_text).map_err(|e| format!("解析响应JSON失败: {}", e))?;
+    client
+        .start_transcription_job(&job_name, &media_uri, language_code, &media_format)
+        .await?;
+    info!("AWS Transcribe任务已创建: {}", job_name);
 
-    // 检查错误
-    if let Some(err_no) = response_json["err_no"].as_i64() {
-        if err_no != 0 {
-            let err_msg = response_json["err_msg"].as_str().unwrap_or("未知错误");
-            return Err(format!("百度API错误 {}: {}", err_no, err_msg));
+    // 轮询任务状态
+    let mut attempts = 0;
+    const MAX_ATTEMPTS: u32 = 120; // 最多等待10分钟（每5秒查询一次）
+
+    let transcript_uri = loop {
+        // 检查取消信号
+        if cancel_rx.try_recv().is_ok() {
+            return Err("任务已取消".to_string());
         }
-    }
 
-    // 提取识别结果
-    let result_text = response_json["result"]
-        .as_array()
-        .and_then(|arr| arr.first())
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
+        attempts += 1;
+        if attempts > MAX_ATTEMPTS {
+            return Err("AWS Transcribe识别超时，请稍后重试".to_string());
+        }
 
-    update_task_status(task_id, "processing".to_string(), 0.9, None, None);
+        let response = client.get_transcription_job(&job_name).await?;
+        let job = &response["TranscriptionJob"];
+        let status = job["TranscriptionJobStatus"].as_str().unwrap_or("UNKNOWN");
 
-    // 将结果转换为字幕格式
-    let subtitles = if result_text.is_empty() {
-        vec![]
-    } else {
-        // 简单处理：将整个识别结果作为一个字幕段
-        // 实际应用中可能需要更复杂的分段逻辑
-        vec![crate::video::Subtitle {
-            id: "1".to_string(),
-            start_time: 0.0,
-            end_time: 10.0, // 默认时长，实际应该根据音频长度计算
-            text: result_text.to_string(),
-        }]
+        info!("AWS Transcribe任务状态: {}", status);
+
+        match status {
+            "COMPLETED" => {
+                break job["Transcript"]["TranscriptFileUri"]
+                    .as_str()
+                    .ok_or("无法获取转写结果地址")?
+                    .to_string();
+            }
+            "FAILED" => {
+                let reason = job["FailureReason"].as_str().unwrap_or("未知原因");
+                return Err(format!("AWS Transcribe识别失败: {}", reason));
+            }
+            _ => {
+                let progress = 0.4 + (attempts as f32 / MAX_ATTEMPTS as f32) * 0.4;
+                update_task_status(
+                    task_id,
+                    "processing".to_string(),
+                    progress,
+                    None,
+                    Some(format!("识别进行中... ({}/{})", attempts, MAX_ATTEMPTS)),
+                );
+                sleep(Duration::from_secs(5)).await;
+            }
+        }
     };
 
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.9,
+        None,
+        Some("正在处理识别结果...".to_string()),
+    );
+
+    let transcript = client.fetch_transcript(&transcript_uri).await?;
+    let subtitles = parse_aws_transcribe_result(&transcript)?;
+
+    info!("AWS Transcribe识别完成，共生成{}条字幕", subtitles.len());
     Ok(subtitles)
 }
 
-/// 获取百度访问令牌
-async fn get_baidu_access_token(api_key: &str, secret_key: &str) -> Result<String, String> {
-    if api_key.is_empty() || secret_key.is_empty() {
-        return Err("请在设置中配置百度API密钥".to_string());
-    }
+/// 解析AWS Transcribe结果，将词级结果分组为字幕
+fn parse_aws_transcribe_result(result: &str) -> Result<Vec<crate::video::Subtitle>, String> {
+    const MAX_WORDS_PER_CUE: usize = 12; // 每条字幕最多词数
+    const MAX_CUE_DURATION: f64 = 6.0; // 每条字幕最长时长（秒）
 
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://aip.baidubce.com/oauth/2.0/token?grant_type=client_credentials&client_id={}&client_secret={}",
-        api_key, secret_key
-    );
+    let result_data: Value =
+        serde_json::from_str(result).map_err(|e| format!("解析转写结果JSON失败: {}", e))?;
 
-    let response = client
-        .post(&url)
-        .send()
-        .await
-        .map_err(|e| format!("获取访问令牌请求失败: {}", e))?;
+    let items = result_data["results"]["items"]
+        .as_array()
+        .ok_or("转写结果中没有items字段")?;
 
-    let response_json: Value = response
-        .json()
-        .await
-        .map_err(|e| format!("解析访问令牌响应失败: {}", e))?;
+    let mut subtitles: Vec<crate::video::Subtitle> = Vec::new();
+    let mut text = String::new();
+    let mut start = 0.0;
+    let mut end = 0.0;
+    let mut words = 0;
 
-    if let Some(error) = response_json["error"].as_str() {
-        return Err(format!("获取访问令牌失败: {}", error));
+    // 有累积内容时输出一条字幕（文本为空也输出，由 `drop_empty_cues` 统一处理）
+    let mut flush = |text: &mut String, start: f64, end: f64, words: &mut usize| {
+        if *words > 0 || !text.is_empty() {
+            subtitles.push(crate::video::Subtitle {
+                id: (subtitles.len() + 1).to_string(),
+                start_time: start,
+                end_time: end,
+                text: text.trim().to_string(),
+                position: None,
+                words: None,
+            });
+        }
+        text.clear();
+        *words = 0;
+    };
+
+    for item in items {
+        let content = item["alternatives"][0]["content"].as_str().unwrap_or("");
+        if content.is_empty() {
+            continue;
+        }
+
+        if item["type"].as_str() == Some("punctuation") {
+            // 标点附加到前一个词后
+            text.push_str(content);
+            if matches!(content, "." | "?" | "!" | "。" | "？" | "！") {
+                flush(&mut text, start, end, &mut words);
+            }
+            continue;
+        }
+
+        let word_start = item["start_time"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(end);
+        let word_end = item["end_time"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(word_start);
+
+        if words > 0 && (words >= MAX_WORDS_PER_CUE || word_end - start > MAX_CUE_DURATION) {
+            flush(&mut text, start, end, &mut words);
+        }
+
+        if words == 0 {
+            start = word_start;
+        } else if text.chars().last().is_some_and(|c| !is_cjk_char(c))
+            && content.chars().next().is_some_and(|c| !is_cjk_char(c))
+        {
+            text.push(' ');
+        }
+
+        text.push_str(content);
+        end = word_end;
+        words += 1;
     }
+    flush(&mut text, start, end, &mut words);
 
-    let access_token = response_json["access_token"]
-        .as_str()
-        .ok_or("响应中未找到访问令牌")?;
+    if subtitles.is_empty() {
+        return Err("识别结果为空".to_string());
+    }
 
-    Ok(access_token.to_string())
+    Ok(subtitles)
 }
 
-/// 调用腾讯云语音识别API
-async fn call_tencent_api(
+/// 调用阿里云录音文件识别
+///
+/// 录音文件识别只接受公网URL：音频先上传到COS（重试任务复用已上传的地址），再提交识别任务并轮询结果。
+/// 识别语言由Appkey对应项目的模型决定，`language` 仅用于日志。
+async fn call_aliyun_api(
     audio_path: &str,
-    _language: &str,
+    language: &str,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
-    secret_id: &str,
-    secret_key: &str,
+    config: crate::aliyun::AliyunConfig,
     cos_config: Option<crate::cos::CosConfig>,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    // 检查API密钥
-    if secret_id.is_empty() || secret_key.is_empty() {
-        return Err("腾讯云API密钥未配置".to_string());
-    }
-
-    println!("腾讯云API调用开始");
-    println!(
-        "Secret ID: {}",
-        if secret_id.is_empty() {
-            "[空]"
-        } else {
-            "[已配置]"
-        }
-    );
-    println!(
-        "Secret Key: {}",
-        if secret_key.is_empty() {
-            "[空]"
-        } else {
-            "[已配置]"
-        }
-    );
-
-    // 更新进度：开始处理
     update_task_status(
         task_id,
         "processing".to_string(),
@@ -1773,146 +6064,197 @@ async fn call_tencent_api(
         Some("正在读取音频文件...".to_string()),
     );
 
-    // 检查取消信号
-    if cancel_rx.try_recv().is_ok() {
-        return Err("任务已取消".to_string());
+    // 重试任务：之前上传的文件仍可访问时直接复用
+    let mut file_url = None;
+    if let Some(uploaded_url) = task_uploaded_url(task_id) {
+        let reachable = reqwest::Client::new()
+            .head(&uploaded_url)
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success());
+        if reachable {
+            info!(
+                "复用已上传的音频文件: {}",
+                crate::logging::redact_url(&uploaded_url)
+            );
+            file_url = Some(uploaded_url);
+        } else {
+            warn!("已上传的音频文件不可访问，重新上传");
+        }
     }
 
-    // 读取音频文件并转换为base64
-    let audio_data = match std::fs::read(audio_path) {
-        Ok(data) => data,
-        Err(e) => {
-            return Err(format!("读取音频文件失败: {}", e));
+    let file_url = match file_url {
+        Some(file_url) => file_url,
+        None => {
+            let cos_cfg = cos_config.ok_or(
+                "阿里云录音文件识别需要公网可访问的音频地址，请在阿里云密钥中配置COS存储（cos.secretId、cos.secretKey、cos.bucket、cos.region）",
+            )?;
+            crate::cos::validate_region(&cos_cfg.region)?;
+            let audio_data =
+                std::fs::read(audio_path).map_err(|e| format!("读取音频文件失败: {}", e))?;
+
+            // 检查取消信号
+            if cancel_rx.try_recv().is_ok() {
+                return Err("任务已取消".to_string());
+            }
+
+            update_task_status(
+                task_id,
+                "processing".to_string(),
+                0.2,
+                None,
+                Some("正在上传音频文件到腾讯云COS...".to_string()),
+            );
+
+            // 上传进度映射到0.2-0.4区间
+            let progress_task_id = task_id.to_string();
+            let mut last_percent = 0;
+            let file_name = format!("audio_{}.wav", chrono::Utc::now().timestamp());
+            let file_url = crate::cos::CosClient::new(cos_cfg)
+                .upload_file_with_progress(
+                    &audio_data,
+                    &file_name,
+                    Some("audio/wav"),
+                    move |sent, total| {
+                        let ratio = sent as f32 / total.max(1) as f32;
+                        let percent = (ratio * 100.0) as u32;
+                        if percent > last_percent {
+                            last_percent = percent;
+                            update_task_status(
+                                &progress_task_id,
+                                "processing".to_string(),
+                                0.2 + ratio * 0.2,
+                                None,
+                                Some(format!("正在上传音频文件到腾讯云COS... {}%", percent)),
+                            );
+                        }
+                    },
+                )
+                .await
+                .map_err(|e| format!("上传文件到COS失败: {}", e))?;
+            info!(
+                "文件上传到COS成功: {}",
+                crate::logging::redact_url(&file_url)
+            );
+            record_uploaded_url(task_id, &file_url);
+            file_url
         }
     };
 
-    // 更新进度：文件读取完成
-    update_task_status(
-        task_id,
-        "processing".to_string(),
-        0.3,
-        None,
-        Some("正在调用腾讯云API...".to_string()),
-    );
-
     // 检查取消信号
     if cancel_rx.try_recv().is_ok() {
         return Err("任务已取消".to_string());
     }
 
-    // 调用腾讯云录音文件识别极速版API
-    match call_tencent_rapid_asr(
-        secret_id,
-        secret_key,
-        &audio_data,
+    update_task_status(
         task_id,
-        cancel_rx,
-        cos_config,
-    )
-    .await
-    {
-        Ok(result) => {
-            println!("腾讯云极速版识别成功，共生成{}条字幕", result.len());
-            Ok(result)
-        }
-        Err(e) => {
-            eprintln!("腾讯云极速版API调用失败: {}", e);
+        "processing".to_string(),
+        0.4,
+        None,
+        Some("正在创建阿里云识别任务...".to_string()),
+    );
 
-            // 如果API调用失败，提供测试数据作为后备
-            println!("API调用失败，返回测试数据");
-            let mut subtitles = generate_test_data_result(audio_path, "腾讯云极速版");
+    let client = crate::aliyun::AliyunClient::new(config);
+    let aliyun_task_id = client.submit_task(&file_url).await?;
+    info!(
+        "阿里云识别任务已创建: {}（语言: {}，由Appkey项目决定）",
+        aliyun_task_id, language
+    );
 
-            // 在测试数据中添加错误信息
-            if !subtitles.is_empty() {
-                subtitles[0].text = format!(
-                    "[极速版API调用失败，显示测试数据]\n错误: {}\n原始文本: {}",
-                    e, subtitles[0].text
-                );
-            }
+    // 轮询任务状态
+    let mut attempts = 0;
+    const MAX_ATTEMPTS: u32 = 180; // 最多等待30分钟（每10秒查询一次）
 
-            Ok(subtitles)
+    let result = loop {
+        // 检查取消信号
+        if cancel_rx.try_recv().is_ok() {
+            return Err("任务已取消".to_string());
         }
-    }
-}
 
-/// 生成Whisper安装指导
-fn generate_whisper_installation_guide(audio_path: &str) -> Vec<crate::video::Subtitle> {
-    use std::path::Path;
+        attempts += 1;
+        if attempts > MAX_ATTEMPTS {
+            return Err("阿里云识别超时，请稍后重试".to_string());
+        }
 
-    let file_name = Path::new(audio_path)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
+        let response = client.get_task_result(&aliyun_task_id).await?;
+        let status = response["StatusText"].as_str().unwrap_or("UNKNOWN");
 
-    vec![
-        crate::video::Subtitle {
-            id: "1".to_string(),
-            start_time: 0.0,
-            end_time: 6.0,
-            text: format!("正在处理文件: {} - Whisper未安装", file_name),
-        },
-        crate::video::Subtitle {
-            id: "2".to_string(),
-            start_time: 6.0,
-            end_time: 12.0,
-            text: "要使用真实Whisper识别，请安装: pip install openai-whisper".to_string(),
-        },
-        crate::video::Subtitle {
-            id: "3".to_string(),
-            start_time: 12.0,
-            end_time: 18.0,
-            text: "或者使用Homebrew安装: brew install whisper".to_string(),
-        },
-        crate::video::Subtitle {
-            id: "4".to_string(),
-            start_time: 18.0,
-            end_time: 24.0,
-            text: "安装后将能够进行真实的语音识别而不是模拟数据".to_string(),
-        },
-        crate::video::Subtitle {
-            id: "5".to_string(),
-            start_time: 24.0,
-            end_time: 30.0,
-            text: "当前显示的是安装指导信息，不是真实识别结果".to_string(),
-        },
-    ]
-}
+        info!("阿里云识别任务状态: {}", status);
+
+        match status {
+            "SUCCESS" => break response["Result"].clone(),
+            // 音频中没有有效语音
+            "SUCCESS_WITH_NO_VALID_FRAGMENT" => break Value::Null,
+            "RUNNING" | "QUEUEING" => {
+                let progress = 0.4 + (attempts as f32 / MAX_ATTEMPTS as f32) * 0.5;
+                update_task_status(
+                    task_id,
+                    "processing".to_string(),
+                    progress,
+                    None,
+                    Some(format!("识别进行中... ({}/{})", attempts, MAX_ATTEMPTS)),
+                );
+                sleep(Duration::from_secs(10)).await;
+            }
+            other => return Err(format!("阿里云识别失败: {}", other)),
+        }
+    };
 
-/// 生成测试数据结果（明确标示是测试数据）
-fn generate_test_data_result(audio_path: &str, engine_name: &str) -> Vec<crate::video::Subtitle> {
-    use std::path::Path;
+    update_task_status(
+        task_id,
+        "processing".to_string(),
+        0.9,
+        None,
+        Some("正在处理识别结果...".to_string()),
+    );
 
-    let file_name = Path::new(audio_path)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
+    let subtitles = parse_aliyun_result(&result);
+    info!("阿里云识别完成，共生成{}条字幕", subtitles.len());
+    Ok(subtitles)
+}
 
-    vec![
-        crate::video::Subtitle {
-            id: "1".to_string(),
-            start_time: 0.0,
-            end_time: 5.0,
-            text: format!("[测试数据] 使用{}引擎识别文件: {}", engine_name, file_name),
-        },
-        crate::video::Subtitle {
-            id: "2".to_string(),
-            start_time: 5.5,
-            end_time: 10.0,
-            text: format!("[测试数据] {}引擎当前处于测试模式", engine_name),
-        },
-        crate::video::Subtitle {
-            id: "3".to_string(),
-            start_time: 10.5,
-            end_time: 15.0,
-            text: "[测试数据] 请配置真实API密钥以获取真实识别结果".to_string(),
-        },
-        crate::video::Subtitle {
-            id: "4".to_string(),
-            start_time: 15.5,
-            end_time: 20.0,
-            text: "[测试数据] 这些是示例字幕，不是真实识别结果".to_string(),
-        },
-    ]
+/// 解析阿里云录音文件识别结果（Sentences中的BeginTime/EndTime单位为毫秒）
+fn parse_aliyun_result(result: &Value) -> Vec<crate::video::Subtitle> {
+    let mut sentences: Vec<&Value> = result["Sentences"]
+        .as_array()
+        .map(|sentences| sentences.iter().collect())
+        .unwrap_or_default();
+    sentences.sort_by_key(|sentence| sentence["BeginTime"].as_i64().unwrap_or(0));
+
+    sentences
+        .into_iter()
+        .map(|sentence| {
+            (
+                sentence["BeginTime"].as_f64().unwrap_or(0.0) / 1000.0,
+                sentence["EndTime"].as_f64().unwrap_or(0.0) / 1000.0,
+                sentence["Text"].as_str().unwrap_or("").trim().to_string(),
+            )
+        })
+        .enumerate()
+        .map(
+            |(index, (start_time, end_time, text))| crate::video::Subtitle {
+                id: (index + 1).to_string(),
+                start_time,
+                end_time,
+                text,
+                position: None,
+                words: None,
+            },
+        )
+        .collect()
+}
+
+/// 判断是否为CJK字符（CJK文本词间不加空格）
+fn is_cjk_char(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // 日文假名
+        | 0x3400..=0x4DBF // CJK扩展A
+        | 0x4E00..=0x9FFF // CJK统一汉字
+        | 0xAC00..=0xD7AF // 韩文音节
+        | 0xF900..=0xFAFF // CJK兼容汉字
+        | 0xFF00..=0xFFEF // 全角字符
+        | 0x3000..=0x303F // CJK标点
+    )
 }
 
 /// 模拟识别结果（用于演示）
@@ -1926,6 +6268,8 @@ fn simulate_recognition_result(_audio_path: &str) -> Vec<crate::video::Subtitle>
         start_time: 0.0,
         end_time: 5.0,
         text: "欢迎使用FlowText视频字幕生成工具".to_string(),
+        position: None,
+        words: None,
     });
 
     subtitles.push(crate::video::Subtitle {
@@ -1933,6 +6277,8 @@ fn simulate_recognition_result(_audio_path: &str) -> Vec<crate::video::Subtitle>
         start_time: 5.5,
         end_time: 10.0,
         text: "这是一个基于Tauri和Rust开发的应用".to_string(),
+        position: None,
+        words: None,
     });
 
     subtitles.push(crate::video::Subtitle {
@@ -1940,6 +6286,8 @@ fn simulate_recognition_result(_audio_path: &str) -> Vec<crate::video::Subtitle>
         start_time: 10.5,
         end_time: 15.0,
         text: "它可以帮助您快速生成视频字幕".to_string(),
+        position: None,
+        words: None,
     });
 
     subtitles.push(crate::video::Subtitle {
@@ -1947,6 +6295,8 @@ fn simulate_recognition_result(_audio_path: &str) -> Vec<crate::video::Subtitle>
         start_time: 15.5,
         end_time: 20.0,
         text: "支持多种语言和字幕格式".to_string(),
+        position: None,
+        words: None,
     });
 
     subtitles
@@ -1974,7 +6324,7 @@ async fn call_tencent_rapid_asr(
     audio_data: &[u8],
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
-    cos_config: Option<crate::cos::CosConfig>,
+    options: TencentOptions,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
     // 更新进度：开始调用录音文件识别API
     update_task_status(
@@ -1991,20 +6341,51 @@ async fn call_tencent_rapid_asr(
     }
 
     // 检查音频文件大小和处理方式选择
-    const LOCAL_UPLOAD_LIMIT: usize = 5 * 1024 * 1024; // 5MB，腾讯云本地文件限制
-    const BASE64_REQUEST_LIMIT: usize = 7 * 1024 * 1024; // 7MB，考虑base64编码后请求体限制
-
-    println!(
-        "音频文件大小: {:.1} MB",
-        audio_data.len() as f64 / (1024.0 * 1024.0)
+    debug!(
+        "音频文件大小: {:.1} MB，预计请求体: {:.1} MB",
+        audio_data.len() as f64 / (1024.0 * 1024.0),
+        estimated_request_size(audio_data.len()) / (1024.0 * 1024.0)
     );
 
-    if audio_data.len() > LOCAL_UPLOAD_LIMIT {
-        println!("音频文件超过5MB，需要使用URL方式上传");
+    if !tencent_request_fits(audio_data.len()) {
+        info!("音频超过直接上传限制，需要使用URL方式上传");
+
+        // 重试任务：之前上传的文件仍可访问时直接复用
+        if let Some(file_url) = &options.uploaded_url {
+            let reachable = reqwest::Client::new()
+                .head(file_url)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success());
+
+            if reachable {
+                info!(
+                    "复用已上传的音频文件: {}",
+                    crate::logging::redact_url(file_url)
+                );
+                update_task_status(
+                    task_id,
+                    "processing".to_string(),
+                    0.6,
+                    None,
+                    Some("复用已上传的音频文件，正在调用识别API...".to_string()),
+                );
+                let result = call_tencent_rapid_api_with_url(
+                    secret_id, secret_key, file_url, task_id, cancel_rx, &options,
+                )
+                .await;
+                if let Some(cos_cfg) = &options.cos_config {
+                    crate::cos::delete_recognition_upload(cos_cfg, file_url).await;
+                }
+                return result;
+            }
+            warn!("已上传的音频文件不可访问，重新上传");
+        }
 
         // 检查是否配置了COS
-        if let Some(cos_cfg) = cos_config {
-            println!("检测到COS配置，尝试上传到腾讯云对象存储");
+        if let Some(cos_cfg) = options.cos_config.clone() {
+            info!("检测到COS配置，尝试上传到腾讯云对象存储");
+            crate::cos::validate_region(&cos_cfg.region)?;
 
             // 更新进度：开始上传到COS
             update_task_status(
@@ -2020,16 +6401,40 @@ async fn call_tencent_rapid_asr(
                 return Err("任务已取消".to_string());
             }
 
-            // 创建COS客户端并上传文件
+            // 创建COS客户端并上传文件，上传进度映射到0.4-0.6区间
             let cos_client = crate::cos::CosClient::new(cos_cfg);
             let file_name = format!("audio_{}.wav", chrono::Utc::now().timestamp());
+            let progress_task_id = task_id.to_string();
+            let mut last_percent = 0;
 
             match cos_client
-                .upload_file(audio_data, &file_name, Some("audio/wav"))
+                .upload_file_with_progress(
+                    audio_data,
+                    &file_name,
+                    Some("audio/wav"),
+                    move |sent, total| {
+                        let ratio = sent as f32 / total.max(1) as f32;
+                        let percent = (ratio * 100.0) as u32;
+                        if percent > last_percent {
+                            last_percent = percent;
+                            update_task_status(
+                                &progress_task_id,
+                                "processing".to_string(),
+                                0.4 + ratio * 0.2,
+                                None,
+                                Some(format!("正在上传音频文件到腾讯云COS... {}%", percent)),
+                            );
+                        }
+                    },
+                )
                 .await
             {
                 Ok(file_url) => {
-                    println!("文件上传到COS成功: {}", file_url);
+                    info!(
+                        "文件上传到COS成功: {}",
+                        crate::logging::redact_url(&file_url)
+                    );
+                    record_uploaded_url(task_id, &file_url);
 
                     // 更新进度：COS上传完成，开始识别
                     update_task_status(
@@ -2040,11 +6445,15 @@ async fn call_tencent_rapid_asr(
                         Some("COS上传完成，正在调用识别API...".to_string()),
                     );
 
-                    // 使用URL方式调用识别API
-                    return call_tencent_rapid_api_with_url(
-                        secret_id, secret_key, &file_url, task_id, cancel_rx,
+                    // 使用URL方式调用识别API，结束后（无论成功与否）删除上传的音频
+                    let result = call_tencent_rapid_api_with_url(
+                        secret_id, secret_key, &file_url, task_id, cancel_rx, &options,
                     )
                     .await;
+                    if let Some(cos_cfg) = &options.cos_config {
+                        crate::cos::delete_recognition_upload(cos_cfg, &file_url).await;
+                    }
+                    return result;
                 }
                 Err(e) => {
                     return Err(format!("上传文件到COS失败: {}", e));
@@ -2080,16 +6489,16 @@ async fn call_tencent_rapid_asr(
         }
     }
 
-    println!(
+    info!(
         "腾讯云录音文件识别API调用开始，音频大小: {} bytes",
         audio_data.len()
     );
 
     // 直接尝试上传（如果文件过大，API会返回相应错误）
-    println!("使用腾讯云录音文件识别API（CreateRecTask）");
+    info!("使用腾讯云录音文件识别API（CreateRecTask）");
 
     // 调用录音文件识别API
-    let response = call_tencent_rapid_api(secret_id, secret_key, audio_data).await?;
+    let response = call_tencent_rapid_api(secret_id, secret_key, audio_data, &options).await?;
 
     // 解析任务创建响应，获取TaskId
     let task_response: Value =
@@ -2165,7 +6574,7 @@ async fn call_tencent_rapid_asr(
 
     let recognition_task_id = task_id_value.as_u64().ok_or("TaskId格式错误")?;
 
-    println!("录音文件识别任务已创建，TaskId: {}", recognition_task_id);
+    info!("录音文件识别任务已创建，TaskId: {}", recognition_task_id);
 
     // 更新进度：开始轮询结果
     update_task_status(
@@ -2183,10 +6592,11 @@ async fn call_tencent_rapid_asr(
         recognition_task_id,
         task_id,
         cancel_rx,
+        options.res_text_format,
     )
     .await?;
 
-    println!("腾讯云录音文件识别完成，共生成{}条字幕", subtitles.len());
+    info!("腾讯云录音文件识别完成，共生成{}条字幕", subtitles.len());
     Ok(subtitles)
 }
 
@@ -2195,6 +6605,7 @@ async fn call_tencent_rapid_api(
     secret_id: &str,
     secret_key: &str,
     audio_data: &[u8],
+    options: &TencentOptions,
 ) -> Result<String, String> {
     let host = "asr.tencentcloudapi.com";
     let service = "asr";
@@ -2207,27 +6618,42 @@ async fn call_tencent_rapid_api(
     let timestamp = Utc::now().timestamp();
     let date = Utc::now().format("%Y-%m-%d").to_string();
 
+    // 预先检查请求体大小，避免发出必然失败的请求
+    if !tencent_request_fits(audio_data.len()) {
+        return Err(format!(
+            "音频数据过大（{:.1} MB，编码后约{:.1} MB），超过腾讯云直接上传限制，请配置COS或缩短音频",
+            audio_data.len() as f64 / (1024.0 * 1024.0),
+            estimated_request_size(audio_data.len()) / (1024.0 * 1024.0)
+        ));
+    }
+
     // 将音频数据转换为base64
     let audio_base64 = base64::engine::general_purpose::STANDARD.encode(audio_data);
+    let (convert_num_mode, filter_dirty, filter_modal, filter_punc) = options.filter_values();
 
     // 构建请求参数（录音文件识别参数）
-    let params = json!({
+    let mut params = json!({
         "EngineModelType": "16k_zh",
         "ChannelNum": 1,
-        "ResTextFormat": 0,
+        "ResTextFormat": options.res_text_format,
         "SourceType": 1,
         "Data": audio_base64,
         "DataLen": audio_data.len(),
         "WordInfo": 1,  // 返回词级别时间戳
-        "FilterDirty": 0,
-        "FilterModal": 0,
-        "ConvertNumMode": 1,
+        "FilterDirty": filter_dirty,
+        "FilterModal": filter_modal,
+        "ConvertNumMode": convert_num_mode,
         "SpeakerDiarization": 0,
         "SpeakerNumber": 0,
-        "FilterPunc": 0,
+        "FilterPunc": filter_punc,
         "OutputFileType": "txt"
     });
 
+    // 添加热词
+    if let Some(hotword_list) = tencent_hotword_list(&options.hotwords) {
+        params["HotwordList"] = json!(hotword_list);
+    }
+
     let payload = params.to_string();
 
     // 构建签名
@@ -2239,9 +6665,9 @@ async fn call_tencent_rapid_api(
     let client = reqwest::Client::new();
     let url = format!("https://{}", host);
 
-    println!("调用腾讯云录音文件识别API: {}", action);
-    println!("音频数据长度: {} bytes", audio_data.len());
-    println!("请求负载大小: {} bytes", payload.len());
+    info!("调用腾讯云录音文件识别API: {}", action);
+    debug!("音频数据长度: {} bytes", audio_data.len());
+    debug!("请求负载大小: {} bytes", payload.len());
 
     let response = client
         .post(&url)
@@ -2263,8 +6689,8 @@ async fn call_tencent_rapid_api(
         .await
         .map_err(|e| format!("读取响应失败: {}", e))?;
 
-    println!("录音文件识别API响应状态: {}", status);
-    println!("录音文件识别API响应内容: {}", response_text);
+    debug!("录音文件识别API响应状态: {}", status);
+    debug!("录音文件识别API响应内容: {}", response_text);
 
     if status.is_success() {
         Ok(response_text)
@@ -2339,6 +6765,209 @@ async fn describe_tencent_task_status(
     }
 }
 
+/// 腾讯云机器翻译单次批量请求的最大字符数（接口上限为6000）
+const TMT_BATCH_CHARS: usize = 2000;
+
+/// 读取机器翻译使用的腾讯云密钥（`translate`/`tencent`子项优先，其次为顶层密钥）
+fn translation_credentials(keys: &Value) -> Result<(&str, &str), String> {
+    ["translate", "tencent"]
+        .iter()
+        .filter_map(|name| keys.get(*name))
+        .chain(std::iter::once(keys))
+        .find_map(|k| TencentEngine::credentials(k).ok())
+        .ok_or_else(|| "字幕翻译使用腾讯云机器翻译，需要提供secretId和secretKey".to_string())
+}
+
+/// 将识别语言代码转换为机器翻译语言代码（如 zh-CN -> zh，16k_en -> en）
+fn tmt_language_code(lang: &str) -> String {
+    let lang = lang.trim().to_lowercase();
+    match lang.as_str() {
+        "" | "auto" => "auto".to_string(),
+        "zh-tw" | "zh_tw" | "zh-hk" | "zh_hk" => "zh-TW".to_string(),
+        _ => lang
+            .split(['-', '_'])
+            .find(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphabetic()))
+            .unwrap_or("auto")
+            .to_string(),
+    }
+}
+
+/// 翻译识别结果，每条字幕的文本变为原文在上、译文在下的两行
+async fn translate_to_bilingual(
+    subtitles: Vec<crate::video::Subtitle>,
+    source_lang: &str,
+    target_lang: &str,
+    ctx: &mut TaskCtx,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    // 翻译阶段直接上报0.7~1.0的进度
+    set_task_progress_range(&ctx.task_id, None);
+    update_task_status(
+        &ctx.task_id,
+        "processing".to_string(),
+        0.7,
+        None,
+        Some("正在翻译字幕...".to_string()),
+    );
+
+    let (secret_id, secret_key) = translation_credentials(&ctx.api_keys)?;
+    let source = tmt_language_code(source_lang);
+    let target = tmt_language_code(target_lang);
+
+    // 按字符数分批，空文本不参与翻译
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_chars = 0;
+    for (index, subtitle) in subtitles.iter().enumerate() {
+        let chars = subtitle.text.trim().chars().count();
+        if chars == 0 {
+            continue;
+        }
+        if !current.is_empty() && current_chars + chars > TMT_BATCH_CHARS {
+            batches.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current.push(index);
+        current_chars += chars;
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    // 受保护的术语替换为占位符，译文中再还原
+    let (masked, protected): (Vec<String>, Vec<Vec<String>>) = subtitles
+        .iter()
+        .map(|subtitle| crate::glossary::protect_terms(subtitle.text.trim()))
+        .unzip();
+
+    let mut translations = vec![String::new(); subtitles.len()];
+    for (batch_index, batch) in batches.iter().enumerate() {
+        if ctx.cancel_rx.try_recv().is_ok() {
+            return Err("任务已取消".to_string());
+        }
+        if batch_index > 0 {
+            // 机器翻译接口默认限频5次/秒
+            sleep(Duration::from_millis(200)).await;
+        }
+
+        let texts: Vec<&str> = batch.iter().map(|&i| masked[i].as_str()).collect();
+        let translated =
+            call_tencent_translate_batch(secret_id, secret_key, &texts, &source, &target).await?;
+        if translated.len() != batch.len() {
+            return Err(format!(
+                "翻译结果数量不匹配: 请求{}条，返回{}条",
+                batch.len(),
+                translated.len()
+            ));
+        }
+        for (&index, text) in batch.iter().zip(translated) {
+            translations[index] = crate::glossary::restore_terms(&text, &protected[index]);
+        }
+
+        update_task_status(
+            &ctx.task_id,
+            "processing".to_string(),
+            0.7 + 0.3 * (batch_index + 1) as f32 / batches.len() as f32,
+            None,
+            Some(format!(
+                "正在翻译字幕 ({}/{})",
+                batch_index + 1,
+                batches.len()
+            )),
+        );
+    }
+
+    info!("字幕翻译完成，共{}批", batches.len());
+
+    Ok(subtitles
+        .into_iter()
+        .zip(translations)
+        .map(|(mut subtitle, translation)| {
+            let translation = translation.trim();
+            if !translation.is_empty() {
+                subtitle.text = format!("{}\n{}", subtitle.text.trim(), translation);
+            }
+            subtitle
+        })
+        .collect())
+}
+
+/// 调用腾讯云机器翻译批量文本翻译接口
+async fn call_tencent_translate_batch(
+    secret_id: &str,
+    secret_key: &str,
+    texts: &[&str],
+    source: &str,
+    target: &str,
+) -> Result<Vec<String>, String> {
+    let host = "tmt.tencentcloudapi.com";
+    let service = "tmt";
+    let version = "2018-03-21";
+    let action = "TextTranslateBatch";
+    let region = "ap-beijing";
+    let algorithm = "TC3-HMAC-SHA256";
+
+    let timestamp = Utc::now().timestamp();
+    let date = Utc::now().format("%Y-%m-%d").to_string();
+
+    let params = json!({
+        "Source": source,
+        "Target": target,
+        "ProjectId": 0,
+        "SourceTextList": texts
+    });
+    let payload = params.to_string();
+
+    let authorization = build_tencent_authorization(
+        secret_id, secret_key, &payload, host, action, &date, timestamp, service, algorithm,
+    )?;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("https://{}", host))
+        .header("Authorization", authorization)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("Host", host)
+        .header("X-TC-Action", action)
+        .header("X-TC-Timestamp", timestamp.to_string())
+        .header("X-TC-Version", version)
+        .header("X-TC-Region", region)
+        .body(payload)
+        .send()
+        .await
+        .map_err(|e| format!("翻译请求失败: {}", e))?;
+
+    let status = response.status();
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("读取翻译响应失败: {}", e))?;
+    if !status.is_success() {
+        return Err(format!(
+            "翻译请求失败，状态码: {}, 响应: {}",
+            status, response_text
+        ));
+    }
+
+    let response_json: Value =
+        serde_json::from_str(&response_text).map_err(|e| format!("解析翻译响应JSON失败: {}", e))?;
+    if let Some(error) = response_json["Response"].get("Error") {
+        return Err(format!(
+            "腾讯云机器翻译失败: {} - {}",
+            error["Code"].as_str().unwrap_or("未知错误"),
+            error["Message"].as_str().unwrap_or("")
+        ));
+    }
+
+    response_json["Response"]["TargetTextList"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .map(|text| text.as_str().unwrap_or("").to_string())
+                .collect()
+        })
+        .ok_or_else(|| "翻译响应缺少TargetTextList".to_string())
+}
+
 /// 构建腾讯云API签名
 fn build_tencent_authorization(
     secret_id: &str,
@@ -2396,30 +7025,176 @@ fn build_tencent_authorization(
     Ok(authorization)
 }
 
+/// WAV音频的PCM格式
+#[derive(Debug, Clone, Copy)]
+struct WavFormat {
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+}
+
+impl WavFormat {
+    /// 每个采样帧（所有声道）的字节数
+    fn block_align(&self) -> usize {
+        self.channels as usize * (self.bits_per_sample as usize / 8)
+    }
+
+    /// 每秒的字节数
+    fn bytes_per_second(&self) -> f64 {
+        self.sample_rate as f64 * self.block_align() as f64
+    }
+}
+
+/// 解析PCM WAV数据，返回格式和data块中的音频数据；不是PCM WAV时返回None
+fn parse_pcm_wav(data: &[u8]) -> Option<(WavFormat, &[u8])> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let id = &data[offset..offset + 4];
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8;
+        match id {
+            b"fmt " if size >= 16 && body + 16 <= data.len() => {
+                let field = |at: usize| u16::from_le_bytes([data[body + at], data[body + at + 1]]);
+                // 只支持整数PCM（格式1，或扩展格式0xFFFE）
+                if !matches!(field(0), 1 | 0xFFFE) {
+                    return None;
+                }
+                format = Some(WavFormat {
+                    channels: field(2),
+                    sample_rate: u32::from_le_bytes(data[body + 4..body + 8].try_into().ok()?),
+                    bits_per_sample: field(14),
+                });
+            }
+            // FFmpeg写入管道时data块大小可能为0或超出文件，按实际长度截取
+            b"data" => {
+                let format = format.filter(|f| f.sample_rate > 0 && f.block_align() > 0)?;
+                let end = if size == 0 {
+                    data.len()
+                } else {
+                    body.saturating_add(size).min(data.len())
+                };
+                return Some((format, &data[body..end]));
+            }
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        offset = body.saturating_add(size + (size & 1));
+    }
+    None
+}
+
+/// 为PCM数据生成WAV文件内容
+fn pcm_wav_bytes(format: WavFormat, pcm: &[u8]) -> Vec<u8> {
+    let data_len = pcm.len() as u32;
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&format.channels.to_le_bytes());
+    wav.extend_from_slice(&format.sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(format.bytes_per_second() as u32).to_le_bytes());
+    wav.extend_from_slice(&(format.block_align() as u16).to_le_bytes());
+    wav.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend_from_slice(pcm);
+    wav
+}
+
+/// 规划PCM数据的分片字节区间 `[start, end)`
+///
+/// 按 `plan_silence_cuts` 在静音处切分，切点换算为字节偏移并向下对齐到采样帧，
+/// 保证每片不超过 `max_chunk_bytes`。
+fn plan_pcm_chunks(
+    format: WavFormat,
+    pcm_len: usize,
+    max_chunk_bytes: usize,
+    silences: &[(f64, f64)],
+) -> Vec<(usize, usize)> {
+    let block_align = format.block_align();
+    let bytes_per_second = format.bytes_per_second();
+    let duration = pcm_len as f64 / bytes_per_second;
+    let max_chunk_seconds = max_chunk_bytes as f64 / bytes_per_second;
+
+    let mut offsets: Vec<usize> =
+        crate::video::plan_silence_cuts(duration, max_chunk_seconds, silences)
+            .into_iter()
+            .map(|cut| ((cut * bytes_per_second) as usize / block_align * block_align).min(pcm_len))
+            .collect();
+    offsets.push(pcm_len);
+    offsets
+        .windows(2)
+        .map(|w| (w[0], w[1]))
+        .filter(|(start, end)| end > start)
+        .collect()
+}
+
 /// 分片处理大音频文件
+///
+/// 按WAV头中的采样率、位深和声道数计算每片时长上限，用FFmpeg silencedetect在
+/// 上限前的静音处切分（检测失败或没有静音时按上限切分），切点对齐到采样帧。
+/// 每片加上WAV头后单独提交识别，字幕按片段的实际起始时间平移。
 async fn process_large_audio_in_chunks(
     secret_id: &str,
     secret_key: &str,
+    audio_path: &str,
     audio_data: &[u8],
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
+    options: &TencentOptions,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    const CHUNK_SIZE: usize = 5 * 1024 * 1024; // 5MB per chunk
-    const CHUNK_DURATION: f64 = 300.0; // 假设每个chunk约5分钟
-
-    let total_chunks = (audio_data.len() + CHUNK_SIZE - 1) / CHUNK_SIZE;
-    let mut all_subtitles = Vec::new();
-    let mut current_time_offset = 0.0;
+    const WAV_HEADER_SIZE: usize = 44;
+
+    let (format, pcm) =
+        parse_pcm_wav(audio_data).ok_or_else(|| "分片识别只支持PCM编码的WAV音频".to_string())?;
+    let block_align = format.block_align();
+    let bytes_per_second = format.bytes_per_second();
+    // 每片（含WAV头）不超过直接上传限制
+    let max_chunk_bytes =
+        (TENCENT_LOCAL_UPLOAD_LIMIT - WAV_HEADER_SIZE) / block_align * block_align;
+    let duration = pcm.len() as f64 / bytes_per_second;
+    let max_chunk_seconds = max_chunk_bytes as f64 / bytes_per_second;
+
+    let silences = if duration > max_chunk_seconds {
+        crate::video::detect_silence(audio_path, -35.0, 0.4).unwrap_or_else(|e| {
+            warn!("静音检测失败，按固定长度分片: {}", e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    let ranges = plan_pcm_chunks(format, pcm.len(), max_chunk_bytes, &silences);
+
+    let total_chunks = ranges.len();
+    let mut chunk_results = Vec::new();
+
+    info!(
+        "开始分片处理，总共 {} 个片段（{}Hz，{}声道，{}位，静音区间{}个）",
+        total_chunks,
+        format.sample_rate,
+        format.channels,
+        format.bits_per_sample,
+        silences.len()
+    );
 
-    println!("开始分片处理，总共 {} 个片段", total_chunks);
+    for (chunk_index, &(chunk_start, chunk_end)) in ranges.iter().enumerate() {
+        // 片段的实际起始时间，识别失败跳过的片段不影响后续片段
+        let current_time_offset = chunk_start as f64 / bytes_per_second;
+        let chunk_data = pcm_wav_bytes(format, &pcm[chunk_start..chunk_end]);
 
-    for (chunk_index, chunk_data) in audio_data.chunks(CHUNK_SIZE).enumerate() {
         // 检查取消信号
         if cancel_rx.try_recv().is_ok() {
-            return Err("任务已取消".to_string());
+            return Err(TASK_CANCELLED.to_string());
         }
 
-        println!(
+        debug!(
             "处理第 {}/{} 个片段，大小: {} bytes",
             chunk_index + 1,
             total_chunks,
@@ -2437,7 +7212,7 @@ async fn process_large_audio_in_chunks(
         );
 
         // 处理当前片段
-        match call_tencent_rapid_api(secret_id, secret_key, chunk_data).await {
+        match call_tencent_rapid_api(secret_id, secret_key, &chunk_data, options).await {
             Ok(response) => {
                 // 解析任务创建响应
                 let task_response: Value = serde_json::from_str(&response)
@@ -2453,12 +7228,23 @@ async fn process_large_audio_in_chunks(
                         .get("Message")
                         .and_then(|m| m.as_str())
                         .unwrap_or("Unknown error");
-                    println!(
+                    warn!(
                         "片段{}识别失败: {} - {}",
                         chunk_index + 1,
                         error_code,
                         error_message
                     );
+                    add_task_warning(
+                        task_id,
+                        format!(
+                            "片段 {}/{}（{:.0}秒起）识别失败: {} - {}",
+                            chunk_index + 1,
+                            total_chunks,
+                            current_time_offset,
+                            error_code,
+                            error_message
+                        ),
+                    );
                     continue; // 跳过失败的片段，继续处理下一个
                 }
 
@@ -2479,6 +7265,7 @@ async fn process_large_audio_in_chunks(
                     recognition_task_id,
                     task_id,
                     cancel_rx,
+                    options.res_text_format,
                 )
                 .await
                 {
@@ -2488,38 +7275,282 @@ async fn process_large_audio_in_chunks(
                             subtitle.start_time += current_time_offset;
                             subtitle.end_time += current_time_offset;
                         }
-                        all_subtitles.extend(chunk_subtitles);
+                        chunk_results.push(chunk_subtitles);
                     }
+                    // 取消信号已被轮询消耗，直接结束任务
+                    Err(e) if e == TASK_CANCELLED => return Err(e),
                     Err(e) => {
-                        println!("片段{}识别失败: {}", chunk_index + 1, e);
-                        // 继续处理下一个片段
+                        warn!("片段{}识别失败: {}", chunk_index + 1, e);
+                        add_task_warning(
+                            task_id,
+                            format!(
+                                "片段 {}/{}（{:.0}秒起）识别失败: {}",
+                                chunk_index + 1,
+                                total_chunks,
+                                current_time_offset,
+                                e
+                            ),
+                        );
                     }
                 }
             }
             Err(e) => {
-                println!("片段{}API调用失败: {}", chunk_index + 1, e);
-                // 继续处理下一个片段
+                warn!("片段{}API调用失败: {}", chunk_index + 1, e);
+                add_task_warning(
+                    task_id,
+                    format!(
+                        "片段 {}/{}（{:.0}秒起）提交失败: {}",
+                        chunk_index + 1,
+                        total_chunks,
+                        current_time_offset,
+                        e
+                    ),
+                );
             }
         }
 
-        // 更新时间偏移
-        current_time_offset += CHUNK_DURATION;
+        // 短暂延迟，避免API调用过于频繁（等待期间响应取消）
+        tokio::select! {
+            _ = tokio::time::sleep(tokio::time::Duration::from_secs(1)) => {}
+            Some(()) = cancel_rx.recv() => return Err(TASK_CANCELLED.to_string()),
+        }
+    }
+
+    // 合并各片段结果（按字节切分的片段之间没有重叠）
+    let all_subtitles = stitch_chunks(chunk_results, 0.0);
+
+    if all_subtitles.is_empty() {
+        Err("所有片段识别都失败了，请检查网络连接和API配置".to_string())
+    } else {
+        info!("分片处理完成，共生成{}条字幕", all_subtitles.len());
+        Ok(all_subtitles)
+    }
+}
+
+/// 合并分片识别结果，去除片段边界重叠区域内的重复字幕
+///
+/// 每个片段的时间戳应已调整为全局时间。对于后一片段中起始于边界后
+/// `overlap` 秒内的字幕，若与前面结束于边界前 `overlap` 秒内的字幕
+/// 归一化文本相同（或互相包含），则视为重复并丢弃。
+pub fn stitch_chunks(
+    chunks: Vec<Vec<crate::video::Subtitle>>,
+    overlap: f64,
+) -> Vec<crate::video::Subtitle> {
+    let mut stitched: Vec<crate::video::Subtitle> = Vec::new();
+
+    for chunk in chunks {
+        let boundary = chunk
+            .iter()
+            .map(|subtitle| subtitle.start_time)
+            .fold(f64::INFINITY, f64::min);
+
+        for subtitle in chunk {
+            let in_overlap = overlap > 0.0 && subtitle.start_time < boundary + overlap;
+            if in_overlap {
+                let text = normalize_cue_text(&subtitle.text);
+                let duplicated = !text.is_empty()
+                    && stitched
+                        .iter()
+                        .rev()
+                        .take_while(|prev| prev.end_time >= boundary - overlap)
+                        .any(|prev| {
+                            let prev_text = normalize_cue_text(&prev.text);
+                            !prev_text.is_empty()
+                                && (prev_text.contains(&text) || text.contains(&prev_text))
+                        });
+                if duplicated {
+                    continue;
+                }
+            }
+            stitched.push(subtitle);
+        }
+    }
+
+    stitched.sort_by(|a, b| {
+        a.start_time
+            .partial_cmp(&b.start_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // 重新编号字幕
+    for (index, subtitle) in stitched.iter_mut().enumerate() {
+        subtitle.id = (index + 1).to_string();
+    }
+
+    stitched
+}
+
+/// 归一化字幕文本用于比较（忽略大小写、空白和标点）
+fn normalize_cue_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// 腾讯云录音文件识别任务状态
+enum TaskState {
+    /// 排队或识别中
+    Running,
+    /// 任务失败，附带错误信息
+    Failed(String),
+    /// 识别完成
+    Done(Vec<crate::video::Subtitle>),
+}
+
+/// 查询腾讯云录音文件识别任务状态（DescribeTaskStatus），完成时解析识别结果
+///
+/// 请求失败或API返回错误时返回Err，由调用方决定是否继续轮询。
+async fn fetch_tencent_result(
+    secret_id: &str,
+    secret_key: &str,
+    recognition_task_id: u64,
+    res_text_format: u32,
+) -> Result<TaskState, String> {
+    let host = "asr.tencentcloudapi.com";
+    let service = "asr";
+    let version = "2019-06-14";
+    let action = "DescribeTaskStatus";
+    let region = "ap-beijing";
+    let algorithm = "TC3-HMAC-SHA256";
+
+    // 获取当前时间戳
+    let timestamp = Utc::now().timestamp();
+    let date = Utc::now().format("%Y-%m-%d").to_string();
+
+    let payload = json!({ "TaskId": recognition_task_id }).to_string();
+
+    // 构建签名
+    let authorization = build_tencent_authorization(
+        secret_id, secret_key, &payload, host, action, &date, timestamp, service, algorithm,
+    )?;
+
+    // 发送查询请求
+    let response = reqwest::Client::new()
+        .post(format!("https://{}", host))
+        .header("Authorization", authorization)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .header("Host", host)
+        .header("X-TC-Action", action)
+        .header("X-TC-Timestamp", timestamp.to_string())
+        .header("X-TC-Version", version)
+        .header("X-TC-Region", region)
+        .body(payload)
+        .send()
+        .await
+        .map_err(|e| format!("查询识别状态失败: {}", e))?;
+
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("读取查询响应失败: {}", e))?;
+
+    debug!("查询识别状态响应: {}", response_text);
+
+    // 解析响应
+    let status_response: Value =
+        serde_json::from_str(&response_text).map_err(|e| format!("解析状态响应失败: {}", e))?;
+
+    // 检查错误
+    if let Some(error) = status_response.get("Response").and_then(|r| r.get("Error")) {
+        let error_code = error
+            .get("Code")
+            .and_then(|c| c.as_str())
+            .unwrap_or("Unknown");
+        let error_message = error
+            .get("Message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Unknown error");
+        return Err(format!(
+            "查询识别状态错误: {} - {}",
+            error_code, error_message
+        ));
+    }
+
+    // 获取任务状态
+    let data = status_response
+        .get("Response")
+        .and_then(|r| r.get("Data"))
+        .ok_or("无法获取状态数据")?;
+
+    let status = data
+        .get("StatusStr")
+        .and_then(|s| s.as_str())
+        .unwrap_or("unknown");
+
+    info!("识别任务状态: {}", status);
 
-        // 短暂延迟，避免API调用过于频繁
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    match status {
+        "success" => parse_tencent_task_data(data, res_text_format).map(TaskState::Done),
+        "failed" => Ok(TaskState::Failed(
+            data.get("ErrorMsg")
+                .and_then(|e| e.as_str())
+                .unwrap_or("识别失败")
+                .to_string(),
+        )),
+        // waiting、doing 及未知状态均视为进行中
+        _ => Ok(TaskState::Running),
     }
+}
 
-    // 重新编号字幕
-    for (index, subtitle) in all_subtitles.iter_mut().enumerate() {
-        subtitle.id = (index + 1).to_string();
+/// 解析DescribeTaskStatus返回的Data：优先使用ResultDetail，缺失时回退到Result文本
+fn parse_tencent_task_data(
+    data: &Value,
+    res_text_format: u32,
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    let result_detail = data
+        .get("ResultDetail")
+        .and_then(|rd| rd.as_array())
+        .filter(|rd| !rd.is_empty());
+
+    if let Some(result_detail) = result_detail {
+        // 词级格式：按ResultDetail中的词时间戳分句
+        if res_text_format == 2 {
+            return parse_tencent_word_detail(result_detail);
+        }
+        return parse_tencent_sentence_detail(result_detail);
     }
 
-    if all_subtitles.is_empty() {
-        Err("所有片段识别都失败了，请检查网络连接和API配置".to_string())
-    } else {
-        println!("分片处理完成，共生成{}条字幕", all_subtitles.len());
-        Ok(all_subtitles)
+    let result = data
+        .get("Result")
+        .and_then(|r| r.as_str())
+        .ok_or("无法获取识别结果")?;
+
+    parse_tencent_file_recognition_result(result)
+}
+
+/// 按ResultDetail中的句子（FinalSentence、StartMs、EndMs）生成字幕
+fn parse_tencent_sentence_detail(
+    result_detail: &[Value],
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    let subtitles: Vec<crate::video::Subtitle> = result_detail
+        .iter()
+        .filter_map(|item| {
+            let text = item.get("FinalSentence").and_then(|t| t.as_str())?.trim();
+            if text.is_empty() {
+                return None;
+            }
+            let millis = |key: &str| item.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as f64;
+            Some((millis("StartMs") / 1000.0, millis("EndMs") / 1000.0, text))
+        })
+        .enumerate()
+        .map(
+            |(index, (start_time, end_time, text))| crate::video::Subtitle {
+                id: (index + 1).to_string(),
+                start_time,
+                end_time,
+                text: text.to_string(),
+                position: None,
+                words: None,
+            },
+        )
+        .collect();
+
+    if subtitles.is_empty() {
+        return Err("识别结果为空".to_string());
     }
+
+    Ok(subtitles)
 }
 
 /// 轮询腾讯云录音文件识别结果
@@ -2529,17 +7560,8 @@ async fn poll_tencent_recognition_result(
     recognition_task_id: u64,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
+    res_text_format: u32,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
-    let host = "asr.tencentcloudapi.com";
-    let service = "asr";
-    let version = "2019-06-14";
-    let action = "DescribeTaskStatus";
-    let region = "ap-beijing";
-    let algorithm = "TC3-HMAC-SHA256";
-
-    let client = reqwest::Client::new();
-    let url = format!("https://{}", host);
-
     let mut attempts = 0;
     const MAX_ATTEMPTS: u32 = 60; // 最多等待5分钟（每5秒一次）
 
@@ -2554,95 +7576,14 @@ async fn poll_tencent_recognition_result(
             return Err("识别超时，请稍后重试".to_string());
         }
 
-        // 获取当前时间戳
-        let timestamp = Utc::now().timestamp();
-        let date = Utc::now().format("%Y-%m-%d").to_string();
-
-        // 构建查询参数
-        let params = json!({
-            "TaskId": recognition_task_id
-        });
-
-        let payload = params.to_string();
-
-        // 构建签名
-        let authorization = build_tencent_authorization(
-            secret_id, secret_key, &payload, host, &action, &date, timestamp, service, &algorithm,
-        )?;
-
-        // 发送查询请求
-        let response = client
-            .post(&url)
-            .header("Authorization", authorization)
-            .header("Content-Type", "application/json; charset=utf-8")
-            .header("Host", host)
-            .header("X-TC-Action", action)
-            .header("X-TC-Timestamp", timestamp.to_string())
-            .header("X-TC-Version", version)
-            .header("X-TC-Region", region)
-            .body(payload)
-            .send()
-            .await
-            .map_err(|e| format!("查询识别状态失败: {}", e))?;
-
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| format!("读取查询响应失败: {}", e))?;
-
-        println!("查询识别状态响应: {}", response_text);
-
-        // 解析响应
-        let status_response: Value =
-            serde_json::from_str(&response_text).map_err(|e| format!("解析状态响应失败: {}", e))?;
-
-        // 检查错误
-        if let Some(error) = status_response.get("Response").and_then(|r| r.get("Error")) {
-            let error_code = error
-                .get("Code")
-                .and_then(|c| c.as_str())
-                .unwrap_or("Unknown");
-            let error_message = error
-                .get("Message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error");
-            return Err(format!(
-                "查询识别状态错误: {} - {}",
-                error_code, error_message
-            ));
-        }
-
-        // 获取任务状态
-        let data = status_response
-            .get("Response")
-            .and_then(|r| r.get("Data"))
-            .ok_or("无法获取状态数据")?;
-
-        let status = data
-            .get("StatusStr")
-            .and_then(|s| s.as_str())
-            .unwrap_or("unknown");
-
-        println!("识别任务状态: {}", status);
-
-        match status {
-            "success" => {
-                // 识别成功，解析结果
-                let result = data
-                    .get("Result")
-                    .and_then(|r| r.as_str())
-                    .ok_or("无法获取识别结果")?;
-
-                return parse_tencent_file_recognition_result(result);
-            }
-            "failed" => {
-                let error_msg = data
-                    .get("ErrorMsg")
-                    .and_then(|e| e.as_str())
-                    .unwrap_or("识别失败");
+        match fetch_tencent_result(secret_id, secret_key, recognition_task_id, res_text_format)
+            .await?
+        {
+            TaskState::Done(subtitles) => return Ok(subtitles),
+            TaskState::Failed(error_msg) => {
                 return Err(format!("录音文件识别失败: {}", error_msg));
             }
-            "running" | "waiting" => {
+            TaskState::Running => {
                 // 任务还在进行中，更新进度
                 let progress = 0.5 + (attempts as f32 / MAX_ATTEMPTS as f32) * 0.3;
                 update_task_status(
@@ -2655,12 +7596,6 @@ async fn poll_tencent_recognition_result(
 
                 // 等待5秒后重试
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                continue;
-            }
-            _ => {
-                // 未知状态，继续等待
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                continue;
             }
         }
     }
@@ -2696,6 +7631,12 @@ fn generate_audio_processing_suggestions(audio_size: usize, audio_path: &str) ->
 fn parse_tencent_file_recognition_result(
     result: &str,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
+    // 常见格式为逐行的时间戳文本，如 "[0:1.640,0:4.200]  文本"
+    let timed_lines = parse_tencent_result_lines(result);
+    if !timed_lines.is_empty() {
+        return Ok(timed_lines);
+    }
+
     // 录音文件识别返回的是JSON格式的详细结果
     let result_data: Value =
         serde_json::from_str(result).map_err(|e| format!("解析识别结果JSON失败: {}", e))?;
@@ -2732,6 +7673,8 @@ fn parse_tencent_file_recognition_result(
                     start_time: start,
                     end_time: end,
                     text: text.trim().to_string(),
+                    position: None,
+                    words: None,
                 });
             }
         }
@@ -2753,6 +7696,8 @@ fn parse_tencent_file_recognition_result(
                     start_time,
                     end_time,
                     text: sentence.trim().to_string(),
+                    position: None,
+                    words: None,
                 });
             }
         }
@@ -2765,6 +7710,172 @@ fn parse_tencent_file_recognition_result(
     Ok(subtitles)
 }
 
+/// 解析Result文本中带时间戳的行，无法识别的行忽略
+fn parse_tencent_result_lines(result: &str) -> Vec<crate::video::Subtitle> {
+    result
+        .lines()
+        .filter_map(|line| {
+            let (range, text) = line.trim().strip_prefix('[')?.split_once(']')?;
+            let (start, end) = range.split_once(',')?;
+            let text = text.trim();
+            Some((
+                parse_tencent_timestamp(start)?,
+                parse_tencent_timestamp(end)?,
+                text,
+            ))
+        })
+        .enumerate()
+        .map(
+            |(index, (start_time, end_time, text))| crate::video::Subtitle {
+                id: (index + 1).to_string(),
+                start_time,
+                end_time,
+                text: text.to_string(),
+                position: None,
+                words: None,
+            },
+        )
+        .collect()
+}
+
+/// 解析 "分:秒.毫秒"（或 "时:分:秒.毫秒"）格式的时间戳为秒
+fn parse_tencent_timestamp(value: &str) -> Option<f64> {
+    value.trim().split(':').try_fold(0.0, |total, part| {
+        Some(total * 60.0 + part.parse::<f64>().ok()?)
+    })
+}
+
+/// 解析腾讯云词级识别结果（ResTextFormat=2）
+///
+/// 词时间戳（OffsetStartMs/OffsetEndMs）相对于所在句子的StartMs，
+/// 按词边界切分过长的句子，使字幕起止时间与语音精确对齐。
+fn parse_tencent_word_detail(
+    result_detail: &[Value],
+) -> Result<Vec<crate::video::Subtitle>, String> {
+    const MAX_WORDS_PER_CUE: usize = 20; // 每条字幕最多词数
+    const MAX_CUE_DURATION: f64 = 6.0; // 每条字幕最长时长（秒）
+
+    let mut subtitles: Vec<crate::video::Subtitle> = Vec::new();
+
+    for sentence in result_detail {
+        let sentence_start = sentence
+            .get("StartMs")
+            .and_then(|s| s.as_u64())
+            .unwrap_or(0);
+        let words = sentence
+            .get("Words")
+            .and_then(|w| w.as_array())
+            .map(|w| w.as_slice())
+            .unwrap_or(&[]);
+
+        // 没有词信息时退回句级结果（空句同样输出，由 `drop_empty_cues` 统一处理）
+        let has_words = words.iter().any(|word| {
+            word.get("Word")
+                .and_then(|w| w.as_str())
+                .is_some_and(|w| !w.is_empty())
+        });
+        if !has_words {
+            let text = sentence
+                .get("FinalSentence")
+                .and_then(|t| t.as_str())
+                .unwrap_or("")
+                .trim();
+            let end_ms = sentence.get("EndMs").and_then(|e| e.as_u64()).unwrap_or(0);
+            subtitles.push(crate::video::Subtitle {
+                id: (subtitles.len() + 1).to_string(),
+                start_time: sentence_start as f64 / 1000.0,
+                end_time: end_ms as f64 / 1000.0,
+                text: text.to_string(),
+                position: None,
+                words: None,
+            });
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut start = 0.0;
+        let mut end = 0.0;
+        let mut count = 0;
+
+        // 有累积内容时输出一条字幕（文本为空也输出，由 `drop_empty_cues` 统一处理）
+        let mut flush = |text: &mut String, start: f64, end: f64, count: &mut usize| {
+            if *count > 0 || !text.is_empty() {
+                subtitles.push(crate::video::Subtitle {
+                    id: (subtitles.len() + 1).to_string(),
+                    start_time: start,
+                    end_time: end,
+                    text: text.trim().to_string(),
+                    position: None,
+                    words: None,
+                });
+            }
+            text.clear();
+            *count = 0;
+        };
+
+        for word in words {
+            let content = word.get("Word").and_then(|w| w.as_str()).unwrap_or("");
+            if content.is_empty() {
+                continue;
+            }
+
+            // 标点附加到前一个词后，句末标点处断句
+            if content
+                .chars()
+                .all(|c| c.is_ascii_punctuation() || is_cjk_punctuation(c))
+            {
+                text.push_str(content);
+                if content.ends_with(['。', '！', '？', '.', '!', '?']) {
+                    flush(&mut text, start, end, &mut count);
+                }
+                continue;
+            }
+
+            let word_start = (sentence_start
+                + word
+                    .get("OffsetStartMs")
+                    .and_then(|s| s.as_u64())
+                    .unwrap_or(0)) as f64
+                / 1000.0;
+            let word_end = (sentence_start
+                + word
+                    .get("OffsetEndMs")
+                    .and_then(|e| e.as_u64())
+                    .unwrap_or(0)) as f64
+                / 1000.0;
+
+            if count > 0 && (count >= MAX_WORDS_PER_CUE || word_end - start > MAX_CUE_DURATION) {
+                flush(&mut text, start, end, &mut count);
+            }
+
+            if count == 0 {
+                start = word_start;
+            } else if text.chars().last().is_some_and(|c| !is_cjk_char(c))
+                && content.chars().next().is_some_and(|c| !is_cjk_char(c))
+            {
+                text.push(' ');
+            }
+
+            text.push_str(content);
+            end = word_end.max(word_start);
+            count += 1;
+        }
+        flush(&mut text, start, end, &mut count);
+    }
+
+    if subtitles.is_empty() {
+        return Err("识别结果为空".to_string());
+    }
+
+    info!("腾讯云词级结果解析完成，共生成{}条字幕", subtitles.len());
+    Ok(subtitles)
+}
+
+/// 判断是否为CJK标点
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c as u32, 0x3000..=0x303F | 0xFF01..=0xFF0F | 0xFF1A..=0xFF20)
+}
+
 /// 解析腾讯云SentenceRecognition API响应结果（保留用于兼容性）
 fn parse_tencent_rapid_result(response: &str) -> Result<Vec<crate::video::Subtitle>, String> {
     let response_json: Value =
@@ -2809,9 +7920,11 @@ fn parse_tencent_rapid_result(response: &str) -> Result<Vec<crate::video::Subtit
         start_time: 0.0,
         end_time: 10.0, // 默认10秒，实际应该根据音频长度计算
         text: result,
+        position: None,
+        words: None,
     }];
 
-    println!(
+    info!(
         "腾讯云SentenceRecognition识别完成，结果: {}",
         subtitles[0].text
     );
@@ -2911,6 +8024,8 @@ fn parse_tencent_rec_result(response: &str) -> Result<Vec<crate::video::Subtitle
                 start_time,
                 end_time,
                 text,
+                position: None,
+                words: None,
             });
         }
     }
@@ -2919,7 +8034,7 @@ fn parse_tencent_rec_result(response: &str) -> Result<Vec<crate::video::Subtitle
         return Err("识别结果为空".to_string());
     }
 
-    println!("腾讯云识别完成，共生成{}条字幕", subtitles.len());
+    info!("腾讯云识别完成，共生成{}条字幕", subtitles.len());
     Ok(subtitles)
 }
 
@@ -2930,12 +8045,13 @@ async fn call_tencent_rapid_api_with_url(
     audio_url: &str,
     task_id: &str,
     cancel_rx: &mut mpsc::Receiver<()>,
+    options: &TencentOptions,
 ) -> Result<Vec<crate::video::Subtitle>, String> {
     use std::collections::HashMap;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    println!("使用URL方式调用腾讯云录音文件识别API");
-    println!("音频URL: {}", audio_url);
+    info!("使用URL方式调用腾讯云录音文件识别API");
+    info!("音频URL: {}", crate::logging::redact_url(audio_url));
 
     // 更新进度
     update_task_status(
@@ -2958,9 +8074,20 @@ async fn call_tencent_rapid_api_with_url(
     params.insert("Region".to_string(), "ap-beijing".to_string());
     params.insert("EngineModelType".to_string(), "16k_zh".to_string());
     params.insert("ChannelNum".to_string(), "1".to_string());
-    params.insert("ResTextFormat".to_string(), "0".to_string());
+    params.insert(
+        "ResTextFormat".to_string(),
+        options.res_text_format.to_string(),
+    );
     params.insert("SourceType".to_string(), "0".to_string()); // 0表示URL
     params.insert("Url".to_string(), audio_url.to_string());
+    let (convert_num_mode, filter_dirty, filter_modal, filter_punc) = options.filter_values();
+    params.insert("ConvertNumMode".to_string(), convert_num_mode.to_string());
+    params.insert("FilterDirty".to_string(), filter_dirty.to_string());
+    params.insert("FilterModal".to_string(), filter_modal.to_string());
+    params.insert("FilterPunc".to_string(), filter_punc.to_string());
+    if let Some(hotword_list) = tencent_hotword_list(&options.hotwords) {
+        params.insert("HotwordList".to_string(), hotword_list);
+    }
 
     // 生成签名
     let timestamp = SystemTime::now()
@@ -2998,7 +8125,7 @@ async fn call_tencent_rapid_api_with_url(
         .await
         .map_err(|e| format!("读取响应失败: {}", e))?;
 
-    println!("腾讯云CreateRecTask响应: {}", response_text);
+    debug!("腾讯云CreateRecTask响应: {}", response_text);
 
     // 解析响应
     let task_response: Value =
@@ -3028,7 +8155,7 @@ async fn call_tencent_rapid_api_with_url(
         .and_then(|t| t.as_u64())
         .ok_or("无法获取TaskId")?;
 
-    println!("腾讯云任务创建成功，TaskId: {}", tencent_task_id);
+    info!("腾讯云任务创建成功，TaskId: {}", tencent_task_id);
 
     // 轮询任务状态
     let mut attempts = 0;
@@ -3062,161 +8189,32 @@ async fn call_tencent_rapid_api_with_url(
         tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
 
         // 查询任务状态
-        match query_tencent_task_status(secret_id, secret_key, tencent_task_id).await {
-            Ok(result) => {
-                if let Some(subtitles) = result {
-                    println!("腾讯云识别完成，共生成{}条字幕", subtitles.len());
-                    return Ok(subtitles);
-                }
+        match fetch_tencent_result(
+            secret_id,
+            secret_key,
+            tencent_task_id,
+            options.res_text_format,
+        )
+        .await
+        {
+            Ok(TaskState::Done(subtitles)) => {
+                info!("腾讯云识别完成，共生成{}条字幕", subtitles.len());
+                return Ok(subtitles);
+            }
+            Ok(TaskState::Failed(error_msg)) => {
+                return Err(format!("腾讯云识别任务失败: {}", error_msg));
+            }
+            Ok(TaskState::Running) => {
                 // 任务还在进行中，继续等待
             }
             Err(e) => {
-                eprintln!("查询任务状态失败: {}", e);
+                error!("查询任务状态失败: {}", e);
                 // 继续尝试，不立即返回错误
             }
         }
     }
 }
 
-/// 查询腾讯云任务状态
-async fn query_tencent_task_status(
-    secret_id: &str,
-    secret_key: &str,
-    tencent_task_id: u64,
-) -> Result<Option<Vec<crate::video::Subtitle>>, String> {
-    use std::collections::HashMap;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    // 构建请求参数
-    let mut params = HashMap::new();
-    params.insert("Action".to_string(), "DescribeTaskStatus".to_string());
-    params.insert("Version".to_string(), "2018-05-22".to_string());
-    params.insert("Region".to_string(), "ap-beijing".to_string());
-    params.insert("TaskId".to_string(), tencent_task_id.to_string());
-
-    // 生成签名
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let authorization = generate_tencent_signature_v3(
-        secret_id,
-        secret_key,
-        "asr",
-        "ap-beijing",
-        &params,
-        timestamp,
-    )?;
-
-    // 发送请求
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://asr.tencentcloudapi.com/")
-        .header("Authorization", authorization)
-        .header("Content-Type", "application/json; charset=utf-8")
-        .header("Host", "asr.tencentcloudapi.com")
-        .header("X-TC-Action", "DescribeTaskStatus")
-        .header("X-TC-Timestamp", timestamp.to_string())
-        .header("X-TC-Version", "2018-05-22")
-        .header("X-TC-Region", "ap-beijing")
-        .json(&params)
-        .send()
-        .await
-        .map_err(|e| format!("查询任务状态请求失败: {}", e))?;
-
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| format!("读取任务状态响应失败: {}", e))?;
-
-    println!("腾讯云DescribeTaskStatus响应: {}", response_text);
-
-    // 解析响应
-    let status_response: Value =
-        serde_json::from_str(&response_text).map_err(|e| format!("解析任务状态响应失败: {}", e))?;
-
-    // 检查是否有错误
-    if let Some(error) = status_response.get("Response").and_then(|r| r.get("Error")) {
-        let error_code = error
-            .get("Code")
-            .and_then(|c| c.as_str())
-            .unwrap_or("Unknown");
-        let error_message = error
-            .get("Message")
-            .and_then(|m| m.as_str())
-            .unwrap_or("Unknown error");
-        return Err(format!(
-            "腾讯云DescribeTaskStatus API错误: {} - {}",
-            error_code, error_message
-        ));
-    }
-
-    // 获取任务状态
-    let data = status_response
-        .get("Response")
-        .and_then(|r| r.get("Data"))
-        .ok_or("响应中没有Data字段")?;
-
-    let status = data
-        .get("StatusStr")
-        .and_then(|s| s.as_str())
-        .unwrap_or("unknown");
-
-    println!("腾讯云任务状态: {}", status);
-
-    match status {
-        "success" => {
-            // 任务完成，解析结果
-            let result_detail = data
-                .get("ResultDetail")
-                .and_then(|rd| rd.as_array())
-                .ok_or("响应中没有ResultDetail字段")?;
-
-            let mut subtitles = Vec::new();
-
-            for (index, item) in result_detail.iter().enumerate() {
-                let start_time =
-                    item.get("StartMs").and_then(|s| s.as_u64()).unwrap_or(0) as f64 / 1000.0;
-                let end_time =
-                    item.get("EndMs").and_then(|e| e.as_u64()).unwrap_or(0) as f64 / 1000.0;
-                let text = item
-                    .get("FinalSentence")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                if !text.trim().is_empty() {
-                    subtitles.push(crate::video::Subtitle {
-                        id: format!("tencent_cos_{}", index + 1),
-                        start_time,
-                        end_time,
-                        text,
-                    });
-                }
-            }
-
-            if subtitles.is_empty() {
-                return Err("识别结果为空".to_string());
-            }
-
-            Ok(Some(subtitles))
-        }
-        "failed" => {
-            let error_msg = data
-                .get("ErrorMsg")
-                .and_then(|e| e.as_str())
-                .unwrap_or("任务失败");
-            Err(format!("腾讯云识别任务失败: {}", error_msg))
-        }
-        "waiting" | "doing" => {
-            // 任务还在进行中
-            Ok(None)
-        }
-        _ => Err(format!("未知的任务状态: {}", status)),
-    }
-}
-
 /// 生成腾讯云API v3签名
 fn generate_tencent_signature_v3(
     secret_id: &str,
@@ -3281,3 +8279,221 @@ fn sha256_hash(data: &str) -> String {
     hasher.update(data.as_bytes());
     hex::encode(hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(id: &str, start_time: f64, text: &str) -> crate::video::Subtitle {
+        crate::video::Subtitle {
+            id: id.to_string(),
+            start_time,
+            end_time: start_time + 1.0,
+            text: text.to_string(),
+            position: None,
+            words: None,
+        }
+    }
+
+    #[test]
+    fn empty_cues_are_dropped_and_renumbered() {
+        let result = handle_empty_cues(
+            vec![
+                cue("1", 0.0, "你好"),
+                cue("2", 1.0, "  \n "),
+                cue("3", 2.0, "world"),
+            ],
+            false,
+        );
+        let summary: Vec<(&str, &str)> = result
+            .iter()
+            .map(|s| (s.id.as_str(), s.text.as_str()))
+            .collect();
+        assert_eq!(summary, vec![("1", "你好"), ("2", "world")]);
+    }
+
+    #[test]
+    fn empty_cues_are_kept_as_placeholders() {
+        let result = handle_empty_cues(
+            vec![
+                cue("1", 0.0, "你好"),
+                cue("2", 1.0, "  \n "),
+                cue("3", 2.0, ""),
+            ],
+            true,
+        );
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].id, "2");
+        assert_eq!(result[1].text, "");
+        assert_eq!(result[2].text, "");
+    }
+
+    #[test]
+    fn parsers_leave_blank_cues_to_empty_cue_handling() {
+        let srt = "1\n00:00:00,000 --> 00:00:01,000\n你好\n\n2\n00:00:01,000 --> 00:00:02,000\n\n3\n00:00:02,000 --> 00:00:03,000\nworld\n";
+        let parsed = parse_srt_content(srt).unwrap();
+        assert_eq!(parsed.len(), 3);
+
+        let kept = handle_empty_cues(parsed.clone(), true);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[1].id, "2");
+        assert_eq!(kept[1].start_time, 1.0);
+        assert_eq!(kept[1].text, "");
+
+        let dropped = handle_empty_cues(parsed, false);
+        let summary: Vec<(&str, &str)> = dropped
+            .iter()
+            .map(|s| (s.id.as_str(), s.text.as_str()))
+            .collect();
+        assert_eq!(summary, vec![("1", "你好"), ("2", "world")]);
+
+        let openai = serde_json::json!({
+            "segments": [
+                { "start": 0.0, "end": 1.0, "text": " hello " },
+                { "start": 1.0, "end": 2.0, "text": "  " }
+            ]
+        });
+        let kept = handle_empty_cues(parse_openai_segments(&openai), true);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[1].text, "");
+
+        let aliyun = serde_json::json!({
+            "Sentences": [
+                { "BeginTime": 0, "EndTime": 1000, "Text": "" },
+                { "BeginTime": 1000, "EndTime": 2000, "Text": "好" }
+            ]
+        });
+        let kept = handle_empty_cues(parse_aliyun_result(&aliyun), true);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].text, "");
+    }
+
+    #[test]
+    fn pcm_wav_round_trip() {
+        let format = WavFormat {
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+        let pcm: Vec<u8> = (0..64).collect();
+        let wav = pcm_wav_bytes(format, &pcm);
+        assert_eq!(wav.len(), 44 + pcm.len());
+
+        let (parsed, data) = parse_pcm_wav(&wav).expect("应解析为PCM WAV");
+        assert_eq!(parsed.sample_rate, 16000);
+        assert_eq!(parsed.channels, 1);
+        assert_eq!(parsed.bits_per_sample, 16);
+        assert_eq!(parsed.block_align(), 2);
+        assert_eq!(parsed.bytes_per_second(), 32000.0);
+        assert_eq!(data, &pcm[..]);
+    }
+
+    #[test]
+    fn pcm_wav_zero_data_size_reads_to_end() {
+        let format = WavFormat {
+            sample_rate: 48000,
+            channels: 2,
+            bits_per_sample: 16,
+        };
+        let pcm = vec![7u8; 32];
+        let mut wav = pcm_wav_bytes(format, &pcm);
+        // FFmpeg写入管道时data块大小为0
+        wav[40..44].copy_from_slice(&0u32.to_le_bytes());
+
+        let (parsed, data) = parse_pcm_wav(&wav).expect("应解析为PCM WAV");
+        assert_eq!(parsed.block_align(), 4);
+        assert_eq!(data.len(), 32);
+    }
+
+    #[test]
+    fn non_pcm_wav_is_rejected() {
+        let format = WavFormat {
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+        let mut wav = pcm_wav_bytes(format, &[0; 8]);
+        // 格式3为IEEE浮点
+        wav[20..22].copy_from_slice(&3u16.to_le_bytes());
+        assert!(parse_pcm_wav(&wav).is_none());
+        assert!(parse_pcm_wav(b"ID3\x03not a wav file").is_none());
+    }
+
+    #[test]
+    fn pcm_chunks_cut_at_silence_on_frame_boundaries() {
+        let format = WavFormat {
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+        // 25秒音频，每片最多10秒，9.0~9.5秒有一段静音
+        let pcm_len = 25 * 32000;
+        let max_chunk_bytes = 10 * 32000;
+        let ranges = plan_pcm_chunks(format, pcm_len, max_chunk_bytes, &[(9.0, 9.5)]);
+
+        assert_eq!(ranges[0], (0, (9.25 * 32000.0) as usize));
+        assert_eq!(ranges.last().unwrap().1, pcm_len);
+        for window in ranges.windows(2) {
+            assert_eq!(window[0].1, window[1].0);
+        }
+        for &(start, end) in &ranges {
+            assert_eq!(start % format.block_align(), 0);
+            assert!(end - start <= max_chunk_bytes);
+        }
+    }
+
+    #[test]
+    fn pcm_chunks_without_silence_use_hard_cuts() {
+        let format = WavFormat {
+            sample_rate: 8000,
+            channels: 2,
+            bits_per_sample: 16,
+        };
+        let bytes_per_second = 32000;
+        let ranges = plan_pcm_chunks(format, 5 * bytes_per_second + 6, 2 * bytes_per_second, &[]);
+        assert_eq!(
+            ranges,
+            vec![
+                (0, 2 * bytes_per_second),
+                (2 * bytes_per_second, 4 * bytes_per_second),
+                (4 * bytes_per_second, 5 * bytes_per_second + 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn srt_content_with_crlf_and_missing_indices() {
+        let crlf = "1\r\n00:00:01,000 --> 00:00:02,500\r\n第一行\r\n第二行\r\n\r\n2\r\n01:00:00,000 --> 01:00:01,250\r\nsecond\r\n";
+        let subtitles = parse_srt_content(crlf).unwrap();
+        assert_eq!(subtitles.len(), 2);
+        assert_eq!(subtitles[0].id, "1");
+        assert_eq!(subtitles[0].start_time, 1.0);
+        assert_eq!(subtitles[0].end_time, 2.5);
+        assert_eq!(subtitles[0].text, "第一行\n第二行");
+        assert_eq!(subtitles[1].start_time, 3600.0);
+        assert_eq!(subtitles[1].end_time, 3601.25);
+        assert_eq!(subtitles[1].text, "second");
+
+        let without_indices =
+            "00:00:00,000 --> 00:00:01,000\nfirst\n\n\n00:00:01,000 --> 00:00:02,000\nsecond\n";
+        let subtitles = parse_srt_content(without_indices).unwrap();
+        assert_eq!(subtitles.len(), 2);
+        assert_eq!(subtitles[0].id, "1");
+        assert_eq!(subtitles[0].text, "first");
+        assert_eq!(subtitles[1].id, "2");
+        assert_eq!(subtitles[1].start_time, 1.0);
+        assert_eq!(subtitles[1].text, "second");
+
+        assert!(parse_srt_content("\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn whisper_language_codes_keep_non_chinese_languages() {
+        assert_eq!(whisper_language_code("en").as_deref(), Some("en"));
+        assert_eq!(whisper_language_code("ja-JP").as_deref(), Some("ja"));
+        assert_eq!(whisper_language_code("fr").as_deref(), Some("fr"));
+        assert_eq!(whisper_language_code("zh-CN").as_deref(), Some("zh"));
+        assert_eq!(whisper_language_code("auto"), None);
+        assert_eq!(whisper_language_code(""), None);
+    }
+}
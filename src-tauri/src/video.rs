@@ -1,9 +1,13 @@
+use crate::error::FlowError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
 use std::time::Duration;
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VideoInfo {
@@ -19,6 +23,9 @@ pub struct VideoInfo {
     pub codec_info: String,
     #[serde(rename = "audioTracks")]
     pub audio_tracks: Vec<AudioTrack>,
+    /// 播放时需要顺时针旋转的角度（0/90/180/270，手机拍摄的视频常见）
+    #[serde(default)]
+    pub rotation: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,11 +41,13 @@ pub struct AudioTrack {
     #[serde(rename = "codecInfo")]
     pub codec_info: String,
     pub channels: u32,
+    #[serde(rename = "channelLayout")]
+    pub channel_layout: Option<String>,
     #[serde(rename = "sampleRate")]
     pub sample_rate: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Subtitle {
     pub id: String,
     #[serde(alias = "startTime")]
@@ -46,49 +55,571 @@ pub struct Subtitle {
     #[serde(alias = "endTime")]
     pub end_time: f64,
     pub text: String,
+    /// 屏幕位置，如 "top-left"、"middle"、"bottom-right"（水平方向省略时居中）；None表示使用样式默认位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    /// 单词级时间（强制对齐等提供），None表示只有整句时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<WordTiming>>,
+}
+
+/// 单词的起止时间
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WordTiming {
+    pub word: String,
+    #[serde(alias = "startTime")]
+    pub start_time: f64,
+    #[serde(alias = "endTime")]
+    pub end_time: f64,
+}
+
+/// 字幕导出选项
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExportOptions {
+    /// 保留字幕原有编号（非数字编号时回退为序号）
+    #[serde(default, alias = "preserveIds")]
+    pub preserve_ids: bool,
+    /// 导出帧率：CSV设置时时间码为 HH:MM:SS:FF（否则以秒为单位）；iTT未设置时使用30
+    #[serde(default)]
+    pub fps: Option<f64>,
+    /// 去除文本中的 `<i>`/`<b>`/`<font>` 等HTML样式标签和ASS `{\...}` 覆盖标签
+    /// （TXT/LRC等纯文本格式通常需要开启，SRT/VTT保留标签即可）
+    #[serde(default, alias = "stripTags")]
+    pub strip_tags: bool,
+}
+
+/// 烧录字幕的样式（映射为ASS默认样式，字号和描边以视频像素为单位）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BurnStyle {
+    /// 字体名称，默认Arial（中文字幕需指定系统中存在的中文字体）
+    #[serde(default, alias = "fontName")]
+    pub font_name: Option<String>,
+    /// 字号，默认为视频高度的1/18
+    #[serde(default, alias = "fontSize")]
+    pub font_size: Option<u32>,
+    /// 文字颜色（#RRGGBB），默认白色
+    #[serde(default, alias = "primaryColour", alias = "primaryColor")]
+    pub primary_colour: Option<String>,
+    /// 黑色描边宽度，默认为字号的1/15
+    #[serde(default)]
+    pub outline: Option<f64>,
+}
+
+/// FFmpeg/FFprobe可执行文件路径
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FfmpegPaths {
+    pub ffmpeg: String,
+    pub ffprobe: String,
+}
+
+/// FFmpeg子进程超时时间（秒），超时后结束进程并返回错误
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ProcessTimeouts {
+    /// ffprobe读取视频信息
+    pub probe_secs: u64,
+    /// FFmpeg提取音频（需要读完整个文件，默认值较大）
+    pub extract_secs: u64,
+}
+
+impl Default for ProcessTimeouts {
+    fn default() -> Self {
+        Self {
+            probe_secs: 30,
+            extract_secs: 30 * 60,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// 用户手动配置的FFmpeg路径（优先于自动检测）
+    static ref FFMPEG_PATHS: Mutex<Option<FfmpegPaths>> = Mutex::new(None);
+    /// 应用资源目录（用于查找随应用打包的FFmpeg）
+    static ref RESOURCE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// FFmpeg子进程超时配置
+    static ref PROCESS_TIMEOUTS: Mutex<ProcessTimeouts> = Mutex::new(ProcessTimeouts::default());
+}
+
+/// FFmpeg常见安装目录（图形界面启动的应用通常拿不到shell中的PATH）
+const COMMON_FFMPEG_DIRS: &[&str] = &[
+    "/opt/homebrew/bin",
+    "/usr/local/bin",
+    "/opt/local/bin",
+    "/usr/bin",
+    "/snap/bin",
+    "C:\\ffmpeg\\bin",
+    "C:\\Program Files\\ffmpeg\\bin",
+    "C:\\ProgramData\\chocolatey\\bin",
+];
+
+/// 字幕格式信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormatInfo {
+    pub format: String,
+    pub name: String,
+}
+
+/// 支持的导入/导出字幕格式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupportedFormats {
+    pub import: Vec<FormatInfo>,
+    pub export: Vec<FormatInfo>,
+}
+
+/// 一次导入允许的最大字幕条数
+const MAX_IMPORT_CUES: usize = 100_000;
+
+/// 可导入的字幕格式（扩展名, 显示名称）
+const IMPORT_FORMATS: &[(&str, &str)] = &[
+    ("srt", "SubRip (SRT)"),
+    ("vtt", "WebVTT"),
+    ("csv", "CSV"),
+    ("ttml", "TTML"),
+    ("dfxp", "DFXP (TTML)"),
+    ("itt", "iTunes Timed Text (iTT)"),
+    ("ass", "Advanced SubStation Alpha (ASS)"),
+    ("ssa", "SubStation Alpha (SSA)"),
+];
+
+/// 可导出的字幕格式（格式, 显示名称）
+const EXPORT_FORMATS: &[(&str, &str)] = &[
+    ("srt", "SubRip (SRT)"),
+    ("vtt", "WebVTT"),
+    ("ass", "Advanced SubStation Alpha (ASS)"),
+    ("txt", "纯文本 (TXT)"),
+    ("json", "JSON"),
+    ("csv", "CSV (剪辑软件/表格)"),
+    ("itt", "iTunes Timed Text (iTT)"),
+    ("ttml", "Timed Text Markup Language (TTML)"),
+    ("dfxp", "DFXP (TTML)"),
+    ("sbv", "YouTube SubViewer (SBV)"),
+    ("lrc", "LRC 歌词"),
+];
+
+/// 远程读取超时时间（微秒，FFmpeg -rw_timeout 参数）
+const REMOTE_TIMEOUT_US: &str = "30000000";
+
+/// 获取支持的字幕格式列表
+pub fn get_supported_formats() -> SupportedFormats {
+    let to_infos = |formats: &[(&str, &str)]| {
+        formats
+            .iter()
+            .map(|(format, name)| FormatInfo {
+                format: format.to_string(),
+                name: name.to_string(),
+            })
+            .collect()
+    };
+
+    SupportedFormats {
+        import: to_infos(IMPORT_FORMATS),
+        export: to_infos(EXPORT_FORMATS),
+    }
+}
+
+/// 检查格式是否在支持列表中
+fn is_format_supported(formats: &[(&str, &str)], format: &str) -> bool {
+    formats
+        .iter()
+        .any(|(supported, _)| supported.eq_ignore_ascii_case(format))
+}
+
+/// 解码子进程输出，返回解码后的文本以及是否发生了无效UTF-8字节替换
+pub fn decode_output(bytes: &[u8]) -> (String, bool) {
+    match String::from_utf8_lossy(bytes) {
+        std::borrow::Cow::Borrowed(text) => (text.to_string(), false),
+        std::borrow::Cow::Owned(text) => (text, true),
+    }
+}
+
+/// 设置应用资源目录（应用启动时调用）
+pub fn set_resource_dir(dir: PathBuf) {
+    if let Ok(mut resource_dir) = RESOURCE_DIR.lock() {
+        *resource_dir = Some(dir);
+    }
+}
+
+/// 获取当前的FFmpeg子进程超时配置
+pub fn get_process_timeouts() -> ProcessTimeouts {
+    PROCESS_TIMEOUTS
+        .lock()
+        .map(|timeouts| *timeouts)
+        .unwrap_or_default()
+}
+
+/// 设置FFmpeg子进程超时时间（秒），未指定的项保持不变
+pub fn set_process_timeouts(
+    probe_secs: Option<u64>,
+    extract_secs: Option<u64>,
+) -> Result<ProcessTimeouts, String> {
+    if probe_secs == Some(0) || extract_secs == Some(0) {
+        return Err("超时时间必须大于0秒".to_string());
+    }
+
+    let mut timeouts = PROCESS_TIMEOUTS
+        .lock()
+        .map_err(|_| "无法获取超时配置锁".to_string())?;
+    if let Some(secs) = probe_secs {
+        timeouts.probe_secs = secs;
+    }
+    if let Some(secs) = extract_secs {
+        timeouts.extract_secs = secs;
+    }
+    Ok(*timeouts)
+}
+
+/// 运行子进程并收集输出，超过 `timeout` 仍未结束时结束进程并返回 `Ok(None)`
+fn output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> io::Result<Option<std::process::Output>> {
+    use std::process::Stdio;
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // 后台读取输出，避免管道写满导致子进程阻塞
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(status.map(|status| std::process::Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+/// 在后台线程中读完子进程管道
+fn read_pipe<R: io::Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// 手动设置FFmpeg路径
+///
+/// 未指定ffprobe时使用ffmpeg同目录下的ffprobe；ffmpeg为空时清除配置，恢复自动检测。
+pub fn set_ffmpeg_path(ffmpeg: Option<&str>, ffprobe: Option<&str>) -> Result<FfmpegPaths, String> {
+    let ffmpeg = match ffmpeg.map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        Some(path) => path,
+        None => {
+            if let Ok(mut paths) = FFMPEG_PATHS.lock() {
+                *paths = None;
+            }
+            return Ok(locate_ffmpeg());
+        }
+    };
+
+    if !Path::new(ffmpeg).is_file() {
+        return Err(format!("FFmpeg路径不存在: {}", ffmpeg));
+    }
+
+    let ffprobe = match ffprobe.map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        Some(path) if Path::new(path).is_file() => path.to_string(),
+        Some(path) => return Err(format!("FFprobe路径不存在: {}", path)),
+        None => Path::new(ffmpeg)
+            .with_file_name(format!("ffprobe{}", std::env::consts::EXE_SUFFIX))
+            .to_str()
+            .filter(|p| Path::new(p).is_file())
+            .map(|p| p.to_string())
+            .ok_or_else(|| "未在FFmpeg同目录下找到ffprobe，请单独指定FFprobe路径".to_string())?,
+    };
+
+    let paths = FfmpegPaths {
+        ffmpeg: ffmpeg.to_string(),
+        ffprobe,
+    };
+
+    let mut configured = FFMPEG_PATHS
+        .lock()
+        .map_err(|_| "无法获取FFmpeg配置锁".to_string())?;
+    *configured = Some(paths.clone());
+
+    debug!(
+        "已设置FFmpeg路径: {}, FFprobe路径: {}",
+        paths.ffmpeg, paths.ffprobe
+    );
+    Ok(paths)
+}
+
+/// 解析FFmpeg/FFprobe路径
+///
+/// 查找顺序：用户配置 > 应用打包（资源目录/sidecar） > PATH > 常见安装目录；
+/// 均未找到时返回命令名本身，由系统PATH决定。
+pub fn locate_ffmpeg() -> FfmpegPaths {
+    if let Some(paths) = FFMPEG_PATHS.lock().ok().and_then(|paths| paths.clone()) {
+        return paths;
+    }
+
+    FfmpegPaths {
+        ffmpeg: locate_binary("ffmpeg"),
+        ffprobe: locate_binary("ffprobe"),
+    }
+}
+
+/// 在候选目录中查找可执行文件
+fn locate_binary(name: &str) -> String {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    // 随应用打包的资源文件
+    if let Some(dir) = RESOURCE_DIR.lock().ok().and_then(|dir| dir.clone()) {
+        candidates.push(dir.join(&file_name));
+        candidates.push(dir.join("binaries").join(&file_name));
+    }
+
+    // sidecar与主程序位于同一目录
+    if let Some(dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+    {
+        candidates.push(dir.join(&file_name));
+    }
+
+    if let Some(path_var) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&path_var).map(|dir| dir.join(&file_name)));
+    }
+
+    candidates.extend(
+        COMMON_FFMPEG_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(&file_name)),
+    );
+
+    candidates
+        .into_iter()
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// 创建FFmpeg命令
+fn ffmpeg_command() -> Command {
+    Command::new(locate_ffmpeg().ffmpeg)
+}
+
+/// 创建FFprobe命令
+fn ffprobe_command() -> Command {
+    Command::new(locate_ffmpeg().ffprobe)
+}
+
+/// 运行 `-version` 探测FFmpeg/FFprobe，可执行时返回版本号（如 "6.1.1"）
+fn probe_version(mut command: Command) -> Option<String> {
+    command.arg("-version");
+    let timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let output = output_with_timeout(&mut command, timeout).ok()??;
+    if !output.status.success() {
+        return None;
+    }
+    // 第一行形如 "ffmpeg version 6.1.1 Copyright (c) ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = stdout.lines().next().unwrap_or_default();
+    Some(
+        first_line
+            .split_whitespace()
+            .nth(2)
+            .unwrap_or(first_line)
+            .to_string(),
+    )
+}
+
+/// FFmpeg版本号，FFmpeg不可用时返回None
+pub fn ffmpeg_version() -> Option<String> {
+    probe_version(ffmpeg_command())
+}
+
+/// FFprobe版本号，FFprobe不可用时返回None
+pub fn ffprobe_version() -> Option<String> {
+    probe_version(ffprobe_command())
+}
+
+/// 实时识别采集的PCM采样率（16位单声道）
+pub const LIVE_SAMPLE_RATE: u32 = 16000;
+
+/// 构建实时音频采集命令：FFmpeg将输入转换为16kHz单声道PCM并写到标准输出
+///
+/// `source` 为空或 "mic" 时采集系统默认麦克风，"mic:<设备>" 指定麦克风设备
+/// （Windows下必须指定dshow设备名）；其他值视为流地址（rtmp、http等）或本地文件，
+/// 本地文件按实时速度读取。
+pub fn live_capture_command(source: Option<&str>) -> Result<Command, String> {
+    let source = source.map(|s| s.trim()).filter(|s| !s.is_empty());
+    let mut command = ffmpeg_command();
+    command.arg("-hide_banner").arg("-loglevel").arg("error");
+
+    match source {
+        None | Some("mic") => add_microphone_input(&mut command, None)?,
+        Some(source) if source.starts_with("mic:") => {
+            add_microphone_input(&mut command, Some(&source["mic:".len()..]))?
+        }
+        Some(source) if source.contains("://") => {
+            command.arg("-i").arg(source);
+        }
+        Some(source) => {
+            if !Path::new(source).exists() {
+                return Err(format!("音频来源不存在: {}", source));
+            }
+            command.arg("-re").arg("-i").arg(source);
+        }
+    }
+
+    command
+        .arg("-vn")
+        .arg("-acodec")
+        .arg("pcm_s16le")
+        .arg("-ar")
+        .arg(LIVE_SAMPLE_RATE.to_string())
+        .arg("-ac")
+        .arg("1")
+        .arg("-f")
+        .arg("s16le")
+        .arg("pipe:1");
+
+    Ok(command)
+}
+
+/// 添加系统麦克风输入参数（macOS: avfoundation，Windows: dshow，Linux: pulse）
+fn add_microphone_input(command: &mut Command, device: Option<&str>) -> Result<(), String> {
+    let device = device.map(|d| d.trim()).filter(|d| !d.is_empty());
+    if cfg!(target_os = "macos") {
+        command
+            .arg("-f")
+            .arg("avfoundation")
+            .arg("-i")
+            .arg(format!(":{}", device.unwrap_or("0")));
+    } else if cfg!(target_os = "windows") {
+        let device = device.ok_or_else(|| {
+            "Windows下请指定麦克风设备名，如 mic:麦克风 (Realtek High Definition Audio)".to_string()
+        })?;
+        command
+            .arg("-f")
+            .arg("dshow")
+            .arg("-i")
+            .arg(format!("audio={}", device));
+    } else {
+        command
+            .arg("-f")
+            .arg("pulse")
+            .arg("-i")
+            .arg(device.unwrap_or("default"));
+    }
+    Ok(())
+}
+
+/// 将16位单声道PCM数据写为WAV文件
+pub fn write_pcm_wav(path: &Path, pcm: &[u8], sample_rate: u32) -> Result<(), String> {
+    let data_len = pcm.len() as u32;
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt块大小
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // 单声道
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // 字节率
+    wav.extend_from_slice(&2u16.to_le_bytes()); // 块对齐
+    wav.extend_from_slice(&16u16.to_le_bytes()); // 位深
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend_from_slice(pcm);
+
+    std::fs::write(path, wav).map_err(|e| format!("写入WAV文件失败: {}", e))
 }
 
 /// 获取视频文件信息#[tauri::command]
-pub fn get_video_info(file_path: &str) -> Result<VideoInfo, String> {
+pub fn get_video_info(file_path: &str) -> Result<VideoInfo, FlowError> {
     use serde_json::Value;
 
-    println!("[DEBUG] 开始获取视频信息: {}", file_path);
-
-    // 检查文件是否存在
-    if !std::path::Path::new(file_path).exists() {
-        let error_msg = format!("文件不存在: {}", file_path);
-        println!("[ERROR] {}", error_msg);
-        return Err(error_msg);
+    debug!("开始获取视频信息: {}", file_path);
+
+    // 远程地址校验协议，本地路径检查文件是否存在
+    let remote = is_remote_url(file_path);
+    if remote {
+        validate_remote_url(file_path)?;
+    } else if !std::path::Path::new(file_path).exists() {
+        let error = FlowError::FileNotFound {
+            path: file_path.to_string(),
+        };
+        error!("{}", error);
+        return Err(error);
     }
 
-    println!("[DEBUG] 文件存在，开始执行ffprobe");
+    debug!("文件存在，开始执行ffprobe");
 
-    // 使用ffprobe获取视频信息
-    let output = Command::new("ffprobe")
+    // 使用ffprobe获取视频信息（远程地址保留错误输出以便给出友好提示）
+    let mut command = ffprobe_command();
+    if remote {
+        command.args(["-rw_timeout", REMOTE_TIMEOUT_US]);
+    }
+    command
         .arg("-v")
-        .arg("quiet")
+        .arg(if remote { "error" } else { "quiet" })
         .arg("-print_format")
         .arg("json")
         .arg("-show_format")
         .arg("-show_streams")
-        .arg(file_path)
-        .output()
-        .map_err(|e| format!("执行ffprobe失败: {}", e))?;
+        .arg(file_path);
+    let timeout_secs = get_process_timeouts().probe_secs;
+    let output = output_with_timeout(&mut command, Duration::from_secs(timeout_secs))
+        .map_err(|e| {
+            FlowError::Ffmpeg(format!(
+                "执行ffprobe失败: {}（请在设置中配置FFmpeg路径）",
+                e
+            ))
+        })?
+        .ok_or_else(|| {
+            let error_msg = format!(
+                "获取视频信息超时（{}秒），请检查文件或网络是否可访问",
+                timeout_secs
+            );
+            error!("{}", error_msg);
+            if remote {
+                FlowError::Network(error_msg)
+            } else {
+                FlowError::Ffmpeg(error_msg)
+            }
+        })?;
 
     if !output.status.success() {
-        let error_msg = format!(
-            "ffprobe执行失败: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        println!("[ERROR] {}", error_msg);
-        return Err(error_msg);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = if remote {
+            FlowError::Network(remote_input_error(&stderr))
+        } else {
+            FlowError::Ffmpeg(format!("ffprobe执行失败: {}", stderr))
+        };
+        error!("{}", error);
+        return Err(error);
     }
 
-    println!("[DEBUG] ffprobe执行成功，开始解析输出");
+    debug!("ffprobe执行成功，开始解析输出");
 
     // 解析JSON输出
-    let json_str = String::from_utf8_lossy(&output.stdout);
-    println!("[DEBUG] ffprobe输出长度: {} 字符", json_str.len());
+    let (json_str, replaced) = decode_output(&output.stdout);
+    if replaced {
+        warn!("ffprobe输出包含无效的UTF-8字节，文件名或标签可能显示为乱码（通常是系统区域/编码设置不匹配导致）");
+    }
+    debug!("ffprobe输出长度: {} 字符", json_str.len());
     let json: Value =
         serde_json::from_str(&json_str).map_err(|e| format!("解析ffprobe输出失败: {}", e))?;
 
@@ -107,125 +638,2477 @@ pub fn get_video_info(file_path: &str) -> Result<VideoInfo, String> {
     let mut height = 0;
     let mut frame_rate = 0.0;
     let mut codec_info = String::new();
+    let mut rotation = 0;
     let mut audio_tracks = Vec::new();
 
-    for (index, stream) in streams.iter().enumerate() {
-        let codec_type = stream["codec_type"].as_str().unwrap_or("");
+    for (index, stream) in streams.iter().enumerate() {
+        let codec_type = stream["codec_type"].as_str().unwrap_or("");
+
+        if codec_type == "video" && width == 0 && height == 0 {
+            width = stream["width"].as_i64().unwrap_or(0) as i32;
+            height = stream["height"].as_i64().unwrap_or(0) as i32;
+
+            // 获取帧率
+            if let Some(r_frame_rate) = stream["r_frame_rate"].as_str() {
+                if let Some((num, den)) = r_frame_rate.split_once('/') {
+                    if let (Ok(n), Ok(d)) = (num.parse::<f64>(), den.parse::<f64>()) {
+                        if d != 0.0 {
+                            frame_rate = n / d;
+                        }
+                    }
+                }
+            }
+
+            // 获取编码信息
+            codec_info = stream["codec_name"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string();
+
+            rotation = stream_rotation(stream);
+        } else if codec_type == "audio" {
+            audio_tracks.push(audio_track_from_stream(index as u32, stream));
+        }
+    }
+
+    // 如果没有找到音频轨道，添加一个默认的
+    if audio_tracks.is_empty() {
+        audio_tracks.push(AudioTrack {
+            id: 0,
+            language: Some("und".to_string()),
+            codec_info: "unknown".to_string(),
+            channels: 2,
+            channel_layout: None,
+            sample_rate: 44100,
+        });
+    }
+
+    // 从文件路径（或URL路径的最后一段）提取文件名
+    let file_name = if remote {
+        url::Url::parse(file_path)
+            .ok()
+            .and_then(|u| {
+                u.path_segments()?
+                    .next_back()
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+            })
+            .unwrap_or_else(|| "unknown".to_string())
+    } else {
+        std::path::Path::new(file_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("unknown")
+            .to_string()
+    };
+
+    let video_info = VideoInfo {
+        file_path: file_path.to_string(),
+        file_name: file_name.clone(),
+        duration,
+        resolution: Resolution { width, height },
+        frame_rate,
+        codec_info: codec_info.clone(),
+        audio_tracks,
+        rotation,
+    };
+
+    debug!(
+        "视频信息获取成功: {} ({}x{}, {:.2}s, 旋转{}°)",
+        file_name, width, height, duration, rotation
+    );
+
+    Ok(video_info)
+}
+
+/// 读取视频流的旋转角度，返回播放时需要顺时针旋转的角度（0/90/180/270）
+///
+/// 新版ffprobe在 `side_data_list` 的显示矩阵中给出逆时针角度（如 -90），
+/// 旧版在 `tags.rotate` 中给出顺时针角度（如 "90"）。
+fn stream_rotation(stream: &serde_json::Value) -> i32 {
+    let side_data_rotation = stream["side_data_list"]
+        .as_array()
+        .and_then(|list| list.iter().find_map(|data| data["rotation"].as_f64()))
+        .map(|degrees| -degrees);
+    let tag_rotation = || {
+        stream["tags"]["rotate"]
+            .as_str()
+            .and_then(|s| s.trim().parse::<f64>().ok())
+    };
+
+    side_data_rotation
+        .or_else(tag_rotation)
+        .map(|degrees| ((degrees / 90.0).round() as i32 * 90).rem_euclid(360))
+        .unwrap_or(0)
+}
+
+/// 将画面按 `stream_rotation` 给出的角度转正的视频滤镜，无需旋转时返回 `None`
+fn rotation_filter(rotation: i32) -> Option<&'static str> {
+    match rotation.rem_euclid(360) {
+        90 => Some("transpose=clock"),
+        180 => Some("hflip,vflip"),
+        270 => Some("transpose=cclock"),
+        _ => None,
+    }
+}
+
+/// 截取视频 `time_seconds` 秒处的一帧作为缩略图，按旋转元数据转正画面
+pub fn extract_thumbnail(
+    video_path: &str,
+    time_seconds: f64,
+    output_path: &str,
+) -> Result<String, String> {
+    let info = get_video_info(video_path)?;
+    let time_seconds = if info.duration > 0.0 {
+        time_seconds.clamp(0.0, info.duration)
+    } else {
+        time_seconds.max(0.0)
+    };
+
+    let mut command = ffmpeg_command();
+    command
+        .arg("-hide_banner")
+        .arg("-ss")
+        .arg(format!("{:.3}", time_seconds))
+        .arg("-noautorotate")
+        .arg("-i")
+        .arg(video_path)
+        .arg("-frames:v")
+        .arg("1");
+    if let Some(filter) = rotation_filter(info.rotation) {
+        command.arg("-vf").arg(filter);
+    }
+    command.arg("-y").arg(output_path);
+
+    let timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let output = output_with_timeout(&mut command, timeout)
+        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?
+        .ok_or_else(|| format!("截取缩略图超时（{}秒）", timeout.as_secs()))?;
+    if !output.status.success() {
+        let (stderr, _) = decode_output(&output.stderr);
+        return Err(format!(
+            "截取缩略图失败: {}",
+            stderr.lines().last().unwrap_or("未知错误")
+        ));
+    }
+
+    Ok(output_path.to_string())
+}
+
+/// 提取音频的输出格式，默认16kHz单声道16位PCM WAV（Whisper和各云端引擎通用）
+#[derive(Debug, Clone)]
+pub struct AudioFormat {
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 声道数，单声道时多声道音轨显式混音
+    pub channels: u32,
+    /// 编码：pcm_s16le（WAV）、mp3、flac、aac（M4A）、opus（OGG）
+    pub codec: String,
+}
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        AudioFormat {
+            sample_rate: 16000,
+            channels: 1,
+            codec: "pcm_s16le".to_string(),
+        }
+    }
+}
+
+impl AudioFormat {
+    /// 校验参数，返回FFmpeg编码器和输出文件扩展名
+    fn encoder(&self) -> Result<(&'static str, &'static str), String> {
+        if !(8000..=48000).contains(&self.sample_rate) {
+            return Err(format!("采样率必须在8000到48000之间: {}", self.sample_rate));
+        }
+        if !(1..=2).contains(&self.channels) {
+            return Err(format!("声道数必须为1或2: {}", self.channels));
+        }
+        match self.codec.trim().to_ascii_lowercase().as_str() {
+            "pcm_s16le" | "pcm" | "wav" => Ok(("pcm_s16le", "wav")),
+            "mp3" | "libmp3lame" => Ok(("libmp3lame", "mp3")),
+            "flac" => Ok(("flac", "flac")),
+            "aac" | "m4a" => Ok(("aac", "m4a")),
+            "opus" | "libopus" => Ok(("libopus", "ogg")),
+            codec => Err(format!(
+                "不支持的音频编码: {}（支持pcm_s16le、mp3、flac、aac、opus）",
+                codec
+            )),
+        }
+    }
+
+    /// 输出文件名后缀：默认格式保持 `audio_{音轨}.wav`，其他格式附加采样率和声道数
+    fn file_suffix(&self, audio_track_id: u32, extension: &str) -> String {
+        let default = AudioFormat::default();
+        if self.sample_rate == default.sample_rate
+            && self.channels == default.channels
+            && extension == "wav"
+        {
+            format!("audio_{}.wav", audio_track_id)
+        } else {
+            format!(
+                "audio_{}_{}hz_{}ch.{}",
+                audio_track_id, self.sample_rate, self.channels, extension
+            )
+        }
+    }
+
+    /// 添加编码、采样率和声道参数
+    fn apply(&self, command: &mut Command, encoder: &str) {
+        command
+            .arg("-acodec")
+            .arg(encoder)
+            .arg("-ar")
+            .arg(self.sample_rate.to_string())
+            .arg("-ac")
+            .arg(self.channels.to_string());
+    }
+}
+
+/// 从视频中提取音频（16kHz单声道WAV）到系统临时目录
+///
+/// 支持http(s)地址：由FFmpeg直接读取远程输入。
+pub fn extract_audio(video_path: &str, audio_track_id: u32) -> Result<String, FlowError> {
+    extract_audio_with_format(video_path, audio_track_id, &AudioFormat::default(), false)
+}
+
+/// 按指定格式从视频中提取音频，用于需要特定采样率或编码的识别引擎
+///
+/// 默认写入系统临时目录（文件名以 `flowtext_` 开头，可由 `cleanup_temp_files` 清理），
+/// `beside_video` 为true时写到视频所在目录的 `{文件名}_audio_{音轨}.wav`；远程地址始终写入临时目录。
+pub fn extract_audio_with_format(
+    video_path: &str,
+    audio_track_id: u32,
+    format: &AudioFormat,
+    beside_video: bool,
+) -> Result<String, FlowError> {
+    let (encoder, extension) = format.encoder()?;
+    if is_remote_url(video_path) {
+        return Ok(extract_remote_audio(
+            video_path,
+            audio_track_id,
+            format,
+            encoder,
+            extension,
+        )?);
+    }
+    if !Path::new(video_path).exists() {
+        return Err(FlowError::FileNotFound {
+            path: video_path.to_string(),
+        });
+    }
+
+    // 创建输出文件路径
+    let video_path_obj = Path::new(video_path);
+    let file_stem = video_path_obj
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| "无法获取文件名".to_string())?;
+    let file_name = format!(
+        "{}_{}",
+        file_stem,
+        format.file_suffix(audio_track_id, extension)
+    );
+
+    let output_path = if beside_video {
+        video_path_obj
+            .parent()
+            .ok_or_else(|| "无法获取父目录".to_string())?
+            .join(file_name)
+    } else {
+        std::env::temp_dir().join(format!(
+            "{}{}_{}",
+            TEMP_FILE_PREFIX,
+            &uuid::Uuid::new_v4().simple().to_string()[..8],
+            file_name
+        ))
+    };
+    extract_local_audio(video_path, audio_track_id, format, encoder, &output_path)
+}
+
+/// FlowText在临时目录中创建的文件名前缀
+const TEMP_FILE_PREFIX: &str = "flowtext_";
+
+/// 清理临时音频的默认时长：超过24小时未修改
+pub const DEFAULT_TEMP_FILE_MAX_AGE_SECS: u64 = 24 * 3600;
+
+/// 提取的音频文件扩展名
+const EXTRACTED_AUDIO_EXTENSIONS: &[&str] = &["wav", "mp3", "flac", "m4a", "ogg"];
+
+/// 是否为提取的音频文件（按扩展名判断）
+fn is_extracted_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTRACTED_AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// 删除提取的音频文件
+///
+/// 只允许删除 `extract_audio` 生成的文件（临时目录中的FlowText文件或 `*_audio_*` 音频），
+/// 避免误删用户的其他文件；文件不存在时视为成功。
+pub fn cleanup_extracted_audio(path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("无效的音频路径: {}", path.display()))?;
+    let in_temp = path.parent() == Some(std::env::temp_dir().as_path())
+        && file_name.starts_with(TEMP_FILE_PREFIX);
+    if !is_extracted_audio(path) || !(in_temp || file_name.contains("_audio_")) {
+        return Err(format!("不是提取的音频文件: {}", path.display()));
+    }
+
+    match std::fs::remove_file(path) {
+        Ok(()) => {
+            info!("已删除提取的音频: {}", path.display());
+            Ok(())
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("删除音频文件失败: {}", e)),
+    }
+}
+
+/// 清理临时目录中超过 `max_age_secs` 秒未修改的FlowText音频文件，返回删除的文件数
+///
+/// 按修改时间判断，正在识别的音频会持续被读取但不会被修改，因此不要设置过短的时长。
+pub fn cleanup_temp_files(max_age_secs: u64) -> Result<usize, String> {
+    let temp_dir = std::env::temp_dir();
+    let entries = std::fs::read_dir(&temp_dir).map_err(|e| format!("读取临时目录失败: {}", e))?;
+    let max_age = Duration::from_secs(max_age_secs);
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_flowtext_file = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(TEMP_FILE_PREFIX));
+        if !is_flowtext_file || !is_extracted_audio(&path) {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= max_age);
+        if !expired {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => warn!("删除临时文件失败: {}: {}", path.display(), e),
+        }
+    }
+
+    info!("已清理{}个临时音频文件: {}", removed, temp_dir.display());
+    Ok(removed)
+}
+
+/// 使用FFmpeg将本地视频的音轨提取到 `output_path`
+fn extract_local_audio(
+    video_path: &str,
+    audio_track_id: u32,
+    format: &AudioFormat,
+    encoder: &str,
+    output_path: &Path,
+) -> Result<String, FlowError> {
+    let output_path_str = output_path
+        .to_str()
+        .ok_or_else(|| "输出路径无效".to_string())?;
+
+    // 多声道音轨显式混音为单声道，保证识别引擎拿到干净的人声
+    let mut command = ffmpeg_command();
+    command
+        .arg("-i")
+        .arg(video_path)
+        .arg("-map")
+        .arg(format!("0:{}", audio_track_id));
+    if format.channels == 1 {
+        if let Some(filter) = probe_stream_channels(video_path, audio_track_id)
+            .and_then(|(channels, layout)| downmix_filter(channels, layout.as_deref()))
+        {
+            debug!("音轨{}使用混音滤镜: {}", audio_track_id, filter);
+            command.arg("-af").arg(filter);
+        }
+    }
+
+    // 使用FFmpeg命令行提取音频
+    format.apply(&mut command, encoder);
+    command.arg("-y").arg(output_path_str);
+    let timeout_secs = get_process_timeouts().extract_secs;
+    let output =
+        output_with_timeout(&mut command, Duration::from_secs(timeout_secs)).map_err(|e| {
+            FlowError::Ffmpeg(format!(
+                "执行FFmpeg命令失败: {}（请在设置中配置FFmpeg路径）",
+                e
+            ))
+        })?;
+
+    let Some(output) = output else {
+        let _ = std::fs::remove_file(output_path);
+        return Err(FlowError::Ffmpeg(format!(
+            "提取音频超时（{}秒）",
+            timeout_secs
+        )));
+    };
+    if !output.status.success() {
+        return Err(FlowError::Ffmpeg(format!(
+            "FFmpeg命令执行失败，退出码: {:?}",
+            output.status.code()
+        )));
+    }
+
+    Ok(output_path_str.to_string())
+}
+
+/// 波形默认每秒的峰值桶数
+pub const DEFAULT_WAVEFORM_SAMPLES_PER_SECOND: u32 = 100;
+/// 提取波形使用的采样率（只用于绘制，无需高采样率）
+const WAVEFORM_SAMPLE_RATE: u32 = 8000;
+
+/// 生成音轨波形峰值，返回 [min0, max0, min1, max1, ...]（取值 -1.0~1.0）
+///
+/// 每秒分为 `samples_per_second` 个桶，每桶输出一对最小/最大值。
+/// FFmpeg输出的单声道PCM从标准输出流式读取，长视频也不会生成临时WAV或占用大量内存。
+pub fn generate_waveform(
+    video_path: &str,
+    audio_track_id: u32,
+    samples_per_second: u32,
+) -> Result<Vec<f32>, String> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    if samples_per_second == 0 || samples_per_second > WAVEFORM_SAMPLE_RATE {
+        return Err(format!("每秒采样数必须在1到{}之间", WAVEFORM_SAMPLE_RATE));
+    }
+    let bucket_size = (WAVEFORM_SAMPLE_RATE / samples_per_second) as usize;
+
+    let mut command = ffmpeg_command();
+    command.arg("-hide_banner").arg("-loglevel").arg("error");
+    if is_remote_url(video_path) {
+        command.args(["-rw_timeout", REMOTE_TIMEOUT_US]);
+    }
+    let mut child = command
+        .arg("-i")
+        .arg(video_path)
+        .arg("-map")
+        .arg(format!("0:{}", audio_track_id))
+        .arg("-f")
+        .arg("s16le")
+        .arg("-acodec")
+        .arg("pcm_s16le")
+        .arg("-ar")
+        .arg(WAVEFORM_SAMPLE_RATE.to_string())
+        .arg("-ac")
+        .arg("1")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("执行FFmpeg命令失败: {}（请在设置中配置FFmpeg路径）", e))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "无法读取FFmpeg输出".to_string())?;
+
+    let mut peaks = Vec::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut pending: Option<u8> = None; // 上次读取剩下的半个采样
+    let (mut min, mut max, mut count) = (0i16, 0i16, 0usize);
+    let mut push_sample = |sample: i16, peaks: &mut Vec<f32>| {
+        min = if count == 0 { sample } else { min.min(sample) };
+        max = if count == 0 { sample } else { max.max(sample) };
+        count += 1;
+        if count == bucket_size {
+            peaks.push(min as f32 / i16::MAX as f32);
+            peaks.push(max as f32 / i16::MAX as f32);
+            count = 0;
+        }
+    };
+
+    loop {
+        let read = match stdout.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("读取音频数据失败: {}", e));
+            }
+        };
+        let mut bytes = &buffer[..read];
+        if let Some(low) = pending.take() {
+            push_sample(i16::from_le_bytes([low, bytes[0]]), &mut peaks);
+            bytes = &bytes[1..];
+        }
+        let mut chunks = bytes.chunks_exact(2);
+        for chunk in &mut chunks {
+            push_sample(i16::from_le_bytes([chunk[0], chunk[1]]), &mut peaks);
+        }
+        pending = chunks.remainder().first().copied();
+    }
+    // 最后不足一桶的采样
+    if count > 0 {
+        peaks.push(min as f32 / i16::MAX as f32);
+        peaks.push(max as f32 / i16::MAX as f32);
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("等待FFmpeg进程失败: {}", e))?;
+    if !status.success() {
+        return Err(format!("生成波形失败，退出码: {:?}", status.code()));
+    }
+
+    debug!(
+        "波形生成完成: {} ({}个峰值对，每秒{}个)",
+        crate::logging::redact_url(video_path),
+        peaks.len() / 2,
+        samples_per_second
+    );
+    Ok(peaks)
+}
+
+/// 音量检测的时间窗口长度（秒）
+const AUDIO_PROBE_WINDOW_SECS: f64 = 60.0;
+/// 长音频均匀抽样检测的窗口数
+const AUDIO_PROBE_WINDOWS: usize = 3;
+
+/// 检查音频文件是否包含有效内容
+///
+/// 文件只有WAV头（音轨为空或映射错误）或FFmpeg volumedetect测得的平均音量
+/// 接近数字静音时返回false。长音频只在均匀分布的几个窗口内检测，任一窗口有声即可；
+/// 无法运行FFmpeg或检测超时时不做判断，返回true。
+pub fn audio_has_content(audio_path: &str) -> bool {
+    const MIN_AUDIO_BYTES: u64 = 1024; // 44字节WAV头 + 极短的音频数据
+    const SILENCE_MEAN_DB: f64 = -70.0; // 平均音量低于此值视为无声
+
+    match std::fs::metadata(audio_path) {
+        Ok(metadata) if metadata.len() > MIN_AUDIO_BYTES => {}
+        Ok(metadata) => {
+            warn!("音频文件过小: {} bytes", metadata.len());
+            return false;
+        }
+        Err(e) => {
+            warn!("无法读取音频文件信息: {}", e);
+            return false;
+        }
+    }
+
+    let duration = get_video_info(audio_path)
+        .map(|info| info.duration)
+        .unwrap_or(0.0);
+    let window_starts: Vec<f64> = if duration > AUDIO_PROBE_WINDOW_SECS {
+        (0..AUDIO_PROBE_WINDOWS)
+            .map(|index| duration * index as f64 / AUDIO_PROBE_WINDOWS as f64)
+            .collect()
+    } else {
+        vec![0.0]
+    };
+
+    for start in window_starts {
+        match window_mean_volume(audio_path, start, AUDIO_PROBE_WINDOW_SECS) {
+            Ok(Some(volume)) => {
+                debug!("音频{:.0}秒起的平均音量: {:.1} dB", start, volume);
+                if volume > SILENCE_MEAN_DB {
+                    return true;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("{}，跳过检查", e);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// 用FFmpeg volumedetect测量音频从 `start` 秒起 `length` 秒内的平均音量（dB）
+///
+/// FFmpeg无法运行或超时返回错误；无法解码或窗口内没有采样时返回 `Ok(None)`。
+fn window_mean_volume(audio_path: &str, start: f64, length: f64) -> Result<Option<f64>, String> {
+    let mut command = ffmpeg_command();
+    command
+        .arg("-hide_banner")
+        .arg("-ss")
+        .arg(format!("{:.3}", start))
+        .arg("-t")
+        .arg(format!("{:.3}", length))
+        .arg("-i")
+        .arg(audio_path)
+        .arg("-af")
+        .arg("volumedetect")
+        .arg("-f")
+        .arg("null")
+        .arg("-");
+    let timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let output = output_with_timeout(&mut command, timeout)
+        .map_err(|e| format!("执行FFmpeg音量检测失败: {}", e))?
+        .ok_or_else(|| format!("FFmpeg音量检测超时（{}秒）", timeout.as_secs()))?;
+
+    if !output.status.success() {
+        warn!("FFmpeg无法解码音频文件");
+        return Ok(None);
+    }
+
+    // 没有任何采样时volumedetect不输出统计
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(stderr.lines().find_map(|line| {
+        let rest = &line[line.find("mean_volume:")? + "mean_volume:".len()..];
+        rest.split_whitespace().next()?.parse::<f64>().ok()
+    }))
+}
+
+/// 从ffprobe的音频流信息生成音轨
+fn audio_track_from_stream(id: u32, stream: &serde_json::Value) -> AudioTrack {
+    AudioTrack {
+        id,
+        language: stream["tags"]["language"].as_str().map(|s| s.to_string()),
+        codec_info: stream["codec_name"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string(),
+        channels: stream["channels"]
+            .as_u64()
+            .filter(|c| *c > 0)
+            .map(|c| c as u32)
+            .unwrap_or(2),
+        channel_layout: stream["channel_layout"]
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string()),
+        sample_rate: parse_sample_rate(&stream["sample_rate"]).unwrap_or(44100),
+    }
+}
+
+/// 解析ffprobe中的采样率（兼容 "48000"、"48000.0" 及数字类型）
+fn parse_sample_rate(value: &serde_json::Value) -> Option<u32> {
+    let rate = match value {
+        serde_json::Value::Number(n) => n.as_f64()?,
+        serde_json::Value::String(s) => {
+            let cleaned: String = s
+                .trim()
+                .chars()
+                .filter(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            cleaned.parse::<f64>().ok()?
+        }
+        _ => return None,
+    };
+
+    (rate.is_finite() && rate >= 1.0).then(|| rate.round() as u32)
+}
+
+/// 查询指定音轨的声道数和声道布局
+fn probe_stream_channels(video_path: &str, stream_index: u32) -> Option<(u32, Option<String>)> {
+    let mut command = ffprobe_command();
+    command
+        .arg("-v")
+        .arg("quiet")
+        .arg("-print_format")
+        .arg("json")
+        .arg("-show_entries")
+        .arg("stream=channels,channel_layout")
+        .arg("-select_streams")
+        .arg(stream_index.to_string())
+        .arg(video_path);
+    let timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let output = output_with_timeout(&mut command, timeout).ok()??;
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let stream = json["streams"].as_array()?.first()?;
+    let channels = stream["channels"].as_u64()? as u32;
+    let layout = stream["channel_layout"].as_str().map(|s| s.to_string());
+    Some((channels, layout))
+}
+
+/// 生成多声道混音为单声道的滤镜
+///
+/// 带中置声道的环绕声（5.1/7.1等）以中置（对白）为主；立体声左右平均；
+/// 单声道及其他情况交给 `-ac 1` 处理。
+fn downmix_filter(channels: u32, layout: Option<&str>) -> Option<String> {
+    let has_center = layout.is_some_and(|l| {
+        (l.starts_with("3.") && l != "3.0(back)")
+            || l.starts_with("4.")
+            || l.starts_with("5.")
+            || l.starts_with("6.")
+            || l.starts_with("7.")
+            || l.contains("FC")
+    });
+
+    match channels {
+        0 | 1 => None,
+        2 => Some("pan=mono|c0=0.5*c0+0.5*c1".to_string()),
+        _ if has_center => Some("pan=mono|c0=0.5*FC+0.25*FL+0.25*FR".to_string()),
+        _ => Some("pan=mono|c0=0.5*c0+0.5*c1".to_string()),
+    }
+}
+
+/// 提取视频中一段时间范围的音频到临时WAV文件（16kHz单声道）
+pub fn extract_audio_range(
+    video_path: &str,
+    audio_track_id: u32,
+    start: f64,
+    duration: f64,
+) -> Result<String, String> {
+    if !start.is_finite() || start < 0.0 || !duration.is_finite() || duration <= 0.0 {
+        return Err(format!("无效的时间范围: {}秒起{}秒", start, duration));
+    }
+    if is_remote_url(video_path) {
+        validate_remote_url(video_path)?;
+    }
+
+    let output_path = std::env::temp_dir().join(format!(
+        "flowtext_range_{}_audio_{}.wav",
+        uuid::Uuid::new_v4(),
+        audio_track_id
+    ));
+    let output_path_str = output_path
+        .to_str()
+        .ok_or_else(|| "输出路径无效".to_string())?;
+
+    let mut command = ffmpeg_command();
+    command.arg("-hide_banner").arg("-loglevel").arg("error");
+    if is_remote_url(video_path) {
+        command.args(["-rw_timeout", REMOTE_TIMEOUT_US]);
+    }
+    command
+        .arg("-ss")
+        .arg(format!("{:.3}", start))
+        .arg("-t")
+        .arg(format!("{:.3}", duration))
+        .arg("-i")
+        .arg(video_path)
+        .arg("-map")
+        .arg(format!("0:{}", audio_track_id))
+        .arg("-acodec")
+        .arg("pcm_s16le")
+        .arg("-ar")
+        .arg("16000")
+        .arg("-ac")
+        .arg("1")
+        .arg("-y")
+        .arg(output_path_str);
+    let timeout_secs = get_process_timeouts().extract_secs;
+    let output = output_with_timeout(&mut command, Duration::from_secs(timeout_secs))
+        .map_err(|e| format!("执行FFmpeg命令失败: {}（请在设置中配置FFmpeg路径）", e))?;
+
+    let Some(output) = output else {
+        let _ = std::fs::remove_file(&output_path);
+        return Err(format!("提取音频超时（{}秒）", timeout_secs));
+    };
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&output_path);
+        return Err(format!(
+            "提取音频片段失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output_path_str.to_string())
+}
+
+/// 从远程视频地址提取音频到临时WAV文件
+fn extract_remote_audio(
+    url: &str,
+    audio_track_id: u32,
+    format: &AudioFormat,
+    encoder: &str,
+    extension: &str,
+) -> Result<String, String> {
+    validate_remote_url(url)?;
+
+    let output_path = std::env::temp_dir().join(format!(
+        "flowtext_remote_{}_{}",
+        uuid::Uuid::new_v4(),
+        format.file_suffix(audio_track_id, extension)
+    ));
+    let output_path_str = output_path
+        .to_str()
+        .ok_or_else(|| "输出路径无效".to_string())?;
+
+    debug!(
+        "从远程地址提取音频: {} -> {}",
+        crate::logging::redact_url(url),
+        output_path_str
+    );
+
+    let mut command = ffmpeg_command();
+    command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .args(["-rw_timeout", REMOTE_TIMEOUT_US])
+        .arg("-i")
+        .arg(url)
+        .arg("-map")
+        .arg(format!("0:{}", audio_track_id));
+    format.apply(&mut command, encoder);
+    command.arg("-y").arg(output_path_str);
+    let timeout_secs = get_process_timeouts().extract_secs;
+    let output = output_with_timeout(&mut command, Duration::from_secs(timeout_secs))
+        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+
+    let Some(output) = output else {
+        let _ = std::fs::remove_file(&output_path);
+        return Err(format!("提取音频超时（{}秒）", timeout_secs));
+    };
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&output_path);
+        return Err(remote_input_error(&String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(output_path_str.to_string())
+}
+
+/// 判断输入是否为远程地址
+fn is_remote_url(path: &str) -> bool {
+    let lower = path.trim_start().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// 校验远程地址，仅允许http(s)协议
+fn validate_remote_url(path: &str) -> Result<(), String> {
+    let parsed = url::Url::parse(path.trim()).map_err(|e| format!("无效的URL: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(()),
+        "http" | "https" => Err(format!("URL缺少主机名: {}", path)),
+        scheme => Err(format!("不支持的URL协议: {}（仅支持http/https）", scheme)),
+    }
+}
+
+/// 将FFmpeg读取远程输入时的错误转换为友好提示
+fn remote_input_error(stderr: &str) -> String {
+    let lower = stderr.to_lowercase();
+    if lower.contains("404") {
+        "远程文件不存在 (HTTP 404)，请检查链接是否正确".to_string()
+    } else if lower.contains("403") || lower.contains("401") {
+        "无权访问远程文件 (HTTP 401/403)，链接可能需要登录或已过期".to_string()
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        "连接远程地址超时，请检查网络后重试".to_string()
+    } else if lower.contains("resolve") || lower.contains("name or service not known") {
+        "无法解析远程主机名，请检查链接或网络设置".to_string()
+    } else {
+        let detail = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("未知错误");
+        format!("读取远程文件失败: {}", detail.trim())
+    }
+}
+
+/// 使用FFmpeg silencedetect检测静音区间，返回 (开始, 结束) 秒
+///
+/// 若音频以静音结尾，最后一个区间的结束时间为 `f64::INFINITY`。
+pub fn detect_silence(
+    audio_path: &str,
+    noise_db: f64,
+    min_duration: f64,
+) -> Result<Vec<(f64, f64)>, String> {
+    let output = ffmpeg_command()
+        .arg("-hide_banner")
+        .arg("-i")
+        .arg(audio_path)
+        .arg("-af")
+        .arg(format!(
+            "silencedetect=noise={}dB:d={}",
+            noise_db, min_duration
+        ))
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .output()
+        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "FFmpeg静音检测失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let parse_value = |line: &str, key: &str| -> Option<f64> {
+        let rest = &line[line.find(key)? + key.len()..];
+        rest.split_whitespace().next()?.parse::<f64>().ok()
+    };
+
+    let mut silences = Vec::new();
+    let mut current_start: Option<f64> = None;
+
+    for line in stderr.lines() {
+        if let Some(start) = parse_value(line, "silence_start:") {
+            current_start = Some(start.max(0.0));
+        } else if let Some(end) = parse_value(line, "silence_end:") {
+            if let Some(start) = current_start.take() {
+                silences.push((start, end));
+            }
+        }
+    }
+
+    if let Some(start) = current_start {
+        silences.push((start, f64::INFINITY));
+    }
+
+    Ok(silences)
+}
+
+/// 场景切换检测的默认阈值（FFmpeg scene 分数，0~1）
+pub const DEFAULT_SCENE_THRESHOLD: f64 = 0.4;
+
+/// 使用FFmpeg的scene分数检测镜头切换，返回切换点的时间（秒，升序）
+///
+/// 可用于 `split_at_times` 和章节生成，避免字幕跨越硬切。需要解码整段视频，
+/// 耗时与视频长度相当，受提取音频的超时配置限制。
+pub fn detect_scene_changes(video_path: &str, threshold: f64) -> Result<Vec<f64>, String> {
+    if !(threshold > 0.0 && threshold < 1.0) {
+        return Err(format!("场景切换阈值必须在0到1之间: {}", threshold));
+    }
+    if !is_remote_url(video_path) && !Path::new(video_path).exists() {
+        return Err(format!("文件不存在: {}", video_path));
+    }
+
+    // 先确认存在视频流，纯音频文件给出明确提示
+    let mut probe = ffprobe_command();
+    probe
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-show_entries")
+        .arg("stream=index")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(video_path);
+    let probe_timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let probe_output = output_with_timeout(&mut probe, probe_timeout)
+        .map_err(|e| format!("执行ffprobe失败: {}", e))?
+        .ok_or_else(|| "获取视频信息超时".to_string())?;
+    if String::from_utf8_lossy(&probe_output.stdout)
+        .trim()
+        .is_empty()
+    {
+        return Err("文件中没有视频流，无法检测场景切换".to_string());
+    }
+
+    let mut command = ffmpeg_command();
+    command
+        .arg("-hide_banner")
+        .arg("-i")
+        .arg(video_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-vf")
+        .arg(format!("select='gt(scene,{})',showinfo", threshold))
+        .arg("-an")
+        .arg("-f")
+        .arg("null")
+        .arg("-");
+    let timeout_secs = get_process_timeouts().extract_secs;
+    let output = output_with_timeout(&mut command, Duration::from_secs(timeout_secs))
+        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?
+        .ok_or_else(|| format!("场景切换检测超时（{}秒）", timeout_secs))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!(
+            "FFmpeg场景检测失败: {}",
+            stderr.lines().last().unwrap_or("未知错误")
+        ));
+    }
+
+    // showinfo对每个被选中的帧输出一行，读取其中的 pts_time
+    let mut changes: Vec<f64> = stderr
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| {
+            let rest = &line[line.find("pts_time:")? + "pts_time:".len()..];
+            rest.split_whitespace().next()?.parse::<f64>().ok()
+        })
+        .filter(|time| time.is_finite() && *time > 0.0)
+        .collect();
+    changes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    changes.dedup_by(|a, b| (*a - *b).abs() < 0.001);
+
+    info!("检测到{}处场景切换", changes.len());
+    Ok(changes)
+}
+
+/// 将字幕对齐到音频中的首个语音位置
+///
+/// 未指定 `rebase_to` 时，仅将第一条字幕的开始时间吸附到检测到的语音起点；
+/// 指定时整体平移字幕，使第一条字幕从 `rebase_to` 秒开始。
+pub fn align_to_speech(
+    subtitles: &[Subtitle],
+    audio_path: &str,
+    rebase_to: Option<f64>,
+) -> Result<Vec<Subtitle>, String> {
+    let mut aligned = subtitles.to_vec();
+
+    let first_index = match aligned
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.start_time
+                .partial_cmp(&b.start_time)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|(index, _)| index)
+    {
+        Some(index) => index,
+        None => return Ok(aligned),
+    };
+
+    if let Some(target) = rebase_to {
+        let delta = target.max(0.0) - aligned[first_index].start_time;
+        for subtitle in &mut aligned {
+            subtitle.start_time = (subtitle.start_time + delta).max(0.0);
+            subtitle.end_time = (subtitle.end_time + delta).max(0.0);
+        }
+        return Ok(aligned);
+    }
+
+    // 开头的静音区间结束处即为语音起点
+    let speech_onset = detect_silence(audio_path, -30.0, 0.5)?
+        .first()
+        .filter(|(start, end)| *start <= 0.05 && end.is_finite())
+        .map(|(_, end)| *end)
+        .unwrap_or(0.0);
+
+    let first = &mut aligned[first_index];
+    if speech_onset < first.end_time {
+        debug!(
+            "首条字幕开始时间 {:.3}s 对齐到语音起点 {:.3}s",
+            first.start_time, speech_onset
+        );
+        first.start_time = speech_onset;
+    }
+
+    Ok(aligned)
+}
+
+/// 批量查找替换字幕文本，返回更新后的字幕和替换次数
+pub fn find_replace(
+    subtitles: &[Subtitle],
+    pattern: &str,
+    replacement: &str,
+    use_regex: bool,
+    case_insensitive: bool,
+) -> Result<(Vec<Subtitle>, usize), String> {
+    if pattern.is_empty() {
+        return Err("查找内容不能为空".to_string());
+    }
+
+    let source = if use_regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    let re = regex::RegexBuilder::new(&source)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| format!("无效的正则表达式: {}", e))?;
+
+    let mut count = 0;
+    let updated = subtitles
+        .iter()
+        .map(|subtitle| {
+            let matches = re.find_iter(&subtitle.text).count();
+            if matches == 0 {
+                return subtitle.clone();
+            }
+            count += matches;
+
+            // 非正则模式下替换内容按字面处理（不展开 $1 等引用）
+            let text = if use_regex {
+                re.replace_all(&subtitle.text, replacement).to_string()
+            } else {
+                re.replace_all(&subtitle.text, regex::NoExpand(replacement))
+                    .to_string()
+            };
+
+            Subtitle {
+                text,
+                ..subtitle.clone()
+            }
+        })
+        .collect();
+
+    Ok((updated, count))
+}
+
+/// 时间轴修复结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NormalizeResult {
+    /// 修复后的字幕（按开始时间排序）
+    pub subtitles: Vec<Subtitle>,
+    /// 修复次数（交换、截断、丢弃各计一次）
+    pub repairs: usize,
+}
+
+/// 修复导入字幕中异常的时间轴
+///
+/// 结束时间早于开始时间的字幕交换两者；与下一条字幕重叠的字幕把结束时间截断到
+/// 下一条的开始时间；修复后时长为0的字幕丢弃。
+pub fn normalize_subtitles(subtitles: &[Subtitle]) -> NormalizeResult {
+    let mut repairs = 0;
+    let mut sorted = subtitles.to_vec();
+    for subtitle in &mut sorted {
+        if subtitle.end_time < subtitle.start_time {
+            std::mem::swap(&mut subtitle.start_time, &mut subtitle.end_time);
+            repairs += 1;
+        }
+    }
+    sorted.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let next_starts: Vec<Option<f64>> = (0..sorted.len())
+        .map(|index| sorted.get(index + 1).map(|next| next.start_time))
+        .collect();
+
+    let normalized: Vec<Subtitle> = sorted
+        .into_iter()
+        .zip(next_starts)
+        .filter_map(|(mut subtitle, next_start)| {
+            if let Some(next_start) = next_start {
+                if subtitle.end_time > next_start {
+                    subtitle.end_time = next_start;
+                    repairs += 1;
+                }
+            }
+            if subtitle.end_time <= subtitle.start_time {
+                repairs += 1;
+                return None;
+            }
+            Some(subtitle)
+        })
+        .collect();
+
+    if repairs > 0 {
+        info!(
+            "修复字幕时间轴: {}处，{}条 -> {}条",
+            repairs,
+            subtitles.len(),
+            normalized.len()
+        );
+    }
+    NormalizeResult {
+        subtitles: normalized,
+        repairs,
+    }
+}
+
+/// 延长过短的字幕，使每条字幕至少显示 `min_duration` 秒
+///
+/// 过短的字幕向后延长结束时间，但不超过下一条字幕的开始时间；
+/// 下一条字幕与其同时或更早开始（没有延长空间）时丢弃该条。结果按开始时间排序。
+pub fn enforce_min_duration(
+    subtitles: &[Subtitle],
+    min_duration: f64,
+) -> Result<Vec<Subtitle>, String> {
+    if !min_duration.is_finite() || min_duration <= 0.0 {
+        return Err(format!("最短显示时长必须大于0秒: {}", min_duration));
+    }
+
+    let mut sorted = subtitles.to_vec();
+    sorted.sort_by(|a, b| {
+        a.start_time
+            .partial_cmp(&b.start_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let next_starts: Vec<Option<f64>> = (0..sorted.len())
+        .map(|index| sorted.get(index + 1).map(|next| next.start_time))
+        .collect();
+
+    Ok(sorted
+        .into_iter()
+        .zip(next_starts)
+        .filter_map(|(mut subtitle, next_start)| {
+            if subtitle.end_time - subtitle.start_time >= min_duration {
+                return Some(subtitle);
+            }
+
+            let mut end_time = subtitle.start_time + min_duration;
+            if let Some(next_start) = next_start {
+                if next_start <= subtitle.start_time {
+                    return None;
+                }
+                end_time = end_time.min(next_start);
+            }
+            subtitle.end_time = subtitle.end_time.max(end_time);
+            Some(subtitle)
+        })
+        .collect())
+}
+
+/// 将字幕限制在视频时长内
+///
+/// 结束时间超过 `duration` 的字幕截断到 `duration`，开始时间不早于 `duration` 的字幕丢弃。
+/// `duration` 无效（非正数或非有限值，通常是时长未知）时原样返回。
+pub fn clamp_to_duration(subtitles: &[Subtitle], duration: f64) -> Vec<Subtitle> {
+    if !duration.is_finite() || duration <= 0.0 {
+        return subtitles.to_vec();
+    }
+
+    subtitles
+        .iter()
+        .filter(|subtitle| subtitle.start_time < duration)
+        .map(|subtitle| Subtitle {
+            end_time: subtitle.end_time.min(duration),
+            ..subtitle.clone()
+        })
+        .collect()
+}
+
+/// 整体平移字幕时间（`offset_seconds` 为负时提前）
+///
+/// 平移后开始时间早于0的字幕截断到0；结束时间不晚于0的字幕整条丢弃，避免产生倒置的时间范围。
+/// 单词级时间同步平移。
+pub fn shift_subtitles(
+    subtitles: &[Subtitle],
+    offset_seconds: f64,
+) -> Result<Vec<Subtitle>, String> {
+    if !offset_seconds.is_finite() {
+        return Err("平移时间无效".to_string());
+    }
+
+    let shifted = subtitles
+        .iter()
+        .filter(|subtitle| subtitle.end_time + offset_seconds > 0.0)
+        .map(|subtitle| Subtitle {
+            start_time: (subtitle.start_time + offset_seconds).max(0.0),
+            end_time: subtitle.end_time + offset_seconds,
+            words: subtitle.words.as_ref().map(|words| {
+                words
+                    .iter()
+                    .filter(|word| word.end_time + offset_seconds > 0.0)
+                    .map(|word| WordTiming {
+                        start_time: (word.start_time + offset_seconds).max(0.0),
+                        end_time: word.end_time + offset_seconds,
+                        ..word.clone()
+                    })
+                    .collect()
+            }),
+            ..subtitle.clone()
+        })
+        .collect::<Vec<_>>();
+
+    info!(
+        "字幕整体平移{:+.3}秒，丢弃{}条",
+        offset_seconds,
+        subtitles.len() - shifted.len()
+    );
+    Ok(shifted)
+}
+
+/// 超出行宽限制的字幕
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LineLengthIssue {
+    pub id: String,
+    /// 超限行中最宽一行的显示宽度
+    pub width: usize,
+    pub limit: usize,
+    /// 按该行文字判定的语言代码
+    pub language: String,
+}
+
+/// 是否为占两个显示单元的全角字符（中日韩文字、假名、谚文及全角符号）
+fn is_wide_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD)
+}
+
+/// 计算文本的显示宽度：全角字符计2，其余计1
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if is_wide_char(c) { 2 } else { 1 })
+        .sum()
+}
+
+/// 按文字判断一行字幕的语言：含假名为日语，含谚文为韩语，含汉字为中文，否则为英语
+fn detect_line_language(line: &str) -> &'static str {
+    let has =
+        |range: std::ops::RangeInclusive<u32>| line.chars().any(|c| range.contains(&(c as u32)));
+    if has(0x3040..=0x30FF) {
+        "ja"
+    } else if has(0xAC00..=0xD7A3) || has(0x1100..=0x11FF) || has(0x3130..=0x318F) {
+        "ko"
+    } else if has(0x4E00..=0x9FFF) || has(0x3400..=0x4DBF) {
+        "zh"
+    } else {
+        "en"
+    }
+}
+
+/// 查找语言对应的行宽限制（"zh-CN" 等带地区的键按主语言匹配），未配置时使用 "default"
+fn line_length_limit(limits: &HashMap<String, usize>, language: &str) -> Option<usize> {
+    let matches = |key: &str| {
+        key.to_lowercase()
+            .split(['-', '_'])
+            .next()
+            .is_some_and(|base| base == language)
+    };
+    limits
+        .iter()
+        .find(|(key, _)| matches(key))
+        .or_else(|| limits.get_key_value("default"))
+        .map(|(_, limit)| *limit)
+}
+
+/// 检查字幕每行的显示宽度是否超出对应语言的限制，返回超限字幕的ID和实测宽度
+///
+/// 每行单独判定语言（双语字幕的两行可分别适用不同限制），全角字符计2个单元。
+pub fn check_line_lengths(
+    subtitles: &[Subtitle],
+    limits: &HashMap<String, usize>,
+) -> Vec<LineLengthIssue> {
+    subtitles
+        .iter()
+        .filter_map(|subtitle| {
+            subtitle
+                .text
+                .lines()
+                .filter_map(|line| {
+                    let language = detect_line_language(line);
+                    let limit = line_length_limit(limits, language)?;
+                    let width = display_width(line.trim());
+                    (width > limit).then(|| LineLengthIssue {
+                        id: subtitle.id.clone(),
+                        width,
+                        limit,
+                        language: language.to_string(),
+                    })
+                })
+                .max_by_key(|issue| issue.width)
+        })
+        .collect()
+}
+
+/// 把一行文字切分为换行单元：中日韩文字逐字切分，其余按空白切分
+fn wrap_tokens(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in line.chars() {
+        if c.is_whitespace() || is_wide_char(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// 拼接换行单元时使用的分隔符：中日韩文字之间不加空格
+fn token_separator(line: &str, token: &str) -> &'static str {
+    let wide_join = line.chars().last().is_some_and(is_wide_char)
+        || token.chars().next().is_some_and(is_wide_char);
+    if line.is_empty() || wide_join {
+        ""
+    } else {
+        " "
+    }
+}
+
+/// 按显示宽度重新折行，每行不超过 `max_width`（单个超长单词独占一行）
+fn wrap_text(text: &str, max_width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for tokens in text.lines().map(wrap_tokens) {
+        let mut line = String::new();
+        for token in tokens {
+            let separator = token_separator(&line, &token);
+            if !line.is_empty()
+                && display_width(&line) + display_width(separator) + display_width(&token)
+                    > max_width
+            {
+                lines.push(std::mem::take(&mut line));
+            } else {
+                line.push_str(separator);
+            }
+            line.push_str(&token);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// 是否为适合在其后断行的标点
+fn is_break_punctuation(token: &str) -> bool {
+    token.ends_with([
+        '，', '。', '、', '！', '？', '；', '：', ',', '.', '!', '?', ';', ':',
+    ])
+}
+
+/// 将文本（忽略原有换行）重排为每行不超过 `max_width` 的多行
+///
+/// 行满时优先在该行后半段最后一个标点之后断开，没有合适的标点时在词边界（中日韩文字为字边界）断开。
+fn reflow_lines(text: &str, max_width: usize) -> Vec<String> {
+    let join = |tokens: &[String]| {
+        tokens.iter().fold(String::new(), |mut line, token| {
+            line.push_str(token_separator(&line, token));
+            line.push_str(token);
+            line
+        })
+    };
+
+    let mut lines = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for token in text.lines().flat_map(wrap_tokens) {
+        let mut candidate = current.clone();
+        candidate.push(token.clone());
+        if current.is_empty() || display_width(&join(&candidate)) <= max_width {
+            current = candidate;
+            continue;
+        }
+
+        let break_at = (current.len() / 2..current.len())
+            .rev()
+            .find(|&i| is_break_punctuation(&current[i]));
+        let mut carry = match break_at {
+            Some(i) if i + 1 < current.len() => current.split_off(i + 1),
+            _ => Vec::new(),
+        };
+        lines.push(join(&current));
+        carry.push(token);
+        if carry.len() > 1 && display_width(&join(&carry)) > max_width {
+            let token = carry.pop().unwrap_or_default();
+            lines.push(join(&carry));
+            carry = vec![token];
+        }
+        current = carry;
+    }
+    if !current.is_empty() {
+        lines.push(join(&current));
+    }
+    lines
+}
+
+/// 将超出行宽的字幕按显示宽度重新折行，未超限的字幕保持原样
+pub fn wrap_subtitles(subtitles: &[Subtitle], max_width: usize) -> Result<Vec<Subtitle>, String> {
+    if max_width == 0 {
+        return Err("每行最大宽度必须大于0".to_string());
+    }
+
+    Ok(subtitles
+        .iter()
+        .map(|subtitle| {
+            if subtitle
+                .text
+                .lines()
+                .all(|line| display_width(line.trim()) <= max_width)
+            {
+                return subtitle.clone();
+            }
+            Subtitle {
+                text: wrap_text(&subtitle.text, max_width),
+                ..subtitle.clone()
+            }
+        })
+        .collect())
+}
+
+/// 拆分过长的字幕：文本重排为每行不超过 `max_chars`（全角字符计2）后，每 `max_lines` 行成为一条字幕
+///
+/// 拆出的字幕按可见字符数比例分配原字幕的时长，拆分后的字幕不保留单词级时间。
+/// 未超出限制的字幕保持原样；返回的字幕按原顺序重新编号。
+pub fn split_long_subtitles(
+    subtitles: &[Subtitle],
+    max_chars: usize,
+    max_lines: usize,
+) -> Result<Vec<Subtitle>, String> {
+    if max_chars == 0 || max_lines == 0 {
+        return Err("每行最大字符数和最大行数必须大于0".to_string());
+    }
+
+    let visible_chars = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+    let mut result = Vec::new();
+    for subtitle in subtitles {
+        let fits = subtitle.text.lines().count() <= max_lines
+            && subtitle
+                .text
+                .lines()
+                .all(|line| display_width(line.trim()) <= max_chars);
+        if fits {
+            result.push(subtitle.clone());
+            continue;
+        }
+
+        let lines = reflow_lines(&subtitle.text, max_chars);
+        let chunks: Vec<String> = lines.chunks(max_lines).map(|c| c.join("\n")).collect();
+        if chunks.len() <= 1 {
+            result.push(Subtitle {
+                text: chunks.into_iter().next().unwrap_or_default(),
+                ..subtitle.clone()
+            });
+            continue;
+        }
+
+        let total_chars: usize = chunks.iter().map(|c| visible_chars(c)).sum();
+        let duration = (subtitle.end_time - subtitle.start_time).max(0.0);
+        let mut start_time = subtitle.start_time;
+        let mut counted = 0;
+        for (index, text) in chunks.iter().enumerate() {
+            counted += visible_chars(text);
+            // 最后一段直接使用原结束时间，避免浮点误差
+            let end_time = if index + 1 == chunks.len() {
+                subtitle.end_time
+            } else {
+                subtitle.start_time + duration * counted as f64 / total_chars.max(1) as f64
+            };
+            result.push(Subtitle {
+                id: String::new(),
+                start_time,
+                end_time,
+                text: text.clone(),
+                position: subtitle.position.clone(),
+                words: None,
+            });
+            start_time = end_time;
+        }
+    }
+
+    for (index, subtitle) in result.iter_mut().enumerate() {
+        subtitle.id = (index + 1).to_string();
+    }
+    info!("拆分过长字幕: {}条 -> {}条", subtitles.len(), result.len());
+    Ok(result)
+}
+
+/// 合并零碎的短字幕：相邻字幕间隔小于 `max_gap_seconds` 且合并后文本宽度不超过
+/// `max_merged_chars`（全角字符计2）时合并为一条
+///
+/// 合并后的字幕从第一条的开始时间持续到最后一条的结束时间，文本连接为一行
+/// （拉丁文字之间加空格，中日韩文字直接相连）。位置不同的字幕不合并；返回的字幕重新编号。
+pub fn merge_subtitles(
+    subtitles: &[Subtitle],
+    max_gap_seconds: f64,
+    max_merged_chars: usize,
+) -> Result<Vec<Subtitle>, String> {
+    if !max_gap_seconds.is_finite() || max_gap_seconds < 0.0 {
+        return Err("最大间隔必须是非负数".to_string());
+    }
+    if max_merged_chars == 0 {
+        return Err("合并后最大字符数必须大于0".to_string());
+    }
+
+    let single_line = |text: &str| {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .fold(String::new(), |mut joined, line| {
+                joined.push_str(token_separator(&joined, line));
+                joined.push_str(line);
+                joined
+            })
+    };
+
+    let mut merged: Vec<Subtitle> = Vec::new();
+    for subtitle in subtitles {
+        if let Some(previous) = merged.last_mut() {
+            let gap = subtitle.start_time - previous.end_time;
+            let text = single_line(&format!("{}\n{}", previous.text, subtitle.text));
+            if gap < max_gap_seconds
+                && previous.position == subtitle.position
+                && display_width(&text) <= max_merged_chars
+            {
+                previous.end_time = previous.end_time.max(subtitle.end_time);
+                previous.text = text;
+                previous.words = match (previous.words.take(), &subtitle.words) {
+                    (Some(mut words), Some(next)) => {
+                        words.extend(next.iter().cloned());
+                        Some(words)
+                    }
+                    _ => None,
+                };
+                continue;
+            }
+        }
+        merged.push(subtitle.clone());
+    }
+
+    for (index, subtitle) in merged.iter_mut().enumerate() {
+        subtitle.id = (index + 1).to_string();
+    }
+    info!("合并短字幕: {}条 -> {}条", subtitles.len(), merged.len());
+    Ok(merged)
+}
+
+/// 阅读速度超限的字幕
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReadingSpeedIssue {
+    pub id: String,
+    /// 实测阅读速度（字符/秒，不计空白）
+    pub cps: f64,
+    pub limit: f64,
+}
+
+/// 检查每条字幕的阅读速度（字符/秒）是否超过 `max_cps`
+pub fn check_reading_speed(subtitles: &[Subtitle], max_cps: f64) -> Vec<ReadingSpeedIssue> {
+    subtitles
+        .iter()
+        .filter_map(|subtitle| {
+            let duration = subtitle.end_time - subtitle.start_time;
+            let chars = subtitle.text.chars().filter(|c| !c.is_whitespace()).count();
+            if chars == 0 {
+                return None;
+            }
+            let cps = if duration > 0.0 {
+                chars as f64 / duration
+            } else {
+                f64::INFINITY
+            };
+            (cps > max_cps).then(|| ReadingSpeedIssue {
+                id: subtitle.id.clone(),
+                cps,
+                limit: max_cps,
+            })
+        })
+        .collect()
+}
+
+/// 两组字幕之间的一处差异
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubtitleDiff {
+    /// 差异类型："added"、"removed"、"text_changed"、"timing_changed"
+    pub kind: String,
+    /// 原字幕（新增时为None）
+    pub before: Option<Subtitle>,
+    /// 新字幕（删除时为None）
+    pub after: Option<Subtitle>,
+    /// 文本相似度（0~1，仅配对的字幕有值）
+    pub similarity: Option<f64>,
+}
+
+/// 时间差在此范围内（秒）视为时间未改动
+const DIFF_TIME_TOLERANCE: f64 = 0.01;
+/// 配对字幕文本相似度低于此值时视为删除后新增，而不是修改
+const DIFF_MIN_SIMILARITY: f64 = 0.3;
+
+/// 计算两个字符串的编辑距离（按字符）
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 文本相似度：1减去编辑距离与较长文本长度之比
+fn text_similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// 比较两组字幕（如机器识别稿与校对稿），返回差异列表
+///
+/// 按时间重叠配对：每条原字幕与重叠时长最长的未配对新字幕配对（重叠相同时取文本更相似的）。
+/// 配对后文本不同为 text_changed（文本相似度过低时拆成 removed + added），
+/// 仅时间不同为 timing_changed；未配对的分别为 removed 和 added。未改动的字幕不输出。
+pub fn diff_subtitles(before: &[Subtitle], after: &[Subtitle]) -> Vec<SubtitleDiff> {
+    let by_start = |subtitles: &[Subtitle]| {
+        let mut sorted = subtitles.to_vec();
+        sorted.sort_by(|a, b| {
+            a.start_time
+                .partial_cmp(&b.start_time)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        sorted
+    };
+    let before = by_start(before);
+    let after = by_start(after);
+
+    let mut matched = vec![false; after.len()];
+    let mut diffs: Vec<(f64, SubtitleDiff)> = Vec::new();
+    let diff = |kind: &str, before: Option<&Subtitle>, after: Option<&Subtitle>, similarity| {
+        SubtitleDiff {
+            kind: kind.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+            similarity,
+        }
+    };
+
+    for old in &before {
+        let best = after
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !matched[*index])
+            .map(|(index, new)| {
+                let overlap = old.end_time.min(new.end_time) - old.start_time.max(new.start_time);
+                (index, overlap, text_similarity(&old.text, &new.text))
+            })
+            .filter(|(_, overlap, _)| *overlap > 0.0)
+            .max_by(|a, b| {
+                a.1.partial_cmp(&b.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+            });
+
+        let Some((index, _, similarity)) = best else {
+            diffs.push((old.start_time, diff("removed", Some(old), None, None)));
+            continue;
+        };
+        let new = &after[index];
+        if old.text != new.text && similarity < DIFF_MIN_SIMILARITY {
+            diffs.push((old.start_time, diff("removed", Some(old), None, None)));
+            continue;
+        }
+        matched[index] = true;
+
+        let timing_changed = (old.start_time - new.start_time).abs() > DIFF_TIME_TOLERANCE
+            || (old.end_time - new.end_time).abs() > DIFF_TIME_TOLERANCE;
+        let kind = if old.text != new.text {
+            "text_changed"
+        } else if timing_changed {
+            "timing_changed"
+        } else {
+            continue;
+        };
+        diffs.push((
+            old.start_time.min(new.start_time),
+            diff(kind, Some(old), Some(new), Some(similarity)),
+        ));
+    }
+
+    for (index, new) in after.iter().enumerate() {
+        if !matched[index] {
+            diffs.push((new.start_time, diff("added", None, Some(new), None)));
+        }
+    }
+
+    diffs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    diffs.into_iter().map(|(_, diff)| diff).collect()
+}
+
+/// 按字幕间隔分章，生成FFMETADATA1格式的章节文件内容
+///
+/// 相邻字幕间隔超过 `gap_threshold` 秒时开始新章节，章节标题取该章第一条字幕的文本。
+pub fn export_chapters(subtitles: &[Subtitle], gap_threshold: f64) -> String {
+    let mut sorted = subtitles.to_vec();
+    sorted.sort_by(|a, b| {
+        a.start_time
+            .partial_cmp(&b.start_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // 分组：(开始, 结束, 标题)
+    let mut chapters: Vec<(f64, f64, String)> = Vec::new();
+    for subtitle in &sorted {
+        match chapters.last_mut() {
+            Some(chapter) if subtitle.start_time - chapter.1 <= gap_threshold => {
+                chapter.1 = chapter.1.max(subtitle.end_time);
+            }
+            _ => chapters.push((
+                subtitle.start_time,
+                subtitle.end_time,
+                subtitle
+                    .text
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+            )),
+        }
+    }
+
+    let mut content = String::from(";FFMETADATA1\n");
+    for (index, (start, end, title)) in chapters.iter().enumerate() {
+        // 章节连续：除最后一章外，结束时间延伸到下一章开始
+        let end = chapters.get(index + 1).map(|next| next.0).unwrap_or(*end);
+        let title = if title.is_empty() {
+            format!("第{}章", index + 1)
+        } else {
+            title.clone()
+        };
+
+        content.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        content.push_str(&format!("START={}\n", (start * 1000.0).round() as u64));
+        content.push_str(&format!("END={}\n", (end * 1000.0).round() as u64));
+        content.push_str(&format!("title={}\n", escape_ffmetadata(&title)));
+    }
+
+    content
+}
+
+/// 写入章节文件；若提供视频路径则同时封装进视频，返回最终输出路径
+pub fn export_chapters_to_path(
+    subtitles: &[Subtitle],
+    gap_threshold: f64,
+    output_path: &str,
+    video_path: Option<&str>,
+) -> Result<String, String> {
+    let metadata = export_chapters(subtitles, gap_threshold);
+    std::fs::write(output_path, metadata).map_err(|e| format!("写入章节文件失败: {}", e))?;
+
+    match video_path {
+        Some(video_path) => mux_chapters(video_path, output_path),
+        None => Ok(output_path.to_string()),
+    }
+}
+
+/// 使用FFmpeg将章节文件写入视频（不重新编码），返回输出视频路径
+fn mux_chapters(video_path: &str, metadata_path: &str) -> Result<String, String> {
+    let video_path_obj = Path::new(video_path);
+    let file_stem = video_path_obj
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| "无法获取文件名".to_string())?;
+    let extension = video_path_obj
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("mp4");
+
+    let output_dir = video_path_obj
+        .parent()
+        .ok_or_else(|| "无法获取父目录".to_string())?;
+
+    let output_path = output_dir.join(format!("{}_chapters.{}", file_stem, extension));
+    let output_path_str = output_path
+        .to_str()
+        .ok_or_else(|| "输出路径无效".to_string())?;
+
+    let status = ffmpeg_command()
+        .arg("-i")
+        .arg(video_path)
+        .arg("-i")
+        .arg(metadata_path)
+        .arg("-map_metadata")
+        .arg("1")
+        .arg("-map_chapters")
+        .arg("1")
+        .arg("-map")
+        .arg("0")
+        .arg("-codec")
+        .arg("copy")
+        .arg("-y")
+        .arg(output_path_str)
+        .status()
+        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("FFmpeg命令执行失败，退出码: {:?}", status.code()));
+    }
+
+    Ok(output_path_str.to_string())
+}
+
+/// 将字幕作为可开关的软字幕轨封装进视频（不重新编码），返回输出路径
+///
+/// 容器由输出文件扩展名决定：MP4/MOV/M4V仅支持mov_text，MKV支持srt（默认）和ass。
+/// `lang_code` 为ISO 639语言代码，两字母代码会转换为容器要求的三字母代码。
+pub fn mux_subtitles(
+    video_path: &str,
+    subtitles: &[Subtitle],
+    output_path: &str,
+    lang_code: Option<&str>,
+    subtitle_codec: Option<&str>,
+) -> Result<String, String> {
+    if subtitles.is_empty() {
+        return Err("没有可封装的字幕".to_string());
+    }
+    if Path::new(output_path) == Path::new(video_path) {
+        return Err("输出路径不能与源视频相同".to_string());
+    }
+
+    let container = Path::new(output_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .ok_or_else(|| "输出路径缺少扩展名".to_string())?;
+    let codec = soft_subtitle_codec(&container, subtitle_codec)?;
+
+    // 字幕先写入临时文件，ass编码使用ASS源文件以保留样式
+    let temp_path = std::env::temp_dir().join(format!(
+        "flowtext_mux_{}.{}",
+        uuid::Uuid::new_v4(),
+        if codec == "ass" { "ass" } else { "srt" }
+    ));
+    let temp_path_str = temp_path.to_string_lossy().to_string();
+    if codec == "ass" {
+        export_ass_to_path(subtitles, &temp_path_str)?;
+    } else {
+        export_srt_to_path(subtitles, &temp_path_str, &ExportOptions::default())?;
+    }
+
+    let mut command = ffmpeg_command();
+    command
+        .arg("-i")
+        .arg(video_path)
+        .arg("-i")
+        .arg(&temp_path_str)
+        .arg("-map")
+        .arg("0:v?")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-map")
+        .arg("1:0")
+        .arg("-c")
+        .arg("copy")
+        .arg("-c:s")
+        .arg(codec);
+    if let Some(lang) = lang_code.map(str::trim).filter(|l| !l.is_empty()) {
+        command
+            .arg("-metadata:s:s:0")
+            .arg(format!("language={}", iso639_2_code(lang)));
+    }
+    let output = command.arg("-y").arg(output_path).output();
+    let _ = std::fs::remove_file(&temp_path);
+
+    let output = output.map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+    if !output.status.success() {
+        let (stderr, _) = decode_output(&output.stderr);
+        return Err(format!(
+            "封装字幕失败: {}",
+            stderr.lines().last().unwrap_or("未知错误")
+        ));
+    }
+
+    Ok(output_path.to_string())
+}
+
+/// 校验容器与字幕编码的组合，返回FFmpeg字幕编码名
+fn soft_subtitle_codec(container: &str, requested: Option<&str>) -> Result<&'static str, String> {
+    let requested = requested.map(|c| c.trim().to_lowercase());
+    match container {
+        "mp4" | "m4v" | "mov" => match requested.as_deref() {
+            None | Some("") | Some("mov_text") => Ok("mov_text"),
+            Some(other) => Err(format!(
+                "{}容器仅支持mov_text字幕，不支持{}",
+                container.to_uppercase(),
+                other
+            )),
+        },
+        "mkv" => match requested.as_deref() {
+            None | Some("") | Some("srt") | Some("subrip") => Ok("srt"),
+            Some("ass") | Some("ssa") => Ok("ass"),
+            Some(other) => Err(format!("MKV容器支持srt和ass字幕，不支持{}", other)),
+        },
+        other => Err(format!(
+            "不支持封装软字幕的容器格式: {}（支持mp4、mov、m4v、mkv）",
+            other
+        )),
+    }
+}
+
+/// 将两字母ISO 639-1语言代码转换为容器元数据使用的ISO 639-2代码，无法识别时原样返回
+fn iso639_2_code(lang: &str) -> String {
+    let base = lang.split(['-', '_']).next().unwrap_or(lang).to_lowercase();
+    let code = match base.as_str() {
+        "zh" => "chi",
+        "en" => "eng",
+        "ja" => "jpn",
+        "ko" => "kor",
+        "fr" => "fre",
+        "de" => "ger",
+        "es" => "spa",
+        "it" => "ita",
+        "pt" => "por",
+        "ru" => "rus",
+        "ar" => "ara",
+        "th" => "tha",
+        "vi" => "vie",
+        "yue" => "yue",
+        _ => return base,
+    };
+    code.to_string()
+}
+
+/// 转义FFMETADATA中的特殊字符（= ; # \ 和换行）
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 将字幕烧录进视频画面（重新编码视频，音频直接复制），返回输出路径
+///
+/// 字幕先按 `style` 写入临时ASS文件，再用FFmpeg的ass滤镜渲染；ASS画布设为视频显示尺寸，
+/// 字号和描边即为视频像素。旋转视频按FFmpeg自动旋转后的画面尺寸计算。
+pub fn burn_subtitles(
+    video_path: &str,
+    subtitles: &[Subtitle],
+    output_path: &str,
+    style: &BurnStyle,
+) -> Result<String, String> {
+    if subtitles.is_empty() {
+        return Err("没有可烧录的字幕".to_string());
+    }
+    if Path::new(output_path) == Path::new(video_path) {
+        return Err("输出路径不能与源视频相同".to_string());
+    }
+
+    let info = get_video_info(video_path)?;
+    let (width, height) = if info.rotation.rem_euclid(180) == 90 {
+        (info.resolution.height, info.resolution.width)
+    } else {
+        (info.resolution.width, info.resolution.height)
+    };
+    if width <= 0 || height <= 0 {
+        return Err("无法获取视频分辨率".to_string());
+    }
+
+    let font_size = style
+        .font_size
+        .filter(|size| *size > 0)
+        .unwrap_or_else(|| (height as u32 / 18).max(12));
+    let outline = style
+        .outline
+        .filter(|outline| *outline >= 0.0)
+        .unwrap_or_else(|| (font_size as f64 / 15.0).max(1.0));
+    let colour = match style.primary_colour.as_deref() {
+        Some(colour) => ass_colour(colour)?,
+        None => "&H00FFFFFF".to_string(),
+    };
+    let font_name = style
+        .font_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Arial")
+        .replace(',', " ");
+    let style_line = format!(
+        "Style: Default,{},{},{},&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,{:.1},0,2,{},{},{},1",
+        font_name,
+        font_size,
+        colour,
+        outline,
+        width / 40,
+        width / 40,
+        height / 20
+    );
+
+    // 文件路径在滤镜参数中需要多层转义（Windows盘符的冒号尤其麻烦），
+    // 因此临时字幕使用不含特殊字符的文件名，并在临时目录下运行FFmpeg
+    let temp_dir = std::env::temp_dir();
+    let temp_name = format!("flowtext_burn_{}.ass", uuid::Uuid::new_v4());
+    let temp_path = temp_dir.join(&temp_name);
+    write_ass(
+        subtitles,
+        &temp_path.to_string_lossy(),
+        Some((width, height)),
+        &style_line,
+    )?;
+
+    let absolute = |path: &str| {
+        std::path::absolute(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string())
+    };
+    // 关闭自动旋转并显式转正画面，保证字幕叠加在转正后的画面上；
+    // 输出已是正向画面，需清除旋转元数据以免播放器再次旋转
+    let video_filter = match rotation_filter(info.rotation) {
+        Some(filter) => format!("{},ass={}", filter, temp_name),
+        None => format!("ass={}", temp_name),
+    };
+    let output = ffmpeg_command()
+        .current_dir(&temp_dir)
+        .arg("-noautorotate")
+        .arg("-i")
+        .arg(absolute(video_path))
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-vf")
+        .arg(video_filter)
+        .arg("-metadata:s:v:0")
+        .arg("rotate=0")
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-crf")
+        .arg("18")
+        .arg("-preset")
+        .arg("medium")
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("copy")
+        .arg("-y")
+        .arg(absolute(output_path))
+        .output();
+    let _ = std::fs::remove_file(&temp_path);
+
+    let output = output.map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+    if !output.status.success() {
+        let (stderr, _) = decode_output(&output.stderr);
+        return Err(format!(
+            "烧录字幕失败: {}",
+            stderr.lines().last().unwrap_or("未知错误")
+        ));
+    }
+
+    info!("字幕已烧录: {} ({}条)", output_path, subtitles.len());
+    Ok(output_path.to_string())
+}
+
+/// 将 #RRGGBB 颜色转换为ASS颜色 &H00BBGGRR
+fn ass_colour(colour: &str) -> Result<String, String> {
+    let hex = colour.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("无效的颜色: {}（应为#RRGGBB）", colour));
+    }
+    Ok(format!("&H00{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_uppercase())
+}
+
+/// 规划长音频的切分点，返回各段的起始时间（第一段为0）
+///
+/// 每段优先在其后四分之一时长内最靠后的静音中点切分，找不到静音时按最大时长切分。
+/// `silences` 为 `detect_silence` 返回的静音区间。
+pub fn plan_silence_cuts(duration: f64, max_segment: f64, silences: &[(f64, f64)]) -> Vec<f64> {
+    let mut cuts = vec![0.0];
+    if max_segment <= 0.0 {
+        return cuts;
+    }
+
+    let mut start = 0.0;
+    while duration - start > max_segment {
+        let window_start = start + max_segment * 0.75;
+        let window_end = start + max_segment;
+        let cut = silences
+            .iter()
+            .filter(|(_, end)| end.is_finite())
+            .map(|(silence_start, silence_end)| (silence_start + silence_end) / 2.0)
+            .filter(|mid| *mid >= window_start && *mid <= window_end)
+            .fold(None, |latest: Option<f64>, mid| {
+                Some(latest.map_or(mid, |latest| latest.max(mid)))
+            })
+            .unwrap_or(window_end);
+        cuts.push(cut);
+        start = cut;
+    }
+    cuts
+}
+
+/// 在静音处将长音频切分为不超过 `max_segment` 秒的片段，返回 (片段路径, 起始偏移秒)
+///
+/// 每段优先在其后四分之一时长内最靠后的静音中点切分，找不到静音时按最大时长切分。
+/// 片段为16kHz单声道WAV，保存在原音频同目录下，命名为 `<原文件名>_segNN.wav`。
+pub fn split_audio_at_silence(
+    audio_path: &str,
+    max_segment: f64,
+) -> Result<Vec<(String, f64)>, String> {
+    if max_segment <= 0.0 {
+        return Err("分段时长必须大于0".to_string());
+    }
+
+    let duration = get_video_info(audio_path)?.duration;
+    let silences = if duration > max_segment {
+        detect_silence(audio_path, -35.0, 0.4)?
+    } else {
+        Vec::new()
+    };
+
+    let cuts = plan_silence_cuts(duration, max_segment, &silences);
 
-        if codec_type == "video" && width == 0 && height == 0 {
-            width = stream["width"].as_i64().unwrap_or(0) as i32;
-            height = stream["height"].as_i64().unwrap_or(0) as i32;
+    let audio_path_obj = Path::new(audio_path);
+    let file_stem = audio_path_obj
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| "无法获取文件名".to_string())?;
+    let output_dir = audio_path_obj
+        .parent()
+        .ok_or_else(|| "无法获取父目录".to_string())?;
 
-            // 获取帧率
-            if let Some(r_frame_rate) = stream["r_frame_rate"].as_str() {
-                if let Some((num, den)) = r_frame_rate.split_once('/') {
-                    if let (Ok(n), Ok(d)) = (num.parse::<f64>(), den.parse::<f64>()) {
-                        if d != 0.0 {
-                            frame_rate = n / d;
-                        }
-                    }
-                }
+    let mut segments = Vec::new();
+    for (index, &segment_start) in cuts.iter().enumerate() {
+        let segment_end = cuts.get(index + 1).copied().unwrap_or(duration);
+        let output_path = output_dir.join(format!("{}_seg{:02}.wav", file_stem, index + 1));
+        let output_path_str = output_path
+            .to_str()
+            .ok_or_else(|| "输出路径无效".to_string())?
+            .to_string();
+
+        let status = ffmpeg_command()
+            .arg("-ss")
+            .arg(format!("{:.3}", segment_start))
+            .arg("-t")
+            .arg(format!("{:.3}", segment_end - segment_start))
+            .arg("-i")
+            .arg(audio_path)
+            .arg("-acodec")
+            .arg("pcm_s16le")
+            .arg("-ar")
+            .arg("16000")
+            .arg("-ac")
+            .arg("1")
+            .arg("-y")
+            .arg(&output_path_str)
+            .status()
+            .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+
+        if !status.success() {
+            for (path, _) in &segments {
+                let _ = std::fs::remove_file(path);
             }
-
-            // 获取编码信息
-            codec_info = stream["codec_name"]
-                .as_str()
-                .unwrap_or("unknown")
-                .to_string();
-        } else if codec_type == "audio" {
-            let track = AudioTrack {
-                id: index as u32,
-                language: stream["tags"]["language"].as_str().map(|s| s.to_string()),
-                codec_info: stream["codec_name"]
-                    .as_str()
-                    .unwrap_or("unknown")
-                    .to_string(),
-                channels: stream["channels"].as_i64().unwrap_or(2) as u32,
-                sample_rate: stream["sample_rate"]
-                    .as_str()
-                    .and_then(|s| s.parse::<u32>().ok())
-                    .unwrap_or(44100),
-            };
-            audio_tracks.push(track);
+            return Err(format!(
+                "切分音频片段{}失败，退出码: {:?}",
+                index + 1,
+                status.code()
+            ));
         }
-    }
 
-    // 如果没有找到音频轨道，添加一个默认的
-    if audio_tracks.is_empty() {
-        audio_tracks.push(AudioTrack {
-            id: 0,
-            language: Some("und".to_string()),
-            codec_info: "unknown".to_string(),
-            channels: 2,
-            sample_rate: 44100,
-        });
+        segments.push((output_path_str, segment_start));
     }
 
-    // 从文件路径提取文件名
-    let file_name = std::path::Path::new(file_path)
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("unknown")
-        .to_string();
-
-    let video_info = VideoInfo {
-        file_path: file_path.to_string(),
-        file_name: file_name.clone(),
-        duration,
-        resolution: Resolution { width, height },
-        frame_rate,
-        codec_info: codec_info.clone(),
-        audio_tracks,
-    };
+    Ok(segments)
+}
 
-    println!(
-        "[DEBUG] 视频信息获取成功: {} ({}x{}, {:.2}s)",
-        file_name, width, height, duration
-    );
+/// 读取视频流在 `until` 秒之前的关键帧时间（相对文件开头，只读取数据包不解码），
+/// 没有视频流时返回空列表
+fn probe_keyframe_times(video_path: &str, until: f64) -> Result<Vec<f64>, String> {
+    let mut command = ffprobe_command();
+    command
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("v:0")
+        .arg("-read_intervals")
+        .arg(format!("%{:.3}", until))
+        .arg("-show_entries")
+        .arg("packet=pts_time,flags:format=start_time")
+        .arg("-print_format")
+        .arg("json")
+        .arg(video_path);
+    let timeout = Duration::from_secs(get_process_timeouts().probe_secs);
+    let output = output_with_timeout(&mut command, timeout)
+        .map_err(|e| format!("执行ffprobe失败: {}", e))?
+        .ok_or_else(|| "读取视频关键帧超时".to_string())?;
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("解析ffprobe输出失败: {}", e))?;
+    let start_time = json["format"]["start_time"]
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let mut keyframes: Vec<f64> = json["packets"]
+        .as_array()
+        .map(|packets| {
+            packets
+                .iter()
+                .filter(|packet| packet["flags"].as_str().is_some_and(|f| f.contains('K')))
+                .filter_map(|packet| packet["pts_time"].as_str()?.parse::<f64>().ok())
+                .map(|pts| (pts - start_time).max(0.0))
+                .collect()
+        })
+        .unwrap_or_default();
+    keyframes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(keyframes)
+}
 
-    Ok(video_info)
+/// 规划切分范围，返回各段的 (开始, 结束)
+///
+/// 流复制只能从关键帧开始，切点对齐到之前最近的关键帧（没有关键帧信息时保持原值）；
+/// 不在视频时长内的切点和对齐后重合的切点被忽略，不会产生空片段。时长未知时末段结束为无穷大。
+fn plan_split_ranges(times: &[f64], keyframes: &[f64], duration: f64) -> Vec<(f64, f64)> {
+    let mut cut_points: Vec<f64> = times
+        .iter()
+        .copied()
+        .filter(|t| *t > 0.0 && (duration <= 0.0 || *t < duration))
+        .map(|t| {
+            if keyframes.is_empty() {
+                return t;
+            }
+            match keyframes.partition_point(|k| *k <= t + 0.001) {
+                0 => 0.0,
+                index => keyframes[index - 1],
+            }
+        })
+        .filter(|t| *t > 0.001)
+        .collect();
+    cut_points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    cut_points.dedup_by(|a, b| (*a - *b).abs() < 0.001);
+
+    let mut boundaries = vec![0.0];
+    boundaries.extend(cut_points);
+    boundaries.push(if duration > 0.0 {
+        duration
+    } else {
+        f64::INFINITY
+    });
+    boundaries
+        .windows(2)
+        .map(|range| (range[0], range[1]))
+        .collect()
 }
 
-/// 从视频中提取音频
-pub fn extract_audio(video_path: &str, audio_track_id: u32) -> Result<String, String> {
-    // 创建输出文件路径
+/// 在指定时间点切分视频及对应字幕
+///
+/// 使用FFmpeg流复制（不重新编码）切出各段视频，切点对齐到之前最近的关键帧，
+/// 字幕按对齐后的时间范围截取并平移到从0开始，与切出的视频一致。返回每段的 (视频路径, SRT路径)。
+pub fn split_at_times(
+    video_path: &str,
+    subtitles: &[Subtitle],
+    times: &[f64],
+    output_dir: &str,
+) -> Result<Vec<(String, String)>, String> {
     let video_path_obj = Path::new(video_path);
     let file_stem = video_path_obj
         .file_stem()
         .and_then(|s| s.to_str())
         .ok_or_else(|| "无法获取文件名".to_string())?;
+    let extension = video_path_obj
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("mp4");
+
+    std::fs::create_dir_all(output_dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+
+    // 首段从0开始，末段到视频结尾
+    let duration = get_video_info(video_path)?.duration;
+    let last_cut = times.iter().copied().fold(0.0, f64::max);
+    let keyframes = probe_keyframe_times(video_path, last_cut + 1.0)?;
+    let ranges = plan_split_ranges(times, &keyframes, duration);
+
+    let mut pieces = Vec::new();
+    for (index, &(start, end)) in ranges.iter().enumerate() {
+        let piece_name = format!("{}_part{:02}", file_stem, index + 1);
+        let piece_video = Path::new(output_dir).join(format!("{}.{}", piece_name, extension));
+        let piece_video_str = piece_video.to_string_lossy().to_string();
+        let piece_srt = Path::new(output_dir).join(format!("{}.srt", piece_name));
+        let piece_srt_str = piece_srt.to_string_lossy().to_string();
+
+        let mut command = ffmpeg_command();
+        command
+            .arg("-ss")
+            .arg(format!("{:.3}", start))
+            .arg("-i")
+            .arg(video_path);
+        if index + 1 < ranges.len() {
+            command.arg("-t").arg(format!("{:.3}", end - start));
+        }
+        let status = command
+            .arg("-map")
+            .arg("0")
+            .arg("-c")
+            .arg("copy")
+            .arg("-avoid_negative_ts")
+            .arg("make_zero")
+            .arg("-y")
+            .arg(&piece_video_str)
+            .status()
+            .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+
+        if !status.success() {
+            return Err(format!(
+                "切分第{}段视频失败，退出码: {:?}",
+                index + 1,
+                status.code()
+            ));
+        }
 
-    let output_dir = video_path_obj
-        .parent()
-        .ok_or_else(|| "无法获取父目录".to_string())?;
-
-    let output_path = output_dir.join(format!("{}_audio_{}.wav", file_stem, audio_track_id));
-    let output_path_str = output_path
-        .to_str()
-        .ok_or_else(|| "输出路径无效".to_string())?;
+        // 截取该段内的字幕并平移时间轴
+        let piece_subtitles: Vec<Subtitle> = subtitles
+            .iter()
+            .filter(|s| s.end_time > start && s.start_time < end)
+            .enumerate()
+            .map(|(i, s)| Subtitle {
+                id: (i + 1).to_string(),
+                start_time: s.start_time.max(start) - start,
+                end_time: s.end_time.min(end) - start,
+                text: s.text.clone(),
+                position: s.position.clone(),
+                words: None,
+            })
+            .collect();
+
+        export_srt_to_path(&piece_subtitles, &piece_srt_str, &ExportOptions::default())?;
+        pieces.push((piece_video_str, piece_srt_str));
+    }
 
-    // 使用FFmpeg命令行提取音频
-    let status = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(video_path)
-        .arg("-map")
-        .arg(format!("0:{}", audio_track_id))
-        .arg("-acodec")
-        .arg("pcm_s16le")
-        .arg("-ar")
-        .arg("16000")
-        .arg("-ac")
-        .arg("1")
-        .arg("-y")
-        .arg(output_path_str)
-        .status()
-        .map_err(|e| format!("执行FFmpeg命令失败: {}", e))?;
+    Ok(pieces)
+}
 
-    if !status.success() {
-        return Err(format!("FFmpeg命令执行失败，退出码: {:?}", status.code()));
+/// 去除字幕文本中的样式标签
+///
+/// 移除以字母或 `/` 开头的HTML风格标签（如 `<i>`、`</b>`、`<font color="red">`）
+/// 和以 `\` 开头的ASS覆盖标签（如 `{\an8}`、`{\b1}`）；未闭合或不像标签的 `<`、`{` 原样保留。
+pub fn strip_subtitle_tags(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(['<', '{']) {
+        plain.push_str(&rest[..open]);
+        let tag = &rest[open..];
+        // '<' 和 '{' 都是单字节字符
+        let inner = &tag[1..];
+        let (close_char, looks_like_tag) = if rest.as_bytes()[open] == b'{' {
+            ('}', inner.starts_with('\\'))
+        } else {
+            (
+                '>',
+                inner.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/'),
+            )
+        };
+        match tag.find(close_char) {
+            Some(close) if looks_like_tag => rest = &tag[close + 1..],
+            _ => {
+                plain.push_str(&tag[..1]);
+                rest = inner;
+            }
+        }
     }
+    plain.push_str(rest);
+    plain
+}
 
-    Ok(output_path_str.to_string())
+/// 按导出选项去除字幕文本中的样式标签，未开启时直接借用原字幕
+fn apply_strip_tags<'a>(
+    subtitles: &'a [Subtitle],
+    options: &ExportOptions,
+) -> std::borrow::Cow<'a, [Subtitle]> {
+    if !options.strip_tags {
+        return std::borrow::Cow::Borrowed(subtitles);
+    }
+    std::borrow::Cow::Owned(
+        subtitles
+            .iter()
+            .map(|subtitle| Subtitle {
+                text: strip_subtitle_tags(&subtitle.text),
+                ..subtitle.clone()
+            })
+            .collect(),
+    )
 }
 
 /// 导出字幕到文件
@@ -233,13 +3116,25 @@ pub fn export_subtitles(
     subtitles: &[Subtitle],
     format: &str,
     file_name: &str,
+    options: &ExportOptions,
 ) -> Result<String, String> {
+    if !is_format_supported(EXPORT_FORMATS, format) {
+        return Err(format!("不支持的字幕格式: {}", format));
+    }
+    let subtitles = &*apply_strip_tags(subtitles, options);
+
     match format.to_lowercase().as_str() {
-        "srt" => export_srt(subtitles, file_name),
-        "vtt" => export_vtt(subtitles, file_name),
+        "srt" => export_srt(subtitles, file_name, options),
+        "vtt" => export_vtt(subtitles, file_name, options),
         "ass" => export_ass(subtitles, file_name),
         "txt" => export_txt(subtitles, file_name),
         "json" => export_json(subtitles, file_name),
+        "csv" => export_csv(subtitles, file_name, options),
+        "itt" => export_itt(subtitles, file_name, options),
+        "ttml" => export_ttml(subtitles, file_name, "ttml"),
+        "dfxp" => export_ttml(subtitles, file_name, "dfxp"),
+        "sbv" => export_sbv(subtitles, file_name),
+        "lrc" => export_lrc(subtitles, file_name),
         _ => Err(format!("不支持的字幕格式: {}", format)),
     }
 }
@@ -250,7 +3145,12 @@ pub fn export_subtitles_to_path(
     format: &str,
     file_name: &str,
     export_path: &str,
+    options: &ExportOptions,
 ) -> Result<String, String> {
+    if !is_format_supported(EXPORT_FORMATS, format) {
+        return Err(format!("不支持的字幕格式: {}", format));
+    }
+
     // 确保导出路径存在
     std::fs::create_dir_all(export_path).map_err(|e| format!("创建导出目录失败: {}", e))?;
 
@@ -259,12 +3159,28 @@ pub fn export_subtitles_to_path(
         std::path::Path::new(export_path).join(format!("{}.{}", file_name, format.to_lowercase()));
     let full_path_str = full_path.to_string_lossy().to_string();
 
+    export_subtitles_to_file(subtitles, format, &full_path_str, options)
+}
+
+/// 按格式导出字幕到完整文件路径
+pub fn export_subtitles_to_file(
+    subtitles: &[Subtitle],
+    format: &str,
+    full_path: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let subtitles = &*apply_strip_tags(subtitles, options);
     match format.to_lowercase().as_str() {
-        "srt" => export_srt_to_path(subtitles, &full_path_str),
-        "vtt" => export_vtt_to_path(subtitles, &full_path_str),
-        "ass" => export_ass_to_path(subtitles, &full_path_str),
-        "txt" => export_txt_to_path(subtitles, &full_path_str),
-        "json" => export_json_to_path(subtitles, &full_path_str),
+        "srt" => export_srt_to_path(subtitles, full_path, options),
+        "vtt" => export_vtt_to_path(subtitles, full_path, options),
+        "ass" => export_ass_to_path(subtitles, full_path),
+        "txt" => export_txt_to_path(subtitles, full_path),
+        "json" => export_json_to_path(subtitles, full_path),
+        "csv" => export_csv_to_path(subtitles, full_path, options),
+        "itt" => export_itt_to_path(subtitles, full_path, options),
+        "ttml" | "dfxp" => export_ttml_to_path(subtitles, full_path),
+        "sbv" => export_sbv_to_path(subtitles, full_path),
+        "lrc" => export_lrc_to_path(subtitles, full_path),
         _ => Err(format!("不支持的字幕格式: {}", format)),
     }
 }
@@ -328,7 +3244,11 @@ pub fn open_folder(path: &str) -> Result<(), String> {
 }
 
 /// 导出SRT格式字幕
-fn export_srt(subtitles: &[Subtitle], file_name: &str) -> Result<String, String> {
+fn export_srt(
+    subtitles: &[Subtitle],
+    file_name: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
     let path = format!("{}.srt", file_name);
     let mut file = File::create(&path).map_err(|e| format!("创建文件失败: {}", e))?;
 
@@ -338,17 +3258,37 @@ fn export_srt(subtitles: &[Subtitle], file_name: &str) -> Result<String, String>
         let end = format_time_srt(subtitle.end_time);
 
         // 写入字幕块
-        writeln!(file, "{}", i + 1).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", cue_number(subtitle, i, options))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file, "{} --> {}", start, end).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{}", subtitle.text).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", normalize_srt_text(&subtitle.text))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
     }
 
     Ok(path)
 }
 
+/// 规范化SRT字幕文本，保证每个字幕块严格为 序号 / 时间行 / 文本 / 空行
+///
+/// SRT中空行即字幕块结束，因此文本内连续的空行合并掉（前后两行直接换行相接），
+/// 并统一换行符、去除行尾空白；其余内容原样保留。
+fn normalize_srt_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// 导出SRT格式字幕到指定路径
-fn export_srt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+pub fn export_srt_to_path(
+    subtitles: &[Subtitle],
+    full_path: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
     let mut file = File::create(full_path).map_err(|e| format!("创建文件失败: {}", e))?;
 
     for (i, subtitle) in subtitles.iter().enumerate() {
@@ -357,9 +3297,11 @@ fn export_srt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
         let end = format_time_srt(subtitle.end_time);
 
         // 写入字幕块
-        writeln!(file, "{}", i + 1).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", cue_number(subtitle, i, options))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file, "{} --> {}", start, end).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{}", subtitle.text).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", normalize_srt_text(&subtitle.text))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
     }
 
@@ -369,47 +3311,68 @@ fn export_srt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
 /// 导出ASS格式字幕
 fn export_ass(subtitles: &[Subtitle], file_name: &str) -> Result<String, String> {
     let path = format!("{}.ass", file_name);
-    let mut file = File::create(&path).map_err(|e| format!("创建文件失败: {}", e))?;
-
-    // 写入ASS头部
-    writeln!(file, "[Script Info]").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "Title: FlowText Generated Subtitles")
-        .map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "ScriptType: v4.00+").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "WrapStyle: 0").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "ScaledBorderAndShadow: yes").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "YCbCr Matrix: TV.601").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
-
-    writeln!(file, "[V4+ Styles]").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,2,2,10,10,10,1").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
+    export_ass_to_path(subtitles, &path)
+}
 
-    writeln!(file, "[Events]").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(
-        file,
-        "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
-    )
-    .map_err(|e| format!("写入文件失败: {}", e))?;
+/// 将字幕位置映射为ASS小键盘对齐代码（1-9，底部为1-3，中部4-6，顶部7-9）
+fn ass_alignment_code(position: &str) -> Option<u8> {
+    let (row, column) = position_parts(position)?;
+    Some(row * 3 + column + 1)
+}
 
-    for subtitle in subtitles {
-        let start = format_time_ass(subtitle.start_time);
-        let end = format_time_ass(subtitle.end_time);
+/// 将字幕位置解析为 (行, 列)：行 0底部/1中部/2顶部，列 0左/1中/2右
+fn position_parts(position: &str) -> Option<(u8, u8)> {
+    let position = position.trim().to_lowercase();
+    let mut parts = position.split(['-', '_', ' ']).filter(|p| !p.is_empty());
+    let row = match parts.next()? {
+        "bottom" => 0,
+        "middle" => 1,
+        "top" => 2,
+        _ => return None,
+    };
+    let column = match parts.next().unwrap_or("center") {
+        "left" => 0,
+        "center" => 1,
+        "right" => 2,
+        _ => return None,
+    };
+    Some((row, column))
+}
 
-        writeln!(
-            file,
-            "Dialogue: 0,{},{},Default,,0,0,0,,{}",
-            start, end, subtitle.text
-        )
-        .map_err(|e| format!("写入文件失败: {}", e))?;
+/// 生成WebVTT cue设置（以空格开头），默认的底部居中位置返回空字符串
+fn vtt_cue_settings(position: Option<&str>) -> String {
+    let Some((row, column)) = position.and_then(position_parts) else {
+        return String::new();
+    };
+    let mut settings = String::new();
+    match row {
+        1 => settings.push_str(" line:50%"),
+        2 => settings.push_str(" line:10%"),
+        _ => {}
     }
-
-    Ok(path)
+    match column {
+        0 => settings.push_str(" align:left"),
+        2 => settings.push_str(" align:right"),
+        _ => {}
+    }
+    settings
 }
 
+/// ASS导出使用的默认样式
+const DEFAULT_ASS_STYLE: &str = "Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,2,2,10,10,10,1";
+
 /// 导出ASS格式字幕到指定路径
 fn export_ass_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+    write_ass(subtitles, full_path, None, DEFAULT_ASS_STYLE)
+}
+
+/// 写入ASS文件：`play_res` 为画布尺寸（未指定时由播放器按默认画布缩放），`style_line` 为Default样式行
+fn write_ass(
+    subtitles: &[Subtitle],
+    full_path: &str,
+    play_res: Option<(i32, i32)>,
+    style_line: &str,
+) -> Result<String, String> {
     let mut file = File::create(full_path).map_err(|e| format!("创建文件失败: {}", e))?;
 
     // 写入ASS头部
@@ -420,11 +3383,15 @@ fn export_ass_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
     writeln!(file, "WrapStyle: 0").map_err(|e| format!("写入文件失败: {}", e))?;
     writeln!(file, "ScaledBorderAndShadow: yes").map_err(|e| format!("写入文件失败: {}", e))?;
     writeln!(file, "YCbCr Matrix: TV.601").map_err(|e| format!("写入文件失败: {}", e))?;
+    if let Some((width, height)) = play_res {
+        writeln!(file, "PlayResX: {}", width).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "PlayResY: {}", height).map_err(|e| format!("写入文件失败: {}", e))?;
+    }
     writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
 
     writeln!(file, "[V4+ Styles]").map_err(|e| format!("写入文件失败: {}", e))?;
     writeln!(file, "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding").map_err(|e| format!("写入文件失败: {}", e))?;
-    writeln!(file, "Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,2,2,10,10,10,1").map_err(|e| format!("写入文件失败: {}", e))?;
+    writeln!(file, "{}", style_line).map_err(|e| format!("写入文件失败: {}", e))?;
     writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
 
     writeln!(file, "[Events]").map_err(|e| format!("写入文件失败: {}", e))?;
@@ -437,11 +3404,20 @@ fn export_ass_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
     for subtitle in subtitles {
         let start = format_time_ass(subtitle.start_time);
         let end = format_time_ass(subtitle.end_time);
+        let alignment = subtitle
+            .position
+            .as_deref()
+            .and_then(ass_alignment_code)
+            .map(|code| format!("{{\\an{}}}", code))
+            .unwrap_or_default();
 
         writeln!(
             file,
-            "Dialogue: 0,{},{},Default,,0,0,0,,{}",
-            start, end, subtitle.text
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}{}",
+            start,
+            end,
+            alignment,
+            subtitle.text.replace("\r\n", "\n").replace('\n', "\\N")
         )
         .map_err(|e| format!("写入文件失败: {}", e))?;
     }
@@ -503,6 +3479,299 @@ fn export_json_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String
     Ok(full_path.to_string())
 }
 
+/// 导出CSV格式字幕
+fn export_csv(
+    subtitles: &[Subtitle],
+    file_name: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let path = format!("{}.csv", file_name);
+    export_csv_to_path(subtitles, &path, options)
+}
+
+/// 导出iTunes Timed Text (iTT) 格式字幕
+fn export_itt(
+    subtitles: &[Subtitle],
+    file_name: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let path = format!("{}.itt", file_name);
+    export_itt_to_path(subtitles, &path, options)
+}
+
+/// 导出iTT格式字幕到指定路径
+///
+/// iTT是Apple使用的TTML子集：时间基准为媒体时间，时间码为 HH:MM:SS:FF，
+/// 帧率取自导出选项（默认30），29.97等NTSC帧率写为整数帧率加 `1000 1001` 倍率。
+/// 字幕位置映射到顶部/中部/底部区域和左右对齐。
+fn export_itt_to_path(
+    subtitles: &[Subtitle],
+    full_path: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let fps = options.fps.filter(|fps| *fps > 0.0).unwrap_or(30.0);
+    let frame_rate = fps.round().max(1.0);
+    let multiplier = if (frame_rate - fps).abs() > 0.001 {
+        " ttp:frameRateMultiplier=\"1000 1001\""
+    } else {
+        ""
+    };
+    let language = subtitles
+        .iter()
+        .find(|s| !s.text.trim().is_empty())
+        .map_or("en", |s| detect_line_language(&s.text));
+
+    let mut content = String::new();
+    content.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    content.push_str(&format!(
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:tts=\"http://www.w3.org/ns/ttml#styling\" \
+         xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" xmlns:ttp=\"http://www.w3.org/ns/ttml#parameter\" \
+         xmlns:tt_feature=\"http://www.w3.org/ns/ttml/feature/\" \
+         ttp:timeBase=\"media\" ttp:frameRate=\"{}\"{} xml:lang=\"{}\">\n",
+        frame_rate as u32, multiplier, language
+    ));
+    content.push_str(TTML_HEAD);
+    for subtitle in subtitles {
+        content.push_str(&ttml_paragraph(
+            subtitle,
+            &format_time_itt(subtitle.start_time, fps),
+            &format_time_itt(subtitle.end_time, fps),
+        ));
+    }
+    content.push_str(TTML_TAIL);
+
+    std::fs::write(full_path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    Ok(full_path.to_string())
+}
+
+/// TTML/iTT文档的 `<head>`（默认样式和顶部/中部/底部区域）及 `<body><div>` 开始标签
+const TTML_HEAD: &str = "  <head>\n\
+     \x20   <styling>\n\
+     \x20     <style xml:id=\"normal\" tts:fontFamily=\"sansSerif\" tts:fontWeight=\"normal\" \
+     tts:fontStyle=\"normal\" tts:color=\"white\" tts:fontSize=\"100%\"/>\n\
+     \x20   </styling>\n\
+     \x20   <layout>\n\
+     \x20     <region xml:id=\"top\" tts:origin=\"0% 0%\" tts:extent=\"100% 15%\" \
+     tts:textAlign=\"center\" tts:displayAlign=\"before\"/>\n\
+     \x20     <region xml:id=\"middle\" tts:origin=\"0% 42.5%\" tts:extent=\"100% 15%\" \
+     tts:textAlign=\"center\" tts:displayAlign=\"center\"/>\n\
+     \x20     <region xml:id=\"bottom\" tts:origin=\"0% 85%\" tts:extent=\"100% 15%\" \
+     tts:textAlign=\"center\" tts:displayAlign=\"after\"/>\n\
+     \x20   </layout>\n\
+     \x20 </head>\n\
+     \x20 <body region=\"bottom\" style=\"normal\">\n\
+     \x20   <div>\n";
+
+/// TTML/iTT文档的结束标签
+const TTML_TAIL: &str = "    </div>\n  </body>\n</tt>\n";
+
+/// 生成一条TTML字幕段落：位置映射到区域和左右对齐，文本转义后换行转为 `<br/>`
+fn ttml_paragraph(subtitle: &Subtitle, begin: &str, end: &str) -> String {
+    let mut attributes = String::new();
+    if let Some((row, column)) = subtitle.position.as_deref().and_then(position_parts) {
+        match row {
+            1 => attributes.push_str(" region=\"middle\""),
+            2 => attributes.push_str(" region=\"top\""),
+            _ => {}
+        }
+        match column {
+            0 => attributes.push_str(" tts:textAlign=\"left\""),
+            2 => attributes.push_str(" tts:textAlign=\"right\""),
+            _ => {}
+        }
+    }
+    let text = subtitle
+        .text
+        .lines()
+        .map(xml_escape)
+        .collect::<Vec<_>>()
+        .join("<br/>");
+    format!(
+        "      <p begin=\"{}\" end=\"{}\"{}>{}</p>\n",
+        begin, end, attributes, text
+    )
+}
+
+/// 导出TTML格式字幕（`.ttml`/`.dfxp` 使用相同内容）
+fn export_ttml(subtitles: &[Subtitle], file_name: &str, extension: &str) -> Result<String, String> {
+    let path = format!("{}.{}", file_name, extension);
+    export_ttml_to_path(subtitles, &path)
+}
+
+/// 导出TTML格式字幕到指定路径
+///
+/// 生成广播/流媒体常用的TTML文档：时间基准为媒体时间，时间码为 HH:MM:SS.mmm，
+/// 区域和样式与iTT导出一致。
+fn export_ttml_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+    let language = subtitles
+        .iter()
+        .find(|s| !s.text.trim().is_empty())
+        .map_or("en", |s| detect_line_language(&s.text));
+
+    let mut content = String::new();
+    content.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    content.push_str(&format!(
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:tts=\"http://www.w3.org/ns/ttml#styling\" \
+         xmlns:ttp=\"http://www.w3.org/ns/ttml#parameter\" ttp:timeBase=\"media\" xml:lang=\"{}\">\n",
+        language
+    ));
+    content.push_str(TTML_HEAD);
+    for subtitle in subtitles {
+        content.push_str(&ttml_paragraph(
+            subtitle,
+            &format_time_ttml(subtitle.start_time),
+            &format_time_ttml(subtitle.end_time),
+        ));
+    }
+    content.push_str(TTML_TAIL);
+
+    std::fs::write(full_path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    Ok(full_path.to_string())
+}
+
+/// 转义XML文本中的特殊字符
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 格式化iTT时间码 (HH:MM:SS:FF)：时分秒为媒体时间，帧数按实际帧率计算
+fn format_time_itt(seconds: f64, fps: f64) -> String {
+    let seconds = seconds.max(0.0);
+    let whole = seconds.floor();
+    let max_frame = (fps.round() as u64).max(1) - 1;
+    let frames = (((seconds - whole) * fps).floor() as u64).min(max_frame);
+    let whole = whole as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        whole / 3600,
+        (whole % 3600) / 60,
+        whole % 60,
+        frames
+    )
+}
+
+/// 导出YouTube SBV格式字幕
+fn export_sbv(subtitles: &[Subtitle], file_name: &str) -> Result<String, String> {
+    let path = format!("{}.sbv", file_name);
+    export_sbv_to_path(subtitles, &path)
+}
+
+/// 导出SBV格式字幕到指定路径（每条为 `开始,结束` 时间行、文本和空行）
+fn export_sbv_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+    let mut file = File::create(full_path).map_err(|e| format!("创建文件失败: {}", e))?;
+
+    for subtitle in subtitles {
+        writeln!(
+            file,
+            "{},{}",
+            format_time_sbv(subtitle.start_time),
+            format_time_sbv(subtitle.end_time)
+        )
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+        // SBV同样以空行分隔字幕块
+        writeln!(file, "{}", normalize_srt_text(&subtitle.text))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
+    }
+
+    Ok(full_path.to_string())
+}
+
+/// 导出LRC歌词（每行 `[mm:ss.xx]文本`，使用字幕开始时间）
+fn export_lrc(subtitles: &[Subtitle], file_name: &str) -> Result<String, String> {
+    let path = format!("{}.lrc", file_name);
+    export_lrc_to_path(subtitles, &path)
+}
+
+/// 导出LRC歌词到指定路径
+///
+/// 文件头的 `[length:]` 取最后一条字幕的结束时间；LRC每个时间戳只能对应一行，多行字幕用空格连接。
+fn export_lrc_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+    let mut file = File::create(full_path).map_err(|e| format!("创建文件失败: {}", e))?;
+
+    if let Some(last) = subtitles.last() {
+        writeln!(file, "[length:{}]", format_time_lrc(last.end_time))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+    }
+    for subtitle in subtitles {
+        let text = subtitle
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(file, "[{}]{}", format_time_lrc(subtitle.start_time), text)
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+    }
+
+    Ok(full_path.to_string())
+}
+
+/// 格式化LRC时间 (秒 -> mm:ss.xx，分钟数可超过59)
+fn format_time_lrc(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        (centis % 6000) / 100,
+        centis % 100
+    )
+}
+
+/// 导出CSV格式字幕到指定路径
+///
+/// 表头为 `index,start,end,duration,text`，文本字段按RFC 4180转义。
+fn export_csv_to_path(
+    subtitles: &[Subtitle],
+    full_path: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    let format_time = |seconds: f64| match options.fps.filter(|fps| *fps > 0.0) {
+        Some(fps) => format_time_frames(seconds, fps),
+        None => format!("{:.3}", seconds),
+    };
+
+    let mut content = String::from("index,start,end,duration,text\r\n");
+    for (i, subtitle) in subtitles.iter().enumerate() {
+        content.push_str(&format!(
+            "{},{},{},{},{}\r\n",
+            cue_number(subtitle, i, options),
+            format_time(subtitle.start_time),
+            format_time(subtitle.end_time),
+            format_time((subtitle.end_time - subtitle.start_time).max(0.0)),
+            csv_escape(&subtitle.text)
+        ));
+    }
+
+    std::fs::write(full_path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+
+    Ok(full_path.to_string())
+}
+
+/// 按RFC 4180转义CSV字段（含逗号、引号或换行时加引号，引号加倍）
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 计算导出时的字幕编号
+fn cue_number(subtitle: &Subtitle, index: usize, options: &ExportOptions) -> String {
+    if options.preserve_ids {
+        if let Ok(id) = subtitle.id.trim().parse::<u64>() {
+            return id.to_string();
+        }
+    }
+    (index + 1).to_string()
+}
+
 /// 格式化时间为ASS格式 (H:MM:SS.CC)
 fn format_time_ass(seconds: f64) -> String {
     let hours = (seconds / 3600.0) as i32;
@@ -517,7 +3786,11 @@ fn format_time_ass(seconds: f64) -> String {
 }
 
 /// 导出WebVTT格式字幕
-fn export_vtt(subtitles: &[Subtitle], file_name: &str) -> Result<String, String> {
+fn export_vtt(
+    subtitles: &[Subtitle],
+    file_name: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
     let path = format!("{}.vtt", file_name);
     let mut file = File::create(&path).map_err(|e| format!("创建文件失败: {}", e))?;
 
@@ -531,17 +3804,70 @@ fn export_vtt(subtitles: &[Subtitle], file_name: &str) -> Result<String, String>
         let end = format_time_vtt(subtitle.end_time);
 
         // 写入字幕块
-        writeln!(file, "{}", i + 1).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{} --> {}", start, end).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{}", subtitle.text).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", cue_number(subtitle, i, options))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(
+            file,
+            "{} --> {}{}",
+            start,
+            end,
+            vtt_cue_settings(subtitle.position.as_deref())
+        )
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", vtt_cue_text(subtitle)).map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
     }
 
     Ok(path)
 }
 
+/// 生成WebVTT cue文本：有单词时间时在词间插入 `<00:00:01.500>` 时间标签（卡拉OK逐词高亮），
+/// 否则原样输出字幕文本
+///
+/// 查找替换等操作只修改 `text`，单词拼接结果与 `text`（忽略空白）不一致时说明文本已被编辑，
+/// 此时以 `text` 为准。
+fn vtt_cue_text(subtitle: &Subtitle) -> String {
+    let Some(words) = subtitle.words.as_deref().filter(|words| !words.is_empty()) else {
+        return subtitle.text.clone();
+    };
+    let without_whitespace = |text: &str| text.split_whitespace().collect::<String>();
+    let words_text: String = words
+        .iter()
+        .map(|word| without_whitespace(&word.word))
+        .collect();
+    if words_text != without_whitespace(&subtitle.text) {
+        return subtitle.text.clone();
+    }
+
+    let mut text = String::new();
+    let mut last_tag = subtitle.start_time;
+    let mut previous: Option<&str> = None;
+    for word in words {
+        if let Some(previous) = previous {
+            // 中日韩文字之间不加空格
+            let wide_join = previous.chars().last().is_some_and(is_wide_char)
+                && word.word.chars().next().is_some_and(is_wide_char);
+            if !wide_join {
+                text.push(' ');
+            }
+            // 时间标签必须递增且位于cue时间范围内
+            if word.start_time > last_tag && word.start_time < subtitle.end_time {
+                text.push_str(&format!("<{}>", format_time_vtt(word.start_time)));
+                last_tag = word.start_time;
+            }
+        }
+        text.push_str(&word.word);
+        previous = Some(&word.word);
+    }
+    text
+}
+
 /// 导出WebVTT格式字幕到指定路径
-fn export_vtt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String, String> {
+fn export_vtt_to_path(
+    subtitles: &[Subtitle],
+    full_path: &str,
+    options: &ExportOptions,
+) -> Result<String, String> {
     let mut file = File::create(full_path).map_err(|e| format!("创建文件失败: {}", e))?;
 
     // 写入WebVTT头部
@@ -554,9 +3880,17 @@ fn export_vtt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
         let end = format_time_vtt(subtitle.end_time);
 
         // 写入字幕块
-        writeln!(file, "{}", i + 1).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{} --> {}", start, end).map_err(|e| format!("写入文件失败: {}", e))?;
-        writeln!(file, "{}", subtitle.text).map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", cue_number(subtitle, i, options))
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(
+            file,
+            "{} --> {}{}",
+            start,
+            end,
+            vtt_cue_settings(subtitle.position.as_deref())
+        )
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+        writeln!(file, "{}", vtt_cue_text(subtitle)).map_err(|e| format!("写入文件失败: {}", e))?;
         writeln!(file).map_err(|e| format!("写入文件失败: {}", e))?;
     }
 
@@ -564,33 +3898,77 @@ fn export_vtt_to_path(subtitles: &[Subtitle], full_path: &str) -> Result<String,
 }
 
 /// 导入字幕文件
-pub fn import_subtitles(file_path: &str) -> Result<Vec<Subtitle>, String> {
+pub fn import_subtitles(file_path: &str, fps: Option<f64>) -> Result<Vec<Subtitle>, String> {
     let path = Path::new(file_path);
     let extension = path
         .extension()
         .and_then(|ext| ext.to_str())
         .ok_or_else(|| "无法获取文件扩展名".to_string())?;
 
+    if !is_format_supported(IMPORT_FORMATS, extension) {
+        return Err(format!("不支持的字幕格式: {}", extension));
+    }
+
     match extension.to_lowercase().as_str() {
         "srt" => import_srt(file_path),
         "vtt" => import_vtt(file_path),
+        "csv" => import_csv(file_path, fps),
+        "ttml" | "dfxp" | "itt" => import_ttml(file_path),
+        "ass" | "ssa" => import_ass(file_path),
         _ => Err(format!("不支持的字幕格式: {}", extension)),
     }
 }
 
-/// 导入SRT格式字幕
-fn import_srt(file_path: &str) -> Result<Vec<Subtitle>, String> {
-    let file = File::open(file_path).map_err(|e| format!("打开文件失败: {}", e))?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
+/// 读取字幕文件文本：去除UTF-8 BOM，内容不是合法UTF-8时按GBK解码
+///
+/// 国内常见的字幕文件多为GBK编码，直接按UTF-8读取会失败。
+fn read_subtitle_text(file_path: &str) -> Result<String, String> {
+    let bytes = std::fs::read(file_path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let (text, had_errors) = decode_subtitle_bytes(&bytes);
+    if had_errors {
+        warn!(
+            "字幕文件既不是UTF-8也不是GBK编码，部分字符可能显示异常: {}",
+            file_path
+        );
+    }
+    Ok(text)
+}
 
-    let mut subtitles = Vec::new();
+/// 解码字幕文件内容：去除UTF-8 BOM，不是合法UTF-8时按GBK解码
+///
+/// 返回解码后的文本，以及GBK解码是否遇到无效字节。
+fn decode_subtitle_bytes(bytes: &[u8]) -> (String, bool) {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), false),
+        Err(_) => {
+            let (text, _, had_errors) = encoding_rs::GBK.decode(bytes);
+            if !had_errors {
+                debug!("字幕文件按GBK编码解码");
+            }
+            (text.into_owned(), had_errors)
+        }
+    }
+}
+
+/// 流式导入SRT格式字幕：逐条解析并交给 `on_cue` 处理，不在内存中保留全部字幕
+///
+/// 文件支持UTF-8（可带BOM）和GBK编码。
+/// `on_cue` 返回错误时立即停止读取并返回该错误；成功时返回解析的字幕条数。
+pub fn import_srt_streaming<F>(file_path: &str, mut on_cue: F) -> Result<usize, String>
+where
+    F: FnMut(Subtitle) -> Result<(), String>,
+{
+    let content = read_subtitle_text(file_path)?;
+
+    let mut count = 0;
     let mut current_id = String::new();
     let mut current_times = (0.0, 0.0);
     let mut current_text = String::new();
     let mut state = 0; // 0: 等待ID, 1: 等待时间, 2: 读取文本
 
-    while let Some(Ok(line)) = lines.next() {
+    for line in content.lines() {
         let line = line.trim();
 
         match state {
@@ -618,12 +3996,15 @@ fn import_srt(file_path: &str) -> Result<Vec<Subtitle>, String> {
                 if line.is_empty() {
                     // 空行表示当前字幕块结束
                     if !current_text.is_empty() {
-                        subtitles.push(Subtitle {
+                        on_cue(Subtitle {
                             id: current_id.clone(),
                             start_time: current_times.0,
                             end_time: current_times.1,
                             text: current_text.trim().to_string(),
-                        });
+                            position: None,
+                            words: None,
+                        })?;
+                        count += 1;
                         current_text.clear();
                     }
                     state = 0;
@@ -641,14 +4022,38 @@ fn import_srt(file_path: &str) -> Result<Vec<Subtitle>, String> {
 
     // 处理最后一个字幕块
     if state == 2 && !current_text.is_empty() {
-        subtitles.push(Subtitle {
+        on_cue(Subtitle {
             id: current_id,
             start_time: current_times.0,
             end_time: current_times.1,
             text: current_text.trim().to_string(),
-        });
+            position: None,
+            words: None,
+        })?;
+        count += 1;
     }
 
+    Ok(count)
+}
+
+/// 导入SRT格式字幕，超过 `MAX_IMPORT_CUES` 条时报错
+fn import_srt(file_path: &str) -> Result<Vec<Subtitle>, String> {
+    import_srt_limited(file_path, MAX_IMPORT_CUES)
+}
+
+/// 导入SRT格式字幕并限制最大条数，避免异常的超大文件占满内存
+fn import_srt_limited(file_path: &str, max_cues: usize) -> Result<Vec<Subtitle>, String> {
+    let mut subtitles = Vec::new();
+    import_srt_streaming(file_path, |subtitle| {
+        if subtitles.len() >= max_cues {
+            return Err(format!(
+                "字幕条数超过上限（{}条），文件可能由多个字幕拼接而成，请拆分后再导入",
+                max_cues
+            ));
+        }
+        subtitles.push(subtitle);
+        Ok(())
+    })?;
     Ok(subtitles)
 }
 
@@ -717,6 +4122,8 @@ fn import_vtt(file_path: &str) -> Result<Vec<Subtitle>, String> {
                             start_time: current_times.0,
                             end_time: current_times.1,
                             text: current_text.trim().to_string(),
+                            position: None,
+                            words: None,
                         });
                         current_text.clear();
                     }
@@ -740,12 +4147,653 @@ fn import_vtt(file_path: &str) -> Result<Vec<Subtitle>, String> {
             start_time: current_times.0,
             end_time: current_times.1,
             text: current_text.trim().to_string(),
+            position: None,
+            words: None,
+        });
+    }
+
+    Ok(subtitles)
+}
+
+/// 导入ASS/SSA格式字幕
+///
+/// 只读取 `[Events]` 段中的 Dialogue 行，按 `Format:` 行定位 Start/End/Text 列；
+/// 去除 `{...}` 样式标签，`\N` 转为换行，`{\anN}` 对齐标签还原为字幕位置。
+fn import_ass(file_path: &str) -> Result<Vec<Subtitle>, String> {
+    let content = std::fs::read_to_string(file_path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    let mut in_events = false;
+    let mut columns: Option<(usize, usize, usize, usize)> = None;
+    let mut subtitles = Vec::new();
+
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = line.strip_prefix("Format:") {
+            let fields: Vec<String> = format.split(',').map(|f| f.trim().to_lowercase()).collect();
+            let column = |name: &str| fields.iter().position(|f| f == name);
+            columns = Some((
+                column("start").ok_or("ASS的Format行缺少Start列")?,
+                column("end").ok_or("ASS的Format行缺少End列")?,
+                column("text").ok_or("ASS的Format行缺少Text列")?,
+                fields.len(),
+            ));
+        } else if let Some(dialogue) = line.strip_prefix("Dialogue:") {
+            let (start_col, end_col, text_col, field_count) =
+                columns.ok_or("ASS文件的Dialogue行之前缺少Format行")?;
+            // Text是最后一列，本身可以包含逗号
+            let fields: Vec<&str> = dialogue.trim_start().splitn(field_count, ',').collect();
+            let field = |col: usize| fields.get(col).copied().unwrap_or("");
+            let start_time = parse_time_str_ass(field(start_col)).ok_or_else(|| {
+                format!("第{}行开始时间无效: {}", line_index + 1, field(start_col))
+            })?;
+            let end_time = parse_time_str_ass(field(end_col))
+                .ok_or_else(|| format!("第{}行结束时间无效: {}", line_index + 1, field(end_col)))?;
+            let (text, position) = strip_ass_tags(field(text_col));
+            if text.is_empty() {
+                continue;
+            }
+
+            subtitles.push(Subtitle {
+                id: (subtitles.len() + 1).to_string(),
+                start_time,
+                end_time,
+                text,
+                position,
+                words: None,
+            });
+        }
+    }
+
+    if columns.is_none() {
+        return Err("无效的ASS文件，缺少[Events]段或Format行".to_string());
+    }
+    subtitles.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    for (index, subtitle) in subtitles.iter_mut().enumerate() {
+        subtitle.id = (index + 1).to_string();
+    }
+    Ok(subtitles)
+}
+
+/// 去除ASS文本中的样式标签，返回纯文本和 `{\anN}` 对应的字幕位置
+fn strip_ass_tags(text: &str) -> (String, Option<String>) {
+    let mut plain = String::new();
+    let mut position = None;
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        plain.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        if let Some(code) = tag.split('\\').find_map(|t| {
+            t.strip_prefix("an")
+                .and_then(|c| c.trim().parse::<u8>().ok())
+        }) {
+            position = ass_alignment_position(code);
+        }
+        rest = &rest[open + close + 1..];
+    }
+    plain.push_str(rest);
+
+    let plain = plain
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ");
+    let plain = plain
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (plain, position)
+}
+
+/// 将ASS小键盘对齐代码还原为字幕位置，默认的底部居中返回None
+fn ass_alignment_position(code: u8) -> Option<String> {
+    if !(1..=9).contains(&code) || code == 2 {
+        return None;
+    }
+    let vertical = ["bottom", "middle", "top"][((code - 1) / 3) as usize];
+    let horizontal = ["left", "center", "right"][((code - 1) % 3) as usize];
+    Some(format!("{}-{}", vertical, horizontal))
+}
+
+/// 导入CSV格式字幕
+///
+/// 按表头识别 start/end/text 列（无表头时按 index,start,end,duration,text 顺序）。
+/// 时间可为秒数、HH:MM:SS.mmm 或帧时间码 HH:MM:SS:FF（需提供帧率）。
+fn import_csv(file_path: &str, fps: Option<f64>) -> Result<Vec<Subtitle>, String> {
+    let content = std::fs::read_to_string(file_path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    let mut records = parse_csv_records(content).into_iter();
+    let first = match records.next() {
+        Some(record) => record,
+        None => return Ok(Vec::new()),
+    };
+
+    // 识别表头，确定各列位置
+    let lower: Vec<String> = first.iter().map(|f| f.trim().to_lowercase()).collect();
+    let column = |name: &str| lower.iter().position(|f| f == name);
+    let has_header = column("text").is_some() || column("start").is_some();
+    let (start_col, end_col, text_col) = if has_header {
+        (
+            column("start").ok_or("CSV缺少start列")?,
+            column("end").ok_or("CSV缺少end列")?,
+            column("text").ok_or("CSV缺少text列")?,
+        )
+    } else {
+        (1, 2, 4)
+    };
+
+    let rows: Vec<Vec<String>> = if has_header {
+        records.collect()
+    } else {
+        std::iter::once(first).chain(records).collect()
+    };
+
+    let mut subtitles = Vec::new();
+    for (row_index, row) in rows.iter().enumerate() {
+        if row.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |col: usize| row.get(col).map(|f| f.trim()).unwrap_or("");
+        let start_time = parse_csv_time(field(start_col), fps)
+            .map_err(|e| format!("第{}行开始时间无效: {}", row_index + 1, e))?;
+        let end_time = parse_csv_time(field(end_col), fps)
+            .map_err(|e| format!("第{}行结束时间无效: {}", row_index + 1, e))?;
+
+        subtitles.push(Subtitle {
+            id: (subtitles.len() + 1).to_string(),
+            start_time,
+            end_time,
+            text: field(text_col).to_string(),
+            position: None,
+            words: None,
         });
     }
 
     Ok(subtitles)
 }
 
+/// 解析CSV内容为记录列表（支持引号内的逗号、换行及 "" 转义）
+fn parse_csv_records(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// 解析CSV中的时间（秒数、HH:MM:SS.mmm 或 HH:MM:SS:FF）
+fn parse_csv_time(value: &str, fps: Option<f64>) -> Result<f64, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    match parts.len() {
+        1 => value
+            .parse::<f64>()
+            .map_err(|_| format!("无法解析时间: {}", value)),
+        3 => (|| {
+            let hours = parts[0].parse::<f64>().ok()?;
+            let minutes = parts[1].parse::<f64>().ok()?;
+            let seconds = parts[2].replace(',', ".").parse::<f64>().ok()?;
+            Some(hours * 3600.0 + minutes * 60.0 + seconds)
+        })()
+        .ok_or_else(|| format!("无法解析时间: {}", value)),
+        4 => {
+            let fps = fps
+                .filter(|fps| *fps > 0.0)
+                .ok_or_else(|| format!("帧时间码 {} 需要指定帧率", value))?;
+            let numbers: Vec<f64> = parts
+                .iter()
+                .map(|p| p.parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("无法解析时间: {}", value))?;
+            // 非丢帧时间码按整数帧率计数，再换算为实际帧率下的秒数
+            let total_frames =
+                (numbers[0] * 3600.0 + numbers[1] * 60.0 + numbers[2]) * fps.round() + numbers[3];
+            Ok(total_frames / fps)
+        }
+        _ => Err(format!("无法解析时间: {}", value)),
+    }
+}
+
+/// TTML元素属性（按去掉命名空间前缀的本地名保存，如 tts:textAlign -> textAlign）
+type TtmlAttrs = HashMap<String, String>;
+
+/// TTML中正在读取的元素（用于继承区域、样式和时间偏移）
+struct TtmlElement {
+    name: String,
+    region: Option<String>,
+    /// 元素开始时间（秒，已累加父元素的偏移）
+    begin: f64,
+}
+
+/// 正在读取的 `<p>` 字幕段落
+struct TtmlParagraph {
+    start: f64,
+    end: f64,
+    text: String,
+    region: Option<String>,
+    attrs: TtmlAttrs,
+}
+
+/// 去掉XML名称的命名空间前缀
+fn ttml_local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// 解析元素属性
+fn ttml_attrs(raw: &str) -> TtmlAttrs {
+    let re = regex::Regex::new(r#"([\w:.-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    re.captures_iter(raw)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .map_or("", |m| m.as_str());
+            (ttml_local_name(&caps[1]).to_string(), ttml_unescape(value))
+        })
+        .collect()
+}
+
+/// 还原XML实体
+fn ttml_unescape(text: &str) -> String {
+    let re = regex::Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|amp|lt|gt|quot|apos);").unwrap();
+    re.replace_all(text, |caps: &regex::Captures| {
+        let entity = &caps[1];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(char::from_u32),
+            _ => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+        };
+        decoded.map_or_else(|| caps[0].to_string(), |c| c.to_string())
+    })
+    .to_string()
+}
+
+/// 解析TTML时间表达式：时钟时间（HH:MM:SS.mmm、HH:MM:SS:FF）或偏移时间（如 1.5s、500ms、30f、900t）
+fn parse_ttml_time(value: &str, frame_rate: f64, tick_rate: f64) -> Option<f64> {
+    let value = value.trim();
+    if value.contains(':') {
+        let parts: Vec<&str> = value.split(':').collect();
+        let hours = parts.first()?.parse::<f64>().ok()?;
+        let minutes = parts.get(1)?.parse::<f64>().ok()?;
+        let seconds = parts.get(2)?.parse::<f64>().ok()?;
+        let frames = match parts.len() {
+            3 => 0.0,
+            4 => parts[3].parse::<f64>().ok()? / frame_rate,
+            _ => return None,
+        };
+        return Some(hours * 3600.0 + minutes * 60.0 + seconds + frames);
+    }
+
+    let unit_start = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = value.split_at(unit_start);
+    let number = number.parse::<f64>().ok()?;
+    match unit {
+        "h" => Some(number * 3600.0),
+        "m" => Some(number * 60.0),
+        "s" => Some(number),
+        "ms" => Some(number / 1000.0),
+        "f" => Some(number / frame_rate),
+        "t" => Some(number / tick_rate),
+        _ => None,
+    }
+}
+
+/// 按元素自身属性和引用的样式链查找样式属性
+fn ttml_style_attr(
+    attrs: &TtmlAttrs,
+    styles: &HashMap<String, TtmlAttrs>,
+    name: &str,
+    depth: usize,
+) -> Option<String> {
+    if let Some(value) = attrs.get(name) {
+        return Some(value.clone());
+    }
+    if depth > 4 {
+        return None;
+    }
+    attrs
+        .get("style")?
+        .split_whitespace()
+        .filter_map(|id| styles.get(id))
+        .find_map(|style| ttml_style_attr(style, styles, name, depth + 1))
+}
+
+/// 解析 tts:origin / tts:extent 中的百分比坐标对
+fn ttml_percent_pair(value: &str) -> Option<(f64, f64)> {
+    let mut parts = value
+        .split_whitespace()
+        .map(|part| part.strip_suffix('%').and_then(|n| n.parse::<f64>().ok()));
+    Some((parts.next()??, parts.next()??))
+}
+
+/// 由段落和所在区域的布局属性推算屏幕位置（底部居中为默认位置，返回None）
+fn ttml_position(
+    paragraph: &TtmlAttrs,
+    region: Option<&TtmlAttrs>,
+    styles: &HashMap<String, TtmlAttrs>,
+) -> Option<String> {
+    let empty = TtmlAttrs::new();
+    let region = region.unwrap_or(&empty);
+    let origin = region.get("origin").and_then(|v| ttml_percent_pair(v));
+    let extent = region
+        .get("extent")
+        .and_then(|v| ttml_percent_pair(v))
+        .unwrap_or((100.0, 100.0));
+    let center = origin.map(|(x, y)| (x + extent.0 / 2.0, y + extent.1 / 2.0));
+
+    let vertical = match ttml_style_attr(region, styles, "displayAlign", 0).as_deref() {
+        // 区域较小时按区域中心所在的三分之一判断，displayAlign只决定区域内的对齐
+        _ if extent.1 < 50.0 && center.is_some() => match center.map(|c| c.1) {
+            Some(y) if y < 100.0 / 3.0 => "top",
+            Some(y) if y < 200.0 / 3.0 => "middle",
+            _ => "bottom",
+        },
+        Some("before") => "top",
+        Some("center") => "middle",
+        _ => "bottom",
+    };
+
+    let text_align = ttml_style_attr(paragraph, styles, "textAlign", 0)
+        .or_else(|| ttml_style_attr(region, styles, "textAlign", 0));
+    let horizontal = match text_align.as_deref() {
+        Some("left") => "left",
+        Some("right") => "right",
+        Some("start") => "left",
+        Some("end") => "right",
+        _ => match center.map(|c| c.0) {
+            Some(x) if extent.0 < 50.0 && x < 100.0 / 3.0 => "left",
+            Some(x) if extent.0 < 50.0 && x > 200.0 / 3.0 => "right",
+            _ => "center",
+        },
+    };
+
+    match (vertical, horizontal) {
+        ("bottom", "center") => None,
+        (vertical, "center") => Some(vertical.to_string()),
+        (vertical, horizontal) => Some(format!("{}-{}", vertical, horizontal)),
+    }
+}
+
+/// 导入TTML/DFXP字幕
+///
+/// 读取 `<p>` 的起止时间（支持 begin/end/dur 及父元素的时间偏移）和文本（`<br/>` 换行），
+/// 并根据所在 `<region>` 的位置与对齐方式设置字幕位置。颜色、字体等无法映射到字幕字段的
+/// 样式会被忽略并记录日志。
+fn import_ttml(file_path: &str) -> Result<Vec<Subtitle>, String> {
+    let bytes = std::fs::read(file_path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let (content, _) = decode_output(&bytes);
+
+    let comment_re = regex::Regex::new(r"(?s)<!--.*?-->|<\?.*?\?>|<!\[CDATA\[|\]\]>").unwrap();
+    let content = comment_re.replace_all(&content, "");
+    if !content.contains("<tt") {
+        return Err("无效的TTML文件，缺少<tt>根元素".to_string());
+    }
+
+    let tag_re = regex::Regex::new(r"(?s)<(/?)([A-Za-z_][\w:.-]*)([^>]*?)(/?)>").unwrap();
+    let mut frame_rate = 30.0;
+    let mut tick_rate = 1.0;
+    let mut styles: HashMap<String, TtmlAttrs> = HashMap::new();
+    let mut regions: HashMap<String, TtmlAttrs> = HashMap::new();
+    let mut open_region: Option<(String, TtmlAttrs)> = None;
+    let mut stack: Vec<TtmlElement> = Vec::new();
+    let mut paragraphs: Vec<TtmlParagraph> = Vec::new();
+    let mut current: Option<TtmlParagraph> = None;
+    let mut untimed = 0;
+    let mut last_end = 0;
+
+    for caps in tag_re.captures_iter(&content) {
+        let whole = caps.get(0).unwrap();
+        if let Some(paragraph) = current.as_mut() {
+            paragraph
+                .text
+                .push_str(&ttml_unescape(&content[last_end..whole.start()]));
+        }
+        last_end = whole.end();
+
+        let closing = !caps[1].is_empty();
+        let name = ttml_local_name(&caps[2]).to_string();
+        let self_closing = !caps[4].is_empty();
+
+        if closing {
+            if name == "p" {
+                paragraphs.extend(current.take());
+            } else if name == "region" {
+                if let Some((id, attrs)) = open_region.take() {
+                    regions.insert(id, attrs);
+                }
+            }
+            if let Some(index) = stack.iter().rposition(|e| e.name == name) {
+                stack.truncate(index);
+            }
+            continue;
+        }
+
+        let attrs = ttml_attrs(&caps[3]);
+        let parent_begin = stack.last().map_or(0.0, |e| e.begin);
+        let parent_region = stack.last().and_then(|e| e.region.clone());
+        let begin = attrs
+            .get("begin")
+            .and_then(|v| parse_ttml_time(v, frame_rate, tick_rate));
+
+        match name.as_str() {
+            "tt" => {
+                if let Some(rate) = attrs.get("frameRate").and_then(|v| v.parse::<f64>().ok()) {
+                    // frameRateMultiplier（如iTT的 "1000 1001"）用于29.97等NTSC帧率
+                    let multiplier = attrs
+                        .get("frameRateMultiplier")
+                        .and_then(|v| {
+                            let (numerator, denominator) =
+                                v.trim().split_once(char::is_whitespace)?;
+                            Some(
+                                numerator.parse::<f64>().ok()?
+                                    / denominator.trim().parse::<f64>().ok()?,
+                            )
+                        })
+                        .filter(|m| m.is_finite() && *m > 0.0)
+                        .unwrap_or(1.0);
+                    frame_rate = rate * multiplier;
+                    tick_rate = frame_rate;
+                }
+                if let Some(rate) = attrs.get("tickRate").and_then(|v| v.parse::<f64>().ok()) {
+                    tick_rate = rate;
+                }
+            }
+            "style" => match open_region.as_mut() {
+                // 区域内嵌的样式直接并入区域属性
+                Some((_, region)) => {
+                    for (key, value) in attrs.iter().filter(|(k, _)| k.as_str() != "id") {
+                        region.insert(key.clone(), value.clone());
+                    }
+                }
+                None => {
+                    if let Some(id) = attrs.get("id") {
+                        styles.insert(id.clone(), attrs.clone());
+                    }
+                }
+            },
+            "region" => {
+                if let Some(id) = attrs.get("id").cloned() {
+                    if self_closing {
+                        regions.insert(id, attrs.clone());
+                    } else {
+                        open_region = Some((id, attrs.clone()));
+                    }
+                }
+            }
+            "br" => {
+                if let Some(paragraph) = current.as_mut() {
+                    paragraph.text.push('\n');
+                }
+            }
+            "p" => {
+                let start = parent_begin + begin.unwrap_or(0.0);
+                let end = attrs
+                    .get("end")
+                    .and_then(|v| parse_ttml_time(v, frame_rate, tick_rate))
+                    .map(|end| parent_begin + end)
+                    .or_else(|| {
+                        attrs
+                            .get("dur")
+                            .and_then(|v| parse_ttml_time(v, frame_rate, tick_rate))
+                            .map(|dur| start + dur)
+                    });
+                match end {
+                    Some(end) if end > start => {
+                        let paragraph = TtmlParagraph {
+                            start,
+                            end,
+                            text: String::new(),
+                            region: attrs.get("region").cloned().or(parent_region.clone()),
+                            attrs: attrs.clone(),
+                        };
+                        if self_closing {
+                            paragraphs.push(paragraph);
+                        } else {
+                            current = Some(paragraph);
+                        }
+                    }
+                    _ => untimed += 1,
+                }
+            }
+            _ => {}
+        }
+
+        if !self_closing && name != "br" {
+            stack.push(TtmlElement {
+                region: attrs.get("region").cloned().or(parent_region),
+                begin: parent_begin
+                    + if name == "p" {
+                        0.0
+                    } else {
+                        begin.unwrap_or(0.0)
+                    },
+                name,
+            });
+        }
+    }
+
+    if untimed > 0 {
+        warn!("TTML中有{}个段落缺少有效的起止时间，已跳过", untimed);
+    }
+
+    // 颜色、字体等样式没有对应的字幕字段，记录后忽略
+    const LAYOUT_ATTRS: &[&str] = &[
+        "id",
+        "style",
+        "textAlign",
+        "displayAlign",
+        "origin",
+        "extent",
+    ];
+    let mut unmapped: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+    for attrs in styles.values().chain(regions.values()) {
+        unmapped.extend(
+            attrs
+                .keys()
+                .filter(|key| !LAYOUT_ATTRS.contains(&key.as_str()))
+                .cloned(),
+        );
+    }
+    if !unmapped.is_empty() {
+        warn!(
+            "TTML样式属性无法映射，已忽略: {}",
+            unmapped.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let mut subtitles: Vec<Subtitle> = paragraphs
+        .into_iter()
+        .filter_map(|paragraph| {
+            // XML中的换行和缩进折叠为单个空格，<br/> 保留为换行
+            let text = paragraph
+                .text
+                .split('\n')
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if text.is_empty() {
+                return None;
+            }
+            let region = paragraph.region.as_ref().and_then(|id| regions.get(id));
+            Some(Subtitle {
+                id: String::new(),
+                start_time: paragraph.start,
+                end_time: paragraph.end,
+                text,
+                position: ttml_position(&paragraph.attrs, region, &styles),
+                words: None,
+            })
+        })
+        .collect();
+
+    subtitles.sort_by(|a, b| {
+        a.start_time
+            .partial_cmp(&b.start_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for (index, subtitle) in subtitles.iter_mut().enumerate() {
+        subtitle.id = (index + 1).to_string();
+    }
+
+    if subtitles.is_empty() {
+        return Err("TTML文件中没有可导入的字幕".to_string());
+    }
+    Ok(subtitles)
+}
+
 /// 解析SRT时间行 (00:00:00,000 --> 00:00:00,000)
 fn parse_time_line_srt(line: &str) -> Option<(f64, f64)> {
     let parts: Vec<&str> = line.split(" --> ").collect();
@@ -827,6 +4875,23 @@ fn parse_time_str_vtt(time_str: &str) -> Option<f64> {
     Some(total_seconds)
 }
 
+/// 解析ASS时间字符串 (H:MM:SS.CC)
+fn parse_time_str_ass(time_str: &str) -> Option<f64> {
+    let parts: Vec<&str> = time_str.trim().split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let hours: u32 = parts[0].parse().ok()?;
+    let minutes: u32 = parts[1].parse().ok()?;
+    let seconds: f64 = parts[2].parse().ok()?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+
+    Some((hours as f64) * 3600.0 + (minutes as f64) * 60.0 + seconds)
+}
+
 /// 格式化时间为SRT格式 (00:00:00,000)
 fn format_time_srt(seconds: f64) -> String {
     let hours = (seconds / 3600.0).floor() as u32;
@@ -837,6 +4902,32 @@ fn format_time_srt(seconds: f64) -> String {
     format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
 }
 
+/// 格式化TTML时钟时间 (HH:MM:SS.mmm)
+///
+/// 先取整到毫秒再拆分，避免 .9995 之类的时间四舍五入成非法的 1000 毫秒。
+fn format_time_ttml(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        millis % 1000
+    )
+}
+
+/// 格式化SBV时间 (H:MM:SS.mmm，小时不补零且可超过9)
+fn format_time_sbv(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        millis % 1000
+    )
+}
+
 /// 格式化时间为WebVTT格式 (00:00:00.000)
 fn format_time_vtt(seconds: f64) -> String {
     let hours = (seconds / 3600.0).floor() as u32;
@@ -846,3 +4937,333 @@ fn format_time_vtt(seconds: f64) -> String {
 
     format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)
 }
+
+/// 格式化时间为帧时间码 (HH:MM:SS:FF，非丢帧)
+fn format_time_frames(seconds: f64, fps: f64) -> String {
+    let frame_rate = (fps.round() as u64).max(1);
+    let total_frames = (seconds.max(0.0) * fps).round() as u64;
+    let frames = total_frames % frame_rate;
+    let total_seconds = total_frames / frame_rate;
+
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds % 3600) / 60,
+        total_seconds % 60,
+        frames
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用的临时文件路径
+    fn temp_path(extension: &str) -> String {
+        std::env::temp_dir()
+            .join(format!(
+                "flowtext_test_{}.{}",
+                uuid::Uuid::new_v4(),
+                extension
+            ))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn word(word: &str, start_time: f64, end_time: f64) -> WordTiming {
+        WordTiming {
+            word: word.to_string(),
+            start_time,
+            end_time,
+        }
+    }
+
+    fn subtitle(id: &str, start_time: f64, end_time: f64, text: &str) -> Subtitle {
+        Subtitle {
+            id: id.to_string(),
+            start_time,
+            end_time,
+            text: text.to_string(),
+            position: None,
+            words: None,
+        }
+    }
+
+    #[test]
+    fn vtt_export_keeps_find_replace_edits() {
+        let subtitles = vec![Subtitle {
+            words: Some(vec![word("hello", 1.0, 1.5), word("world", 1.5, 2.0)]),
+            ..subtitle("1", 1.0, 2.0, "hello world")
+        }];
+
+        let path = temp_path("vtt");
+        export_vtt_to_path(&subtitles, &path, &ExportOptions::default()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("hello <00:00:01.500>world"));
+
+        let (edited, count) = find_replace(&subtitles, "world", "there", false, false).unwrap();
+        assert_eq!(count, 1);
+        export_vtt_to_path(&edited, &path, &ExportOptions::default()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(content.contains("hello there"));
+        assert!(!content.contains("world"));
+    }
+
+    #[test]
+    fn srt_round_trip_with_adversarial_text() {
+        let subtitles = vec![
+            subtitle("1", 1.0, 2.5, "2\n00:00:05,000 --> 00:00:06,000\nnot a cue"),
+            subtitle("2", 3.0, 4.0, "42 apples  \n\n\n\nand --> arrows"),
+            subtitle("3", 3600.5, 3602.25, "7"),
+        ];
+
+        let path = temp_path("srt");
+        export_srt_to_path(&subtitles, &path, &ExportOptions::default()).unwrap();
+        let imported = import_srt(&path);
+        std::fs::remove_file(&path).ok();
+        let imported = imported.unwrap();
+
+        assert_eq!(imported.len(), 3);
+        assert_eq!(
+            imported[0].text,
+            "2\n00:00:05,000 --> 00:00:06,000\nnot a cue"
+        );
+        assert_eq!(imported[1].text, "42 apples\nand --> arrows");
+        assert_eq!(imported[2].text, "7");
+        for (original, imported) in subtitles.iter().zip(&imported) {
+            assert!((original.start_time - imported.start_time).abs() < 0.001);
+            assert!((original.end_time - imported.end_time).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn surround_audio_stream_from_ffprobe() {
+        let stream: serde_json::Value = serde_json::from_str(
+            r#"{
+                "index": 1,
+                "codec_name": "ac3",
+                "codec_type": "audio",
+                "sample_rate": "48000.0",
+                "channels": 6,
+                "channel_layout": "5.1(side)",
+                "tags": { "language": "eng" }
+            }"#,
+        )
+        .unwrap();
+
+        let track = audio_track_from_stream(1, &stream);
+        assert_eq!(track.channels, 6);
+        assert_eq!(track.channel_layout.as_deref(), Some("5.1(side)"));
+        assert_eq!(track.sample_rate, 48000);
+        assert_eq!(track.language.as_deref(), Some("eng"));
+        assert_eq!(
+            downmix_filter(track.channels, track.channel_layout.as_deref()).as_deref(),
+            Some("pan=mono|c0=0.5*FC+0.25*FL+0.25*FR")
+        );
+    }
+
+    #[test]
+    fn rotated_video_stream_from_ffprobe() {
+        let side_data: serde_json::Value = serde_json::from_str(
+            r#"{
+                "index": 0,
+                "codec_type": "video",
+                "width": 1920,
+                "height": 1080,
+                "side_data_list": [
+                    { "side_data_type": "Display Matrix", "rotation": -90 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(stream_rotation(&side_data), 90);
+        assert_eq!(rotation_filter(90), Some("transpose=clock"));
+
+        let legacy_tag: serde_json::Value =
+            serde_json::from_str(r#"{ "codec_type": "video", "tags": { "rotate": "270" } }"#)
+                .unwrap();
+        assert_eq!(stream_rotation(&legacy_tag), 270);
+        assert_eq!(rotation_filter(270), Some("transpose=cclock"));
+
+        let upside_down: serde_json::Value =
+            serde_json::from_str(r#"{ "side_data_list": [{ "rotation": 180 }] }"#).unwrap();
+        assert_eq!(stream_rotation(&upside_down), 180);
+        assert_eq!(rotation_filter(180), Some("hflip,vflip"));
+
+        assert_eq!(stream_rotation(&serde_json::json!({})), 0);
+        assert_eq!(rotation_filter(0), None);
+    }
+
+    #[test]
+    fn line_lengths_count_cjk_as_double_width() {
+        assert_eq!(display_width("字幕abc"), 7);
+        assert_eq!(display_width("カナ"), 4);
+
+        let limits: HashMap<String, usize> =
+            [("zh-CN".to_string(), 16), ("default".to_string(), 10)]
+                .into_iter()
+                .collect();
+        let subtitles = vec![
+            // 10个汉字宽20，超过中文限制16
+            subtitle("1", 0.0, 1.0, "这是一条比较长的字幕\nshort"),
+            // 8个汉字宽16，未超限
+            subtitle("2", 1.0, 2.0, "这条字幕刚好不超"),
+            // 英文按default限制10
+            subtitle("3", 2.0, 3.0, "hello world"),
+        ];
+
+        let issues = check_line_lengths(&subtitles, &limits);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].id, "1");
+        assert_eq!(issues[0].width, 20);
+        assert_eq!(issues[0].limit, 16);
+        assert_eq!(issues[0].language, "zh");
+        assert_eq!(issues[1].id, "3");
+        assert_eq!(issues[1].width, 11);
+        assert_eq!(issues[1].limit, 10);
+        assert_eq!(issues[1].language, "en");
+    }
+
+    #[test]
+    fn clamp_caps_last_cue_past_duration() {
+        let subtitles = vec![
+            subtitle("1", 0.0, 2.0, "第一句"),
+            subtitle("2", 8.5, 12.0, "最后一句"),
+            subtitle("3", 10.0, 11.0, "超出时长"),
+        ];
+
+        let clamped = clamp_to_duration(&subtitles, 10.0);
+        assert_eq!(clamped.len(), 2);
+        assert_eq!(clamped[0].end_time, 2.0);
+        assert_eq!(clamped[1].start_time, 8.5);
+        assert_eq!(clamped[1].end_time, 10.0);
+
+        assert_eq!(clamp_to_duration(&subtitles, 0.0).len(), 3);
+        assert_eq!(clamp_to_duration(&subtitles, f64::NAN).len(), 3);
+    }
+
+    #[test]
+    fn subtitle_bytes_with_bom_or_gbk() {
+        let bom = b"\xEF\xBB\xBF1\n00:00:01,000 --> 00:00:02,000\n\xE4\xB8\xAD\xE6\x96\x87\n";
+        let (text, had_errors) = decode_subtitle_bytes(bom);
+        assert!(!had_errors);
+        assert_eq!(text, "1\n00:00:01,000 --> 00:00:02,000\n中文\n");
+
+        // "中文字幕" 的GBK编码
+        let gbk = b"1\r\n00:00:01,000 --> 00:00:02,000\r\n\xD6\xD0\xCE\xC4\xD7\xD6\xC4\xBB\r\n";
+        let (text, had_errors) = decode_subtitle_bytes(gbk);
+        assert!(!had_errors);
+        assert_eq!(text, "1\r\n00:00:01,000 --> 00:00:02,000\r\n中文字幕\r\n");
+
+        let path = temp_path("srt");
+        std::fs::write(&path, gbk).unwrap();
+        let imported = import_srt(&path);
+        let _ = std::fs::remove_file(&path);
+        let imported = imported.unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].text, "中文字幕");
+    }
+
+    #[test]
+    fn sbv_export_past_one_hour() {
+        assert_eq!(format_time_sbv(3725.5), "1:02:05.500");
+        assert_eq!(format_time_sbv(36000.042), "10:00:00.042");
+
+        let subtitles = vec![
+            subtitle("1", 59.9995, 61.0, "first"),
+            subtitle("2", 3725.5, 36000.042, "line one  \r\n\r\n\nline two"),
+        ];
+        let path = temp_path("sbv");
+        export_sbv_to_path(&subtitles, &path).unwrap();
+        let content = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            content.unwrap(),
+            "0:01:00.000,0:01:01.000\nfirst\n\n1:02:05.500,10:00:00.042\nline one\nline two\n\n"
+        );
+    }
+
+    #[test]
+    fn listed_formats_match_dispatch() {
+        let subtitles = vec![subtitle("1", 1.0, 2.0, "hello")];
+        let options = ExportOptions::default();
+
+        for (format, _) in EXPORT_FORMATS {
+            let base = temp_path(format);
+            let base = base.trim_end_matches(&format!(".{}", format));
+            let exported = export_subtitles(&subtitles, format, base, &options);
+            let full_path = temp_path(format);
+            let exported_to_file =
+                export_subtitles_to_file(&subtitles, format, &full_path, &options);
+            if let Ok(path) = &exported {
+                let _ = std::fs::remove_file(path);
+            }
+            let _ = std::fs::remove_file(&full_path);
+            assert!(exported.is_ok(), "{}: {:?}", format, exported);
+            assert!(
+                exported_to_file.is_ok(),
+                "{}: {:?}",
+                format,
+                exported_to_file
+            );
+        }
+
+        for (format, _) in IMPORT_FORMATS {
+            let source_format = if *format == "ssa" { "ass" } else { format };
+            let path = temp_path(format);
+            export_subtitles_to_file(&subtitles, source_format, &path, &options).unwrap();
+            let imported = import_subtitles(&path, None);
+            let _ = std::fs::remove_file(&path);
+            let imported = imported.unwrap_or_else(|e| panic!("{}: {}", format, e));
+            assert_eq!(imported.len(), 1, "{}", format);
+            assert_eq!(imported[0].text, "hello", "{}", format);
+        }
+
+        assert!(export_subtitles_to_file(&subtitles, "xyz", &temp_path("xyz"), &options).is_err());
+    }
+
+    #[test]
+    fn ass_export_escapes_line_breaks() {
+        let mut top = subtitle("1", 1.0, 2.0, "第一行\r\n第二行");
+        top.position = Some("top".to_string());
+        let subtitles = vec![top, subtitle("2", 3.0, 4.0, "single")];
+
+        let base = temp_path("ass");
+        let base = base.trim_end_matches(".ass");
+        let path = export_subtitles(&subtitles, "ass", base, &ExportOptions::default()).unwrap();
+        let content = std::fs::read_to_string(&path);
+        let imported = import_subtitles(&path, None);
+        let _ = std::fs::remove_file(&path);
+
+        let content = content.unwrap();
+        assert!(content.contains(
+            "Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\an8}第一行\\N第二行\n"
+        ));
+        assert!(content.contains("Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,single\n"));
+        let imported = imported.unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].text, "第一行\n第二行");
+    }
+
+    #[test]
+    fn split_ranges_snap_to_keyframes_and_skip_empty_pieces() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0, 8.0];
+        // 5→4、3→2、4→4；0和超出时长的12被忽略，1对齐到0后也被忽略
+        assert_eq!(
+            plan_split_ranges(&[5.0, 0.0, 12.0, 3.0, 4.0, 1.0], &keyframes, 10.0),
+            vec![(0.0, 2.0), (2.0, 4.0), (4.0, 10.0)]
+        );
+        // 切点等于时长时不产生空的末段
+        assert_eq!(
+            plan_split_ranges(&[10.0], &keyframes, 10.0),
+            vec![(0.0, 10.0)]
+        );
+        // 没有关键帧信息（纯音频）时按原切点切分，时长未知时末段到结尾
+        assert_eq!(
+            plan_split_ranges(&[2.5], &[], 0.0),
+            vec![(0.0, 2.5), (2.5, f64::INFINITY)]
+        );
+    }
+}
@@ -10,6 +10,7 @@ export interface VideoInfo {
   frameRate: number; // 帧率
   codecInfo: string; // 编码格式
   audioTracks: AudioTrack[];
+  rotation?: number; // 播放时需要顺时针旋转的角度（0/90/180/270）
 }
 
 // 音频轨道信息
@@ -18,6 +19,7 @@ export interface AudioTrack {
   language?: string;
   codecInfo: string;
   channels: number;
+  channelLayout?: string;
   sampleRate: number;
 }
 
@@ -27,9 +29,16 @@ export interface Subtitle {
   startTime: number; // 开始时间（秒）
   endTime: number; // 结束时间（秒）
   text: string; // 字幕文本
+  position?: string; // 屏幕位置，如 'top-left'、'middle'（导出ASS时转换为\anN）
   style?: SubtitleStyle; // 字幕样式
 }
 
+// 字幕时间轴修复结果
+export interface NormalizeResult {
+  subtitles: Subtitle[]; // 修复后的字幕（按开始时间排序）
+  repairs: number; // 修复次数
+}
+
 // 字幕样式接口
 export interface SubtitleStyle {
   fontFamily?: string;
@@ -146,6 +155,16 @@ export interface AppSettings {
     aliyun?: {
       accessKeyId: string;
       accessKeySecret: string;
+      appKey: string; // 智能语音交互项目Appkey
+      region?: string; // 默认cn-shanghai
+      // COS配置（录音文件识别需要公网URL，音频先上传到COS）
+      cos?: {
+        secretId: string;
+        secretKey: string;
+        bucket: string;
+        region: string;
+        domain?: string;
+      };
     };
   };
   // 模型配置
@@ -175,4 +194,24 @@ export interface AppSettings {
   autoSave: boolean;
   autoSaveInterval: number; // 秒
   exportPath?: string; // 字幕导出路径
-}
\ No newline at end of file
+}
+// 后端命令返回的结构化错误
+export type FlowErrorKind =
+  | 'fileNotFound'
+  | 'apiKeyMissing'
+  | 'fileTooLarge'
+  | 'engineUnsupported'
+  | 'network'
+  | 'ffmpeg'
+  | 'other';
+
+export interface FlowError {
+  kind: FlowErrorKind;
+  message: string;
+  path?: string;
+  provider?: string;
+  field?: string | null;
+  sizeMb?: number;
+  limitMb?: number;
+  engine?: string;
+}
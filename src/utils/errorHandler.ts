@@ -1,5 +1,6 @@
 import { ElMessage, ElNotification } from 'element-plus';
 import type { NotificationOptions } from 'element-plus';
+import type { FlowError } from '../types';
 
 /**
  * 错误类型枚举
@@ -286,4 +287,23 @@ export function withSyncErrorHandling<T extends (...args: any[]) => any>(
       throw error;
     }
   }) as T;
-}
\ No newline at end of file
+}
+/**
+ * 判断是否为后端命令返回的结构化错误
+ * @param error 捕获的错误
+ */
+export function isFlowError(error: unknown): error is FlowError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+/**
+ * 获取后端命令错误的说明文字
+ * @param error 捕获的错误（结构化错误、Error或字符串）
+ * @returns 错误说明
+ */
+export function commandErrorMessage(error: unknown): string {
+  if (isFlowError(error) || error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+}
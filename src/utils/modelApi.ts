@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { commandErrorMessage } from './errorHandler';
 import type { ExtendedRecognitionParams, ModelConfig } from '../types';
 
 /**
@@ -15,7 +16,7 @@ export class ModelApi {
       return models;
     } catch (error) {
       console.error('获取可用模型失败:', error);
-      throw new Error(`获取可用模型失败: ${error}`);
+      throw new Error(`获取可用模型失败: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -67,7 +68,7 @@ export class ModelApi {
       return info;
     } catch (error) {
       console.error('获取模型信息失败:', error);
-      throw new Error(`获取模型信息失败: ${error}`);
+      throw new Error(`获取模型信息失败: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -85,7 +86,7 @@ export class ModelApi {
       });
     } catch (error) {
       console.error('启动扩展识别失败:', error);
-      throw new Error(`启动扩展识别失败: ${error}`);
+      throw new Error(`启动扩展识别失败: ${commandErrorMessage(error)}`);
     }
   }
 
@@ -1,4 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { commandErrorMessage } from './errorHandler';
 import type { RecognitionEngine, RecognitionTask, Subtitle, ExtendedRecognitionParams } from '../types';
 import { generateId } from './videoUtils';
 import { ModelApi } from './modelApi';
@@ -36,15 +38,17 @@ export async function startRecognition(
     await invoke('start_recognition', {
       taskId: task.id,
       audioPath,
-      engine,
-      language,
-      apiKeys
+      options: {
+        engine,
+        language,
+        apiKeys
+      }
     });
     
     return task;
   } catch (error) {
     console.error('开始识别任务失败:', error);
-    throw new Error(`开始识别任务失败: ${error}`);
+    throw new Error(`开始识别任务失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -94,10 +98,37 @@ export async function startRecognitionWithConfig(
     return task;
   } catch (error) {
     console.error('启动扩展配置识别任务失败:', error);
-    throw new Error(`启动扩展配置识别任务失败: ${error}`);
+    throw new Error(`启动扩展配置识别任务失败: ${commandErrorMessage(error)}`);
   }
 }
 
+/**
+ * 识别进度事件（后端在任务状态更新时推送 recognition://progress）
+ */
+export interface RecognitionProgressEvent {
+  task_id: string;
+  status: {
+    status: 'pending' | 'processing' | 'completed' | 'failed' | 'cancelled';
+    progress: number;
+    result?: Subtitle[] | null;
+    error?: string | null;
+    warnings: string[];
+    retry_of?: string | null;
+    detected_language?: string | null;
+  };
+}
+
+/**
+ * 监听识别进度事件，可替代定时轮询 getRecognitionStatus
+ * @param callback 收到事件时的回调
+ * @returns 取消监听的函数
+ */
+export async function onRecognitionProgress(
+  callback: (event: RecognitionProgressEvent) => void
+): Promise<UnlistenFn> {
+  return listen<RecognitionProgressEvent>('recognition://progress', (event) => callback(event.payload));
+}
+
 /**
  * 获取识别任务状态
  * @param taskId 任务ID
@@ -113,7 +144,7 @@ export async function getRecognitionStatus(taskId: string): Promise<{
     return await invoke('get_recognition_status', { taskId });
   } catch (error) {
     console.error('获取识别状态失败:', error);
-    throw new Error(`获取识别状态失败: ${error}`);
+    throw new Error(`获取识别状态失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -126,7 +157,7 @@ export async function cancelRecognition(taskId: string): Promise<void> {
     await invoke('cancel_recognition', { taskId });
   } catch (error) {
     console.error('取消识别任务失败:', error);
-    throw new Error(`取消识别任务失败: ${error}`);
+    throw new Error(`取消识别任务失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -143,10 +174,19 @@ export async function getSupportedLanguages(engine: RecognitionEngine): Promise<
     return await invoke('get_supported_languages', { engine });
   } catch (error) {
     console.error('获取支持的语言列表失败:', error);
-    throw new Error(`获取支持的语言列表失败: ${error}`);
+    throw new Error(`获取支持的语言列表失败: ${commandErrorMessage(error)}`);
   }
 }
 
+/**
+ * 端到端测试腾讯云COS配置（上传、读取、删除测试文件）
+ * @param config 腾讯云密钥及COS配置
+ * @throws 失败时抛出包含出错步骤的错误信息
+ */
+export async function testCosConfig(config: any): Promise<void> {
+  await invoke('test_cos_config', { config });
+}
+
 /**
  * 检查API密钥是否有效
  * @param engine 识别引擎
@@ -155,7 +195,11 @@ export async function getSupportedLanguages(engine: RecognitionEngine): Promise<
  */
 export async function validateApiKeys(engine: RecognitionEngine, apiKeys: any): Promise<boolean> {
   try {
-    return await invoke('validate_api_keys', { engine, apiKeys });
+    const result = await invoke<{ status: string; message?: string }>('validate_api_keys', { engine, apiKeys });
+    if (result.status !== 'valid') {
+      console.warn(`API密钥验证未通过(${result.status}):`, result.message);
+    }
+    return result.status === 'valid';
   } catch (error) {
     console.error('验证API密钥失败:', error);
     return false;
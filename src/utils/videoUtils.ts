@@ -1,6 +1,7 @@
 import { invoke } from '@tauri-apps/api/core';
+import { commandErrorMessage } from './errorHandler';
 import { open } from '@tauri-apps/plugin-dialog';
-import type { VideoInfo, Subtitle, SubtitleFormat } from '../types';
+import type { VideoInfo, Subtitle, SubtitleFormat, NormalizeResult } from '../types';
 
 /**
  * 检查Tauri API是否可用
@@ -36,7 +37,7 @@ export async function selectVideoFile(): Promise<string | null> {
     return selected as string;
   } catch (error) {
     console.error('选择视频文件失败:', error);
-    throw new Error(`文件选择失败: ${error}`);
+    throw new Error(`文件选择失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -50,7 +51,7 @@ export async function getVideoInfo(filePath: string): Promise<VideoInfo> {
     return await invoke<VideoInfo>('get_video_info', { filePath });
   } catch (error) {
     console.error('获取视频信息失败:', error);
-    throw new Error(`获取视频信息失败: ${error}`);
+    throw new Error(`获取视频信息失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -65,7 +66,7 @@ export async function extractAudio(videoPath: string, audioTrackId: number): Pro
     return await invoke<string>('extract_audio', { videoPath, audioTrackId });
   } catch (error) {
     console.error('提取音频失败:', error);
-    throw new Error(`提取音频失败: ${error}`);
+    throw new Error(`提取音频失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -105,6 +106,13 @@ export function parseTime(timeString: string): number {
   );
 }
 
+/**
+ * 按格式生成导出选项：纯文本格式去除样式标签，字幕格式保留
+ */
+function exportOptions(format: SubtitleFormat) {
+  return { stripTags: format === 'txt' };
+}
+
 /**
  * 导出字幕为指定格式
  * @param subtitles 字幕数组
@@ -114,10 +122,10 @@ export function parseTime(timeString: string): number {
  */
 export async function exportSubtitles(subtitles: Subtitle[], format: SubtitleFormat, fileName: string): Promise<string> {
   try {
-    return await invoke<string>('export_subtitles', { subtitles, format, fileName });
+    return await invoke<string>('export_subtitles', { subtitles, format, fileName, options: exportOptions(format) });
   } catch (error) {
     console.error('导出字幕失败:', error);
-    throw new Error(`导出字幕失败: ${error}`);
+    throw new Error(`导出字幕失败: ${commandErrorMessage(error)}`);
   }
 }
 
@@ -131,27 +139,114 @@ export async function exportSubtitles(subtitles: Subtitle[], format: SubtitleFor
  */
 export async function exportSubtitlesToPath(subtitles: Subtitle[], format: SubtitleFormat, fileName: string, exportPath: string): Promise<string> {
   try {
-    return await invoke<string>('export_subtitles_to_path', { subtitles, format, fileName, exportPath });
+    return await invoke<string>('export_subtitles_to_path', {
+      subtitles,
+      format,
+      fileName,
+      exportPath,
+      options: exportOptions(format),
+    });
   } catch (error) {
     console.error('导出字幕到指定路径失败:', error);
-    throw new Error(`导出字幕失败: ${error}`);
+    throw new Error(`导出字幕失败: ${commandErrorMessage(error)}`);
   }
 }
 
 /**
  * 导入字幕文件
  * @param filePath 字幕文件路径
+ * @param normalize 是否修复颠倒、重叠和零时长的时间轴
  * @returns 字幕数组
  */
-export async function importSubtitles(filePath: string): Promise<Subtitle[]> {
+export async function importSubtitles(filePath: string, normalize = false): Promise<Subtitle[]> {
   try {
-    return await invoke<Subtitle[]>('import_subtitles', { filePath });
+    return await invoke<Subtitle[]>('import_subtitles', { filePath, normalize });
   } catch (error) {
     console.error('导入字幕失败:', error);
-    throw new Error(`导入字幕失败: ${error}`);
+    throw new Error(`导入字幕失败: ${commandErrorMessage(error)}`);
   }
 }
 
+/**
+ * 修复字幕时间轴：交换颠倒的时间、截断重叠的字幕、丢弃零时长字幕
+ * @param subtitles 字幕数组
+ * @returns 修复后的字幕和修复次数
+ */
+export async function normalizeSubtitles(subtitles: Subtitle[]): Promise<NormalizeResult> {
+  try {
+    return await invoke<NormalizeResult>('normalize_subtitles', { subtitles });
+  } catch (error) {
+    console.error('修复字幕时间轴失败:', error);
+    throw new Error(`修复字幕时间轴失败: ${commandErrorMessage(error)}`);
+  }
+}
+
+/**
+ * 开启字幕自动保存
+ * @param path 自动保存基础路径（视频或字幕文件路径）
+ * @param intervalSecs 保存间隔（秒）
+ * @returns 自动保存文件路径
+ */
+export async function enableAutosave(path: string, intervalSecs: number): Promise<string> {
+  return await invoke<string>('enable_autosave', { path, intervalSecs });
+}
+
+/**
+ * 推送编辑中的字幕，下次定时保存时写入
+ * @param subtitles 当前字幕
+ */
+export async function updateAutosave(subtitles: Subtitle[]): Promise<void> {
+  await invoke('update_autosave', { subtitles });
+}
+
+/**
+ * 停止自动保存（会先写入未保存的修改）
+ */
+export async function disableAutosave(): Promise<void> {
+  await invoke('disable_autosave');
+}
+
+/**
+ * 读取自动保存的字幕
+ * @param path 自动保存基础路径
+ * @returns 字幕数组
+ */
+export async function recoverAutosave(path: string): Promise<Subtitle[]> {
+  return await invoke<Subtitle[]>('recover_autosave', { path });
+}
+
+/**
+ * 清空编辑历史并以当前字幕作为初始状态（载入新字幕时调用）
+ * @param subtitles 当前字幕
+ */
+export async function resetEditHistory(subtitles: Subtitle[]): Promise<void> {
+  await invoke('reset_edit_history', { subtitles });
+}
+
+/**
+ * 记录一次编辑后的字幕状态
+ * @param subtitles 编辑后的字幕
+ */
+export async function recordEdit(subtitles: Subtitle[]): Promise<void> {
+  await invoke('record_edit', { subtitles });
+}
+
+/**
+ * 撤销上一次编辑
+ * @returns 撤销后的字幕
+ */
+export async function undoEdit(): Promise<Subtitle[]> {
+  return await invoke<Subtitle[]>('undo');
+}
+
+/**
+ * 重做被撤销的编辑
+ * @returns 重做后的字幕
+ */
+export async function redoEdit(): Promise<Subtitle[]> {
+  return await invoke<Subtitle[]>('redo');
+}
+
 /**
  * 获取默认导出路径
  * @returns 默认导出路径
@@ -161,7 +256,7 @@ export async function getDefaultExportPath(): Promise<string> {
     return await invoke<string>('get_default_export_path');
   } catch (error) {
     console.error('获取默认导出路径失败:', error);
-    throw new Error(`获取默认导出路径失败: ${error}`);
+    throw new Error(`获取默认导出路径失败: ${commandErrorMessage(error)}`);
   }
 }
 